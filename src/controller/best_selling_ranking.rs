@@ -0,0 +1,92 @@
+use std::sync::Arc;
+
+use megacommerce_proto::{
+  best_selling_ranking_response::Response::{Data, Error},
+  BestSellingRankingRequest, BestSellingRankingResponse, BestSellingRankingResponseData,
+};
+use megacommerce_shared::models::{
+  context::Context,
+  errors::{AppError, AppErrorErrors, BoxedErr, MSG_ID_ERR_INTERNAL},
+};
+use tonic::{Code, Request, Response, Status};
+
+use crate::{
+  controller::{
+    helpers::{
+      build_pagination_response, display_currency, display_prices_fill, page_size,
+      pagination_cursor,
+    },
+    Controller,
+  },
+  models::best_selling::BestSellingWindow,
+};
+
+/// The products ranked by their units sold over a window (24h, 7d or 30d, the week by default),
+/// optionally in a single category
+pub(super) async fn best_selling_ranking(
+  c: &Controller,
+  request: Request<BestSellingRankingRequest>,
+) -> Result<Response<BestSellingRankingResponse>, Status> {
+  let start = std::time::Instant::now();
+  c.metrics.best_selling_ranking_total.inc();
+
+  let ctx = request.extensions().get::<Arc<Context>>().cloned().unwrap();
+  let req = request.into_inner();
+
+  let w = "products.controller.best_selling_ranking";
+  let return_err = |e: AppError| {
+    c.metrics.record_best_selling_ranking_error();
+    Response::new(BestSellingRankingResponse { response: Some(Error(e.to_proto())) })
+  };
+  let ie = |err: BoxedErr| {
+    let errors = Some(AppErrorErrors { err: Some(err), ..Default::default() });
+    AppError::new(ctx.clone(), w, MSG_ID_ERR_INTERNAL, None, "", Code::Internal.into(), errors)
+  };
+
+  let window = match req.window.as_deref() {
+    None | Some("") => BestSellingWindow::default(),
+    Some(window) => match BestSellingWindow::from_str(window) {
+      Some(window) => window,
+      None => {
+        let id = "products.best_selling.window.invalid";
+        let code = Code::InvalidArgument.into();
+        return Ok(return_err(AppError::new(ctx.clone(), w, id, None, "", code, None)));
+      }
+    },
+  };
+  let category_id = req.category_id.as_deref().filter(|id| !id.is_empty());
+
+  // a cursor only pages through the ranking it was issued for
+  let sort = format!("rank:{}:{}", window.as_str(), category_id.unwrap_or_default());
+  let secret = &c.products_cfg.pagination_cursor_secret;
+  let cursor = match pagination_cursor(ctx.clone(), w, &req.pagination, secret, &sort) {
+    Ok(cursor) => cursor,
+    Err(err) => return Ok(return_err(err)),
+  };
+
+  let currency = match display_currency(ctx.clone(), w, &req.display_currency) {
+    Ok(currency) => currency,
+    Err(err) => return Ok(return_err(err)),
+  };
+
+  let limit = page_size(&req.pagination, 20, c.products_cfg.pagination_max_page_size);
+  let result =
+    c.store.best_selling_ranking(ctx.clone(), window, category_id, cursor.as_ref(), limit).await;
+  let mut page = match result {
+    Ok(page) => page,
+    Err(err) => return Ok(return_err(ie(Box::new(err)))),
+  };
+  display_prices_fill(c, currency, &mut page.items);
+
+  let duration = start.elapsed().as_secs_f64();
+  c.metrics.record_best_selling_ranking_success(duration);
+
+  let pagination = build_pagination_response(cursor.as_ref(), &page, None, &sort, secret);
+  Ok(Response::new(BestSellingRankingResponse {
+    response: Some(Data(BestSellingRankingResponseData {
+      products: page.items,
+      pagination: Some(pagination),
+      window: window.as_str().to_string(),
+    })),
+  }))
+}
@@ -10,7 +10,10 @@ use megacommerce_shared::models::{
 };
 use tonic::{Code, Request, Response, Status};
 
-use crate::controller::Controller;
+use crate::controller::{
+  helpers::{display_currency, display_prices_fill, sellers_fill},
+  Controller,
+};
 
 pub async fn category_navbar(
   c: &Controller,
@@ -31,11 +34,24 @@ pub async fn category_navbar(
     AppError::new(ctx.clone(), path, id, None, "", code.unwrap_or(Code::Internal).into(), errors)
   };
 
+  let currency = match display_currency(ctx.clone(), path, &req_data.display_currency) {
+    Ok(currency) => currency,
+    Err(err) => {
+      c.metrics.record_category_navbar_error();
+      return Ok(return_err(err));
+    }
+  };
+
   let result =
     c.store.category_navbar(ctx.clone(), &req_data.category_id, &req_data.subcategory_id).await;
 
   match result {
-    Ok(data) => {
+    Ok(mut data) => {
+      if let Err(err) = sellers_fill(c, ctx.clone(), &mut data.recommended_products).await {
+        c.metrics.record_category_navbar_error();
+        return Ok(return_err(ie(Box::new(err), MSG_ID_ERR_INTERNAL, None)));
+      }
+      display_prices_fill(c, currency, &mut data.recommended_products);
       let duration = start.elapsed().as_secs_f64();
       c.metrics.record_category_navbar_success(duration);
       return Ok(Response::new(CategoryNavbarResponse { response: Some(Data(data)) }));
@@ -1,46 +1,242 @@
 use std::{str::FromStr, sync::Arc};
 
-use megacommerce_proto::{PaginationRequest, PaginationResponse};
-use megacommerce_shared::models::{context::Context, errors::AppError};
+use megacommerce_proto::{
+  InventoryStock, PaginationRequest, PaginationResponse, ProductDetailsResponseData,
+};
+use megacommerce_shared::{
+  models::{
+    context::Context,
+    errors::{AppError, AppErrorErrors, ErrorType},
+  },
+  store::errors::DBError,
+  utils::time::time_get_millis,
+};
 use tonic::Code;
-use ulid::Ulid;
 
-pub(super) fn check_last_id(
+use crate::{
+  controller::Controller,
+  data::currencies::{currency_get, Currency},
+  models::{
+    exchange_rates::DisplayPriced,
+    inventory::{InventoryOutcome, INVENTORY_IDEMPOTENCY_KEY_MAX_LENGTH},
+    money::VariantPrice,
+    pagination::{KeysetPage, ListTotal, PageCursor, PageDirection, PagePosition},
+    permissions::roles_allow,
+    sellers::{seller_ids, SoldBy},
+  },
+};
+
+/// The sort of the lists ordered by the id only, newest first
+pub(super) const ID_DESC_SORT: &str = "id:desc";
+
+/// Checks the pagination and decodes its cursor, which has to be issued for the same sort.
+/// No cursor means the first page.
+pub(super) fn pagination_cursor(
   ctx: Arc<Context>,
   _where: &str,
   pagination: &Option<PaginationRequest>,
-) -> Result<(), AppError> {
+  secret: &str,
+  sort: &str,
+) -> Result<Option<PageCursor>, AppError> {
   let mk_err =
     |id: &str| AppError::new(ctx, _where, id, None, "", Code::InvalidArgument.into(), None);
 
-  if pagination.is_none() {
-    return Err(mk_err("request.pagination.invalid"));
-  }
-
-  let pagination = pagination.as_ref().unwrap();
-  let last_id = pagination.last_id();
-  let page = pagination.page();
+  let pagination = match pagination {
+    Some(pagination) => pagination,
+    None => return Err(mk_err("request.pagination.invalid")),
+  };
 
-  if page > 1 && last_id == "" {
-    return Err(mk_err("request.last_id.missing"));
+  let cursor = match pagination.cursor.as_deref() {
+    None | Some("") => return Ok(None),
+    Some(cursor) => cursor,
+  };
+  match PageCursor::decode(cursor, secret) {
+    Some(cursor) if cursor.sort == sort => Ok(Some(cursor)),
+    _ => Err(mk_err("request.cursor.invalid")),
   }
-  if page > 1 {
-    Ulid::from_string(last_id).map_err(|_| mk_err("request.last_id.invalid"))?;
+}
+
+/// The page size the client asked for, up to `max`, or the list's default if it didn't
+pub(super) fn page_size(pagination: &Option<PaginationRequest>, default: u32, max: u32) -> i64 {
+  match pagination.as_ref().and_then(|p| p.page_size) {
+    Some(size) if size > 0 => size.min(max) as i64,
+    _ => default as i64,
   }
-  Ok(())
 }
 
-pub(super) fn build_pagination_response(
-  pr: &PaginationRequest,
-  items_count: usize,
+/// Whether the client asked for the total count of the list
+pub(super) fn include_total(pagination: &Option<PaginationRequest>) -> bool {
+  pagination.as_ref().is_some_and(|p| p.include_total())
+}
+
+/// Issues the cursors of the pages around the given one, along with the total if it was counted
+pub(super) fn build_pagination_response<T>(
+  cursor: Option<&PageCursor>,
+  page: &KeysetPage<T>,
+  total: Option<ListTotal>,
+  sort: &str,
+  secret: &str,
 ) -> PaginationResponse {
+  // the page was read from the cursor onwards, so there's always one on the cursor's side
+  let (has_previous, has_next) = match cursor.map(|c| c.direction) {
+    None => (false, page.has_more),
+    Some(PageDirection::Next) => (true, page.has_more),
+    Some(PageDirection::Previous) => (page.has_more, true),
+  };
+
+  let issue = |direction: PageDirection, position: &Option<PagePosition>, has: bool| {
+    let position = position.as_ref().filter(|_| has)?.clone();
+    let (sort, snapshot) = (sort.to_string(), page.snapshot.clone());
+    Some(PageCursor { sort, direction, position, snapshot }.encode(secret))
+  };
+
   PaginationResponse {
-    has_previous: Some(pr.page() != 1),
-    has_next: Some(pr.page_size() == items_count as u32),
+    has_previous: Some(has_previous),
+    has_next: Some(has_next),
+    previous_cursor: issue(PageDirection::Previous, &page.first, has_previous),
+    next_cursor: issue(PageDirection::Next, &page.last, has_next),
+    total_count: total.map(|t| t.count),
+    total_count_estimated: total.map(|t| t.estimated),
     ..Default::default()
   }
 }
 
+/// Fills in the sellers of the products, looking them all up at once
+pub(super) async fn sellers_fill<T: SoldBy>(
+  c: &Controller,
+  ctx: Arc<Context>,
+  products: &mut [T],
+) -> Result<(), DBError> {
+  let ids = seller_ids(products.iter().map(|p| p.seller_id()));
+  let sellers = c.store.seller_profiles(ctx, &ids).await?;
+  for product in products.iter_mut() {
+    if let Some(seller) = sellers.get(product.seller_id()) {
+      product.set_seller(seller);
+    }
+  }
+  Ok(())
+}
+
+/// The currency the shopper asked to see the prices in, none if they didn't ask for one
+pub(super) fn display_currency(
+  ctx: Arc<Context>,
+  _where: &str,
+  display_currency: &Option<String>,
+) -> Result<Option<&'static Currency>, AppError> {
+  match display_currency.as_deref().map(str::trim) {
+    None | Some("") => Ok(None),
+    Some(code) => match currency_get(&code.to_uppercase()) {
+      Some(currency) => Ok(Some(currency)),
+      None => {
+        let id = "products.display_currency.invalid";
+        Err(AppError::new(ctx, _where, id, None, "", Code::InvalidArgument.into(), None))
+      }
+    },
+  }
+}
+
+/// Adds the prices in the display currency next to the original ones. Without a fresh
+/// rate for a product's currency, its prices are only shown in that currency.
+pub(super) fn display_prices_fill<T: DisplayPriced>(
+  c: &Controller,
+  currency: Option<&'static Currency>,
+  products: &mut [T],
+) {
+  let (currency, rates) = match (currency, c.exchange_rates.get()) {
+    (Some(currency), Some(rates)) => (currency, rates),
+    _ => return,
+  };
+  for product in products.iter_mut() {
+    let (price, discount_price) = product.prices();
+    let display = rates.display_price(product.currency_code(), price, discount_price, currency);
+    if let Some(display) = display {
+      product.set_display_price(display);
+    }
+  }
+}
+
+/// Adds the prices of every variant of the product in the display currency, keyed by variant id
+pub(super) fn details_display_prices_fill(
+  c: &Controller,
+  currency: Option<&'static Currency>,
+  product: &mut ProductDetailsResponseData,
+) {
+  let (currency, rates) = match (currency, c.exchange_rates.get()) {
+    (Some(currency), Some(rates)) => (currency, rates),
+    _ => return,
+  };
+  let offer = match product.offer.as_ref() {
+    Some(offer) => &offer.offer,
+    None => return,
+  };
+  // the store has already dropped the sales that don't apply now
+  let now = time_get_millis();
+  for (variant_id, variant) in offer.iter() {
+    let prices = match VariantPrice::from_offer(variant, &product.currency_code, now) {
+      Ok(prices) => prices,
+      Err(_) => continue,
+    };
+    let sale_price = prices.sale_price.map(|p| p.minor_units());
+    let display =
+      rates.display_price(&product.currency_code, prices.price.minor_units(), sale_price, currency);
+    if let Some(display) = display {
+      product.display_prices.insert(variant_id.clone(), display);
+    }
+  }
+}
+
+/// Checks the idempotency key and the quantity of an inventory operation, a zero quantity
+/// is only allowed where the operation has none (e.g. a commit)
+pub(super) fn inventory_request_is_valid(
+  ctx: Arc<Context>,
+  _where: &str,
+  key: &str,
+  quantity: Option<u32>,
+) -> Result<(), AppError> {
+  let mk_err =
+    |id: &str| AppError::new(ctx.clone(), _where, id, None, "", Code::InvalidArgument.into(), None);
+
+  if key.trim().is_empty() || key.len() > INVENTORY_IDEMPOTENCY_KEY_MAX_LENGTH {
+    return Err(mk_err("inventory.idempotency_key.invalid"));
+  }
+  if quantity == Some(0) {
+    return Err(mk_err("inventory.quantity.invalid"));
+  }
+  Ok(())
+}
+
+/// The stock after an inventory operation, or the error the refusal of the operation maps to
+pub(super) fn inventory_outcome(
+  ctx: Arc<Context>,
+  _where: &str,
+  outcome: Result<InventoryOutcome, DBError>,
+) -> Result<InventoryStock, AppError> {
+  let mk_err = |id: &str, code: Code, errors: Option<AppErrorErrors>| {
+    AppError::new(ctx.clone(), _where, id, None, "", code.into(), errors)
+  };
+
+  match outcome {
+    Ok(InventoryOutcome::Applied(stock)) => Ok(stock),
+    Ok(InventoryOutcome::Replayed(stock)) => Ok(InventoryStock { replayed: true, ..stock }),
+    Ok(InventoryOutcome::Insufficient(_)) => {
+      Err(mk_err("inventory.insufficient_stock", Code::FailedPrecondition, None))
+    }
+    Ok(InventoryOutcome::KeyConflict) => {
+      Err(mk_err("inventory.idempotency_key.conflict", Code::AlreadyExists, None))
+    }
+    Ok(InventoryOutcome::Settled(_)) => {
+      Err(mk_err("inventory.reservation.settled", Code::FailedPrecondition, None))
+    }
+    Err(err) => match err.err_type {
+      ErrorType::NoRows => {
+        let errors = Some(AppErrorErrors { err: Some(Box::new(err)), ..Default::default() });
+        Err(mk_err("products.not_found.error", Code::NotFound, errors))
+      }
+      _ => Err(err.to_app_error_internal(ctx.clone(), _where.into())),
+    },
+  }
+}
+
 pub fn is_valid_ulid(id: &str) -> bool {
   if id.len() != 26 {
     return false;
@@ -51,3 +247,13 @@ pub fn is_valid_ulid(id: &str) -> bool {
   }
   true
 }
+
+/// Whether the session has one of the configured moderator roles
+pub(super) fn session_is_moderator(c: &Controller, ctx: &Context) -> bool {
+  roles_allow(ctx.session().roles(), &c.products_cfg.moderator_roles)
+}
+
+/// Whether the session is the order service, the only caller holding and settling stock
+pub(super) fn session_is_inventory_service(c: &Controller, ctx: &Context) -> bool {
+  roles_allow(ctx.session().roles(), &c.products_cfg.inventory_service_roles)
+}
@@ -10,7 +10,10 @@ use megacommerce_shared::models::{
 };
 use tonic::{Code, Request, Response, Status};
 
-use crate::controller::Controller;
+use crate::controller::{
+  helpers::{display_currency, display_prices_fill},
+  Controller,
+};
 
 pub async fn hero_products(
   c: &Controller,
@@ -20,7 +23,7 @@ pub async fn hero_products(
   c.metrics.hero_products_total.inc();
   
   let ctx = req.extensions().get::<Arc<Context>>().cloned().unwrap();
-  let _req = req.into_inner();
+  let req = req.into_inner();
   let path = "products.controller.hero_products";
   let return_err = |e: AppError| {
     return Response::new(HeroProductsResponse { response: Some(Error(e.to_proto())) });
@@ -31,10 +34,24 @@ pub async fn hero_products(
     AppError::new(ctx.clone(), path, MSG_ID_ERR_INTERNAL, None, "", Code::Internal.into(), errors)
   };
 
+  let currency = match display_currency(ctx.clone(), path, &req.display_currency) {
+    Ok(currency) => currency,
+    Err(err) => {
+      c.metrics.record_hero_products_error();
+      return Ok(return_err(err));
+    }
+  };
+
   let products = c.store.hero_products(ctx.clone()).await;
 
   match products {
-    Ok(products) => {
+    Ok(mut products) => {
+      if let Some(slider) = products.category_slider.as_mut() {
+        display_prices_fill(c, currency, &mut slider.products);
+      }
+      if let Some(slider) = products.welcome_deals_slider.as_mut() {
+        display_prices_fill(c, currency, &mut slider.products);
+      }
       let duration = start.elapsed().as_secs_f64();
       c.metrics.record_hero_products_success(duration);
       return Ok(Response::new(HeroProductsResponse {
@@ -0,0 +1,76 @@
+use std::{
+  io::{Error, ErrorKind},
+  sync::Arc,
+};
+
+use megacommerce_proto::{
+  inventory_stock_response::Response::{Data, Error as ResError},
+  InventoryCommitRequest, InventoryStockResponse,
+};
+use megacommerce_shared::models::{
+  context::Context,
+  errors::{AppError, AppErrorErrors},
+};
+use tonic::{Code, Request, Response, Status};
+
+use crate::{
+  controller::{
+    helpers::{
+      inventory_outcome, inventory_request_is_valid, is_valid_ulid, session_is_inventory_service,
+    },
+    Controller,
+  },
+  models::inventory::ReservationStatus,
+};
+
+/// Sells the units of a reservation, they leave the variant's offer quantity. Retrying with
+/// the same idempotency key returns the stock without selling twice.
+pub(super) async fn inventory_commit(
+  c: &Controller,
+  req: Request<InventoryCommitRequest>,
+) -> Result<Response<InventoryStockResponse>, Status> {
+  let start = std::time::Instant::now();
+  c.metrics.inventory_commit_total.inc();
+
+  let path = "products.controller.inventory_commit";
+  let ctx = req.extensions().get::<Arc<Context>>().cloned().unwrap();
+  let req = req.into_inner();
+
+  let return_err = |e: AppError| {
+    c.metrics.record_inventory_commit_error();
+    Response::new(InventoryStockResponse { response: Some(ResError(e.to_proto())) })
+  };
+
+  if !session_is_inventory_service(c, &ctx) {
+    let msg = "only the order service can hold and settle stock";
+    let err = Box::new(Error::new(ErrorKind::PermissionDenied, msg));
+    let errors = Some(AppErrorErrors { err: Some(err), ..Default::default() });
+    let (id, code) = ("inventory.permission.denied", Code::PermissionDenied.into());
+    return Ok(return_err(AppError::new(ctx, path, id, None, "", code, errors)));
+  }
+
+  let key = &req.idempotency_key;
+  if let Err(err) = inventory_request_is_valid(ctx.clone(), path, key, None) {
+    return Ok(return_err(err));
+  }
+  if !is_valid_ulid(&req.reservation_id) {
+    let err = Box::new(Error::new(ErrorKind::NotFound, "the reservation is not found"));
+    let errors = Some(AppErrorErrors { err: Some(err), ..Default::default() });
+    let id = "products.not_found.error";
+    return Ok(return_err(AppError::new(ctx, path, id, None, "", Code::NotFound.into(), errors)));
+  }
+
+  let outcome = c
+    .store
+    .inventory_settle(ctx.clone(), key, &req.reservation_id, ReservationStatus::Committed)
+    .await;
+  let stock = match inventory_outcome(ctx.clone(), path, outcome) {
+    Ok(stock) => stock,
+    Err(err) => return Ok(return_err(err)),
+  };
+
+  let duration = start.elapsed().as_secs_f64();
+  c.metrics.record_inventory_commit_success(duration);
+
+  Ok(Response::new(InventoryStockResponse { response: Some(Data(stock)) }))
+}
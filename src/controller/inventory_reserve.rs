@@ -0,0 +1,81 @@
+use std::{
+  io::{Error, ErrorKind},
+  sync::Arc,
+};
+
+use megacommerce_proto::{
+  inventory_stock_response::Response::{Data, Error as ResError},
+  InventoryReserveRequest, InventoryStockResponse,
+};
+use megacommerce_shared::models::{
+  context::Context,
+  errors::{AppError, AppErrorErrors},
+};
+use tonic::{Code, Request, Response, Status};
+
+use crate::controller::{
+  helpers::{
+    inventory_outcome, inventory_request_is_valid, is_valid_ulid, session_is_inventory_service,
+  },
+  Controller,
+};
+
+/// Holds units of a variant for an order. Retrying with the same idempotency key returns
+/// the first reservation instead of reserving twice.
+pub(super) async fn inventory_reserve(
+  c: &Controller,
+  req: Request<InventoryReserveRequest>,
+) -> Result<Response<InventoryStockResponse>, Status> {
+  let start = std::time::Instant::now();
+  c.metrics.inventory_reserve_total.inc();
+
+  let path = "products.controller.inventory_reserve";
+  let ctx = req.extensions().get::<Arc<Context>>().cloned().unwrap();
+  let req = req.into_inner();
+
+  let return_err = |e: AppError| {
+    c.metrics.record_inventory_reserve_error();
+    Response::new(InventoryStockResponse { response: Some(ResError(e.to_proto())) })
+  };
+
+  if !session_is_inventory_service(c, &ctx) {
+    let msg = "only the order service can hold and settle stock";
+    let err = Box::new(Error::new(ErrorKind::PermissionDenied, msg));
+    let errors = Some(AppErrorErrors { err: Some(err), ..Default::default() });
+    let (id, code) = ("inventory.permission.denied", Code::PermissionDenied.into());
+    return Ok(return_err(AppError::new(ctx, path, id, None, "", code, errors)));
+  }
+
+  let key = &req.idempotency_key;
+  if let Err(err) = inventory_request_is_valid(ctx.clone(), path, key, Some(req.quantity)) {
+    return Ok(return_err(err));
+  }
+  if !is_valid_ulid(&req.product_id) || req.variant_id.is_empty() {
+    let err = Box::new(Error::new(ErrorKind::NotFound, "the variant is not found"));
+    let errors = Some(AppErrorErrors { err: Some(err), ..Default::default() });
+    let id = "products.not_found.error";
+    return Ok(return_err(AppError::new(ctx, path, id, None, "", Code::NotFound.into(), errors)));
+  }
+  // the buyer the purchase is recorded for once committed, a guest has none
+  if !req.user_id.is_empty() && !is_valid_ulid(&req.user_id) {
+    let err = Box::new(Error::new(ErrorKind::InvalidInput, "invalid buyer user id"));
+    let errors = Some(AppErrorErrors { err: Some(err), ..Default::default() });
+    let (id, code) = ("inventory.user_id.invalid", Code::InvalidArgument.into());
+    return Ok(return_err(AppError::new(ctx, path, id, None, "", code, errors)));
+  }
+
+  let (product_id, variant_id) = (&req.product_id, &req.variant_id);
+  let outcome = c
+    .store
+    .inventory_reserve(ctx.clone(), key, product_id, variant_id, req.quantity, &req.user_id)
+    .await;
+  let stock = match inventory_outcome(ctx.clone(), path, outcome) {
+    Ok(stock) => stock,
+    Err(err) => return Ok(return_err(err)),
+  };
+
+  let duration = start.elapsed().as_secs_f64();
+  c.metrics.record_inventory_reserve_success(duration);
+
+  Ok(Response::new(InventoryStockResponse { response: Some(Data(stock)) }))
+}
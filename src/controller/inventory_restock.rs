@@ -0,0 +1,86 @@
+use std::{
+  io::{Error, ErrorKind},
+  sync::Arc,
+};
+
+use megacommerce_proto::{
+  inventory_stock_response::Response::{Data, Error as ResError},
+  InventoryRestockRequest, InventoryStockResponse,
+};
+use megacommerce_shared::models::{
+  context::Context,
+  errors::{AppError, AppErrorErrors, BoxedErr, ErrorType},
+};
+use tonic::{Code, Request, Response, Status};
+
+use crate::controller::{
+  helpers::{inventory_outcome, inventory_request_is_valid, is_valid_ulid},
+  Controller,
+};
+
+/// Adds units to a variant of the supplier's own product, and optionally sets the available
+/// units at or under which it shows as low on stock
+pub(super) async fn inventory_restock(
+  c: &Controller,
+  req: Request<InventoryRestockRequest>,
+) -> Result<Response<InventoryStockResponse>, Status> {
+  let start = std::time::Instant::now();
+  c.metrics.inventory_restock_total.inc();
+
+  let path = "products.controller.inventory_restock";
+  let ctx = req.extensions().get::<Arc<Context>>().cloned().unwrap();
+  let req = req.into_inner();
+
+  let return_err = |e: AppError| {
+    c.metrics.record_inventory_restock_error();
+    Response::new(InventoryStockResponse { response: Some(ResError(e.to_proto())) })
+  };
+  let not_found = |err: Option<BoxedErr>| {
+    let err = err.unwrap_or(Box::new(Error::new(ErrorKind::NotFound, "the variant is not found")));
+    let errors = Some(AppErrorErrors { err: Some(err), ..Default::default() });
+    let id = "products.not_found.error";
+    return_err(AppError::new(ctx.clone(), path, id, None, "", Code::NotFound.into(), errors))
+  };
+
+  let key = &req.idempotency_key;
+  if let Err(err) = inventory_request_is_valid(ctx.clone(), path, key, Some(req.quantity)) {
+    return Ok(return_err(err));
+  }
+  if !is_valid_ulid(&req.product_id) || req.variant_id.is_empty() {
+    return Ok(not_found(None));
+  }
+
+  let stored = match c.store.product_get(ctx.clone(), &req.product_id).await {
+    Ok(stored) => stored,
+    Err(err) => match err.err_type {
+      ErrorType::NoRows => return Ok(not_found(Some(Box::new(err)))),
+      _ => return Ok(return_err(err.to_app_error_internal(ctx.clone(), path.into()))),
+    },
+  };
+
+  // suppliers can only restock their own listings, don't leak the existence of other ones
+  if stored.user_id != ctx.session().user_id() {
+    return Ok(not_found(None));
+  }
+
+  let outcome = c
+    .store
+    .inventory_restock(
+      ctx.clone(),
+      key,
+      &req.product_id,
+      &req.variant_id,
+      req.quantity,
+      req.low_stock_threshold,
+    )
+    .await;
+  let stock = match inventory_outcome(ctx.clone(), path, outcome) {
+    Ok(stock) => stock,
+    Err(err) => return Ok(return_err(err)),
+  };
+
+  let duration = start.elapsed().as_secs_f64();
+  c.metrics.record_inventory_restock_success(duration);
+
+  Ok(Response::new(InventoryStockResponse { response: Some(Data(stock)) }))
+}
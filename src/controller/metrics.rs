@@ -27,6 +27,36 @@ pub struct MetricsCollector {
   pub product_snapshot_errors: IntCounter,
   pub product_data_total: IntCounter,
   pub product_data_errors: IntCounter,
+  pub product_update_total: IntCounter,
+  pub product_update_errors: IntCounter,
+  pub product_status_update_total: IntCounter,
+  pub product_status_update_errors: IntCounter,
+  pub product_media_upload_urls_total: IntCounter,
+  pub product_media_upload_urls_errors: IntCounter,
+  pub products_search_total: IntCounter,
+  pub products_search_errors: IntCounter,
+  pub product_review_create_total: IntCounter,
+  pub product_review_create_errors: IntCounter,
+  pub product_reviews_list_total: IntCounter,
+  pub product_reviews_list_errors: IntCounter,
+  pub product_review_moderate_total: IntCounter,
+  pub product_review_moderate_errors: IntCounter,
+  pub price_quote_total: IntCounter,
+  pub price_quote_errors: IntCounter,
+  pub inventory_commit_total: IntCounter,
+  pub inventory_commit_errors: IntCounter,
+  pub inventory_release_total: IntCounter,
+  pub inventory_release_errors: IntCounter,
+  pub inventory_reserve_total: IntCounter,
+  pub inventory_reserve_errors: IntCounter,
+  pub inventory_restock_total: IntCounter,
+  pub inventory_restock_errors: IntCounter,
+  pub best_selling_ranking_total: IntCounter,
+  pub best_selling_ranking_errors: IntCounter,
+  pub product_events_record_total: IntCounter,
+  pub product_events_record_errors: IntCounter,
+  pub related_products_total: IntCounter,
+  pub related_products_errors: IntCounter,
   pub cache_hits: IntCounter,
   pub cache_misses: IntCounter,
   pub db_query_duration_seconds: HistogramVec,
@@ -189,6 +219,213 @@ impl MetricsCollector {
         .map_err(|e| e.to_string())?;
     registry.register(Box::new(product_data_errors.clone())).map_err(|e| e.to_string())?;
 
+    // Product update
+    let product_update_total =
+      IntCounter::new("products_product_update_total", "Total product update requests")
+        .map_err(|e| e.to_string())?;
+    registry.register(Box::new(product_update_total.clone())).map_err(|e| e.to_string())?;
+
+    let product_update_errors = IntCounter::new(
+      "products_product_update_errors_total",
+      "Total failed product update requests",
+    )
+    .map_err(|e| e.to_string())?;
+    registry.register(Box::new(product_update_errors.clone())).map_err(|e| e.to_string())?;
+
+    // Product status update
+    let product_status_update_total = IntCounter::new(
+      "products_product_status_update_total",
+      "Total product status update requests",
+    )
+    .map_err(|e| e.to_string())?;
+    registry.register(Box::new(product_status_update_total.clone())).map_err(|e| e.to_string())?;
+
+    let product_status_update_errors = IntCounter::new(
+      "products_product_status_update_errors_total",
+      "Total failed product status update requests",
+    )
+    .map_err(|e| e.to_string())?;
+    registry.register(Box::new(product_status_update_errors.clone())).map_err(|e| e.to_string())?;
+
+    // Product media upload urls
+    let product_media_upload_urls_total = IntCounter::new(
+      "products_product_media_upload_urls_total",
+      "Total product media upload urls requests",
+    )
+    .map_err(|e| e.to_string())?;
+    registry
+      .register(Box::new(product_media_upload_urls_total.clone()))
+      .map_err(|e| e.to_string())?;
+
+    let product_media_upload_urls_errors = IntCounter::new(
+      "products_product_media_upload_urls_errors_total",
+      "Total failed product media upload urls requests",
+    )
+    .map_err(|e| e.to_string())?;
+    registry
+      .register(Box::new(product_media_upload_urls_errors.clone()))
+      .map_err(|e| e.to_string())?;
+
+    // Products search
+    let products_search_total =
+      IntCounter::new("products_products_search_total", "Total products search requests")
+        .map_err(|e| e.to_string())?;
+    registry.register(Box::new(products_search_total.clone())).map_err(|e| e.to_string())?;
+
+    let products_search_errors = IntCounter::new(
+      "products_products_search_errors_total",
+      "Total failed products search requests",
+    )
+    .map_err(|e| e.to_string())?;
+    registry.register(Box::new(products_search_errors.clone())).map_err(|e| e.to_string())?;
+
+    // Product review create
+    let product_review_create_total = IntCounter::new(
+      "products_product_review_create_total",
+      "Total product review create requests",
+    )
+    .map_err(|e| e.to_string())?;
+    registry.register(Box::new(product_review_create_total.clone())).map_err(|e| e.to_string())?;
+
+    let product_review_create_errors = IntCounter::new(
+      "products_product_review_create_errors_total",
+      "Total failed product review create requests",
+    )
+    .map_err(|e| e.to_string())?;
+    registry.register(Box::new(product_review_create_errors.clone())).map_err(|e| e.to_string())?;
+
+    // Product reviews list
+    let product_reviews_list_total =
+      IntCounter::new("products_product_reviews_list_total", "Total product reviews list requests")
+        .map_err(|e| e.to_string())?;
+    registry.register(Box::new(product_reviews_list_total.clone())).map_err(|e| e.to_string())?;
+
+    let product_reviews_list_errors = IntCounter::new(
+      "products_product_reviews_list_errors_total",
+      "Total failed product reviews list requests",
+    )
+    .map_err(|e| e.to_string())?;
+    registry.register(Box::new(product_reviews_list_errors.clone())).map_err(|e| e.to_string())?;
+
+    // Product review moderate
+    let product_review_moderate_total = IntCounter::new(
+      "products_product_review_moderate_total",
+      "Total product review moderate requests",
+    )
+    .map_err(|e| e.to_string())?;
+    registry.register(Box::new(product_review_moderate_total.clone())).map_err(|e| e.to_string())?;
+
+    let product_review_moderate_errors = IntCounter::new(
+      "products_product_review_moderate_errors_total",
+      "Total failed product review moderate requests",
+    )
+    .map_err(|e| e.to_string())?;
+    registry.register(Box::new(product_review_moderate_errors.clone())).map_err(|e| e.to_string())?;
+
+    // Price quote
+    let price_quote_total =
+      IntCounter::new("products_price_quote_total", "Total price quote requests")
+        .map_err(|e| e.to_string())?;
+    registry.register(Box::new(price_quote_total.clone())).map_err(|e| e.to_string())?;
+
+    let price_quote_errors =
+      IntCounter::new("products_price_quote_errors_total", "Total failed price quote requests")
+        .map_err(|e| e.to_string())?;
+    registry.register(Box::new(price_quote_errors.clone())).map_err(|e| e.to_string())?;
+
+    // Inventory commit
+    let inventory_commit_total =
+      IntCounter::new("products_inventory_commit_total", "Total inventory commit requests")
+        .map_err(|e| e.to_string())?;
+    registry.register(Box::new(inventory_commit_total.clone())).map_err(|e| e.to_string())?;
+
+    let inventory_commit_errors = IntCounter::new(
+      "products_inventory_commit_errors_total",
+      "Total failed inventory commit requests",
+    )
+    .map_err(|e| e.to_string())?;
+    registry.register(Box::new(inventory_commit_errors.clone())).map_err(|e| e.to_string())?;
+
+    // Inventory release
+    let inventory_release_total =
+      IntCounter::new("products_inventory_release_total", "Total inventory release requests")
+        .map_err(|e| e.to_string())?;
+    registry.register(Box::new(inventory_release_total.clone())).map_err(|e| e.to_string())?;
+
+    let inventory_release_errors = IntCounter::new(
+      "products_inventory_release_errors_total",
+      "Total failed inventory release requests",
+    )
+    .map_err(|e| e.to_string())?;
+    registry.register(Box::new(inventory_release_errors.clone())).map_err(|e| e.to_string())?;
+
+    // Inventory reserve
+    let inventory_reserve_total =
+      IntCounter::new("products_inventory_reserve_total", "Total inventory reserve requests")
+        .map_err(|e| e.to_string())?;
+    registry.register(Box::new(inventory_reserve_total.clone())).map_err(|e| e.to_string())?;
+
+    let inventory_reserve_errors = IntCounter::new(
+      "products_inventory_reserve_errors_total",
+      "Total failed inventory reserve requests",
+    )
+    .map_err(|e| e.to_string())?;
+    registry.register(Box::new(inventory_reserve_errors.clone())).map_err(|e| e.to_string())?;
+
+    // Inventory restock
+    let inventory_restock_total =
+      IntCounter::new("products_inventory_restock_total", "Total inventory restock requests")
+        .map_err(|e| e.to_string())?;
+    registry.register(Box::new(inventory_restock_total.clone())).map_err(|e| e.to_string())?;
+
+    let inventory_restock_errors = IntCounter::new(
+      "products_inventory_restock_errors_total",
+      "Total failed inventory restock requests",
+    )
+    .map_err(|e| e.to_string())?;
+    registry.register(Box::new(inventory_restock_errors.clone())).map_err(|e| e.to_string())?;
+
+    // Best selling ranking
+    let best_selling_ranking_total =
+      IntCounter::new("products_best_selling_ranking_total", "Total best selling ranking requests")
+        .map_err(|e| e.to_string())?;
+    registry.register(Box::new(best_selling_ranking_total.clone())).map_err(|e| e.to_string())?;
+
+    let best_selling_ranking_errors = IntCounter::new(
+      "products_best_selling_ranking_errors_total",
+      "Total failed best selling ranking requests",
+    )
+    .map_err(|e| e.to_string())?;
+    registry.register(Box::new(best_selling_ranking_errors.clone())).map_err(|e| e.to_string())?;
+
+    // Product events record
+    let product_events_record_total = IntCounter::new(
+      "products_product_events_record_total",
+      "Total product events record requests",
+    )
+    .map_err(|e| e.to_string())?;
+    registry.register(Box::new(product_events_record_total.clone())).map_err(|e| e.to_string())?;
+
+    let product_events_record_errors = IntCounter::new(
+      "products_product_events_record_errors_total",
+      "Total failed product events record requests",
+    )
+    .map_err(|e| e.to_string())?;
+    registry.register(Box::new(product_events_record_errors.clone())).map_err(|e| e.to_string())?;
+
+    // Related products
+    let related_products_total =
+      IntCounter::new("products_related_products_total", "Total related products requests")
+        .map_err(|e| e.to_string())?;
+    registry.register(Box::new(related_products_total.clone())).map_err(|e| e.to_string())?;
+
+    let related_products_errors = IntCounter::new(
+      "products_related_products_errors_total",
+      "Total failed related products requests",
+    )
+    .map_err(|e| e.to_string())?;
+    registry.register(Box::new(related_products_errors.clone())).map_err(|e| e.to_string())?;
+
     // Cache metrics
     let cache_hits = IntCounter::new("products_cache_hits_total", "Total cache hits")
       .map_err(|e| e.to_string())?;
@@ -241,6 +478,36 @@ impl MetricsCollector {
       product_snapshot_errors,
       product_data_total,
       product_data_errors,
+      product_update_total,
+      product_update_errors,
+      product_status_update_total,
+      product_status_update_errors,
+      product_media_upload_urls_total,
+      product_media_upload_urls_errors,
+      products_search_total,
+      products_search_errors,
+      product_review_create_total,
+      product_review_create_errors,
+      product_reviews_list_total,
+      product_reviews_list_errors,
+      product_review_moderate_total,
+      product_review_moderate_errors,
+      price_quote_total,
+      price_quote_errors,
+      inventory_commit_total,
+      inventory_commit_errors,
+      inventory_release_total,
+      inventory_release_errors,
+      inventory_reserve_total,
+      inventory_reserve_errors,
+      inventory_restock_total,
+      inventory_restock_errors,
+      best_selling_ranking_total,
+      best_selling_ranking_errors,
+      product_events_record_total,
+      product_events_record_errors,
+      related_products_total,
+      related_products_errors,
       cache_hits,
       cache_misses,
       db_query_duration_seconds,
@@ -368,6 +635,156 @@ impl MetricsCollector {
     self.product_data_errors.inc();
   }
 
+  pub fn record_product_update_success(&self, duration_secs: f64) {
+    self.product_update_total.inc();
+    self.request_duration_seconds.with_label_values(&[]).observe(duration_secs);
+  }
+
+  pub fn record_product_update_error(&self) {
+    self.product_update_total.inc();
+    self.product_update_errors.inc();
+  }
+
+  pub fn record_product_status_update_success(&self, duration_secs: f64) {
+    self.product_status_update_total.inc();
+    self.request_duration_seconds.with_label_values(&[]).observe(duration_secs);
+  }
+
+  pub fn record_product_status_update_error(&self) {
+    self.product_status_update_total.inc();
+    self.product_status_update_errors.inc();
+  }
+
+  pub fn record_product_media_upload_urls_success(&self, duration_secs: f64) {
+    self.product_media_upload_urls_total.inc();
+    self.request_duration_seconds.with_label_values(&[]).observe(duration_secs);
+  }
+
+  pub fn record_product_media_upload_urls_error(&self) {
+    self.product_media_upload_urls_total.inc();
+    self.product_media_upload_urls_errors.inc();
+  }
+
+  pub fn record_products_search_success(&self, duration_secs: f64) {
+    self.products_search_total.inc();
+    self.request_duration_seconds.with_label_values(&[]).observe(duration_secs);
+  }
+
+  pub fn record_products_search_error(&self) {
+    self.products_search_total.inc();
+    self.products_search_errors.inc();
+  }
+
+  pub fn record_product_review_create_success(&self, duration_secs: f64) {
+    self.product_review_create_total.inc();
+    self.request_duration_seconds.with_label_values(&[]).observe(duration_secs);
+  }
+
+  pub fn record_product_review_create_error(&self) {
+    self.product_review_create_total.inc();
+    self.product_review_create_errors.inc();
+  }
+
+  pub fn record_product_reviews_list_success(&self, duration_secs: f64) {
+    self.product_reviews_list_total.inc();
+    self.request_duration_seconds.with_label_values(&[]).observe(duration_secs);
+  }
+
+  pub fn record_product_reviews_list_error(&self) {
+    self.product_reviews_list_total.inc();
+    self.product_reviews_list_errors.inc();
+  }
+
+  pub fn record_product_review_moderate_success(&self, duration_secs: f64) {
+    self.product_review_moderate_total.inc();
+    self.request_duration_seconds.with_label_values(&[]).observe(duration_secs);
+  }
+
+  pub fn record_product_review_moderate_error(&self) {
+    self.product_review_moderate_total.inc();
+    self.product_review_moderate_errors.inc();
+  }
+
+  pub fn record_price_quote_success(&self, duration_secs: f64) {
+    self.price_quote_total.inc();
+    self.request_duration_seconds.with_label_values(&[]).observe(duration_secs);
+  }
+
+  pub fn record_price_quote_error(&self) {
+    self.price_quote_total.inc();
+    self.price_quote_errors.inc();
+  }
+
+  pub fn record_inventory_commit_success(&self, duration_secs: f64) {
+    self.inventory_commit_total.inc();
+    self.request_duration_seconds.with_label_values(&[]).observe(duration_secs);
+  }
+
+  pub fn record_inventory_commit_error(&self) {
+    self.inventory_commit_total.inc();
+    self.inventory_commit_errors.inc();
+  }
+
+  pub fn record_inventory_release_success(&self, duration_secs: f64) {
+    self.inventory_release_total.inc();
+    self.request_duration_seconds.with_label_values(&[]).observe(duration_secs);
+  }
+
+  pub fn record_inventory_release_error(&self) {
+    self.inventory_release_total.inc();
+    self.inventory_release_errors.inc();
+  }
+
+  pub fn record_inventory_reserve_success(&self, duration_secs: f64) {
+    self.inventory_reserve_total.inc();
+    self.request_duration_seconds.with_label_values(&[]).observe(duration_secs);
+  }
+
+  pub fn record_inventory_reserve_error(&self) {
+    self.inventory_reserve_total.inc();
+    self.inventory_reserve_errors.inc();
+  }
+
+  pub fn record_inventory_restock_success(&self, duration_secs: f64) {
+    self.inventory_restock_total.inc();
+    self.request_duration_seconds.with_label_values(&[]).observe(duration_secs);
+  }
+
+  pub fn record_inventory_restock_error(&self) {
+    self.inventory_restock_total.inc();
+    self.inventory_restock_errors.inc();
+  }
+
+  pub fn record_best_selling_ranking_success(&self, duration_secs: f64) {
+    self.best_selling_ranking_total.inc();
+    self.request_duration_seconds.with_label_values(&[]).observe(duration_secs);
+  }
+
+  pub fn record_best_selling_ranking_error(&self) {
+    self.best_selling_ranking_total.inc();
+    self.best_selling_ranking_errors.inc();
+  }
+
+  pub fn record_product_events_record_success(&self, duration_secs: f64) {
+    self.product_events_record_total.inc();
+    self.request_duration_seconds.with_label_values(&[]).observe(duration_secs);
+  }
+
+  pub fn record_product_events_record_error(&self) {
+    self.product_events_record_total.inc();
+    self.product_events_record_errors.inc();
+  }
+
+  pub fn record_related_products_success(&self, duration_secs: f64) {
+    self.related_products_total.inc();
+    self.request_duration_seconds.with_label_values(&[]).observe(duration_secs);
+  }
+
+  pub fn record_related_products_error(&self) {
+    self.related_products_total.inc();
+    self.related_products_errors.inc();
+  }
+
   pub fn record_cache_hit(&self) {
     self.cache_hits.inc();
   }
@@ -1,18 +1,33 @@
 mod audit;
 mod best_selling_products;
+mod best_selling_ranking;
 mod big_discount_products;
 mod category_navbar;
 mod helpers;
 mod hero_products;
+mod inventory_commit;
+mod inventory_release;
+mod inventory_reserve;
+mod inventory_restock;
 mod metrics;
 mod newly_added_products;
+mod price_quote;
 mod product_create;
 mod product_data;
 mod product_details;
+mod product_events_record;
+mod product_media_upload_urls;
+mod product_review_create;
+mod product_review_moderate;
+mod product_reviews_list;
 mod product_snapshot;
+mod product_status_update;
+mod product_update;
 mod products_category;
 mod products_list;
+mod products_search;
 mod products_to_like;
+mod related_products;
 mod router;
 
 use std::{error::Error, net::SocketAddr, sync::Arc};
@@ -25,41 +40,59 @@ use megacommerce_shared::{
   },
   utils::middleware::middleware_context,
 };
+use tokio::sync::mpsc;
 use tonic::{service::InterceptorLayer, transport::Server as GrpcServer};
 use tower::ServiceBuilder;
 
 use self::metrics::MetricsCollector;
 use crate::{
+  models::{config::ProductsConfig, product_events::ProductEvent},
   otel::init_otel,
   server::object_storage::ObjectStorage,
-  store::{cache::Cache, database::ProductsStore},
+  store::{
+    cache::Cache, database::ProductsStore, exchange_rates::ExchangeRatesCache,
+    recommender::ProductsRecommender,
+  },
   utils::net::validate_url_target,
 };
 
 #[derive(Debug)]
 pub struct Controller {
   pub(super) cfg: RLock<SharedConfig>,
+  pub(super) products_cfg: ProductsConfig,
   pub(super) cache: Arc<Cache>,
   pub(super) store: Arc<dyn ProductsStore + Send + Sync>,
   pub storage: RLock<ObjectStorage>,
+  pub(super) exchange_rates: Arc<ExchangeRatesCache>,
+  pub(super) recommender: Arc<dyn ProductsRecommender>,
+  /// The queue `ProductEventsWriter` writes the recorded events from
+  pub(super) product_events: mpsc::Sender<ProductEvent>,
   pub metrics: Arc<MetricsCollector>,
 }
 
 #[derive(Debug)]
 pub struct ControllerArgs {
   pub cfg: RLock<SharedConfig>,
+  pub products_cfg: ProductsConfig,
   pub storage: RLock<ObjectStorage>,
   pub cache: Arc<Cache>,
   pub store: Arc<dyn ProductsStore + Send + Sync>,
+  pub exchange_rates: Arc<ExchangeRatesCache>,
+  pub recommender: Arc<dyn ProductsRecommender>,
+  pub product_events: mpsc::Sender<ProductEvent>,
 }
 
 impl Controller {
   pub fn new(args: ControllerArgs) -> Controller {
     Controller {
       cfg: args.cfg,
+      products_cfg: args.products_cfg,
       cache: args.cache,
       store: args.store,
       storage: args.storage,
+      exchange_rates: args.exchange_rates,
+      recommender: args.recommender,
+      product_events: args.product_events,
       metrics: Arc::new(MetricsCollector::new(&prometheus::Registry::new()).unwrap()),
     }
   }
@@ -10,7 +10,10 @@ use megacommerce_shared::models::{
 };
 use tonic::{Code, Request, Response, Status};
 
-use crate::controller::Controller;
+use crate::controller::{
+  helpers::{display_currency, display_prices_fill},
+  Controller,
+};
 
 pub(super) async fn newly_added_products(
   c: &Controller,
@@ -28,19 +31,28 @@ pub(super) async fn newly_added_products(
     AppError::new(ctx.clone(), w, MSG_ERR_INTERNAL, None, "", Code::Internal.into(), errors)
   };
 
+  let currency = match display_currency(ctx.clone(), w, &request.get_ref().display_currency) {
+    Ok(currency) => currency,
+    Err(err) => {
+      c.metrics.record_newly_added_products_error();
+      return Ok(return_err(err));
+    }
+  };
+
   let products = c.store.newly_added_products(ctx.clone()).await;
   if products.is_err() {
     c.metrics.record_newly_added_products_error();
     return Ok(return_err(ie(Box::new(products.unwrap_err()))));
   }
 
+  let mut products = products.unwrap();
+  display_prices_fill(c, currency, &mut products);
+
   let duration = start.elapsed().as_secs_f64();
   c.metrics.record_newly_added_products_success(duration);
 
   Ok(Response::new(NewlyAddedProductsResponse {
-    response: Some(Data(megacommerce_proto::NewlyAddedProductsResponseData {
-      products: products.unwrap(),
-    })),
+    response: Some(Data(megacommerce_proto::NewlyAddedProductsResponseData { products })),
   }))
 }
 
@@ -0,0 +1,91 @@
+use std::{
+  io::{Error, ErrorKind},
+  sync::Arc,
+};
+
+use megacommerce_proto::{
+  price_quote_response::Response::{Data, Error as ResError},
+  PriceQuoteRequest, PriceQuoteResponse, PriceQuoteResponseData,
+};
+use megacommerce_shared::{
+  models::{
+    context::Context,
+    errors::{AppError, AppErrorErrors, BoxedErr, ErrorType, MSG_ID_ERR_INTERNAL},
+  },
+  utils::time::time_get_millis,
+};
+use tonic::{Code, Request, Response, Status};
+
+use crate::{
+  controller::{helpers::is_valid_ulid, Controller},
+  models::price_quote::price_quote as quote,
+};
+
+/// The unit price of a quantity of a variant, with its minimum-order tier and sale applied,
+/// and whether the quantity is in stock. It's what the cart and checkout charge.
+pub(super) async fn price_quote(
+  c: &Controller,
+  req: Request<PriceQuoteRequest>,
+) -> Result<Response<PriceQuoteResponse>, Status> {
+  let start = std::time::Instant::now();
+  c.metrics.price_quote_total.inc();
+
+  let path = "products.controller.price_quote";
+  let ctx = req.extensions().get::<Arc<Context>>().cloned().unwrap();
+  let req = req.into_inner();
+
+  let return_err = |e: AppError| {
+    c.metrics.record_price_quote_error();
+    Response::new(PriceQuoteResponse { response: Some(ResError(e.to_proto())) })
+  };
+  let mk_err = |err: BoxedErr, id: &str, code: Code| {
+    let errors = Some(AppErrorErrors { err: Some(err), ..Default::default() });
+    return_err(AppError::new(ctx.clone(), path, id, None, "", code.into(), errors))
+  };
+  let not_found = |err: BoxedErr| mk_err(err, "products.not_found.error", Code::NotFound);
+
+  if !is_valid_ulid(&req.product_id) || req.variant_id.is_empty() {
+    return Ok(not_found(Box::new(Error::new(ErrorKind::NotFound, "the variant is not found"))));
+  }
+  if req.quantity == 0 {
+    let err = Box::new(Error::new(ErrorKind::InvalidInput, "the quantity must be at least 1"));
+    return Ok(mk_err(err, "products.price_quote.quantity.invalid", Code::InvalidArgument));
+  }
+
+  let stock = c.store.product_variant_stock(ctx.clone(), &req.product_id, &req.variant_id).await;
+  let stock = match stock {
+    Ok(stock) => stock,
+    Err(err) => match err.err_type {
+      ErrorType::NoRows => return Ok(not_found(Box::new(err))),
+      _ => return Ok(return_err(err.to_app_error_internal(ctx.clone(), path.into()))),
+    },
+  };
+
+  let quoted_at = time_get_millis();
+  let quoted = match quote(&stock, req.quantity, quoted_at) {
+    Ok(quoted) => quoted,
+    Err(err) => return Ok(mk_err(Box::new(err), MSG_ID_ERR_INTERNAL, Code::Internal)),
+  };
+
+  let duration = start.elapsed().as_secs_f64();
+  c.metrics.record_price_quote_success(duration);
+
+  let available = stock.available();
+  Ok(Response::new(PriceQuoteResponse {
+    response: Some(Data(PriceQuoteResponseData {
+      product_id: req.product_id,
+      variant_id: req.variant_id,
+      quantity: req.quantity,
+      currency_code: stock.currency_code,
+      price_cents: quoted.prices.price.minor_units(),
+      sale_price_cents: quoted.prices.sale_price.map(|p| p.minor_units()),
+      tier_quantity: quoted.tier.map(|(quantity, _)| quantity),
+      tier_price_cents: quoted.tier.map(|(_, price)| price.minor_units()),
+      unit_price_cents: quoted.unit_price.minor_units(),
+      total_cents: quoted.total.minor_units(),
+      available_quantity: available,
+      in_stock: available >= req.quantity as i64,
+      quoted_at,
+    })),
+  }))
+}
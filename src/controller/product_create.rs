@@ -1,31 +1,49 @@
-use std::{collections::HashMap, str::FromStr, sync::Arc};
+use std::{
+  collections::HashMap,
+  io::{Error, ErrorKind},
+  str::FromStr,
+  sync::Arc,
+  time::Duration,
+};
 
+use futures::{stream, StreamExt};
 use megacommerce_proto::{
+  product_create_request_media::Media::{
+    WithVariants as MediaWithVariants, WithoutVariants as MediaNoVariants,
+  },
   product_create_response::Response::{Data as ResData, Error as ResError},
-  ProductCreateRequest, ProductCreateResponse, ProductMedia, ProductMediaImage,
-  ProductMediaVariant, ProductMediaVideo, SuccessResponseData,
+  Attachment, Config, ProductCreateRequest, ProductCreateRequestMedia, ProductCreateResponse,
+  ProductMedia, ProductMediaImage, ProductMediaImageDerivative, ProductMediaVariant,
+  ProductMediaVideo, SuccessResponseData,
 };
 use megacommerce_shared::models::{
   context::Context,
-  errors::{AppError, AppErrorErrors, BoxedErr, MSG_ID_ERR_INTERNAL},
+  errors::{AppError, AppErrorError, AppErrorErrors, BoxedErr},
+  files::UnitSizeType,
   images::ImageValidationResult,
   r_lock::RLock,
   translate::tr,
 };
-use tokio::spawn;
+use serde_json::Value;
+use tokio::{spawn, task::spawn_blocking, time::timeout};
 use tonic::Code;
 use tonic::{Request, Response, Status};
 use ulid::Ulid;
 
 use crate::{
-  controller::{audit::process_audit, Controller},
+  controller::{audit::process_audit, helpers::is_valid_ulid, Controller},
   models::{
     audit::{AuditRecord, EventName::ProductCreate, EventParameterKey, EventStatus::Fail},
+    config::ProductsConfig,
     product_create::{
       products_create_auditable_v1, products_create_is_valid, products_create_pre_save,
+      ProductCreateIsValidResult, VideoValidationResult,
     },
+    products::{product_media_staging_prefix, ProductCreateStepsNames},
+    products_facets::product_attribute_values,
   },
   server::object_storage::ObjectStorage,
+  utils::{images::image_derivatives, video::video_container},
 };
 
 pub(super) async fn product_create(
@@ -41,7 +59,7 @@ pub(super) async fn product_create(
   let lang = ctx.accept_language();
 
   let mut audit = AuditRecord::new(ctx.clone(), ProductCreate, Fail);
-  let pro = req.into_inner();
+  let mut pro = req.into_inner();
 
   let return_err = |e: AppError| {
     c.metrics.record_product_create_error();
@@ -51,10 +69,20 @@ pub(super) async fn product_create(
   let pro_clone = pro.clone();
   let audit_data_future = spawn(async move { products_create_auditable_v1(&pro_clone) });
 
+  let (product_id, staged, mut staged_data) =
+    match media_staged_resolve(ctx.clone(), c, &cfg, &pro).await {
+      Ok(staged) => staged,
+      Err(err) => return Ok(return_err(err)),
+    };
+  // an empty id (none sent, or one that wasn't reserved for this user) gets a new one
+  pro.product_id = product_id;
+
   let identity = pro.identity.clone().unwrap_or_default();
   let sub = c.cache.subcategory_data(&identity.category, &identity.subcategory, lang);
+  let sub_data = sub.as_ref().and_then(|s| s.data.clone()).unwrap_or_default();
 
-  let is_valid = products_create_is_valid(ctx.clone(), &pro, sub, &cfg);
+  let is_valid =
+    products_create_is_valid(ctx.clone(), &pro, sub, &cfg, &c.products_cfg, &mut staged_data);
   if is_valid.is_err() {
     return Ok(return_err(is_valid.unwrap_err()));
   }
@@ -67,25 +95,42 @@ pub(super) async fn product_create(
   let is_valid = is_valid.unwrap();
   let pro_db = &mut pro_db.unwrap();
 
-  let media_upload = upload_media(
-    ctx.clone(),
-    &c.storage,
-    is_valid.media_validation_results_with_variants,
-    is_valid.media_validation_results_no_variants,
-    &pro_db.variants_ids,
-    &pro_db.main_variant_key,
-  )
-  .await;
+  let uploads = media_uploads_plan(is_valid, &pro_db.variants_ids, &pro_db.main_variant_key);
+
+  let uploads = match media_derivatives_build(ctx.clone(), uploads, &c.products_cfg).await {
+    Ok(uploads) => uploads,
+    Err(err) => return Ok(return_err(err)),
+  };
+
+  // record the keys before uploading anything, so the sweeper can remove the objects
+  // if the process dies before the product is inserted or the compensation runs
+  let keys: Vec<String> = uploads.iter().flat_map(|u| u.keys().cloned()).collect();
+  let recorded = c.store.product_media_uploads_add(ctx.clone(), &pro_db.product.id, &keys).await;
+  if let Err(err) = recorded {
+    return Ok(return_err(err.to_app_error_internal(ctx.clone(), path.into())));
+  }
+
+  let media_upload = upload_media(ctx.clone(), &c.storage, uploads, &c.products_cfg).await;
   if media_upload.is_err() {
-    // TODO: consider removing the inserted product and all its related data
+    media_compensate(ctx.clone(), c, &pro_db.product.id, &keys).await;
     return Ok(return_err(media_upload.unwrap_err()));
   }
 
   pro_db.product.media = Some(media_upload.unwrap());
-  if let Err(err) = c.store.product_create(ctx.clone(), &pro_db.product).await {
+  let attributes = match pro_db.product.details.as_ref() {
+    Some(details) => product_attribute_values(details, &sub_data),
+    None => vec![],
+  };
+  if let Err(err) = c.store.product_create(ctx.clone(), &pro_db.product, &attributes).await {
+    media_compensate(ctx.clone(), c, &pro_db.product.id, &keys).await;
     return Ok(return_err(err.to_app_error_internal(ctx.clone(), path.into())));
   }
 
+  // the staged objects were copied under their content addressed keys, release them
+  if staged.len() > 0 {
+    media_compensate(ctx.clone(), c, &pro_db.product.id, &staged).await;
+  }
+
   let audit_data = audit_data_future.await.unwrap_or_default();
   audit.set_event_parameter(EventParameterKey::ProductCreate, audit_data);
   audit.success();
@@ -104,81 +149,580 @@ pub(super) async fn product_create(
   }))
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MediaKind {
+  Image,
+  Video { duration_ms: u64 },
+}
+
+/// A single object to upload, keyed by the product's variant it belongs to
+struct MediaUpload {
+  kind: MediaKind,
+  /// The variant id as sent by the client, `None` for products without variants
+  form_id: Option<String>,
+  attachment_id: String,
+  variant_id: String,
+  media_id: String,
+  /// The SHA-256 of the uploaded attachment, which is also its (content addressed) key
+  checksum: String,
+  key: String,
+  mime_type: String,
+  size: u64,
+  data: Vec<u8>,
+  derivatives: Vec<MediaDerivative>,
+}
+
+/// A scaled down copy of an uploaded image, stored next to it as `<key>_<name>`
+struct MediaDerivative {
+  name: String,
+  key: String,
+  width: u32,
+  height: u32,
+  size: u64,
+  data: Vec<u8>,
+}
+
+impl MediaUpload {
+  /// Every storage key this upload writes to
+  fn keys(&self) -> impl Iterator<Item = &String> {
+    std::iter::once(&self.key).chain(self.derivatives.iter().map(|d| &d.key))
+  }
+
+  fn field_key(&self) -> String {
+    media_field_key(self.form_id.as_deref(), &self.attachment_id)
+  }
+}
+
+/// The field the errors of a media attachment are reported on: media.<variant>.<attachment_id>
+fn media_field_key(form_id: Option<&str>, attachment_id: &str) -> String {
+  let step = ProductCreateStepsNames::Media.as_str();
+  match form_id {
+    Some(fid) => format!("{}.{}.{}", step, fid, attachment_id),
+    None => format!("{}.{}", step, attachment_id),
+  }
+}
+
+/// How many leading bytes of a staged object are fetched to check its type
+const STAGED_SNIFF_BYTES: u64 = 64;
+
+/// A media attachment that references an object uploaded with a presigned url
+struct StagedAttachment<'a> {
+  attachment: &'a Attachment,
+  form_id: Option<String>,
+  video: bool,
+}
+
+fn staged_attachments(media: &Option<ProductCreateRequestMedia>) -> Vec<StagedAttachment<'_>> {
+  let mut staged = vec![];
+  let form = match media.as_ref().and_then(|m| m.media.as_ref()) {
+    Some(form) => form,
+    None => return staged,
+  };
+
+  let referenced = |a: &&Attachment| !a.key.is_empty();
+  match form {
+    MediaWithVariants(m) => {
+      for (form_id, images) in m.images.iter() {
+        for attachment in images.attachments.iter().filter(referenced) {
+          let form_id = Some(form_id.clone());
+          staged.push(StagedAttachment { attachment, form_id, video: false });
+        }
+      }
+      for (form_id, videos) in m.videos.iter() {
+        for attachment in videos.attachments.iter().filter(referenced) {
+          let form_id = Some(form_id.clone());
+          staged.push(StagedAttachment { attachment, form_id, video: true });
+        }
+      }
+    }
+    MediaNoVariants(m) => {
+      for attachment in m.images.iter().filter(referenced) {
+        staged.push(StagedAttachment { attachment, form_id: None, video: false });
+      }
+      for attachment in m.videos.iter().filter(referenced) {
+        staged.push(StagedAttachment { attachment, form_id: None, video: true });
+      }
+    }
+  }
+  staged
+}
+
+/// The error of a staged object: the field error, and the storage error if it wasn't the object
+type StagedError = (AppErrorError, Option<BoxedErr>);
+
+/// Downloads the attachments that reference a presigned upload (by `key`), so they go
+/// through the same validation and processing as the inline ones. The bytes are returned
+/// by key and validated as they are, they're never base64 encoded into the request.
+/// Every object is checked with a HEAD (existence, size) and a ranged download of its
+/// first bytes (type) first, and nothing is downloaded unless all of them are valid and
+/// fit in `media_staged_max_total_mb` together, so a wrong object is rejected cheaply.
+/// A product id chosen by the client is only kept if `product_media_upload_urls` reserved it
+/// for this user, otherwise it's dropped and the product gets a new one.
+/// Returns the product id (empty for a new one), the staged keys, which are released once
+/// the product is created, and their bytes.
+async fn media_staged_resolve(
+  ctx: Arc<Context>,
+  c: &Controller,
+  cfg: &Config,
+  pro: &ProductCreateRequest,
+) -> Result<(String, Vec<String>, HashMap<String, Vec<u8>>), AppError> {
+  let path = "products.controller.media_staged_resolve";
+  let invalid = |id: &str, msg: &str, errors: HashMap<String, AppErrorError>| {
+    let err = Box::new(Error::new(ErrorKind::InvalidInput, msg.to_string())) as BoxedErr;
+    media_error(ctx.clone(), path, id, msg, Code::InvalidArgument, errors, Some(err))
+  };
+
+  if !pro.product_id.is_empty() && !is_valid_ulid(&pro.product_id) {
+    return Err(invalid("products.id.invalid", "invalid product id", HashMap::new()));
+  }
+
+  // only the objects staged for this user and this product can be referenced
+  let mut product_id = pro.product_id.clone();
+  let prefix = product_media_staging_prefix(ctx.session().user_id(), &product_id);
+  if !product_id.is_empty() {
+    let reserved = c
+      .store
+      .product_media_uploads_reserved(ctx.clone(), &product_id, &prefix)
+      .await
+      .map_err(|err| err.to_app_error_internal(ctx.clone(), path.into()))?;
+    if !reserved {
+      product_id.clear();
+    }
+  }
+
+  let staged = staged_attachments(&pro.media);
+  if staged.is_empty() {
+    return Ok((product_id, vec![], HashMap::new()));
+  }
+
+  let keys: Vec<String> = staged.iter().map(|s| s.attachment.key.clone()).collect();
+  let recorded = c
+    .store
+    .product_media_uploads_recorded(ctx.clone(), &product_id, &keys)
+    .await
+    .map_err(|err| err.to_app_error_internal(ctx.clone(), path.into()))?;
+
+  let mut errors: HashMap<String, AppErrorError> = HashMap::new();
+  for s in staged.iter() {
+    let key = &s.attachment.key;
+    if product_id.is_empty() || !key.starts_with(&prefix) || !recorded.contains(key) {
+      let err = AppErrorError { id: "attachment.upload.missing".into(), params: None };
+      errors.insert(media_field_key(s.form_id.as_deref(), &s.attachment.id), err);
+    }
+  }
+  if errors.len() > 0 {
+    return Err(invalid("products.media.staged.invalid", "unknown staged uploads", errors));
+  }
+
+  let products = cfg.products.as_ref().unwrap();
+  let storage = &*c.storage.get().await;
+  let field_err =
+    |id: &str| -> StagedError { (AppErrorError { id: id.into(), params: None }, None) };
+  let failed =
+    |err: BoxedErr| -> StagedError { (field_err("products.media.staged.failed").0, Some(err)) };
+  let size_err = |id: &str, max_mb: u64| {
+    let params = Some(HashMap::from([
+      ("Max".into(), Value::Number(max_mb.into())),
+      ("Unit".into(), Value::String(UnitSizeType::MB.as_str().to_string())),
+    ]));
+    AppErrorError { id: id.into(), params }
+  };
+
+  let probe = |key: String, video: bool| async move {
+    let (max_mb, size_id) = match video {
+      true => (c.products_cfg.video_max_size_mb, "video.max_size.error"),
+      false => (products.product_image_max_size_mb as u64, "image.max_size.error"),
+    };
+
+    let head = match storage.file_head(&key).await {
+      Ok(Some(head)) => head,
+      Ok(None) => return Err(field_err("attachment.upload.missing")),
+      Err(err) => return Err(failed(err)),
+    };
+    if head.size > max_mb * 1024 * 1024 {
+      return Err((size_err(size_id, max_mb), None));
+    }
+
+    let end = STAGED_SNIFF_BYTES.min(head.size).saturating_sub(1);
+    let sniff = storage.download_file_range(&key, 0, end).await.map_err(failed)?;
+    let accepted = match video {
+      true => video_container(&sniff)
+        .map(|f| c.products_cfg.video_accepted_formats.iter().any(|a| a == f.to_mime_type()))
+        .unwrap_or(false),
+      false => image::guess_format(&sniff)
+        .map(|f| products.product_image_accepted_formats.iter().any(|a| a == f.to_mime_type()))
+        .unwrap_or(false),
+    };
+    if !accepted {
+      let id = if video { "video.type.unsupported" } else { "image.type.unsupported" };
+      return Err(field_err(id));
+    }
+    Ok(head.size)
+  };
+
+  let concurrency = c.products_cfg.media_upload_concurrency.max(1);
+  let results: Vec<_> = stream::iter(staged.iter().enumerate())
+    .map(|(i, s)| {
+      let probed = probe(s.attachment.key.clone(), s.video);
+      async move { (i, probed.await) }
+    })
+    .buffer_unordered(concurrency)
+    .collect()
+    .await;
+  let sizes = staged_results(ctx.clone(), path, &staged, results)?;
+
+  let max_total_mb = c.products_cfg.media_staged_max_total_mb;
+  if sizes.values().sum::<u64>() > max_total_mb * 1024 * 1024 {
+    let errors = HashMap::from([(
+      ProductCreateStepsNames::Media.as_str().to_string(),
+      size_err("products.media.staged.max_total_size", max_total_mb),
+    )]);
+    let msg = "the staged uploads are too large";
+    return Err(invalid("products.media.staged.too_large", msg, errors));
+  }
+
+  let results: Vec<_> = stream::iter(staged.iter().enumerate())
+    .map(|(i, s)| {
+      let key = s.attachment.key.clone();
+      async move { (i, storage.download_file(&key).await.map_err(failed)) }
+    })
+    .buffer_unordered(concurrency)
+    .collect()
+    .await;
+  let mut data = staged_results(ctx.clone(), path, &staged, results)?;
+
+  // the key takes precedence over the inline (base64) data
+  let data_by_key = staged
+    .iter()
+    .enumerate()
+    .filter_map(|(i, s)| Some((s.attachment.key.clone(), data.remove(&i)?)))
+    .collect();
+
+  Ok((product_id, keys, data_by_key))
+}
+
+/// Collects the results of a step over the staged attachments by their index, or the errors
+/// of the failed ones by their field. A storage failure is reported as unavailable,
+/// the objects themselves being wrong as invalid.
+fn staged_results<T>(
+  ctx: Arc<Context>,
+  path: &str,
+  staged: &[StagedAttachment<'_>],
+  results: Vec<(usize, Result<T, StagedError>)>,
+) -> Result<HashMap<usize, T>, AppError> {
+  let mut values: HashMap<usize, T> = HashMap::new();
+  let mut errors: HashMap<String, AppErrorError> = HashMap::new();
+  let mut last_err: Option<BoxedErr> = None;
+  for (i, result) in results.into_iter() {
+    let s = &staged[i];
+    match result {
+      Ok(value) => {
+        values.insert(i, value);
+      }
+      Err((err, boxed)) => {
+        errors.insert(media_field_key(s.form_id.as_deref(), &s.attachment.id), err);
+        last_err = boxed.or(last_err);
+      }
+    }
+  }
+
+  if errors.is_empty() {
+    return Ok(values);
+  }
+  Err(match last_err {
+    Some(err) => {
+      let msg = "failed to load some of the staged uploads";
+      let id = "products.media.staged.failed";
+      media_error(ctx, path, id, msg, Code::Unavailable, errors, Some(err))
+    }
+    None => {
+      let msg = "invalid staged uploads";
+      let err = Box::new(Error::new(ErrorKind::InvalidInput, msg)) as BoxedErr;
+      let id = "products.media.staged.invalid";
+      media_error(ctx, path, id, msg, Code::InvalidArgument, errors, Some(err))
+    }
+  })
+}
+
+/// Assigns the storage key and the media id of every validated image and video up front,
+/// so the keys can be recorded as pending uploads before anything is sent.
+/// Keys are the verified checksums, so identical files are stored once in the bucket.
+fn media_uploads_plan(
+  media: ProductCreateIsValidResult,
+  variants_ids: &HashMap<String, String>,
+  main_variant_key: &String,
+) -> Vec<MediaUpload> {
+  let path = "products.controller.media_uploads_plan";
+  let db_variant_id = |form_id: Option<&String>| match form_id {
+    Some(variant) => {
+      variants_ids.get(variant).expect(&format!("{}: the variant id is not found!", path)).clone()
+    }
+    None => variants_ids
+      .get(main_variant_key)
+      .expect(&format!("{}: the main variant id is not found!", path))
+      .clone(),
+  };
+  let checksum = |attachment_id: &String| {
+    let msg = format!("{}: the attachment checksum is not found!", path);
+    media.media_checksums.get(attachment_id).expect(&msg).clone()
+  };
+  let image = |form_id: Option<&String>, attachment_id: String, result: ImageValidationResult| {
+    MediaUpload {
+      kind: MediaKind::Image,
+      form_id: form_id.cloned(),
+      attachment_id,
+      variant_id: db_variant_id(form_id),
+      media_id: Ulid::new().to_string(),
+      checksum: checksum(&attachment_id),
+      key: checksum(&attachment_id),
+      mime_type: String::from_str(result.format.to_mime_type()).unwrap(),
+      size: result.size_bytes as u64,
+      data: result.decoded_data,
+      derivatives: vec![],
+    }
+  };
+  let video = |form_id: Option<&String>, attachment_id: String, result: VideoValidationResult| {
+    MediaUpload {
+      kind: MediaKind::Video { duration_ms: result.info.duration_ms },
+      form_id: form_id.cloned(),
+      attachment_id,
+      variant_id: db_variant_id(form_id),
+      media_id: Ulid::new().to_string(),
+      checksum: checksum(&attachment_id),
+      key: checksum(&attachment_id),
+      mime_type: result.info.container.to_mime_type().to_string(),
+      size: result.size_bytes as u64,
+      data: result.decoded_data,
+      derivatives: vec![],
+    }
+  };
+
+  // only one of the with/without variants results is populated
+  let mut uploads = vec![];
+  for (variant, results) in media.media_validation_results_with_variants.into_iter() {
+    for (attachment_id, result) in results.into_iter() {
+      uploads.push(image(Some(&variant), attachment_id, result));
+    }
+  }
+  for (attachment_id, result) in media.media_validation_results_no_variants.into_iter() {
+    uploads.push(image(None, attachment_id, result));
+  }
+  for (variant, results) in media.media_videos_validation_results_with_variants.into_iter() {
+    for (attachment_id, result) in results.into_iter() {
+      uploads.push(video(Some(&variant), attachment_id, result));
+    }
+  }
+  for (attachment_id, result) in media.media_videos_validation_results_no_variants.into_iter() {
+    uploads.push(video(None, attachment_id, result));
+  }
+
+  uploads
+}
+
+/// Re-encodes every image (JPEG, or WebP when it has transparency), which also strips
+/// its EXIF/GPS metadata, and builds the configured derivatives. The work is CPU bound so it
+/// runs on the blocking pool. Videos are passed through as they are.
+async fn media_derivatives_build(
+  ctx: Arc<Context>,
+  uploads: Vec<MediaUpload>,
+  cfg: &ProductsConfig,
+) -> Result<Vec<MediaUpload>, AppError> {
+  let (images, videos): (Vec<MediaUpload>, Vec<MediaUpload>) =
+    uploads.into_iter().partition(|u| u.kind == MediaKind::Image);
+
+  let specs = Arc::new(cfg.image_derivatives.clone());
+  let quality = cfg.image_jpeg_quality;
+  let build = |mut upload: MediaUpload| {
+    let specs = specs.clone();
+    async move {
+      let data = std::mem::take(&mut upload.data);
+      let result = spawn_blocking(move || image_derivatives(&data, &specs, quality)).await;
+      (upload, result)
+    }
+  };
+
+  let results: Vec<_> = stream::iter(images)
+    .map(build)
+    .buffer_unordered(cfg.media_upload_concurrency.max(1))
+    .collect()
+    .await;
+
+  let mut processed: Vec<MediaUpload> = videos;
+  let mut errors: HashMap<String, AppErrorError> = HashMap::new();
+  let mut last_err: Option<BoxedErr> = None;
+  for (mut upload, result) in results.into_iter() {
+    let built = match result {
+      Ok(Ok(built)) => built,
+      Ok(Err(err)) => {
+        media_field_error(&mut errors, &upload, "products.media.process.failed");
+        last_err = Some(Box::new(err) as BoxedErr);
+        continue;
+      }
+      Err(err) => {
+        media_field_error(&mut errors, &upload, "products.media.process.failed");
+        last_err = Some(Box::new(err) as BoxedErr);
+        continue;
+      }
+    };
+
+    upload.mime_type = built.original.mime_type.to_string();
+    upload.size = built.original.data.len() as u64;
+    upload.data = built.original.data;
+    upload.derivatives = built
+      .derivatives
+      .into_iter()
+      .map(|(spec, img)| MediaDerivative {
+        key: spec.derivative_key(&upload.key, quality),
+        name: spec.name,
+        width: img.width,
+        height: img.height,
+        size: img.data.len() as u64,
+        data: img.data,
+      })
+      .collect();
+    processed.push(upload);
+  }
+
+  if errors.len() > 0 {
+    let path = "products.controller.media_derivatives_build";
+    let msg = "failed to process some of the product's images";
+    let id = "products.media.process.failed";
+    return Err(media_error(ctx, path, id, msg, Code::Internal, errors, last_err));
+  }
+
+  Ok(processed)
+}
+
 // TODO:
 // consider caching the uploading status in redis
-// consider uploading the videos
 async fn upload_media(
   ctx: Arc<Context>,
   uploader: &RLock<ObjectStorage>,
-  variants: HashMap<String, HashMap<String, ImageValidationResult>>,
-  no_variants: HashMap<String, ImageValidationResult>,
-  variants_ids: &HashMap<String, String>,
-  main_variant_key: &String,
+  uploads: Vec<MediaUpload>,
+  cfg: &ProductsConfig,
 ) -> Result<ProductMedia, AppError> {
   let mut media = ProductMedia { media: HashMap::new() };
 
-  let uploader = uploader.get().await;
-  let path = "products.controller.upload_media".to_string();
-  let ie = |err: BoxedErr, msg: &str| {
-    AppError::new(
-      ctx.clone(),
-      path.clone(),
-      MSG_ID_ERR_INTERNAL,
-      None,
-      msg,
-      Code::Internal.into(),
-      Some(AppErrorErrors { err: Some(err), ..Default::default() }),
-    )
+  let uploader = &*uploader.get().await;
+  let upload_timeout = Duration::from_secs(cfg.media_upload_timeout_secs);
+  let upload = move |mut upload: MediaUpload| async move {
+    let mut objects = vec![(upload.key.clone(), std::mem::take(&mut upload.data))];
+    for derivative in upload.derivatives.iter_mut() {
+      objects.push((derivative.key.clone(), std::mem::take(&mut derivative.data)));
+    }
+
+    let mut result: Result<(), BoxedErr> = Ok(());
+    for (key, data) in objects.into_iter() {
+      // the same content is already in the bucket (e.g. an image shared between products)
+      if uploader.file_exists(&key).await.unwrap_or(false) {
+        continue;
+      }
+      let sent = timeout(upload_timeout, uploader.upload_file(&key, data, &upload.mime_type));
+      result = match sent.await {
+        Ok(result) => result,
+        Err(elapsed) => Err(Box::new(elapsed) as BoxedErr),
+      };
+      if result.is_err() {
+        break;
+      }
+    }
+    (upload, result)
   };
 
-  if variants.len() > 0 {
-    for variant in variants.iter() {
-      let db_var_id =
-        variants_ids.get(variant.0).expect(&format!("{}: the variant id is not found!", path));
-
-      let mut images: HashMap<String, ProductMediaImage> = HashMap::new();
-      let videos: HashMap<String, ProductMediaVideo> = HashMap::new();
-      for (_, result) in variant.1.iter() {
-        let id = Ulid::new().to_string();
-        let image_id = Ulid::new().to_string();
-        let _ = uploader
-          .upload_file(&id, result.decoded_data.clone(), result.format.to_mime_type())
-          .await
-          .map_err(|err| ie(err, "failed to update an image"))?;
-        images.insert(
-          image_id,
-          ProductMediaImage {
-            url: id,
-            format: String::from_str(result.format.to_mime_type()).unwrap(),
-            size: result.size_bytes as u64,
-          },
-        );
+  let results: Vec<(MediaUpload, Result<(), BoxedErr>)> = stream::iter(uploads)
+    .map(upload)
+    .buffer_unordered(cfg.media_upload_concurrency.max(1))
+    .collect()
+    .await;
+
+  let mut errors: HashMap<String, AppErrorError> = HashMap::new();
+  let mut last_err: Option<BoxedErr> = None;
+  for (upload, result) in results.into_iter() {
+    if let Err(err) = result {
+      media_field_error(&mut errors, &upload, "products.media.upload.failed");
+      last_err = Some(err);
+      continue;
+    }
+
+    let variant = media
+      .media
+      .entry(upload.variant_id)
+      .or_insert_with(|| ProductMediaVariant { images: HashMap::new(), videos: HashMap::new() });
+
+    match upload.kind {
+      MediaKind::Image => {
+        let derivatives = upload
+          .derivatives
+          .into_iter()
+          .map(|d| {
+            let derivative = ProductMediaImageDerivative {
+              url: d.key,
+              format: upload.mime_type.clone(),
+              size: d.size,
+              width: d.width,
+              height: d.height,
+            };
+            (d.name, derivative)
+          })
+          .collect();
+        let image = ProductMediaImage {
+          url: upload.key,
+          format: upload.mime_type,
+          size: upload.size,
+          checksum: upload.checksum,
+          derivatives,
+        };
+        variant.images.insert(upload.media_id, image);
       }
-      media.media.insert(db_var_id.to_string(), ProductMediaVariant { images, videos });
-    }
-  } else {
-    let mut images: HashMap<String, ProductMediaImage> = HashMap::new();
-    let videos: HashMap<String, ProductMediaVideo> = HashMap::new();
-    for (_, result) in no_variants.iter() {
-      let id = Ulid::new().to_string();
-      let _ = uploader
-        .upload_file(&id, result.decoded_data.clone(), result.format.to_mime_type())
-        .await
-        .map_err(|err| ie(err, "failed to update an image"))?;
-      images.insert(
-        Ulid::new().to_string(),
-        ProductMediaImage {
-          format: String::from_str(result.format.to_mime_type()).unwrap(),
-          url: id,
-          size: result.size_bytes as u64,
-        },
-      );
-    }
-    let db_var_id = variants_ids
-      .get(main_variant_key)
-      .expect(&format!("{}: the main variant id is not found!", path));
-    media.media.insert(db_var_id.to_string(), ProductMediaVariant { images, videos });
+      MediaKind::Video { duration_ms } => {
+        let video = ProductMediaVideo {
+          url: upload.key,
+          format: upload.mime_type,
+          size: upload.size,
+          checksum: upload.checksum,
+          duration_ms,
+        };
+        variant.videos.insert(upload.media_id, video);
+      }
+    }
+  }
+
+  if errors.len() > 0 {
+    let path = "products.controller.upload_media";
+    let msg = "failed to upload some of the product's media";
+    let id = "products.media.upload.failed";
+    return Err(media_error(ctx, path, id, msg, Code::Unavailable, errors, last_err));
   }
 
   Ok(media)
 }
+
+fn media_field_error(
+  errors: &mut HashMap<String, AppErrorError>,
+  upload: &MediaUpload,
+  id: &str,
+) {
+  errors.insert(upload.field_key(), AppErrorError { id: id.into(), params: None });
+}
+
+fn media_error(
+  ctx: Arc<Context>,
+  path: &str,
+  id: &str,
+  msg: &str,
+  code: Code,
+  errors: HashMap<String, AppErrorError>,
+  err: Option<BoxedErr>,
+) -> AppError {
+  let errors = Some(AppErrorErrors { err, errors_internal: Some(errors), ..Default::default() });
+  AppError::new(ctx, path, id, None, msg, code.into(), errors)
+}
+
+/// Undoes the uploads of a failed creation. Deleting a missing object is a no-op,
+/// so every planned key that no other product references is removed, including the ones
+/// whose upload never completed. The keys that fail to be deleted stay in the pending
+/// uploads for the sweeper.
+async fn media_compensate(ctx: Arc<Context>, c: &Controller, product_id: &str, keys: &[String]) {
+  let storage = c.storage.get().await;
+  let _ = c.store.product_media_uploads_release(ctx, product_id, keys, &*storage).await;
+}
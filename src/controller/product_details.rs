@@ -13,7 +13,10 @@ use megacommerce_shared::models::{
 };
 use tonic::{Code, Request, Response, Status};
 
-use crate::controller::{helpers::is_valid_ulid, Controller};
+use crate::controller::{
+  helpers::{details_display_prices_fill, display_currency, is_valid_ulid, sellers_fill},
+  Controller,
+};
 
 pub async fn product_details(
   c: &Controller,
@@ -46,6 +49,14 @@ pub async fn product_details(
     return Ok(not_found(None));
   }
 
+  let currency = match display_currency(ctx.clone(), path, &req.display_currency) {
+    Ok(currency) => currency,
+    Err(err) => {
+      c.metrics.record_product_details_error();
+      return Ok(return_err(err));
+    }
+  };
+
   let product = c.store.product_details(ctx.clone(), &req.product_id).await;
   if product.is_err() {
     c.metrics.record_product_details_error();
@@ -56,7 +67,14 @@ pub async fn product_details(
     }
   }
 
+  let mut product = product.unwrap();
+  if let Err(err) = sellers_fill(c, ctx.clone(), std::slice::from_mut(&mut product)).await {
+    c.metrics.record_product_details_error();
+    return Ok(return_err(ie(Box::new(err), MSG_ID_ERR_INTERNAL, None)));
+  }
+  details_display_prices_fill(c, currency, &mut product);
+
   let duration = start.elapsed().as_secs_f64();
   c.metrics.record_product_details_success(duration);
-  Ok(Response::new(ProductDetailsResponse { response: Some(Data(product.unwrap())) }))
+  Ok(Response::new(ProductDetailsResponse { response: Some(Data(product)) }))
 }
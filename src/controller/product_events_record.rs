@@ -0,0 +1,90 @@
+use std::{
+  io::{Error, ErrorKind},
+  sync::Arc,
+};
+
+use megacommerce_proto::{
+  product_events_record_response::Response::{Data, Error as ResError},
+  ProductEventsRecordRequest, ProductEventsRecordResponse, ProductEventsRecordResponseData,
+};
+use megacommerce_shared::{
+  models::{
+    context::Context,
+    errors::{AppError, AppErrorErrors, BoxedErr, MSG_ID_ERR_INTERNAL},
+  },
+  utils::time::time_get_millis,
+};
+use tokio::sync::mpsc::error::TrySendError;
+use tonic::{Code, Request, Response, Status};
+
+use crate::{
+  controller::Controller,
+  models::product_events::{product_event_sampled, product_events_record_is_valid, ProductEvent},
+};
+
+/// Records what the session did with products (views, clicks from a list, adds to the cart
+/// or the wishlist). The events are sampled by type, then queued for `ProductEventsWriter`:
+/// when the queue can't take all of them, none is queued and the caller is told to retry.
+pub(super) async fn product_events_record(
+  c: &Controller,
+  req: Request<ProductEventsRecordRequest>,
+) -> Result<Response<ProductEventsRecordResponse>, Status> {
+  let start = std::time::Instant::now();
+  c.metrics.product_events_record_total.inc();
+
+  let path = "products.controller.product_events_record";
+  let ctx = req.extensions().get::<Arc<Context>>().cloned().unwrap();
+  let req = req.into_inner();
+
+  let return_err = |e: AppError| {
+    c.metrics.record_product_events_record_error();
+    Response::new(ProductEventsRecordResponse { response: Some(ResError(e.to_proto())) })
+  };
+  let mk_err = |err: BoxedErr, id: &str, code: Code| {
+    let errors = Some(AppErrorErrors { err: Some(err), ..Default::default() });
+    return_err(AppError::new(ctx.clone(), path, id, None, "", code.into(), errors))
+  };
+
+  let max = c.products_cfg.product_events_max_per_request as usize;
+  if let Err(err) = product_events_record_is_valid(ctx.clone(), &req, max) {
+    return Ok(return_err(err));
+  }
+
+  let (user_id, now) = (ctx.session().user_id().to_string(), time_get_millis());
+  let rates = &c.products_cfg.product_events_sample_rates;
+  let received = req.events.len();
+  let events: Vec<ProductEvent> = req
+    .events
+    .into_iter()
+    .filter_map(|input| ProductEvent::new(input, &user_id, now))
+    .filter(|event| product_event_sampled(rates, event.event_type, rand::random::<f64>()))
+    .collect();
+  let accepted = events.len();
+
+  if accepted > 0 {
+    let permits = match c.product_events.try_reserve_many(accepted) {
+      Ok(permits) => permits,
+      Err(TrySendError::Full(_)) => {
+        let err = Box::new(Error::new(ErrorKind::WouldBlock, "the events queue is full"));
+        return Ok(mk_err(err, "products.events.busy.error", Code::ResourceExhausted));
+      }
+      Err(TrySendError::Closed(_)) => {
+        let err = Box::new(Error::new(ErrorKind::BrokenPipe, "the events queue is closed"));
+        return Ok(mk_err(err, MSG_ID_ERR_INTERNAL, Code::Internal));
+      }
+    };
+    for (permit, event) in permits.zip(events) {
+      permit.send(event);
+    }
+  }
+
+  let duration = start.elapsed().as_secs_f64();
+  c.metrics.record_product_events_record_success(duration);
+
+  Ok(Response::new(ProductEventsRecordResponse {
+    response: Some(Data(ProductEventsRecordResponseData {
+      accepted: accepted as u32,
+      sampled_out: (received - accepted) as u32,
+    })),
+  }))
+}
@@ -0,0 +1,168 @@
+use std::{
+  collections::{HashMap, HashSet},
+  io::{Error, ErrorKind},
+  sync::Arc,
+  time::Duration,
+};
+
+use megacommerce_proto::{
+  product_media_upload_urls_response::Response::{Data as ResData, Error as ResError},
+  ConfigProducts, ProductMediaUploadUrl, ProductMediaUploadUrlsRequest,
+  ProductMediaUploadUrlsRequestFile, ProductMediaUploadUrlsResponse,
+  ProductMediaUploadUrlsResponseData,
+};
+use megacommerce_shared::{
+  models::{
+    context::Context,
+    errors::{AppError, AppErrorError, AppErrorErrors, BoxedErr, MSG_ID_ERR_INTERNAL},
+    files::UnitSizeType,
+  },
+  utils::time::time_get_millis,
+};
+use serde_json::Value;
+use tonic::{Code, Request, Response, Status};
+use ulid::Ulid;
+
+use crate::{
+  controller::{helpers::is_valid_ulid, Controller},
+  models::{config::ProductsConfig, products::product_media_staging_key},
+};
+
+/// Hands out presigned PUT urls, so big media is sent directly to the bucket instead of
+/// inside the create request. The staged keys are recorded as pending uploads of the
+/// (reserved) product id, the product creation references them by key.
+pub(super) async fn product_media_upload_urls(
+  c: &Controller,
+  req: Request<ProductMediaUploadUrlsRequest>,
+) -> Result<Response<ProductMediaUploadUrlsResponse>, Status> {
+  let start = std::time::Instant::now();
+  c.metrics.product_media_upload_urls_total.inc();
+
+  let path = "products.controller.product_media_upload_urls";
+  let ctx = req.extensions().get::<Arc<Context>>().cloned().unwrap();
+  let req = req.into_inner();
+
+  let return_err = |e: AppError| {
+    c.metrics.record_product_media_upload_urls_error();
+    Response::new(ProductMediaUploadUrlsResponse { response: Some(ResError(e.to_proto())) })
+  };
+  let ie = |err: BoxedErr, id: &str, code: Code, fields: Option<HashMap<String, AppErrorError>>| {
+    let errors =
+      Some(AppErrorErrors { err: Some(err), errors_internal: fields, ..Default::default() });
+    AppError::new(ctx.clone(), path, id, None, "", code.into(), errors)
+  };
+
+  // an empty id reserves a new one, which the product is then created with
+  let product_id =
+    if req.product_id.is_empty() { Ulid::new().to_string() } else { req.product_id.clone() };
+  if !is_valid_ulid(&product_id) {
+    let err = Box::new(Error::new(ErrorKind::InvalidInput, "invalid product id"));
+    return Ok(return_err(ie(err, "products.id.invalid", Code::InvalidArgument, None)));
+  }
+
+  let max_files = c.products_cfg.media_presigned_max_files;
+  if req.files.is_empty() || req.files.len() > max_files {
+    let msg = format!("the files count must be between 1 and {}", max_files);
+    let err = Box::new(Error::new(ErrorKind::InvalidInput, msg));
+    let id = "products.media.upload_urls.count";
+    return Ok(return_err(ie(err, id, Code::InvalidArgument, None)));
+  }
+
+  let cfg = c.cfg.get().await.products.clone().unwrap_or_default();
+  let errors = upload_files_validation(&req.files, &cfg, &c.products_cfg);
+  if errors.len() > 0 {
+    let err = Box::new(Error::new(ErrorKind::InvalidInput, "invalid upload files"));
+    let id = "products.media.upload_urls.invalid";
+    return Ok(return_err(ie(err, id, Code::InvalidArgument, Some(errors))));
+  }
+
+  let user_id = ctx.session().user_id().to_string();
+  let key = |file: &ProductMediaUploadUrlsRequestFile| {
+    product_media_staging_key(&user_id, &product_id, &file.checksum.to_lowercase())
+  };
+
+  // record before presigning, an upload the creation never picks up is removed by the sweeper
+  let keys: Vec<String> = req.files.iter().map(key).collect();
+  let recorded = c.store.product_media_uploads_add(ctx.clone(), &product_id, &keys).await;
+  if let Err(err) = recorded {
+    return Ok(return_err(err.to_app_error_internal(ctx.clone(), path.into())));
+  }
+
+  let expiry_secs = c.products_cfg.media_presigned_url_expiry_secs;
+  let expires_at = time_get_millis() + expiry_secs * 1000;
+  let storage = c.storage.get().await;
+  let mut uploads: Vec<ProductMediaUploadUrl> = Vec::with_capacity(req.files.len());
+  for file in req.files.iter() {
+    let key = key(file);
+    let presigned = storage
+      .presigned_upload(&key, &file.mime_type, file.size, Duration::from_secs(expiry_secs))
+      .await;
+
+    match presigned {
+      Ok(presigned) => uploads.push(ProductMediaUploadUrl {
+        id: file.id.clone(),
+        key,
+        url: presigned.url,
+        headers: presigned.headers,
+        expires_at,
+      }),
+      Err(err) => return Ok(return_err(ie(err, MSG_ID_ERR_INTERNAL, Code::Internal, None))),
+    }
+  }
+
+  let duration = start.elapsed().as_secs_f64();
+  c.metrics.record_product_media_upload_urls_success(duration);
+
+  Ok(Response::new(ProductMediaUploadUrlsResponse {
+    response: Some(ResData(ProductMediaUploadUrlsResponseData { product_id, uploads })),
+  }))
+}
+
+/// Validates the declared type, size and checksum of every file, keyed by the file id
+fn upload_files_validation(
+  files: &[ProductMediaUploadUrlsRequestFile],
+  cfg: &ConfigProducts,
+  products_cfg: &ProductsConfig,
+) -> HashMap<String, AppErrorError> {
+  let mut errors: HashMap<String, AppErrorError> = HashMap::new();
+  let mut ids: HashSet<&str> = HashSet::new();
+
+  for file in files.iter() {
+    if file.id.is_empty() || !ids.insert(&file.id) {
+      let err = AppErrorError { id: "form.field.id.missing_or_invalid".into(), params: None };
+      errors.insert("files".into(), err);
+      continue;
+    }
+
+    let checksum = &file.checksum;
+    if checksum.len() != 64 || !checksum.chars().all(|c| c.is_ascii_hexdigit()) {
+      let err = AppErrorError { id: "attachment.checksum.invalid".into(), params: None };
+      errors.insert(file.id.clone(), err);
+      continue;
+    }
+
+    let max_mb = if cfg.product_image_accepted_formats.contains(&file.mime_type) {
+      cfg.product_image_max_size_mb as u64
+    } else if products_cfg.video_accepted_formats.contains(&file.mime_type) {
+      products_cfg.video_max_size_mb
+    } else {
+      let err = AppErrorError { id: "attachment.type.unsupported".into(), params: None };
+      errors.insert(file.id.clone(), err);
+      continue;
+    };
+
+    if file.size == 0 {
+      let err = AppErrorError { id: "attachment.size.invalid".into(), params: None };
+      errors.insert(file.id.clone(), err);
+    } else if file.size > max_mb * 1024 * 1024 {
+      let params = Some(HashMap::from([
+        ("Max".into(), Value::Number(max_mb.into())),
+        ("Unit".into(), Value::String(UnitSizeType::MB.as_str().to_string())),
+      ]));
+      let err = AppErrorError { id: "attachment.max_size.error".into(), params };
+      errors.insert(file.id.clone(), err);
+    }
+  }
+
+  errors
+}
@@ -0,0 +1,106 @@
+use std::{
+  io::{Error, ErrorKind},
+  sync::Arc,
+};
+
+use megacommerce_proto::{
+  product_review_create_response::Response::{Data as ResData, Error as ResError},
+  ProductReview, ProductReviewCreateRequest, ProductReviewCreateResponse,
+};
+use megacommerce_shared::{
+  models::{
+    context::Context,
+    errors::{AppError, AppErrorErrors, BoxedErr, ErrorType},
+  },
+  utils::time::time_get_millis,
+};
+use tonic::{Code, Request, Response, Status};
+use ulid::Ulid;
+
+use crate::{
+  controller::{helpers::is_valid_ulid, Controller},
+  models::{
+    product_reviews::{product_review_create_is_valid, ProductReviewStatus},
+    products::ProductStatus,
+  },
+};
+
+/// Submits a review of a published product. Reviews are moderated before they're shown
+/// and counted in the product's rating.
+pub(super) async fn product_review_create(
+  c: &Controller,
+  req: Request<ProductReviewCreateRequest>,
+) -> Result<Response<ProductReviewCreateResponse>, Status> {
+  let start = std::time::Instant::now();
+  c.metrics.product_review_create_total.inc();
+
+  let path = "products.controller.product_review_create";
+  let ctx = req.extensions().get::<Arc<Context>>().cloned().unwrap();
+  let req = req.into_inner();
+
+  let return_err = |e: AppError| {
+    c.metrics.record_product_review_create_error();
+    Response::new(ProductReviewCreateResponse { response: Some(ResError(e.to_proto())) })
+  };
+  let ie = |err: BoxedErr, id: &str, code: Code| {
+    let errors = Some(AppErrorErrors { err: Some(err), ..Default::default() });
+    AppError::new(ctx.clone(), path, id, None, "", code.into(), errors)
+  };
+  let not_found = || {
+    let err = Box::new(Error::new(ErrorKind::NotFound, "the requsted product is not found"));
+    return_err(ie(err, "products.not_found.error", Code::NotFound))
+  };
+
+  if let Err(err) = product_review_create_is_valid(ctx.clone(), &req) {
+    return Ok(return_err(err));
+  }
+  if !is_valid_ulid(&req.product_id) {
+    return Ok(not_found());
+  }
+
+  let product = match c.store.product_get(ctx.clone(), &req.product_id).await {
+    Ok(product) => product,
+    Err(err) => match err.err_type {
+      ErrorType::NoRows => return Ok(not_found()),
+      _ => return Ok(return_err(err.to_app_error_internal(ctx.clone(), path.into()))),
+    },
+  };
+  if product.status != ProductStatus::Published.as_str() {
+    return Ok(not_found());
+  }
+
+  let user_id = ctx.session().user_id().to_string();
+  if product.user_id == user_id {
+    let msg = "a seller can't review its own product";
+    let err = Box::new(Error::new(ErrorKind::PermissionDenied, msg));
+    return Ok(return_err(ie(err, "products.reviews.own_product", Code::PermissionDenied)));
+  }
+
+  let review = ProductReview {
+    id: Ulid::new().to_string(),
+    product_id: product.id,
+    user_id,
+    rating: req.rating,
+    title: req.title.trim().to_string(),
+    body: req.body.trim().to_string(),
+    verified_purchase: false,
+    status: ProductReviewStatus::Pending.as_str().to_string(),
+    created_at: time_get_millis(),
+  };
+
+  let review = match c.store.product_review_create(ctx.clone(), &review).await {
+    Ok(review) => review,
+    Err(err) => match err.err_type {
+      ErrorType::NoRows => {
+        let id = "products.reviews.already_exists";
+        return Ok(return_err(ie(Box::new(err), id, Code::AlreadyExists)));
+      }
+      _ => return Ok(return_err(err.to_app_error_internal(ctx.clone(), path.into()))),
+    },
+  };
+
+  let duration = start.elapsed().as_secs_f64();
+  c.metrics.record_product_review_create_success(duration);
+
+  Ok(Response::new(ProductReviewCreateResponse { response: Some(ResData(review)) }))
+}
@@ -0,0 +1,138 @@
+use std::{
+  io::{Error, ErrorKind},
+  sync::Arc,
+};
+
+use megacommerce_proto::{
+  product_review_moderate_response::Response::{Data as ResData, Error as ResError},
+  ProductReviewModerateRequest, ProductReviewModerateResponse, SuccessResponseData,
+};
+use megacommerce_shared::models::{
+  context::Context,
+  errors::{AppError, AppErrorErrors, BoxedErr, ErrorType, MSG_ID_ERR_INTERNAL},
+  translate::tr,
+};
+use serde_json::json;
+use tokio::spawn;
+use tonic::{Code, Request, Response, Status};
+
+use crate::{
+  controller::{
+    audit::process_audit,
+    helpers::{is_valid_ulid, session_is_moderator},
+    Controller,
+  },
+  models::{
+    audit::{AuditRecord, EventName::ProductReviewModerate, EventParameterKey, EventStatus::Fail},
+    product_reviews::ProductReviewStatus,
+  },
+};
+
+/// Approves or rejects a review, the product's rating follows the approved reviews
+pub(super) async fn product_review_moderate(
+  c: &Controller,
+  req: Request<ProductReviewModerateRequest>,
+) -> Result<Response<ProductReviewModerateResponse>, Status> {
+  let start = std::time::Instant::now();
+  c.metrics.product_review_moderate_total.inc();
+
+  let path = "products.controller.product_review_moderate";
+  let ctx = req.extensions().get::<Arc<Context>>().cloned().unwrap();
+  let lang = ctx.accept_language();
+
+  let mut audit = AuditRecord::new(ctx.clone(), ProductReviewModerate, Fail);
+  let req = req.into_inner();
+
+  let return_err = |e: AppError| {
+    c.metrics.record_product_review_moderate_error();
+    Response::new(ProductReviewModerateResponse { response: Some(ResError(e.to_proto())) })
+  };
+  let ie = |err: BoxedErr, id: &str, code: Option<Code>| {
+    let errors = Some(AppErrorErrors { err: Some(err), ..Default::default() });
+    AppError::new(ctx.clone(), path, id, None, "", code.unwrap_or(Code::Internal).into(), errors)
+  };
+
+  if !session_is_moderator(c, &ctx) {
+    let err = Box::new(Error::new(ErrorKind::PermissionDenied, "only a moderator can do this"));
+    let id = "products.reviews.moderation.denied";
+    return Ok(return_err(ie(err, id, Some(Code::PermissionDenied))));
+  }
+
+  if !is_valid_ulid(&req.review_id) {
+    let err = Box::new(Error::new(ErrorKind::NotFound, "the requested review is not found"));
+    return Ok(return_err(ie(err, "products.reviews.not_found", Some(Code::NotFound))));
+  }
+
+  let next = match ProductReviewStatus::from_str(&req.status) {
+    Some(next) => next,
+    None => {
+      let err = Box::new(Error::new(ErrorKind::InvalidInput, "unknown review status"));
+      let id = "products.reviews.status.invalid";
+      return Ok(return_err(ie(err, id, Some(Code::InvalidArgument))));
+    }
+  };
+
+  let review = match c.store.product_review_get(ctx.clone(), &req.review_id).await {
+    Ok(review) => review,
+    Err(err) => match err.err_type {
+      ErrorType::NoRows => {
+        let id = "products.reviews.not_found";
+        return Ok(return_err(ie(Box::new(err), id, Some(Code::NotFound))));
+      }
+      _ => return Ok(return_err(err.to_app_error_internal(ctx.clone(), path.into()))),
+    },
+  };
+
+  let current = match ProductReviewStatus::from_str(&review.status) {
+    Some(current) => current,
+    None => {
+      let msg = format!("the review {} has an unknown status: {}", review.id, review.status);
+      let err = Box::new(Error::new(ErrorKind::InvalidData, msg));
+      return Ok(return_err(ie(err, MSG_ID_ERR_INTERNAL, None)));
+    }
+  };
+
+  if !current.can_transition_to(next) {
+    let msg = format!("can't move a review from {} to {}", current.as_str(), next.as_str());
+    let err = Box::new(Error::new(ErrorKind::InvalidInput, msg));
+    let id = "products.reviews.status.transition.invalid";
+    return Ok(return_err(ie(err, id, Some(Code::FailedPrecondition))));
+  }
+
+  let reason = req.reason.trim();
+  let result =
+    c.store.product_review_moderate(ctx.clone(), &review.id, current, next, reason).await;
+  if let Err(err) = result {
+    return match err.err_type {
+      ErrorType::NoRows => Ok(return_err(ie(
+        Box::new(err),
+        "products.reviews.status.transition.conflict",
+        Some(Code::Aborted),
+      ))),
+      _ => Ok(return_err(ie(Box::new(err), MSG_ID_ERR_INTERNAL, None))),
+    };
+  }
+
+  let audit_data = json!({
+    "review_id": review.id,
+    "product_id": review.product_id,
+    "from": current.as_str(),
+    "to": next.as_str(),
+    "reason": reason,
+  });
+  audit.set_event_parameter(EventParameterKey::ProductReviewModerate, audit_data);
+  audit.success();
+  spawn(async move {
+    process_audit(&audit);
+  });
+
+  let message = tr::<()>(lang, "products.reviews.moderated", None)
+    .unwrap_or("The review moderated successfully!".to_string());
+
+  let duration = start.elapsed().as_secs_f64();
+  c.metrics.record_product_review_moderate_success(duration);
+
+  Ok(Response::new(ProductReviewModerateResponse {
+    response: Some(ResData(SuccessResponseData { message: Some(message), ..Default::default() })),
+  }))
+}
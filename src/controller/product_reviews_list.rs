@@ -0,0 +1,84 @@
+use std::{
+  io::{Error, ErrorKind},
+  sync::Arc,
+};
+
+use megacommerce_proto::{
+  product_reviews_list_response::Response::{Data, Error as ResError},
+  ProductReviewsListRequest, ProductReviewsListResponse, ProductReviewsListResponseData,
+};
+use megacommerce_shared::models::{
+  context::Context,
+  errors::{AppError, AppErrorErrors, BoxedErr, ErrorType},
+};
+use tonic::{Code, Request, Response, Status};
+
+use crate::controller::{
+  helpers::{build_pagination_response, is_valid_ulid, page_size, pagination_cursor, ID_DESC_SORT},
+  Controller,
+};
+
+/// The approved reviews of a product, newest first, along with its rating
+pub(super) async fn product_reviews_list(
+  c: &Controller,
+  req: Request<ProductReviewsListRequest>,
+) -> Result<Response<ProductReviewsListResponse>, Status> {
+  let start = std::time::Instant::now();
+  c.metrics.product_reviews_list_total.inc();
+
+  let path = "products.controller.product_reviews_list";
+  let ctx = req.extensions().get::<Arc<Context>>().cloned().unwrap();
+  let req = req.into_inner();
+
+  let return_err = |e: AppError| {
+    c.metrics.record_product_reviews_list_error();
+    Response::new(ProductReviewsListResponse { response: Some(ResError(e.to_proto())) })
+  };
+  let not_found = |err: BoxedErr| {
+    let errors = Some(AppErrorErrors { err: Some(err), ..Default::default() });
+    let id = "products.not_found.error";
+    return_err(AppError::new(ctx.clone(), path, id, None, "", Code::NotFound.into(), errors))
+  };
+
+  if !is_valid_ulid(&req.product_id) {
+    return Ok(not_found(Box::new(Error::new(ErrorKind::NotFound, "the product is not found"))));
+  }
+
+  // verified-only lists are paged separately, the cursors of one don't apply to the other
+  let sort = if req.verified_only { "id:desc:verified" } else { ID_DESC_SORT };
+  let secret = &c.products_cfg.pagination_cursor_secret;
+  let cursor = match pagination_cursor(ctx.clone(), path, &req.pagination, secret, sort) {
+    Ok(cursor) => cursor,
+    Err(err) => return Ok(return_err(err)),
+  };
+
+  let rating = match c.store.product_rating(ctx.clone(), &req.product_id).await {
+    Ok(rating) => rating,
+    Err(err) => match err.err_type {
+      ErrorType::NoRows => return Ok(not_found(Box::new(err))),
+      _ => return Ok(return_err(err.to_app_error_internal(ctx.clone(), path.into()))),
+    },
+  };
+
+  let limit = page_size(&req.pagination, 10, c.products_cfg.pagination_max_page_size);
+  let page = c
+    .store
+    .product_reviews_list(ctx.clone(), &req.product_id, req.verified_only, cursor.as_ref(), limit)
+    .await;
+  let page = match page {
+    Ok(page) => page,
+    Err(err) => return Ok(return_err(err.to_app_error_internal(ctx.clone(), path.into()))),
+  };
+
+  let duration = start.elapsed().as_secs_f64();
+  c.metrics.record_product_reviews_list_success(duration);
+
+  let pagination = build_pagination_response(cursor.as_ref(), &page, None, sort, secret);
+  Ok(Response::new(ProductReviewsListResponse {
+    response: Some(Data(ProductReviewsListResponseData {
+      reviews: page.items,
+      rating: Some(rating),
+      pagination: Some(pagination),
+    })),
+  }))
+}
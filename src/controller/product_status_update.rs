@@ -0,0 +1,145 @@
+use std::{
+  io::{Error, ErrorKind},
+  sync::Arc,
+};
+
+use megacommerce_proto::{
+  product_status_update_response::Response::{Data as ResData, Error as ResError},
+  ProductStatusUpdateRequest, ProductStatusUpdateResponse, SuccessResponseData,
+};
+use megacommerce_shared::models::{
+  context::Context,
+  errors::{AppError, AppErrorErrors, BoxedErr, ErrorType, MSG_ID_ERR_INTERNAL},
+  translate::tr,
+};
+use serde_json::json;
+use tokio::spawn;
+use tonic::{Code, Request, Response, Status};
+
+use crate::{
+  controller::{
+    audit::process_audit,
+    helpers::{is_valid_ulid, session_is_moderator},
+    Controller,
+  },
+  models::{
+    audit::{AuditRecord, EventName::ProductStatusUpdate, EventParameterKey, EventStatus::Fail},
+    products::ProductStatus,
+  },
+};
+
+pub(super) async fn product_status_update(
+  c: &Controller,
+  req: Request<ProductStatusUpdateRequest>,
+) -> Result<Response<ProductStatusUpdateResponse>, Status> {
+  let start = std::time::Instant::now();
+  c.metrics.product_status_update_total.inc();
+
+  let path = "products.controller.product_status_update";
+  let ctx = req.extensions().get::<Arc<Context>>().cloned().unwrap();
+  let lang = ctx.accept_language();
+
+  let mut audit = AuditRecord::new(ctx.clone(), ProductStatusUpdate, Fail);
+  let req = req.into_inner();
+
+  let return_err = |e: AppError| {
+    c.metrics.record_product_status_update_error();
+    Response::new(ProductStatusUpdateResponse { response: Some(ResError(e.to_proto())) })
+  };
+  let ie = |err: BoxedErr, id: &str, code: Option<Code>| {
+    let errors = Some(AppErrorErrors { err: Some(err), ..Default::default() });
+    AppError::new(ctx.clone(), path, id, None, "", code.unwrap_or(Code::Internal).into(), errors)
+  };
+  let invalid = |msg: &str, id: &str| {
+    let err = Box::new(Error::new(ErrorKind::InvalidInput, msg));
+    return_err(ie(err, id, Some(Code::InvalidArgument)))
+  };
+
+  if !is_valid_ulid(&req.product_id) {
+    let err = Box::new(Error::new(ErrorKind::NotFound, "the requsted product is not found"));
+    return Ok(return_err(ie(err, "products.not_found.error", Some(Code::NotFound))));
+  }
+
+  let next = match ProductStatus::from_str(&req.status) {
+    Some(next) => next,
+    None => return Ok(invalid("unknown product status", "products.status.invalid")),
+  };
+  if next.requires_reason() && req.reason.trim().is_empty() {
+    return Ok(invalid("a reason is required for this status", "products.status.reason.required"));
+  }
+
+  let stored = match c.store.product_get(ctx.clone(), &req.product_id).await {
+    Ok(stored) => stored,
+    Err(err) => match err.err_type {
+      ErrorType::NoRows => {
+        return Ok(return_err(ie(Box::new(err), "products.not_found.error", Some(Code::NotFound))))
+      }
+      _ => return Ok(return_err(err.to_app_error_internal(ctx.clone(), path.into()))),
+    },
+  };
+
+  // the suppliers only manage their own products, and don't moderate them
+  let is_owner = stored.user_id == ctx.session().user_id();
+  let is_moderator = session_is_moderator(c, &ctx);
+  if !is_owner && !is_moderator {
+    let err = Box::new(Error::new(ErrorKind::NotFound, "the requsted product is not found"));
+    return Ok(return_err(ie(err, "products.not_found.error", Some(Code::NotFound))));
+  }
+  let current = match ProductStatus::from_str(&stored.status) {
+    Some(current) => current,
+    None => {
+      let msg = format!("the product {} has an unknown status: {}", stored.id, stored.status);
+      let err = Box::new(Error::new(ErrorKind::InvalidData, msg));
+      return Ok(return_err(ie(err, MSG_ID_ERR_INTERNAL, None)));
+    }
+  };
+  if !next.can_be_set_by(current, is_owner, is_moderator) {
+    let (from, to) = (current.as_str(), next.as_str());
+    let msg = format!("only a moderator can move a product from {} to {}", from, to);
+    let err = Box::new(Error::new(ErrorKind::PermissionDenied, msg));
+    let id = "products.status.moderation.denied";
+    return Ok(return_err(ie(err, id, Some(Code::PermissionDenied))));
+  }
+
+  if !current.can_transition_to(next) {
+    let msg = format!("can't move a product from {} to {}", current.as_str(), next.as_str());
+    let err = Box::new(Error::new(ErrorKind::InvalidInput, msg));
+    let id = "products.status.transition.invalid";
+    return Ok(return_err(ie(err, id, Some(Code::FailedPrecondition))));
+  }
+
+  let reason = req.reason.trim();
+  let result = c.store.product_status_update(ctx.clone(), &stored.id, current, next, reason).await;
+  if let Err(err) = result {
+    return match err.err_type {
+      ErrorType::NoRows => Ok(return_err(ie(
+        Box::new(err),
+        "products.status.transition.conflict",
+        Some(Code::Aborted),
+      ))),
+      _ => Ok(return_err(ie(Box::new(err), MSG_ID_ERR_INTERNAL, None))),
+    };
+  }
+
+  let audit_data = json!({
+    "product_id": stored.id,
+    "from": current.as_str(),
+    "to": next.as_str(),
+    "reason": reason,
+  });
+  audit.set_event_parameter(EventParameterKey::ProductStatusUpdate, audit_data);
+  audit.success();
+  spawn(async move {
+    process_audit(&audit);
+  });
+
+  let message = tr::<()>(lang, "products.status.updated", None)
+    .unwrap_or("The product status updated successfully!".to_string());
+
+  let duration = start.elapsed().as_secs_f64();
+  c.metrics.record_product_status_update_success(duration);
+
+  Ok(Response::new(ProductStatusUpdateResponse {
+    response: Some(ResData(SuccessResponseData { message: Some(message), ..Default::default() })),
+  }))
+}
@@ -0,0 +1,131 @@
+use std::{
+  io::{Error, ErrorKind},
+  sync::Arc,
+};
+
+use megacommerce_proto::{
+  product_update_response::Response::{Data as ResData, Error as ResError},
+  ProductUpdateRequest, ProductUpdateResponse, ProductUpdateResponseData,
+};
+use megacommerce_shared::models::{
+  context::Context,
+  errors::{AppError, AppErrorErrors, BoxedErr, ErrorType, MSG_ID_ERR_INTERNAL},
+  translate::tr,
+};
+use tokio::spawn;
+use tonic::{Code, Request, Response, Status};
+
+use crate::{
+  controller::{audit::process_audit, helpers::is_valid_ulid, Controller},
+  models::{
+    audit::{AuditRecord, EventName::ProductUpdate, EventParameterKey, EventStatus::Fail},
+    product_update::{
+      products_update_auditable_v1, products_update_is_valid, products_update_pre_save,
+    },
+  },
+};
+
+pub(super) async fn product_update(
+  c: &Controller,
+  req: Request<ProductUpdateRequest>,
+) -> Result<Response<ProductUpdateResponse>, Status> {
+  let start = std::time::Instant::now();
+  c.metrics.product_update_total.inc();
+
+  let path = "products.controller.product_update";
+  let ctx = req.extensions().get::<Arc<Context>>().cloned().unwrap();
+  let lang = ctx.accept_language();
+
+  let mut audit = AuditRecord::new(ctx.clone(), ProductUpdate, Fail);
+  let pro = req.into_inner();
+
+  let return_err = |e: AppError| {
+    c.metrics.record_product_update_error();
+    Response::new(ProductUpdateResponse { response: Some(ResError(e.to_proto())) })
+  };
+  let ie = |err: BoxedErr, id: &str, code: Option<Code>| {
+    let errors = Some(AppErrorErrors { err: Some(err), ..Default::default() });
+    AppError::new(ctx.clone(), path, id, None, "", code.unwrap_or(Code::Internal).into(), errors)
+  };
+  let not_found = |err: Option<BoxedErr>| {
+    return_err(ie(
+      err.unwrap_or(Box::new(Error::new(ErrorKind::NotFound, "the requsted product is not found"))),
+      "products.not_found.error",
+      Some(Code::NotFound),
+    ))
+  };
+
+  if !is_valid_ulid(&pro.product_id) {
+    return Ok(not_found(None));
+  }
+
+  let stored = match c.store.product_get(ctx.clone(), &pro.product_id).await {
+    Ok(stored) => stored,
+    Err(err) => match err.err_type {
+      ErrorType::NoRows => return Ok(not_found(Some(Box::new(err)))),
+      _ => return Ok(return_err(err.to_app_error_internal(ctx.clone(), path.into()))),
+    },
+  };
+
+  // suppliers can only edit their own listings, don't leak the existence of other ones
+  if stored.user_id != ctx.session().user_id() {
+    return Ok(not_found(None));
+  }
+
+  if stored.version != pro.version {
+    let err = Box::new(Error::new(ErrorKind::Other, "the product version is stale"));
+    return Ok(return_err(ie(err, "products.update.version_conflict", Some(Code::Aborted))));
+  }
+
+  let pro_clone = pro.clone();
+  let audit_data_future = spawn(async move { products_update_auditable_v1(&pro_clone) });
+
+  let sub = c.cache.subcategory_data(&stored.category, &stored.subcategory, lang);
+  if let Err(err) = products_update_is_valid(ctx.clone(), &pro, &stored, sub) {
+    return Ok(return_err(err));
+  }
+
+  let pro_db = match products_update_pre_save(ctx.clone(), &pro, &stored) {
+    Ok(pro_db) => pro_db,
+    Err(err) => return Ok(return_err(err.to_internal(ctx.clone(), path.into()))),
+  };
+
+  let result = c.store.product_update(ctx.clone(), &pro_db, pro.version, &stored.status).await;
+  let version = match result {
+    Ok(version) => version,
+    Err(err) => {
+      return match err.err_type {
+        // the row was changed by another writer between the read and the update
+        ErrorType::NoRows => Ok(return_err(ie(
+          Box::new(err),
+          "products.update.version_conflict",
+          Some(Code::Aborted),
+        ))),
+        _ => Ok(return_err(ie(Box::new(err), MSG_ID_ERR_INTERNAL, None))),
+      };
+    }
+  };
+
+  let audit_data = audit_data_future.await.unwrap_or_default();
+  audit.set_event_parameter(EventParameterKey::ProductUpdate, audit_data);
+  audit.success();
+  spawn(async move {
+    process_audit(&audit);
+  });
+
+  let message = tr::<()>(lang, "products.update.successfully", None)
+    .unwrap_or("The Product updated successfully!".to_string());
+
+  let duration = start.elapsed().as_secs_f64();
+  c.metrics.record_product_update_success(duration);
+
+  // the client sends the new version with its next edit
+  Ok(Response::new(ProductUpdateResponse {
+    response: Some(ResData(ProductUpdateResponseData {
+      message: Some(message),
+      product_id: pro_db.id,
+      version,
+      status: pro_db.status,
+    })),
+  }))
+}
@@ -1,8 +1,9 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use megacommerce_proto::{
   products_category_response::Response::{Data, Error},
-  ProductsCategoryRequest, ProductsCategoryResponse, ProductsCategoryResponseData, SortDirection,
+  ProductsCategoryFacet, ProductsCategoryFacetValue, ProductsCategoryRequest,
+  ProductsCategoryResponse, ProductsCategoryResponseData, SortDirection,
 };
 use megacommerce_shared::models::{
   context::Context,
@@ -10,9 +11,17 @@ use megacommerce_shared::models::{
 };
 use tonic::{Code, Request, Response, Status};
 
-use crate::controller::{
-  helpers::{build_pagination_response, check_last_id},
-  Controller,
+use crate::{
+  controller::{
+    helpers::{
+      build_pagination_response, display_currency, display_prices_fill, include_total, page_size,
+      pagination_cursor, sellers_fill,
+    },
+    Controller,
+  },
+  models::products_facets::{
+    product_facet_filters_validate, ProductFacetCount, ProductFacetFilter, ProductFacetSchema,
+  },
 };
 
 pub(super) async fn products_category(
@@ -35,62 +44,166 @@ pub(super) async fn products_category(
     AppError::new(ctx.clone(), path, MSG_ID_ERR_INTERNAL, None, "", Code::Internal.into(), errors)
   };
 
-  // Validate pagination
-  if let Err(err) = check_last_id(ctx.clone(), path, &req.pagination) {
-    c.metrics.record_products_category_error();
-    return Ok(return_err(err));
-  }
-
-  let pagination = req.pagination.unwrap();
-  let limit = 20i64;
-  let page = pagination.page();
-  let last_id = pagination.last_id();
-
   // Extract sorting info from pagination.sort_by
-  let (sort_by, sort_direction) = if !pagination.sort_by.is_empty() {
-    if let Some(first_sort) = pagination.sort_by.first() {
+  let (sort_by, sort_direction) = match req.pagination.as_ref().and_then(|p| p.sort_by.first()) {
+    Some(first_sort) => {
       let direction = match first_sort.direction() {
         SortDirection::Asc => Some("asc"),
         SortDirection::Desc => Some("desc"),
         _ => None,
       };
       (Some(first_sort.name.as_str()), direction)
-    } else {
-      (None, None)
     }
-  } else {
-    (None, None)
+    None => (None, None),
+  };
+
+  // Validate pagination, a cursor only pages through the sort it was issued for
+  let sort = format!("{}:{}", sort_by.unwrap_or_default(), sort_direction.unwrap_or_default());
+  let secret = &c.products_cfg.pagination_cursor_secret;
+  let cursor = match pagination_cursor(ctx.clone(), path, &req.pagination, secret, &sort) {
+    Ok(cursor) => cursor,
+    Err(err) => {
+      c.metrics.record_products_category_error();
+      return Ok(return_err(err));
+    }
+  };
+
+  let currency = match display_currency(ctx.clone(), path, &req.display_currency) {
+    Ok(currency) => currency,
+    Err(err) => {
+      c.metrics.record_products_category_error();
+      return Ok(return_err(err));
+    }
+  };
+
+  let schema = c.cache.category_facets(&req.category_id, &req.subcategory_ids);
+  let requested: HashMap<String, Vec<String>> =
+    req.facets.iter().map(|(name, f)| (name.clone(), f.values.clone())).collect();
+  let facets = match product_facet_filters_validate(&schema, &requested) {
+    Ok(facets) => facets,
+    Err(errors) => {
+      c.metrics.record_products_category_error();
+      let errors = Some(AppErrorErrors { errors_internal: Some(errors), ..Default::default() });
+      let id = "products.facets.invalid";
+      let code = Code::InvalidArgument.into();
+      return Ok(return_err(AppError::new(ctx.clone(), path, id, None, "", code, errors)));
+    }
   };
 
+  let in_stock = req.in_stock.unwrap_or(false);
+  let limit = page_size(&req.pagination, 20, c.products_cfg.pagination_max_page_size);
+
   let result = c
     .store
     .products_category(
       ctx.clone(),
       &req.category_id,
       &req.subcategory_ids,
-      page,
-      &last_id,
+      cursor.as_ref(),
       limit,
       sort_by,
       sort_direction,
+      &facets,
+      in_stock,
     )
     .await;
 
-  match result {
-    Ok(products) => {
-      let duration = start.elapsed().as_secs_f64();
-      c.metrics.record_products_category_success(duration);
-      let pagination_response = build_pagination_response(&pagination, products.iter().count());
-      Ok(Response::new(ProductsCategoryResponse {
-        response: Some(Data(ProductsCategoryResponseData {
-          products,
-          pagination: Some(pagination_response),
-        })),
-      }))
+  let mut page = match result {
+    Ok(page) => page,
+    Err(err) => {
+      c.metrics.record_products_category_error();
+      return Ok(return_err(ie(Box::new(err))));
     }
+  };
+
+  if let Err(err) = sellers_fill(c, ctx.clone(), &mut page.items).await {
+    c.metrics.record_products_category_error();
+    return Ok(return_err(ie(Box::new(err))));
+  }
+  display_prices_fill(c, currency, &mut page.items);
+
+  let names: Vec<String> = schema.iter().map(|f| f.name.clone()).collect();
+  let counts = c
+    .store
+    .products_category_facets(
+      ctx.clone(),
+      &req.category_id,
+      &req.subcategory_ids,
+      &names,
+      &facets,
+      in_stock,
+    )
+    .await;
+
+  let counts = match counts {
+    Ok(counts) => counts,
     Err(err) => {
       c.metrics.record_products_category_error();
-      Ok(return_err(ie(Box::new(err))))
+      return Ok(return_err(ie(Box::new(err))));
+    }
+  };
+
+  let mut total = None;
+  if include_total(&req.pagination) {
+    let exact_max = c.products_cfg.pagination_exact_total_max;
+    let (category_id, subcategory_ids) = (&req.category_id, &req.subcategory_ids);
+    let counted = c
+      .store
+      .products_category_total(
+        ctx.clone(),
+        category_id,
+        subcategory_ids,
+        &facets,
+        in_stock,
+        exact_max,
+      )
+      .await;
+    match counted {
+      Ok(counted) => total = Some(counted),
+      Err(err) => {
+        c.metrics.record_products_category_error();
+        return Ok(return_err(ie(Box::new(err))));
+      }
     }
   }
+
+  let duration = start.elapsed().as_secs_f64();
+  c.metrics.record_products_category_success(duration);
+  let pagination_response = build_pagination_response(cursor.as_ref(), &page, total, &sort, secret);
+  Ok(Response::new(ProductsCategoryResponse {
+    response: Some(Data(ProductsCategoryResponseData {
+      products: page.items,
+      pagination: Some(pagination_response),
+      facets: facets_response(&schema, &facets, &counts),
+    })),
+  }))
+}
+
+/// Every option of the facets with its count (zero if no product has it),
+/// in the order the options are defined in the subcategories
+fn facets_response(
+  schema: &[ProductFacetSchema],
+  filters: &[ProductFacetFilter],
+  counts: &[ProductFacetCount],
+) -> Vec<ProductsCategoryFacet> {
+  schema
+    .iter()
+    .map(|facet| {
+      let selected = filters.iter().find(|f| f.name == facet.name);
+      let values = facet
+        .options
+        .iter()
+        .map(|option| ProductsCategoryFacetValue {
+          value: option.clone(),
+          count: counts
+            .iter()
+            .find(|c| c.name == facet.name && &c.value == option)
+            .map(|c| c.count)
+            .unwrap_or(0),
+          selected: selected.is_some_and(|f| f.values.contains(option)),
+        })
+        .collect();
+      ProductsCategoryFacet { name: facet.name.clone(), values }
+    })
+    .collect()
 }
@@ -11,7 +11,7 @@ use megacommerce_shared::models::{
 use tonic::{Code, Request, Response, Status};
 
 use crate::controller::{
-  helpers::{build_pagination_response, check_last_id},
+  helpers::{build_pagination_response, page_size, pagination_cursor, ID_DESC_SORT},
   Controller,
 };
 
@@ -34,28 +34,31 @@ pub(super) async fn products_list(
     AppError::new(ctx.clone(), w, MSG_ID_ERR_INTERNAL, None, "", Code::Internal.into(), errors)
   };
 
-  if let Err(err) = check_last_id(ctx.clone(), w, &req.pagination) {
-    c.metrics.record_products_list_error();
-    return Ok(return_err(err));
-  }
+  let secret = &c.products_cfg.pagination_cursor_secret;
+  let cursor = match pagination_cursor(ctx.clone(), w, &req.pagination, secret, ID_DESC_SORT) {
+    Ok(cursor) => cursor,
+    Err(err) => {
+      c.metrics.record_products_list_error();
+      return Ok(return_err(err));
+    }
+  };
 
-  let pagination = &mut req.pagination.unwrap();
-  pagination.page_size = Some(10);
-  let limit = pagination.page_size() as i64;
-  let result =
-    c.store.products_list(ctx.clone(), pagination.page(), pagination.last_id(), limit).await;
+  let max = c.products_cfg.pagination_max_page_size;
+  let limit = page_size(&req.pagination, 10, max);
+  let result = c.store.products_list(ctx.clone(), cursor.as_ref(), limit).await;
 
   if let Err(err) = result {
     c.metrics.record_products_list_error();
     return Ok(return_err(ie(Box::new(err))));
   } else {
-    let products = result.unwrap();
+    let page = result.unwrap();
     let duration = start.elapsed().as_secs_f64();
     c.metrics.record_products_list_success(duration);
+    let pagination = build_pagination_response(cursor.as_ref(), &page, None, ID_DESC_SORT, secret);
     Ok(Response::new(ProductsListResponse {
       response: Some(Data(ProductsListResponseData {
-        pagination: Some(build_pagination_response(pagination, products.iter().count())),
-        products,
+        pagination: Some(pagination),
+        products: page.items,
       })),
     }))
   }
@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use megacommerce_proto::{
+  products_search_response::Response::{Data, Error},
+  ProductsSearchRequest, ProductsSearchResponse, ProductsSearchResponseData,
+};
+use megacommerce_shared::models::{
+  context::Context,
+  errors::{AppError, AppErrorErrors, BoxedErr, MSG_ID_ERR_INTERNAL},
+};
+use tonic::{Code, Request, Response, Status};
+
+use crate::{
+  controller::{
+    helpers::{
+      build_pagination_response, display_currency, display_prices_fill, include_total, page_size,
+      pagination_cursor, sellers_fill,
+    },
+    Controller,
+  },
+  models::products_search::products_search_is_valid,
+};
+
+pub(super) async fn products_search(
+  c: &Controller,
+  request: Request<ProductsSearchRequest>,
+) -> Result<Response<ProductsSearchResponse>, Status> {
+  let start = std::time::Instant::now();
+  c.metrics.products_search_total.inc();
+
+  let ctx = request.extensions().get::<Arc<Context>>().cloned().unwrap();
+  let req = request.into_inner();
+
+  let path = "products.controller.products_search";
+  let return_err = |e: AppError| {
+    c.metrics.record_products_search_error();
+    Response::new(ProductsSearchResponse { response: Some(Error(e.to_proto())) })
+  };
+
+  let ie = |err: BoxedErr| {
+    let errors = Some(AppErrorErrors { err: Some(err), ..Default::default() });
+    AppError::new(ctx.clone(), path, MSG_ID_ERR_INTERNAL, None, "", Code::Internal.into(), errors)
+  };
+
+  let filters = match products_search_is_valid(ctx.clone(), &req) {
+    Ok(filters) => filters,
+    Err(err) => return Ok(return_err(err)),
+  };
+
+  let currency = match display_currency(ctx.clone(), path, &req.display_currency) {
+    Ok(currency) => currency,
+    Err(err) => return Ok(return_err(err)),
+  };
+
+  let sort = filters.sort.as_str();
+  let secret = &c.products_cfg.pagination_cursor_secret;
+  let cursor = match pagination_cursor(ctx.clone(), path, &req.pagination, secret, sort) {
+    Ok(cursor) => cursor,
+    Err(err) => return Ok(return_err(err)),
+  };
+
+  let limit = page_size(&req.pagination, 20, c.products_cfg.pagination_max_page_size);
+  let result = c.store.products_search(ctx.clone(), &filters, cursor.as_ref(), limit).await;
+  let mut page = match result {
+    Ok(page) => page,
+    Err(err) => return Ok(return_err(ie(Box::new(err)))),
+  };
+  if let Err(err) = sellers_fill(c, ctx.clone(), &mut page.items).await {
+    return Ok(return_err(ie(Box::new(err))));
+  }
+  display_prices_fill(c, currency, &mut page.items);
+
+  let mut total = None;
+  if include_total(&req.pagination) {
+    let exact_max = c.products_cfg.pagination_exact_total_max;
+    match c.store.products_search_total(ctx.clone(), &filters, exact_max).await {
+      Ok(counted) => total = Some(counted),
+      Err(err) => return Ok(return_err(ie(Box::new(err)))),
+    }
+  }
+
+  let duration = start.elapsed().as_secs_f64();
+  c.metrics.record_products_search_success(duration);
+  let pagination_response = build_pagination_response(cursor.as_ref(), &page, total, sort, secret);
+  Ok(Response::new(ProductsSearchResponse {
+    response: Some(Data(ProductsSearchResponseData {
+      products: page.items,
+      pagination: Some(pagination_response),
+    })),
+  }))
+}
@@ -11,7 +11,7 @@ use megacommerce_shared::models::{
 use tonic::{Code, Request, Response, Status};
 
 use crate::controller::{
-  helpers::{build_pagination_response, check_last_id},
+  helpers::{build_pagination_response, page_size, pagination_cursor},
   Controller,
 };
 
@@ -34,28 +34,31 @@ pub(super) async fn products_to_like(
     AppError::new(ctx.clone(), w, MSG_ID_ERR_INTERNAL, None, "", Code::Internal.into(), errors)
   };
 
-  if let Err(err) = check_last_id(ctx.clone(), w, &req.pagination) {
-    c.metrics.record_products_to_like_error();
-    return Ok(return_err(err));
-  }
+  let (secret, sort) = (&c.products_cfg.pagination_cursor_secret, c.recommender.sort());
+  let cursor = match pagination_cursor(ctx.clone(), w, &req.pagination, secret, sort) {
+    Ok(cursor) => cursor,
+    Err(err) => {
+      c.metrics.record_products_to_like_error();
+      return Ok(return_err(err));
+    }
+  };
 
-  let pagination = &mut req.pagination.unwrap();
-  pagination.page_size = Some(20);
-  let limit = pagination.page_size() as i64;
-  let result =
-    c.store.products_to_like(ctx.clone(), pagination.page(), pagination.last_id(), limit).await;
+  let max = c.products_cfg.pagination_max_page_size;
+  let limit = page_size(&req.pagination, 20, max);
+  let result = c.recommender.products_to_like(ctx.clone(), cursor.as_ref(), limit).await;
 
   if let Err(err) = result {
     c.metrics.record_products_to_like_error();
     return Ok(return_err(ie(Box::new(err))));
   } else {
-    let products = result.unwrap();
+    let page = result.unwrap();
     let duration = start.elapsed().as_secs_f64();
     c.metrics.record_products_to_like_success(duration);
+    let pagination = build_pagination_response(cursor.as_ref(), &page, None, sort, secret);
     Ok(Response::new(ProductsToLikeResponse {
       response: Some(Data(ProductsToLikeResponseData {
-        pagination: Some(build_pagination_response(pagination, products.iter().count())),
-        products,
+        pagination: Some(pagination),
+        products: page.items,
       })),
     }))
   }
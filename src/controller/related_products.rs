@@ -0,0 +1,82 @@
+use std::{
+  io::{Error, ErrorKind},
+  sync::Arc,
+};
+
+use megacommerce_proto::{
+  related_products_response::Response::{Data, Error as ResError},
+  RelatedProductsRequest, RelatedProductsResponse, RelatedProductsResponseData,
+};
+use megacommerce_shared::models::{
+  context::Context,
+  errors::{AppError, AppErrorErrors, BoxedErr, ErrorType},
+};
+use tonic::{Code, Request, Response, Status};
+
+use crate::{
+  controller::{
+    helpers::{display_currency, display_prices_fill, is_valid_ulid},
+    Controller,
+  },
+  models::related_products::RelatedProductsWeights,
+};
+
+/// The products similar to a product, for its "similar items" section
+pub(super) async fn related_products(
+  c: &Controller,
+  req: Request<RelatedProductsRequest>,
+) -> Result<Response<RelatedProductsResponse>, Status> {
+  let start = std::time::Instant::now();
+  c.metrics.related_products_total.inc();
+
+  let path = "products.controller.related_products";
+  let ctx = req.extensions().get::<Arc<Context>>().cloned().unwrap();
+  let req = req.into_inner();
+
+  let return_err = |e: AppError| {
+    c.metrics.record_related_products_error();
+    Response::new(RelatedProductsResponse { response: Some(ResError(e.to_proto())) })
+  };
+  let not_found = |err: BoxedErr| {
+    let errors = Some(AppErrorErrors { err: Some(err), ..Default::default() });
+    let id = "products.not_found.error";
+    return_err(AppError::new(ctx.clone(), path, id, None, "", Code::NotFound.into(), errors))
+  };
+
+  if !is_valid_ulid(&req.product_id) {
+    return Ok(not_found(Box::new(Error::new(ErrorKind::NotFound, "the product is not found"))));
+  }
+  let currency = match display_currency(ctx.clone(), path, &req.display_currency) {
+    Ok(currency) => currency,
+    Err(err) => return Ok(return_err(err)),
+  };
+
+  let cfg = &c.products_cfg;
+  let max = cfg.related_products_max.max(1);
+  let limit = req.limit.filter(|limit| *limit > 0).unwrap_or(max).min(max);
+  let weights = RelatedProductsWeights {
+    attributes: cfg.related_products_attributes_weight,
+    brand: cfg.related_products_brand_weight,
+    price: cfg.related_products_price_weight,
+    tags: cfg.related_products_tags_weight,
+  };
+  let pool = cfg.related_products_candidates as i64;
+
+  let result =
+    c.store.related_products(ctx.clone(), &req.product_id, weights, pool, limit as i64).await;
+  let mut products = match result {
+    Ok(products) => products,
+    Err(err) => match err.err_type {
+      ErrorType::NoRows => return Ok(not_found(Box::new(err))),
+      _ => return Ok(return_err(err.to_app_error_internal(ctx.clone(), path.into()))),
+    },
+  };
+  display_prices_fill(c, currency, &mut products);
+
+  let duration = start.elapsed().as_secs_f64();
+  c.metrics.record_related_products_success(duration);
+
+  Ok(Response::new(RelatedProductsResponse {
+    response: Some(Data(RelatedProductsResponseData { product_id: req.product_id, products })),
+  }))
+}
@@ -1,22 +1,37 @@
 use megacommerce_proto::{
-  products_service_server::ProductsService, BestSellingProductsRequest,
-  BestSellingProductsResponse, BigDiscountProductsRequest, BigDiscountProductsResponse,
-  CategoryNavbarRequest, CategoryNavbarResponse, HeroProductsRequest, HeroProductsResponse,
-  NewlyAddedProductsRequest, NewlyAddedProductsResponse, ProductCreateRequest,
+  products_service_server::ProductsService, BestSellingProductsRequest, BestSellingProductsResponse,
+  BestSellingRankingRequest, BestSellingRankingResponse, BigDiscountProductsRequest,
+  BigDiscountProductsResponse, CategoryNavbarRequest, CategoryNavbarResponse, HeroProductsRequest,
+  HeroProductsResponse, InventoryCommitRequest, InventoryReleaseRequest, InventoryReserveRequest,
+  InventoryRestockRequest, InventoryStockResponse, NewlyAddedProductsRequest,
+  NewlyAddedProductsResponse, PriceQuoteRequest, PriceQuoteResponse, ProductCreateRequest,
   ProductCreateResponse, ProductDataRequest, ProductDataResponse, ProductDetailsRequest,
-  ProductDetailsResponse, ProductSnapshotRequest, ProductSnapshotResponse, ProductsCategoryRequest,
-  ProductsCategoryResponse, ProductsListRequest, ProductsListResponse, ProductsToLikeRequest,
-  ProductsToLikeResponse,
+  ProductDetailsResponse, ProductEventsRecordRequest, ProductEventsRecordResponse,
+  ProductMediaUploadUrlsRequest, ProductMediaUploadUrlsResponse, ProductReviewCreateRequest,
+  ProductReviewCreateResponse, ProductReviewModerateRequest, ProductReviewModerateResponse,
+  ProductReviewsListRequest, ProductReviewsListResponse, ProductSnapshotRequest,
+  ProductSnapshotResponse, ProductStatusUpdateRequest, ProductStatusUpdateResponse,
+  ProductUpdateRequest, ProductUpdateResponse, ProductsCategoryRequest, ProductsCategoryResponse,
+  ProductsListRequest, ProductsListResponse, ProductsSearchRequest, ProductsSearchResponse,
+  ProductsToLikeRequest, ProductsToLikeResponse, RelatedProductsRequest, RelatedProductsResponse,
 };
 use tonic::{Request, Response, Status};
 
 use crate::controller::{
-  best_selling_products::best_selling_products, big_discount_products::big_discount_products,
-  category_navbar::category_navbar, hero_products::hero_products,
-  newly_added_products::newly_added_products, product_create::product_create,
-  product_data::product_data, product_details::product_details, product_snapshot::product_snapshot,
+  best_selling_products::best_selling_products, best_selling_ranking::best_selling_ranking,
+  big_discount_products::big_discount_products, category_navbar::category_navbar,
+  hero_products::hero_products, inventory_commit::inventory_commit,
+  inventory_release::inventory_release, inventory_reserve::inventory_reserve,
+  inventory_restock::inventory_restock, newly_added_products::newly_added_products,
+  price_quote::price_quote, product_create::product_create, product_data::product_data,
+  product_details::product_details, product_events_record::product_events_record,
+  product_media_upload_urls::product_media_upload_urls,
+  product_review_create::product_review_create, product_review_moderate::product_review_moderate,
+  product_reviews_list::product_reviews_list, product_snapshot::product_snapshot,
+  product_status_update::product_status_update, product_update::product_update,
   products_category::products_category, products_list::products_list,
-  products_to_like::products_to_like, Controller,
+  products_search::products_search, products_to_like::products_to_like,
+  related_products::related_products, Controller,
 };
 
 #[tonic::async_trait]
@@ -27,6 +42,24 @@ impl ProductsService for Controller {
   ) -> Result<Response<ProductCreateResponse>, Status> {
     product_create(self, req).await
   }
+  async fn product_update(
+    &self,
+    req: Request<ProductUpdateRequest>,
+  ) -> Result<Response<ProductUpdateResponse>, Status> {
+    product_update(self, req).await
+  }
+  async fn product_status_update(
+    &self,
+    req: Request<ProductStatusUpdateRequest>,
+  ) -> Result<Response<ProductStatusUpdateResponse>, Status> {
+    product_status_update(self, req).await
+  }
+  async fn product_media_upload_urls(
+    &self,
+    req: Request<ProductMediaUploadUrlsRequest>,
+  ) -> Result<Response<ProductMediaUploadUrlsResponse>, Status> {
+    product_media_upload_urls(self, req).await
+  }
   async fn product_data(
     &self,
     req: Request<ProductDataRequest>,
@@ -93,4 +126,76 @@ impl ProductsService for Controller {
   ) -> Result<Response<ProductsListResponse>, Status> {
     products_list(self, req).await
   }
+  async fn products_search(
+    &self,
+    req: Request<ProductsSearchRequest>,
+  ) -> Result<Response<ProductsSearchResponse>, Status> {
+    products_search(self, req).await
+  }
+  async fn product_review_create(
+    &self,
+    req: Request<ProductReviewCreateRequest>,
+  ) -> Result<Response<ProductReviewCreateResponse>, Status> {
+    product_review_create(self, req).await
+  }
+  async fn product_reviews_list(
+    &self,
+    req: Request<ProductReviewsListRequest>,
+  ) -> Result<Response<ProductReviewsListResponse>, Status> {
+    product_reviews_list(self, req).await
+  }
+  async fn product_review_moderate(
+    &self,
+    req: Request<ProductReviewModerateRequest>,
+  ) -> Result<Response<ProductReviewModerateResponse>, Status> {
+    product_review_moderate(self, req).await
+  }
+  async fn price_quote(
+    &self,
+    req: Request<PriceQuoteRequest>,
+  ) -> Result<Response<PriceQuoteResponse>, Status> {
+    price_quote(self, req).await
+  }
+  async fn inventory_reserve(
+    &self,
+    req: Request<InventoryReserveRequest>,
+  ) -> Result<Response<InventoryStockResponse>, Status> {
+    inventory_reserve(self, req).await
+  }
+  async fn inventory_commit(
+    &self,
+    req: Request<InventoryCommitRequest>,
+  ) -> Result<Response<InventoryStockResponse>, Status> {
+    inventory_commit(self, req).await
+  }
+  async fn inventory_release(
+    &self,
+    req: Request<InventoryReleaseRequest>,
+  ) -> Result<Response<InventoryStockResponse>, Status> {
+    inventory_release(self, req).await
+  }
+  async fn inventory_restock(
+    &self,
+    req: Request<InventoryRestockRequest>,
+  ) -> Result<Response<InventoryStockResponse>, Status> {
+    inventory_restock(self, req).await
+  }
+  async fn best_selling_ranking(
+    &self,
+    req: Request<BestSellingRankingRequest>,
+  ) -> Result<Response<BestSellingRankingResponse>, Status> {
+    best_selling_ranking(self, req).await
+  }
+  async fn product_events_record(
+    &self,
+    req: Request<ProductEventsRecordRequest>,
+  ) -> Result<Response<ProductEventsRecordResponse>, Status> {
+    product_events_record(self, req).await
+  }
+  async fn related_products(
+    &self,
+    req: Request<RelatedProductsRequest>,
+  ) -> Result<Response<RelatedProductsResponse>, Status> {
+    related_products(self, req).await
+  }
 }
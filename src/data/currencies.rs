@@ -1,176 +1,183 @@
+#[derive(Debug, PartialEq, Eq)]
 pub struct Currency {
   pub name: &'static str,
   pub code: &'static str,
   pub symbol: &'static str,
+  /// The number of decimals of the minor unit (ISO 4217), e.g. 2 for USD, 0 for JPY, 3 for KWD
+  pub exponent: u32,
 }
 
 pub const CURRENCY_LIST: &[Currency] = &[
-  Currency { name: "Afghan Afghani", code: "AFA", symbol: "؋" },
-  Currency { name: "Albanian Lek", code: "ALL", symbol: "Lek" },
-  Currency { name: "Algerian Dinar", code: "DZD", symbol: "دج" },
-  Currency { name: "Angolan Kwanza", code: "AOA", symbol: "Kz" },
-  Currency { name: "Argentine Peso", code: "ARS", symbol: "$" },
-  Currency { name: "Armenian Dram", code: "AMD", symbol: "֏" },
-  Currency { name: "Aruban Florin", code: "AWG", symbol: "ƒ" },
-  Currency { name: "Australian Dollar", code: "AUD", symbol: "$" },
-  Currency { name: "Azerbaijani Manat", code: "AZN", symbol: "m" },
-  Currency { name: "Bahamian Dollar", code: "BSD", symbol: "B$" },
-  Currency { name: "Bahraini Dinar", code: "BHD", symbol: ".د.ب" },
-  Currency { name: "Bangladeshi Taka", code: "BDT", symbol: "৳" },
-  Currency { name: "Barbadian Dollar", code: "BBD", symbol: "Bds$" },
-  Currency { name: "Belarusian Ruble", code: "BYR", symbol: "Br" },
-  Currency { name: "Belgian Franc", code: "BEF", symbol: "fr" },
-  Currency { name: "Belize Dollar", code: "BZD", symbol: "$" },
-  Currency { name: "Bermudan Dollar", code: "BMD", symbol: "$" },
-  Currency { name: "Bhutanese Ngultrum", code: "BTN", symbol: "Nu." },
-  Currency { name: "Bitcoin", code: "BTC", symbol: "฿" },
-  Currency { name: "Bolivian Boliviano", code: "BOB", symbol: "Bs." },
-  Currency { name: "Bosnia-Herzegovina Convertible Mark", code: "BAM", symbol: "KM" },
-  Currency { name: "Botswanan Pula", code: "BWP", symbol: "P" },
-  Currency { name: "Brazilian Real", code: "BRL", symbol: "R$" },
-  Currency { name: "British Pound Sterling", code: "GBP", symbol: "£" },
-  Currency { name: "Brunei Dollar", code: "BND", symbol: "B$" },
-  Currency { name: "Bulgarian Lev", code: "BGN", symbol: "Лв." },
-  Currency { name: "Burundian Franc", code: "BIF", symbol: "FBu" },
-  Currency { name: "Cambodian Riel", code: "KHR", symbol: "KHR" },
-  Currency { name: "Canadian Dollar", code: "CAD", symbol: "$" },
-  Currency { name: "Cape Verdean Escudo", code: "CVE", symbol: "$" },
-  Currency { name: "Cayman Islands Dollar", code: "KYD", symbol: "$" },
-  Currency { name: "CFA Franc BCEAO", code: "XOF", symbol: "CFA" },
-  Currency { name: "CFA Franc BEAC", code: "XAF", symbol: "FCFA" },
-  Currency { name: "CFP Franc", code: "XPF", symbol: "₣" },
-  Currency { name: "Chilean Peso", code: "CLP", symbol: "$" },
-  Currency { name: "Chilean Unit of Account", code: "CLF", symbol: "CLF" },
-  Currency { name: "Chinese Yuan", code: "CNY", symbol: "¥" },
-  Currency { name: "Colombian Peso", code: "COP", symbol: "$" },
-  Currency { name: "Comorian Franc", code: "KMF", symbol: "CF" },
-  Currency { name: "Congolese Franc", code: "CDF", symbol: "FC" },
-  Currency { name: "Costa Rican Colón", code: "CRC", symbol: "₡" },
-  Currency { name: "Croatian Kuna", code: "HRK", symbol: "kn" },
-  Currency { name: "Cuban Convertible Peso", code: "CUC", symbol: "$, CUC" },
-  Currency { name: "Czech Republic Koruna", code: "CZK", symbol: "Kč" },
-  Currency { name: "Danish Krone", code: "DKK", symbol: "Kr." },
-  Currency { name: "Djiboutian Franc", code: "DJF", symbol: "Fdj" },
-  Currency { name: "Dominican Peso", code: "DOP", symbol: "$" },
-  Currency { name: "East Caribbean Dollar", code: "XCD", symbol: "$" },
-  Currency { name: "Egyptian Pound", code: "EGP", symbol: "ج.م" },
-  Currency { name: "Eritrean Nakfa", code: "ERN", symbol: "Nfk" },
-  Currency { name: "Estonian Kroon", code: "EEK", symbol: "kr" },
-  Currency { name: "Ethiopian Birr", code: "ETB", symbol: "Nkf" },
-  Currency { name: "Euro", code: "EUR", symbol: "€" },
-  Currency { name: "Falkland Islands Pound", code: "FKP", symbol: "£" },
-  Currency { name: "Fijian Dollar", code: "FJD", symbol: "FJ$" },
-  Currency { name: "Gambian Dalasi", code: "GMD", symbol: "D" },
-  Currency { name: "Georgian Lari", code: "GEL", symbol: "ლ" },
-  Currency { name: "German Mark", code: "DEM", symbol: "DM" },
-  Currency { name: "Ghanaian Cedi", code: "GHS", symbol: "GH₵" },
-  Currency { name: "Gibraltar Pound", code: "GIP", symbol: "£" },
-  Currency { name: "Greek Drachma", code: "GRD", symbol: "₯, Δρχ, Δρ" },
-  Currency { name: "Guatemalan Quetzal", code: "GTQ", symbol: "Q" },
-  Currency { name: "Guinean Franc", code: "GNF", symbol: "FG" },
-  Currency { name: "Guyanaese Dollar", code: "GYD", symbol: "$" },
-  Currency { name: "Haitian Gourde", code: "HTG", symbol: "G" },
-  Currency { name: "Honduran Lempira", code: "HNL", symbol: "L" },
-  Currency { name: "Hong Kong Dollar", code: "HKD", symbol: "$" },
-  Currency { name: "Hungarian Forint", code: "HUF", symbol: "Ft" },
-  Currency { name: "Icelandic Króna", code: "ISK", symbol: "kr" },
-  Currency { name: "Indian Rupee", code: "INR", symbol: "₹" },
-  Currency { name: "Indonesian Rupiah", code: "IDR", symbol: "Rp" },
-  Currency { name: "Iranian Rial", code: "IRR", symbol: "﷼" },
-  Currency { name: "Iraqi Dinar", code: "IQD", symbol: "د.ع" },
-  Currency { name: "Israeli New Sheqel", code: "ILS", symbol: "₪" },
-  Currency { name: "Italian Lira", code: "ITL", symbol: "L,£" },
-  Currency { name: "Jamaican Dollar", code: "JMD", symbol: "J$" },
-  Currency { name: "Japanese Yen", code: "JPY", symbol: "¥" },
-  Currency { name: "Jordanian Dinar", code: "JOD", symbol: "ا.د" },
-  Currency { name: "Kazakhstani Tenge", code: "KZT", symbol: "лв" },
-  Currency { name: "Kenyan Shilling", code: "KES", symbol: "KSh" },
-  Currency { name: "Kuwaiti Dinar", code: "KWD", symbol: "ك.د" },
-  Currency { name: "Kyrgystani Som", code: "KGS", symbol: "лв" },
-  Currency { name: "Laotian Kip", code: "LAK", symbol: "₭" },
-  Currency { name: "Latvian Lats", code: "LVL", symbol: "Ls" },
-  Currency { name: "Lebanese Pound", code: "LBP", symbol: "£" },
-  Currency { name: "Lesotho Loti", code: "LSL", symbol: "L" },
-  Currency { name: "Liberian Dollar", code: "LRD", symbol: "$" },
-  Currency { name: "Libyan Dinar", code: "LYD", symbol: "د.ل" },
-  Currency { name: "Litecoin", code: "LTC", symbol: "Ł" },
-  Currency { name: "Lithuanian Litas", code: "LTL", symbol: "Lt" },
-  Currency { name: "Macanese Pataca", code: "MOP", symbol: "$" },
-  Currency { name: "Macedonian Denar", code: "MKD", symbol: "ден" },
-  Currency { name: "Malagasy Ariary", code: "MGA", symbol: "Ar" },
-  Currency { name: "Malawian Kwacha", code: "MWK", symbol: "MK" },
-  Currency { name: "Malaysian Ringgit", code: "MYR", symbol: "RM" },
-  Currency { name: "Maldivian Rufiyaa", code: "MVR", symbol: "Rf" },
-  Currency { name: "Mauritanian Ouguiya", code: "MRO", symbol: "MRU" },
-  Currency { name: "Mauritian Rupee", code: "MUR", symbol: "₨" },
-  Currency { name: "Mexican Peso", code: "MXN", symbol: "$" },
-  Currency { name: "Moldovan Leu", code: "MDL", symbol: "L" },
-  Currency { name: "Mongolian Tugrik", code: "MNT", symbol: "₮" },
-  Currency { name: "Moroccan Dirham", code: "MAD", symbol: "MAD" },
-  Currency { name: "Mozambican Metical", code: "MZM", symbol: "MT" },
-  Currency { name: "Myanmar Kyat", code: "MMK", symbol: "K" },
-  Currency { name: "Namibian Dollar", code: "NAD", symbol: "$" },
-  Currency { name: "Nepalese Rupee", code: "NPR", symbol: "₨" },
-  Currency { name: "Netherlands Antillean Guilder", code: "ANG", symbol: "ƒ" },
-  Currency { name: "New Taiwan Dollar", code: "TWD", symbol: "$" },
-  Currency { name: "New Zealand Dollar", code: "NZD", symbol: "$" },
-  Currency { name: "Nicaraguan Córdoba", code: "NIO", symbol: "C$" },
-  Currency { name: "Nigerian Naira", code: "NGN", symbol: "₦" },
-  Currency { name: "North Korean Won", code: "KPW", symbol: "₩" },
-  Currency { name: "Norwegian Krone", code: "NOK", symbol: "kr" },
-  Currency { name: "Omani Rial", code: "OMR", symbol: ".ع.ر" },
-  Currency { name: "Pakistani Rupee", code: "PKR", symbol: "₨" },
-  Currency { name: "Panamanian Balboa", code: "PAB", symbol: "B/." },
-  Currency { name: "Papua New Guinean Kina", code: "PGK", symbol: "K" },
-  Currency { name: "Paraguayan Guarani", code: "PYG", symbol: "₲" },
-  Currency { name: "Peruvian Nuevo Sol", code: "PEN", symbol: "S/." },
-  Currency { name: "Philippine Peso", code: "PHP", symbol: "₱" },
-  Currency { name: "Polish Zloty", code: "PLN", symbol: "zł" },
-  Currency { name: "Qatari Rial", code: "QAR", symbol: "ق.ر" },
-  Currency { name: "Romanian Leu", code: "RON", symbol: "lei" },
-  Currency { name: "Russian Ruble", code: "RUB", symbol: "₽" },
-  Currency { name: "Rwandan Franc", code: "RWF", symbol: "FRw" },
-  Currency { name: "Salvadoran Colón", code: "SVC", symbol: "₡" },
-  Currency { name: "Samoan Tala", code: "WST", symbol: "SAT" },
-  Currency { name: "São Tomé and Príncipe Dobra", code: "STD", symbol: "Db" },
-  Currency { name: "Saudi Riyal", code: "SAR", symbol: "﷼" },
-  Currency { name: "Serbian Dinar", code: "RSD", symbol: "din" },
-  Currency { name: "Seychellois Rupee", code: "SCR", symbol: "SRe" },
-  Currency { name: "Sierra Leonean Leone", code: "SLL", symbol: "Le" },
-  Currency { name: "Singapore Dollar", code: "SGD", symbol: "$" },
-  Currency { name: "Slovak Koruna", code: "SKK", symbol: "Sk" },
-  Currency { name: "Solomon Islands Dollar", code: "SBD", symbol: "Si$" },
-  Currency { name: "Somali Shilling", code: "SOS", symbol: "Sh.so." },
-  Currency { name: "South African Rand", code: "ZAR", symbol: "R" },
-  Currency { name: "South Korean Won", code: "KRW", symbol: "₩" },
-  Currency { name: "South Sudanese Pound", code: "SSP", symbol: "£" },
-  Currency { name: "Special Drawing Rights", code: "XDR", symbol: "SDR" },
-  Currency { name: "Sri Lankan Rupee", code: "LKR", symbol: "Rs" },
-  Currency { name: "St. Helena Pound", code: "SHP", symbol: "£" },
-  Currency { name: "Sudanese Pound", code: "SDG", symbol: ".س.ج" },
-  Currency { name: "Surinamese Dollar", code: "SRD", symbol: "$" },
-  Currency { name: "Swazi Lilangeni", code: "SZL", symbol: "E" },
-  Currency { name: "Swedish Krona", code: "SEK", symbol: "kr" },
-  Currency { name: "Swiss Franc", code: "CHF", symbol: "CHf" },
-  Currency { name: "Syrian Pound", code: "SYP", symbol: "LS" },
-  Currency { name: "Tajikistani Somoni", code: "TJS", symbol: "SM" },
-  Currency { name: "Tanzanian Shilling", code: "TZS", symbol: "TSh" },
-  Currency { name: "Thai Baht", code: "THB", symbol: "฿" },
-  Currency { name: "Tongan Pa'anga", code: "TOP", symbol: "$" },
-  Currency { name: "Trinidad & Tobago Dollar", code: "TTD", symbol: "$" },
-  Currency { name: "Tunisian Dinar", code: "TND", symbol: "ت.د" },
-  Currency { name: "Turkish Lira", code: "TRY", symbol: "₺" },
-  Currency { name: "Turkmenistani Manat", code: "TMT", symbol: "T" },
-  Currency { name: "Ugandan Shilling", code: "UGX", symbol: "USh" },
-  Currency { name: "Ukrainian Hryvnia", code: "UAH", symbol: "₴" },
-  Currency { name: "United Arab Emirates Dirham", code: "AED", symbol: "إ.د" },
-  Currency { name: "Uruguayan Peso", code: "UYU", symbol: "$" },
-  Currency { name: "US Dollar", code: "USD", symbol: "$" },
-  Currency { name: "Uzbekistan Som", code: "UZS", symbol: "лв" },
-  Currency { name: "Vanuatu Vatu", code: "VUV", symbol: "VT" },
-  Currency { name: "Venezuelan BolÃvar", code: "VEF", symbol: "Bs" },
-  Currency { name: "Vietnamese Dong", code: "VND", symbol: "₫" },
-  Currency { name: "Yemeni Rial", code: "YER", symbol: "﷼" },
-  Currency { name: "Zambian Kwacha", code: "ZMK", symbol: "ZK" },
-  Currency { name: "Zimbabwean dollar", code: "ZWL", symbol: "$" },
+  Currency { name: "Afghan Afghani", code: "AFA", symbol: "؋", exponent: 2 },
+  Currency { name: "Albanian Lek", code: "ALL", symbol: "Lek", exponent: 2 },
+  Currency { name: "Algerian Dinar", code: "DZD", symbol: "دج", exponent: 2 },
+  Currency { name: "Angolan Kwanza", code: "AOA", symbol: "Kz", exponent: 2 },
+  Currency { name: "Argentine Peso", code: "ARS", symbol: "$", exponent: 2 },
+  Currency { name: "Armenian Dram", code: "AMD", symbol: "֏", exponent: 2 },
+  Currency { name: "Aruban Florin", code: "AWG", symbol: "ƒ", exponent: 2 },
+  Currency { name: "Australian Dollar", code: "AUD", symbol: "$", exponent: 2 },
+  Currency { name: "Azerbaijani Manat", code: "AZN", symbol: "m", exponent: 2 },
+  Currency { name: "Bahamian Dollar", code: "BSD", symbol: "B$", exponent: 2 },
+  Currency { name: "Bahraini Dinar", code: "BHD", symbol: ".د.ب", exponent: 3 },
+  Currency { name: "Bangladeshi Taka", code: "BDT", symbol: "৳", exponent: 2 },
+  Currency { name: "Barbadian Dollar", code: "BBD", symbol: "Bds$", exponent: 2 },
+  Currency { name: "Belarusian Ruble", code: "BYR", symbol: "Br", exponent: 0 },
+  Currency { name: "Belgian Franc", code: "BEF", symbol: "fr", exponent: 0 },
+  Currency { name: "Belize Dollar", code: "BZD", symbol: "$", exponent: 2 },
+  Currency { name: "Bermudan Dollar", code: "BMD", symbol: "$", exponent: 2 },
+  Currency { name: "Bhutanese Ngultrum", code: "BTN", symbol: "Nu.", exponent: 2 },
+  Currency { name: "Bitcoin", code: "BTC", symbol: "฿", exponent: 8 },
+  Currency { name: "Bolivian Boliviano", code: "BOB", symbol: "Bs.", exponent: 2 },
+  Currency { name: "Bosnia-Herzegovina Convertible Mark", code: "BAM", symbol: "KM", exponent: 2 },
+  Currency { name: "Botswanan Pula", code: "BWP", symbol: "P", exponent: 2 },
+  Currency { name: "Brazilian Real", code: "BRL", symbol: "R$", exponent: 2 },
+  Currency { name: "British Pound Sterling", code: "GBP", symbol: "£", exponent: 2 },
+  Currency { name: "Brunei Dollar", code: "BND", symbol: "B$", exponent: 2 },
+  Currency { name: "Bulgarian Lev", code: "BGN", symbol: "Лв.", exponent: 2 },
+  Currency { name: "Burundian Franc", code: "BIF", symbol: "FBu", exponent: 0 },
+  Currency { name: "Cambodian Riel", code: "KHR", symbol: "KHR", exponent: 2 },
+  Currency { name: "Canadian Dollar", code: "CAD", symbol: "$", exponent: 2 },
+  Currency { name: "Cape Verdean Escudo", code: "CVE", symbol: "$", exponent: 2 },
+  Currency { name: "Cayman Islands Dollar", code: "KYD", symbol: "$", exponent: 2 },
+  Currency { name: "CFA Franc BCEAO", code: "XOF", symbol: "CFA", exponent: 0 },
+  Currency { name: "CFA Franc BEAC", code: "XAF", symbol: "FCFA", exponent: 0 },
+  Currency { name: "CFP Franc", code: "XPF", symbol: "₣", exponent: 0 },
+  Currency { name: "Chilean Peso", code: "CLP", symbol: "$", exponent: 0 },
+  Currency { name: "Chilean Unit of Account", code: "CLF", symbol: "CLF", exponent: 4 },
+  Currency { name: "Chinese Yuan", code: "CNY", symbol: "¥", exponent: 2 },
+  Currency { name: "Colombian Peso", code: "COP", symbol: "$", exponent: 2 },
+  Currency { name: "Comorian Franc", code: "KMF", symbol: "CF", exponent: 0 },
+  Currency { name: "Congolese Franc", code: "CDF", symbol: "FC", exponent: 2 },
+  Currency { name: "Costa Rican Colón", code: "CRC", symbol: "₡", exponent: 2 },
+  Currency { name: "Croatian Kuna", code: "HRK", symbol: "kn", exponent: 2 },
+  Currency { name: "Cuban Convertible Peso", code: "CUC", symbol: "$, CUC", exponent: 2 },
+  Currency { name: "Czech Republic Koruna", code: "CZK", symbol: "Kč", exponent: 2 },
+  Currency { name: "Danish Krone", code: "DKK", symbol: "Kr.", exponent: 2 },
+  Currency { name: "Djiboutian Franc", code: "DJF", symbol: "Fdj", exponent: 0 },
+  Currency { name: "Dominican Peso", code: "DOP", symbol: "$", exponent: 2 },
+  Currency { name: "East Caribbean Dollar", code: "XCD", symbol: "$", exponent: 2 },
+  Currency { name: "Egyptian Pound", code: "EGP", symbol: "ج.م", exponent: 2 },
+  Currency { name: "Eritrean Nakfa", code: "ERN", symbol: "Nfk", exponent: 2 },
+  Currency { name: "Estonian Kroon", code: "EEK", symbol: "kr", exponent: 2 },
+  Currency { name: "Ethiopian Birr", code: "ETB", symbol: "Nkf", exponent: 2 },
+  Currency { name: "Euro", code: "EUR", symbol: "€", exponent: 2 },
+  Currency { name: "Falkland Islands Pound", code: "FKP", symbol: "£", exponent: 2 },
+  Currency { name: "Fijian Dollar", code: "FJD", symbol: "FJ$", exponent: 2 },
+  Currency { name: "Gambian Dalasi", code: "GMD", symbol: "D", exponent: 2 },
+  Currency { name: "Georgian Lari", code: "GEL", symbol: "ლ", exponent: 2 },
+  Currency { name: "German Mark", code: "DEM", symbol: "DM", exponent: 2 },
+  Currency { name: "Ghanaian Cedi", code: "GHS", symbol: "GH₵", exponent: 2 },
+  Currency { name: "Gibraltar Pound", code: "GIP", symbol: "£", exponent: 2 },
+  Currency { name: "Greek Drachma", code: "GRD", symbol: "₯, Δρχ, Δρ", exponent: 2 },
+  Currency { name: "Guatemalan Quetzal", code: "GTQ", symbol: "Q", exponent: 2 },
+  Currency { name: "Guinean Franc", code: "GNF", symbol: "FG", exponent: 0 },
+  Currency { name: "Guyanaese Dollar", code: "GYD", symbol: "$", exponent: 2 },
+  Currency { name: "Haitian Gourde", code: "HTG", symbol: "G", exponent: 2 },
+  Currency { name: "Honduran Lempira", code: "HNL", symbol: "L", exponent: 2 },
+  Currency { name: "Hong Kong Dollar", code: "HKD", symbol: "$", exponent: 2 },
+  Currency { name: "Hungarian Forint", code: "HUF", symbol: "Ft", exponent: 2 },
+  Currency { name: "Icelandic Króna", code: "ISK", symbol: "kr", exponent: 0 },
+  Currency { name: "Indian Rupee", code: "INR", symbol: "₹", exponent: 2 },
+  Currency { name: "Indonesian Rupiah", code: "IDR", symbol: "Rp", exponent: 2 },
+  Currency { name: "Iranian Rial", code: "IRR", symbol: "﷼", exponent: 2 },
+  Currency { name: "Iraqi Dinar", code: "IQD", symbol: "د.ع", exponent: 3 },
+  Currency { name: "Israeli New Sheqel", code: "ILS", symbol: "₪", exponent: 2 },
+  Currency { name: "Italian Lira", code: "ITL", symbol: "L,£", exponent: 0 },
+  Currency { name: "Jamaican Dollar", code: "JMD", symbol: "J$", exponent: 2 },
+  Currency { name: "Japanese Yen", code: "JPY", symbol: "¥", exponent: 0 },
+  Currency { name: "Jordanian Dinar", code: "JOD", symbol: "ا.د", exponent: 3 },
+  Currency { name: "Kazakhstani Tenge", code: "KZT", symbol: "лв", exponent: 2 },
+  Currency { name: "Kenyan Shilling", code: "KES", symbol: "KSh", exponent: 2 },
+  Currency { name: "Kuwaiti Dinar", code: "KWD", symbol: "ك.د", exponent: 3 },
+  Currency { name: "Kyrgystani Som", code: "KGS", symbol: "лв", exponent: 2 },
+  Currency { name: "Laotian Kip", code: "LAK", symbol: "₭", exponent: 2 },
+  Currency { name: "Latvian Lats", code: "LVL", symbol: "Ls", exponent: 2 },
+  Currency { name: "Lebanese Pound", code: "LBP", symbol: "£", exponent: 2 },
+  Currency { name: "Lesotho Loti", code: "LSL", symbol: "L", exponent: 2 },
+  Currency { name: "Liberian Dollar", code: "LRD", symbol: "$", exponent: 2 },
+  Currency { name: "Libyan Dinar", code: "LYD", symbol: "د.ل", exponent: 3 },
+  Currency { name: "Litecoin", code: "LTC", symbol: "Ł", exponent: 8 },
+  Currency { name: "Lithuanian Litas", code: "LTL", symbol: "Lt", exponent: 2 },
+  Currency { name: "Macanese Pataca", code: "MOP", symbol: "$", exponent: 2 },
+  Currency { name: "Macedonian Denar", code: "MKD", symbol: "ден", exponent: 2 },
+  Currency { name: "Malagasy Ariary", code: "MGA", symbol: "Ar", exponent: 2 },
+  Currency { name: "Malawian Kwacha", code: "MWK", symbol: "MK", exponent: 2 },
+  Currency { name: "Malaysian Ringgit", code: "MYR", symbol: "RM", exponent: 2 },
+  Currency { name: "Maldivian Rufiyaa", code: "MVR", symbol: "Rf", exponent: 2 },
+  Currency { name: "Mauritanian Ouguiya", code: "MRO", symbol: "MRU", exponent: 2 },
+  Currency { name: "Mauritian Rupee", code: "MUR", symbol: "₨", exponent: 2 },
+  Currency { name: "Mexican Peso", code: "MXN", symbol: "$", exponent: 2 },
+  Currency { name: "Moldovan Leu", code: "MDL", symbol: "L", exponent: 2 },
+  Currency { name: "Mongolian Tugrik", code: "MNT", symbol: "₮", exponent: 2 },
+  Currency { name: "Moroccan Dirham", code: "MAD", symbol: "MAD", exponent: 2 },
+  Currency { name: "Mozambican Metical", code: "MZM", symbol: "MT", exponent: 2 },
+  Currency { name: "Myanmar Kyat", code: "MMK", symbol: "K", exponent: 2 },
+  Currency { name: "Namibian Dollar", code: "NAD", symbol: "$", exponent: 2 },
+  Currency { name: "Nepalese Rupee", code: "NPR", symbol: "₨", exponent: 2 },
+  Currency { name: "Netherlands Antillean Guilder", code: "ANG", symbol: "ƒ", exponent: 2 },
+  Currency { name: "New Taiwan Dollar", code: "TWD", symbol: "$", exponent: 2 },
+  Currency { name: "New Zealand Dollar", code: "NZD", symbol: "$", exponent: 2 },
+  Currency { name: "Nicaraguan Córdoba", code: "NIO", symbol: "C$", exponent: 2 },
+  Currency { name: "Nigerian Naira", code: "NGN", symbol: "₦", exponent: 2 },
+  Currency { name: "North Korean Won", code: "KPW", symbol: "₩", exponent: 2 },
+  Currency { name: "Norwegian Krone", code: "NOK", symbol: "kr", exponent: 2 },
+  Currency { name: "Omani Rial", code: "OMR", symbol: ".ع.ر", exponent: 3 },
+  Currency { name: "Pakistani Rupee", code: "PKR", symbol: "₨", exponent: 2 },
+  Currency { name: "Panamanian Balboa", code: "PAB", symbol: "B/.", exponent: 2 },
+  Currency { name: "Papua New Guinean Kina", code: "PGK", symbol: "K", exponent: 2 },
+  Currency { name: "Paraguayan Guarani", code: "PYG", symbol: "₲", exponent: 0 },
+  Currency { name: "Peruvian Nuevo Sol", code: "PEN", symbol: "S/.", exponent: 2 },
+  Currency { name: "Philippine Peso", code: "PHP", symbol: "₱", exponent: 2 },
+  Currency { name: "Polish Zloty", code: "PLN", symbol: "zł", exponent: 2 },
+  Currency { name: "Qatari Rial", code: "QAR", symbol: "ق.ر", exponent: 2 },
+  Currency { name: "Romanian Leu", code: "RON", symbol: "lei", exponent: 2 },
+  Currency { name: "Russian Ruble", code: "RUB", symbol: "₽", exponent: 2 },
+  Currency { name: "Rwandan Franc", code: "RWF", symbol: "FRw", exponent: 0 },
+  Currency { name: "Salvadoran Colón", code: "SVC", symbol: "₡", exponent: 2 },
+  Currency { name: "Samoan Tala", code: "WST", symbol: "SAT", exponent: 2 },
+  Currency { name: "São Tomé and Príncipe Dobra", code: "STD", symbol: "Db", exponent: 2 },
+  Currency { name: "Saudi Riyal", code: "SAR", symbol: "﷼", exponent: 2 },
+  Currency { name: "Serbian Dinar", code: "RSD", symbol: "din", exponent: 2 },
+  Currency { name: "Seychellois Rupee", code: "SCR", symbol: "SRe", exponent: 2 },
+  Currency { name: "Sierra Leonean Leone", code: "SLL", symbol: "Le", exponent: 2 },
+  Currency { name: "Singapore Dollar", code: "SGD", symbol: "$", exponent: 2 },
+  Currency { name: "Slovak Koruna", code: "SKK", symbol: "Sk", exponent: 2 },
+  Currency { name: "Solomon Islands Dollar", code: "SBD", symbol: "Si$", exponent: 2 },
+  Currency { name: "Somali Shilling", code: "SOS", symbol: "Sh.so.", exponent: 2 },
+  Currency { name: "South African Rand", code: "ZAR", symbol: "R", exponent: 2 },
+  Currency { name: "South Korean Won", code: "KRW", symbol: "₩", exponent: 0 },
+  Currency { name: "South Sudanese Pound", code: "SSP", symbol: "£", exponent: 2 },
+  Currency { name: "Special Drawing Rights", code: "XDR", symbol: "SDR", exponent: 2 },
+  Currency { name: "Sri Lankan Rupee", code: "LKR", symbol: "Rs", exponent: 2 },
+  Currency { name: "St. Helena Pound", code: "SHP", symbol: "£", exponent: 2 },
+  Currency { name: "Sudanese Pound", code: "SDG", symbol: ".س.ج", exponent: 2 },
+  Currency { name: "Surinamese Dollar", code: "SRD", symbol: "$", exponent: 2 },
+  Currency { name: "Swazi Lilangeni", code: "SZL", symbol: "E", exponent: 2 },
+  Currency { name: "Swedish Krona", code: "SEK", symbol: "kr", exponent: 2 },
+  Currency { name: "Swiss Franc", code: "CHF", symbol: "CHf", exponent: 2 },
+  Currency { name: "Syrian Pound", code: "SYP", symbol: "LS", exponent: 2 },
+  Currency { name: "Tajikistani Somoni", code: "TJS", symbol: "SM", exponent: 2 },
+  Currency { name: "Tanzanian Shilling", code: "TZS", symbol: "TSh", exponent: 2 },
+  Currency { name: "Thai Baht", code: "THB", symbol: "฿", exponent: 2 },
+  Currency { name: "Tongan Pa'anga", code: "TOP", symbol: "$", exponent: 2 },
+  Currency { name: "Trinidad & Tobago Dollar", code: "TTD", symbol: "$", exponent: 2 },
+  Currency { name: "Tunisian Dinar", code: "TND", symbol: "ت.د", exponent: 3 },
+  Currency { name: "Turkish Lira", code: "TRY", symbol: "₺", exponent: 2 },
+  Currency { name: "Turkmenistani Manat", code: "TMT", symbol: "T", exponent: 2 },
+  Currency { name: "Ugandan Shilling", code: "UGX", symbol: "USh", exponent: 0 },
+  Currency { name: "Ukrainian Hryvnia", code: "UAH", symbol: "₴", exponent: 2 },
+  Currency { name: "United Arab Emirates Dirham", code: "AED", symbol: "إ.د", exponent: 2 },
+  Currency { name: "Uruguayan Peso", code: "UYU", symbol: "$", exponent: 2 },
+  Currency { name: "US Dollar", code: "USD", symbol: "$", exponent: 2 },
+  Currency { name: "Uzbekistan Som", code: "UZS", symbol: "лв", exponent: 2 },
+  Currency { name: "Vanuatu Vatu", code: "VUV", symbol: "VT", exponent: 0 },
+  Currency { name: "Venezuelan BolÃvar", code: "VEF", symbol: "Bs", exponent: 2 },
+  Currency { name: "Vietnamese Dong", code: "VND", symbol: "₫", exponent: 0 },
+  Currency { name: "Yemeni Rial", code: "YER", symbol: "﷼", exponent: 2 },
+  Currency { name: "Zambian Kwacha", code: "ZMK", symbol: "ZK", exponent: 2 },
+  Currency { name: "Zimbabwean dollar", code: "ZWL", symbol: "$", exponent: 2 },
 ];
+
+pub fn currency_get(code: &str) -> Option<&'static Currency> {
+  CURRENCY_LIST.iter().find(|cur| cur.code == code)
+}
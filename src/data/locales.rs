@@ -0,0 +1,102 @@
+/// How the digits are grouped, the Indian grouping keeps the last 3 digits together
+/// and then groups by 2, e.g. 12,34,567
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DigitGrouping {
+  Thousands,
+  Indian,
+}
+
+/// The way a locale writes amounts of money
+#[derive(Debug, PartialEq, Eq)]
+pub struct NumberLocale {
+  /// A BCP 47 tag in lower case, a language alone applies to all its regions
+  pub tag: &'static str,
+  pub decimal: &'static str,
+  pub group: &'static str,
+  pub grouping: DigitGrouping,
+  pub symbol_first: bool,
+  /// A no-break space between the symbol and the number
+  pub symbol_space: bool,
+}
+
+const fn locale(
+  tag: &'static str,
+  decimal: &'static str,
+  group: &'static str,
+  symbol_first: bool,
+  symbol_space: bool,
+) -> NumberLocale {
+  let grouping = DigitGrouping::Thousands;
+  NumberLocale { tag, decimal, group, grouping, symbol_first, symbol_space }
+}
+
+const NBSP: &str = "\u{a0}";
+const NNBSP: &str = "\u{202f}";
+
+pub const DEFAULT_NUMBER_LOCALE: &NumberLocale = &NUMBER_LOCALE_LIST[0];
+
+pub const NUMBER_LOCALE_LIST: &[NumberLocale] = &[
+  locale("en", ".", ",", true, false),
+  NumberLocale {
+    tag: "en-in",
+    decimal: ".",
+    group: ",",
+    grouping: DigitGrouping::Indian,
+    symbol_first: true,
+    symbol_space: false,
+  },
+  NumberLocale {
+    tag: "hi",
+    decimal: ".",
+    group: ",",
+    grouping: DigitGrouping::Indian,
+    symbol_first: true,
+    symbol_space: false,
+  },
+  locale("ar", ".", ",", false, true),
+  locale("cs", ",", NBSP, false, true),
+  locale("da", ",", ".", false, true),
+  locale("de", ",", ".", false, true),
+  locale("de-at", ",", NBSP, true, true),
+  locale("de-ch", ".", "’", true, true),
+  locale("el", ",", ".", false, true),
+  locale("es", ",", ".", false, true),
+  locale("es-mx", ".", ",", true, false),
+  locale("fi", ",", NBSP, false, true),
+  locale("fr", ",", NNBSP, false, true),
+  locale("fr-ch", ",", NNBSP, false, true),
+  locale("he", ".", ",", false, true),
+  locale("hu", ",", NBSP, false, true),
+  locale("id", ",", ".", true, false),
+  locale("it", ",", ".", false, true),
+  locale("ja", ".", ",", true, false),
+  locale("ko", ".", ",", true, false),
+  locale("ms", ".", ",", true, false),
+  locale("nb", ",", NBSP, false, true),
+  locale("nl", ",", ".", true, true),
+  locale("pl", ",", NBSP, false, true),
+  locale("pt", ",", NBSP, false, true),
+  locale("pt-br", ",", ".", true, true),
+  locale("ro", ",", ".", false, true),
+  locale("ru", ",", NBSP, false, true),
+  locale("sv", ",", NBSP, false, true),
+  locale("th", ".", ",", true, false),
+  locale("tr", ",", ".", true, false),
+  locale("uk", ",", NBSP, false, true),
+  locale("vi", ",", ".", false, true),
+  locale("zh", ".", ",", true, false),
+];
+
+/// The locale of an `Accept-Language` value, its first language is matched with its region
+/// (e.g. pt-BR), then alone (pt), and English is the fallback
+pub fn number_locale_get(accept_language: &str) -> &'static NumberLocale {
+  let tag = accept_language.split([',', ';']).next().unwrap_or_default();
+  let tag = tag.trim().replace('_', "-").to_lowercase();
+  let language = tag.split('-').next().unwrap_or_default();
+
+  NUMBER_LOCALE_LIST
+    .iter()
+    .find(|l| l.tag == tag)
+    .or_else(|| NUMBER_LOCALE_LIST.iter().find(|l| l.tag == language))
+    .unwrap_or(DEFAULT_NUMBER_LOCALE)
+}
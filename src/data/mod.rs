@@ -1 +1,2 @@
 pub mod currencies;
+pub mod locales;
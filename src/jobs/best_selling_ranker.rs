@@ -0,0 +1,65 @@
+use std::{sync::Arc, time::Duration};
+
+use megacommerce_shared::{
+  models::{
+    context::Context,
+    errors::{ErrorType, InternalError},
+  },
+  utils::time::time_get_millis,
+};
+use tokio::{sync::mpsc, time::interval};
+
+use crate::store::database::ProductsStore;
+
+/// Recomputes the best selling rankings out of the sales events, so the lists read them
+/// from the precomputed table instead of summing the sales on every request
+#[derive(Debug)]
+pub struct BestSellingRanker {
+  store: Arc<dyn ProductsStore + Send + Sync>,
+  errors: mpsc::Sender<InternalError>,
+  interval: Duration,
+  size: i64,
+}
+
+#[derive(Debug)]
+pub struct BestSellingRankerArgs {
+  pub store: Arc<dyn ProductsStore + Send + Sync>,
+  pub errors: mpsc::Sender<InternalError>,
+  pub interval: Duration,
+  /// How many products each ranking keeps
+  pub size: i64,
+}
+
+impl BestSellingRanker {
+  pub fn new(args: BestSellingRankerArgs) -> Self {
+    Self { store: args.store, errors: args.errors, interval: args.interval, size: args.size }
+  }
+
+  pub async fn run(self) {
+    let mut ticker = interval(self.interval);
+    loop {
+      ticker.tick().await;
+      if let Err(err) = self.rank().await {
+        let _ = self.errors.send(err).await;
+      }
+    }
+  }
+
+  async fn rank(&self) -> Result<(), InternalError> {
+    let path = "products.jobs.best_selling_ranker.rank";
+    let ctx = Arc::new(Context::default());
+
+    self
+      .store
+      .best_selling_rankings_refresh(ctx, time_get_millis(), self.size)
+      .await
+      .map_err(|err| InternalError {
+        err_type: ErrorType::DBInsertError,
+        temp: true,
+        msg: "failed to refresh the best selling rankings".into(),
+        path: path.into(),
+        err: Box::new(err),
+      })?;
+    Ok(())
+  }
+}
@@ -0,0 +1,45 @@
+use std::{sync::Arc, time::Duration};
+
+use megacommerce_shared::models::errors::{ErrorType, InternalError};
+use tokio::{sync::mpsc, time::interval};
+
+use crate::store::exchange_rates::ExchangeRatesCache;
+
+/// Reloads the exchange rates the display prices are converted with
+#[derive(Debug)]
+pub struct ExchangeRatesRefresher {
+  rates: Arc<ExchangeRatesCache>,
+  errors: mpsc::Sender<InternalError>,
+  interval: Duration,
+}
+
+#[derive(Debug)]
+pub struct ExchangeRatesRefresherArgs {
+  pub rates: Arc<ExchangeRatesCache>,
+  pub errors: mpsc::Sender<InternalError>,
+  pub interval: Duration,
+}
+
+impl ExchangeRatesRefresher {
+  pub fn new(args: ExchangeRatesRefresherArgs) -> Self {
+    Self { rates: args.rates, errors: args.errors, interval: args.interval }
+  }
+
+  /// The first tick completes right away, so the rates are loaded on startup
+  pub async fn run(self) {
+    let mut ticker = interval(self.interval);
+    loop {
+      ticker.tick().await;
+      if let Err(err) = self.rates.refresh().await {
+        let err = InternalError {
+          err_type: ErrorType::DBSelectError,
+          temp: true,
+          msg: "failed to refresh the exchange rates".into(),
+          path: "products.jobs.exchange_rates_refresher.run".into(),
+          err,
+        };
+        let _ = self.errors.send(err).await;
+      }
+    }
+  }
+}
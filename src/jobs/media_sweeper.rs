@@ -0,0 +1,103 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use megacommerce_shared::{
+  models::{
+    context::Context,
+    errors::{ErrorType, InternalError},
+    r_lock::RLock,
+  },
+  utils::time::time_get_millis,
+};
+use tokio::{sync::mpsc, time::interval};
+
+use crate::{server::object_storage::ObjectStorage, store::database::ProductsStore};
+
+/// How many pending uploads are handled in a single sweep
+const SWEEP_BATCH_SIZE: i64 = 500;
+
+/// Deletes the objects of product creations that never completed (e.g. the process
+/// crashed between the upload and the insert), using the pending uploads ledger.
+#[derive(Debug)]
+pub struct MediaSweeper {
+  store: Arc<dyn ProductsStore + Send + Sync>,
+  storage: RLock<ObjectStorage>,
+  errors: mpsc::Sender<InternalError>,
+  interval: Duration,
+  stale_after: Duration,
+}
+
+#[derive(Debug)]
+pub struct MediaSweeperArgs {
+  pub store: Arc<dyn ProductsStore + Send + Sync>,
+  pub storage: RLock<ObjectStorage>,
+  pub errors: mpsc::Sender<InternalError>,
+  pub interval: Duration,
+  pub stale_after: Duration,
+}
+
+impl MediaSweeper {
+  pub fn new(args: MediaSweeperArgs) -> Self {
+    Self {
+      store: args.store,
+      storage: args.storage,
+      errors: args.errors,
+      interval: args.interval,
+      stale_after: args.stale_after,
+    }
+  }
+
+  pub async fn run(self) {
+    let mut ticker = interval(self.interval);
+    loop {
+      ticker.tick().await;
+      if let Err(err) = self.sweep().await {
+        let _ = self.errors.send(err).await;
+      }
+    }
+  }
+
+  async fn sweep(&self) -> Result<(), InternalError> {
+    let path = "products.jobs.media_sweeper.sweep";
+    let ctx = Arc::new(Context::default());
+    let before = time_get_millis().saturating_sub(self.stale_after.as_millis() as u64);
+
+    let stale = self
+      .store
+      .product_media_uploads_stale(ctx.clone(), before, SWEEP_BATCH_SIZE)
+      .await
+      .map_err(|err| InternalError {
+        err_type: ErrorType::DBSelectError,
+        temp: true,
+        msg: "failed to get the stale pending uploads".into(),
+        path: path.into(),
+        err: Box::new(err),
+      })?;
+    if stale.is_empty() {
+      return Ok(());
+    }
+
+    let mut by_product: HashMap<String, Vec<String>> = HashMap::new();
+    for (product_id, key) in stale.into_iter() {
+      by_product.entry(product_id).or_default().push(key);
+    }
+
+    let storage = self.storage.get().await;
+    for (product_id, keys) in by_product.iter() {
+      // objects shared with a created product (or another pending one) stay in the bucket,
+      // the rows whose object couldn't be deleted are retried on the next tick
+      self
+        .store
+        .product_media_uploads_release(ctx.clone(), product_id, keys, &*storage)
+        .await
+        .map_err(|err| InternalError {
+          err_type: ErrorType::DBDeleteError,
+          temp: true,
+          msg: "failed to release the stale pending uploads".into(),
+          path: path.into(),
+          err: Box::new(err),
+        })?;
+    }
+
+    Ok(())
+  }
+}
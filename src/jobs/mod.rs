@@ -0,0 +1,15 @@
+mod best_selling_ranker;
+mod exchange_rates_refresher;
+mod media_sweeper;
+mod product_events_writer;
+mod product_similarities_builder;
+mod reservations_expirer;
+mod sales_expirer;
+
+pub use best_selling_ranker::{BestSellingRanker, BestSellingRankerArgs};
+pub use exchange_rates_refresher::{ExchangeRatesRefresher, ExchangeRatesRefresherArgs};
+pub use media_sweeper::{MediaSweeper, MediaSweeperArgs};
+pub use product_events_writer::{ProductEventsWriter, ProductEventsWriterArgs};
+pub use product_similarities_builder::{ProductSimilaritiesBuilder, ProductSimilaritiesBuilderArgs};
+pub use reservations_expirer::{ReservationsExpirer, ReservationsExpirerArgs};
+pub use sales_expirer::{SalesExpirer, SalesExpirerArgs};
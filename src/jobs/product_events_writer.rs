@@ -0,0 +1,92 @@
+use std::{sync::Arc, time::Duration};
+
+use megacommerce_shared::models::{
+  context::Context,
+  errors::{ErrorType, InternalError},
+};
+use tokio::{
+  select,
+  sync::mpsc,
+  time::{interval, MissedTickBehavior},
+};
+
+use crate::{models::product_events::ProductEvent, store::database::ProductsStore};
+
+/// Writes the events queued by `product_events_record` in batches: a batch is written once
+/// it's full, or when the flush interval ticks. The queue is bounded, so a slow database
+/// pushes back on the callers instead of growing the memory.
+#[derive(Debug)]
+pub struct ProductEventsWriter {
+  store: Arc<dyn ProductsStore + Send + Sync>,
+  errors: mpsc::Sender<InternalError>,
+  events: mpsc::Receiver<ProductEvent>,
+  batch_size: usize,
+  flush_interval: Duration,
+}
+
+#[derive(Debug)]
+pub struct ProductEventsWriterArgs {
+  pub store: Arc<dyn ProductsStore + Send + Sync>,
+  pub errors: mpsc::Sender<InternalError>,
+  pub events: mpsc::Receiver<ProductEvent>,
+  /// How many events are written at most in one statement
+  pub batch_size: usize,
+  /// How long an event waits at most before it's written
+  pub flush_interval: Duration,
+}
+
+impl ProductEventsWriter {
+  pub fn new(args: ProductEventsWriterArgs) -> Self {
+    Self {
+      store: args.store,
+      errors: args.errors,
+      events: args.events,
+      batch_size: args.batch_size.max(1),
+      flush_interval: args.flush_interval,
+    }
+  }
+
+  /// Runs until every sender is dropped, then writes what's left
+  pub async fn run(mut self) {
+    let mut batch = Vec::with_capacity(self.batch_size);
+    let mut ticker = interval(self.flush_interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+      let open = select! {
+        received = self.events.recv_many(&mut batch, self.batch_size - batch.len()) => {
+          if received > 0 && batch.len() < self.batch_size {
+            continue;
+          }
+          received > 0
+        }
+        _ = ticker.tick() => true,
+      };
+
+      if !batch.is_empty() {
+        if let Err(err) = self.write(&batch).await {
+          let _ = self.errors.send(err).await;
+        }
+        // the events of a failed batch are dropped, they're only a signal
+        batch.clear();
+      }
+      if !open {
+        return;
+      }
+    }
+  }
+
+  async fn write(&self, batch: &[ProductEvent]) -> Result<(), InternalError> {
+    let path = "products.jobs.product_events_writer.write";
+    let ctx = Arc::new(Context::default());
+
+    self.store.product_events_insert(ctx, batch).await.map_err(|err| InternalError {
+      err_type: ErrorType::DBInsertError,
+      temp: true,
+      msg: format!("failed to write {} product events", batch.len()),
+      path: path.into(),
+      err: Box::new(err),
+    })?;
+    Ok(())
+  }
+}
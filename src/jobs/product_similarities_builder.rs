@@ -0,0 +1,92 @@
+use std::{sync::Arc, time::Duration};
+
+use megacommerce_shared::{
+  models::{
+    context::Context,
+    errors::{ErrorType, InternalError},
+  },
+  utils::time::time_get_millis,
+};
+use tokio::{sync::mpsc, time::interval};
+
+use crate::store::database::ProductsStore;
+
+/// Recomputes the item-to-item similarities out of the product events, which the personalized
+/// recommendations read instead of comparing the users' events on every request.
+/// It also removes the expired recommendations snapshots.
+#[derive(Debug)]
+pub struct ProductSimilaritiesBuilder {
+  store: Arc<dyn ProductsStore + Send + Sync>,
+  errors: mpsc::Sender<InternalError>,
+  interval: Duration,
+  history: Duration,
+  per_product: i64,
+  snapshot_ttl: Duration,
+}
+
+#[derive(Debug)]
+pub struct ProductSimilaritiesBuilderArgs {
+  pub store: Arc<dyn ProductsStore + Send + Sync>,
+  pub errors: mpsc::Sender<InternalError>,
+  pub interval: Duration,
+  /// How far back the events are read
+  pub history: Duration,
+  /// How many similar products are kept per product
+  pub per_product: i64,
+  /// How long the scored recommendations of a first page are kept for its next pages
+  pub snapshot_ttl: Duration,
+}
+
+impl ProductSimilaritiesBuilder {
+  pub fn new(args: ProductSimilaritiesBuilderArgs) -> Self {
+    Self {
+      store: args.store,
+      errors: args.errors,
+      interval: args.interval,
+      history: args.history,
+      per_product: args.per_product,
+      snapshot_ttl: args.snapshot_ttl,
+    }
+  }
+
+  pub async fn run(self) {
+    let mut ticker = interval(self.interval);
+    loop {
+      ticker.tick().await;
+      if let Err(err) = self.build().await {
+        let _ = self.errors.send(err).await;
+      }
+    }
+  }
+
+  async fn build(&self) -> Result<(), InternalError> {
+    let path = "products.jobs.product_similarities_builder.build";
+    let ctx = Arc::new(Context::default());
+    let now = time_get_millis();
+    let since = now.saturating_sub(self.history.as_millis() as u64);
+
+    self
+      .store
+      .product_similarities_refresh(ctx.clone(), since, now, self.per_product)
+      .await
+      .map_err(|err| InternalError {
+        err_type: ErrorType::DBInsertError,
+        temp: true,
+        msg: "failed to refresh the product similarities".into(),
+        path: path.into(),
+        err: Box::new(err),
+      })?;
+
+    let before = now.saturating_sub(self.snapshot_ttl.as_millis() as u64);
+    self.store.product_recommendation_snapshots_expire(ctx, before).await.map_err(|err| {
+      InternalError {
+        err_type: ErrorType::DBDeleteError,
+        temp: true,
+        msg: "failed to delete the expired recommendations snapshots".into(),
+        path: path.into(),
+        err: Box::new(err),
+      }
+    })?;
+    Ok(())
+  }
+}
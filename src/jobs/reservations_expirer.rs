@@ -0,0 +1,73 @@
+use std::{sync::Arc, time::Duration};
+
+use megacommerce_shared::{
+  models::{
+    context::Context,
+    errors::{ErrorType, InternalError},
+  },
+  utils::time::time_get_millis,
+};
+use tokio::{sync::mpsc, time::interval};
+
+use crate::store::database::ProductsStore;
+
+/// How many reservations are released in a single transaction
+const EXPIRE_BATCH_SIZE: i64 = 500;
+
+/// Releases the reservations that were neither committed nor released in time, e.g. the
+/// order was abandoned or the order service lost track of it, so their units are
+/// available again.
+#[derive(Debug)]
+pub struct ReservationsExpirer {
+  store: Arc<dyn ProductsStore + Send + Sync>,
+  errors: mpsc::Sender<InternalError>,
+  interval: Duration,
+  ttl: Duration,
+}
+
+#[derive(Debug)]
+pub struct ReservationsExpirerArgs {
+  pub store: Arc<dyn ProductsStore + Send + Sync>,
+  pub errors: mpsc::Sender<InternalError>,
+  pub interval: Duration,
+  pub ttl: Duration,
+}
+
+impl ReservationsExpirer {
+  pub fn new(args: ReservationsExpirerArgs) -> Self {
+    Self { store: args.store, errors: args.errors, interval: args.interval, ttl: args.ttl }
+  }
+
+  pub async fn run(self) {
+    let mut ticker = interval(self.interval);
+    loop {
+      ticker.tick().await;
+      if let Err(err) = self.expire().await {
+        let _ = self.errors.send(err).await;
+      }
+    }
+  }
+
+  async fn expire(&self) -> Result<(), InternalError> {
+    let path = "products.jobs.reservations_expirer.expire";
+    let ctx = Arc::new(Context::default());
+    let before = time_get_millis().saturating_sub(self.ttl.as_millis() as u64);
+
+    loop {
+      let released = self
+        .store
+        .inventory_reservations_expire(ctx.clone(), before, EXPIRE_BATCH_SIZE)
+        .await
+        .map_err(|err| InternalError {
+          err_type: ErrorType::DBUpdateError,
+          temp: true,
+          msg: "failed to release the stale reservations".into(),
+          path: path.into(),
+          err: Box::new(err),
+        })?;
+      if released < EXPIRE_BATCH_SIZE as u64 {
+        return Ok(());
+      }
+    }
+  }
+}
@@ -0,0 +1,70 @@
+use std::{sync::Arc, time::Duration};
+
+use megacommerce_shared::{
+  models::{
+    context::Context,
+    errors::{ErrorType, InternalError},
+  },
+  utils::time::time_get_millis,
+};
+use tokio::{sync::mpsc, time::interval};
+
+use crate::store::database::ProductsStore;
+
+/// How many products are updated in a single statement
+const EXPIRE_BATCH_SIZE: i64 = 500;
+
+/// Takes the ended sales off the products' offers. The read paths already ignore a sale
+/// outside its window, this keeps the stored offers (and the seller's view of them) current.
+#[derive(Debug)]
+pub struct SalesExpirer {
+  store: Arc<dyn ProductsStore + Send + Sync>,
+  errors: mpsc::Sender<InternalError>,
+  interval: Duration,
+}
+
+#[derive(Debug)]
+pub struct SalesExpirerArgs {
+  pub store: Arc<dyn ProductsStore + Send + Sync>,
+  pub errors: mpsc::Sender<InternalError>,
+  pub interval: Duration,
+}
+
+impl SalesExpirer {
+  pub fn new(args: SalesExpirerArgs) -> Self {
+    Self { store: args.store, errors: args.errors, interval: args.interval }
+  }
+
+  pub async fn run(self) {
+    let mut ticker = interval(self.interval);
+    loop {
+      ticker.tick().await;
+      if let Err(err) = self.expire().await {
+        let _ = self.errors.send(err).await;
+      }
+    }
+  }
+
+  async fn expire(&self) -> Result<(), InternalError> {
+    let path = "products.jobs.sales_expirer.expire";
+    let ctx = Arc::new(Context::default());
+    let now = time_get_millis();
+
+    loop {
+      let updated = self
+        .store
+        .products_sales_expire(ctx.clone(), now, EXPIRE_BATCH_SIZE)
+        .await
+        .map_err(|err| InternalError {
+          err_type: ErrorType::DBUpdateError,
+          temp: true,
+          msg: "failed to expire the ended sales".into(),
+          path: path.into(),
+          err: Box::new(err),
+        })?;
+      if updated < EXPIRE_BATCH_SIZE as u64 {
+        return Ok(());
+      }
+    }
+  }
+}
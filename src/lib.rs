@@ -1,6 +1,7 @@
 pub mod common;
 pub mod controller;
 pub mod data;
+pub mod jobs;
 pub mod models;
 pub mod otel;
 pub mod server;
@@ -11,18 +11,27 @@ pub type AnyMap = HashMap<String, Value>;
 #[serde(rename_all = "snake_case")]
 pub enum EventName {
   ProductCreate,
+  ProductUpdate,
+  ProductStatusUpdate,
+  ProductReviewModerate,
 }
 
 #[derive(Serialize, Deserialize, Debug, Display, Clone, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum EventParameterKey {
   ProductCreate,
+  ProductUpdate,
+  ProductStatusUpdate,
+  ProductReviewModerate,
 }
 
 impl EventParameterKey {
   pub fn as_string(&self) -> Cow<'static, str> {
     match self {
       Self::ProductCreate => Cow::Borrowed("product_create"),
+      Self::ProductUpdate => Cow::Borrowed("product_update"),
+      Self::ProductStatusUpdate => Cow::Borrowed("product_status_update"),
+      Self::ProductReviewModerate => Cow::Borrowed("product_review_moderate"),
     }
   }
 }
@@ -0,0 +1,52 @@
+/// The period the units sold are summed over to rank the best selling products
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BestSellingWindow {
+  Day,
+  #[default]
+  Week,
+  Month,
+}
+
+impl BestSellingWindow {
+  pub const ALL: [BestSellingWindow; 3] = [Self::Day, Self::Week, Self::Month];
+
+  /// The name clients ask for the window with, and the rankings are stored under
+  pub const fn as_str(&self) -> &'static str {
+    match self {
+      Self::Day => "24h",
+      Self::Week => "7d",
+      Self::Month => "30d",
+    }
+  }
+
+  pub fn from_str(value: &str) -> Option<Self> {
+    Self::ALL.into_iter().find(|w| w.as_str() == value)
+  }
+
+  pub const fn millis(&self) -> u64 {
+    const DAY: u64 = 24 * 60 * 60 * 1000;
+    match self {
+      Self::Day => DAY,
+      Self::Week => 7 * DAY,
+      Self::Month => 30 * DAY,
+    }
+  }
+
+  /// The start of the window ending at `now` (in milliseconds)
+  pub fn since(&self, now: u64) -> u64 {
+    now.saturating_sub(self.millis())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_best_selling_window() {
+    assert_eq!(BestSellingWindow::from_str("7d"), Some(BestSellingWindow::Week));
+    assert_eq!(BestSellingWindow::from_str("1y"), None);
+    assert_eq!(BestSellingWindow::Day.since(90_000_000), 3_600_000);
+    assert_eq!(BestSellingWindow::Month.since(1_000), 0);
+  }
+}
@@ -1,9 +1,19 @@
+use std::{collections::HashMap, fmt};
+
 use derive_more::Display;
 use serde::Deserialize;
 
+use crate::{
+  models::{recommendations::RecommenderStrategy, variant_selection::VariantSelection},
+  utils::images::ImageDerivativeSpec,
+};
+
 #[derive(Clone, Debug, Deserialize, Display)]
+#[display("Config {service} {products}")]
 pub struct Config {
   pub service: ServiceConfig,
+  #[serde(default)]
+  pub products: ProductsConfig,
 }
 
 #[derive(Clone, Debug, Deserialize, Display)]
@@ -14,6 +24,217 @@ pub struct ServiceConfig {
   pub common_service_grpc_url: String,
 }
 
+/// The environment variable the pagination cursor secret is read from, it takes precedence
+/// over the config file so the secret doesn't have to be kept there
+pub const PAGINATION_CURSOR_SECRET_ENV: &str = "PRODUCTS_PAGINATION_CURSOR_SECRET";
+/// The cursors are signed with HMAC-SHA256, a shorter key is too easy to guess
+pub const PAGINATION_CURSOR_SECRET_MIN_LENGTH: usize = 32;
+
+/// Tunables of the products service itself, every field falls back to its default
+/// so the section can be omitted from the config file.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ProductsConfig {
+  /// How often the sweeper looks for leftovers of interrupted media uploads
+  pub media_sweeper_interval_secs: u64,
+  /// A pending upload older than this is considered abandoned and its object is deleted
+  pub media_upload_stale_after_secs: u64,
+  /// How many objects of a single product are uploaded at the same time
+  pub media_upload_concurrency: usize,
+  /// The deadline of a single object upload
+  pub media_upload_timeout_secs: u64,
+  /// The sizes every product image is scaled down to, the `card` one is used by list endpoints
+  pub image_derivatives: Vec<ImageDerivativeSpec>,
+  /// The quality (1-100) the opaque images and their derivatives are encoded as JPEG with
+  pub image_jpeg_quality: u8,
+  pub video_max_size_mb: u64,
+  pub video_max_duration_secs: u64,
+  pub video_max_count_per_variant: usize,
+  /// The accepted containers, as mime types (video/mp4, video/webm)
+  pub video_accepted_formats: Vec<String>,
+  /// The accepted codecs of the video tracks (h264, h265, vp8, vp9, av1)
+  pub video_accepted_codecs: Vec<String>,
+  /// How long a presigned direct-to-bucket upload url stays valid
+  pub media_presigned_url_expiry_secs: u64,
+  /// The max files a single presigned upload urls request can ask for
+  pub media_presigned_max_files: usize,
+  /// The most a product creation loads of its staged uploads, all of them together, checked
+  /// on their sizes before any is downloaded
+  pub media_staged_max_total_mb: u64,
+  /// The key the pagination cursors are signed with, so clients can't forge them. It's
+  /// normally set with `PRODUCTS_PAGINATION_CURSOR_SECRET`, the service doesn't start without it.
+  pub pagination_cursor_secret: String,
+  /// The largest page a client can ask for, the lists fall back to their own page size
+  pub pagination_max_page_size: u32,
+  /// Up to this many rows a list total is counted exactly, above it the planner's estimate is used
+  pub pagination_exact_total_max: u64,
+  /// A JSON file of the exchange rates (`{"USD": "1", "EUR": "0.92"}`), empty reads them from
+  /// the `exchange_rates` table
+  pub exchange_rates_file: String,
+  /// How often the exchange rates are reloaded
+  pub exchange_rates_refresh_interval_secs: u64,
+  /// Rates older than this are not used, and prices are only shown in their own currency
+  pub exchange_rates_max_age_secs: u64,
+  /// How often the ended sales are taken off the products' offers
+  pub sales_expirer_interval_secs: u64,
+  /// How the lists choose the variant a product is shown with: `cheapest_in_stock`,
+  /// `seller_default` or `best_seller`
+  pub display_variant_selection: VariantSelection,
+  /// How often the best selling rankings are recomputed from the sales
+  pub best_selling_rankings_interval_secs: u64,
+  /// How many products a best selling ranking (of a window and a category) keeps
+  pub best_selling_rankings_size: u32,
+  /// How the "products to like" are picked: `baseline` or `personalized`
+  pub recommender: RecommenderStrategy,
+  /// How far back the user's events are read to personalize the recommendations
+  pub recommendations_history_days: u32,
+  /// How often the product similarities are recomputed from the events
+  pub recommendations_similarity_interval_secs: u64,
+  /// How many similar products are kept per product
+  pub recommendations_similar_per_product: u32,
+  /// The weights of the similarity, the category affinity and the popularity in the score
+  /// of a personalized recommendation
+  pub recommendations_similarity_weight: f64,
+  pub recommendations_affinity_weight: f64,
+  pub recommendations_popularity_weight: f64,
+  /// How many products each candidate source (the similar products, the best sellers of the
+  /// user's top categories, the overall best sellers) contributes to the scored ones
+  pub recommendations_candidates: u32,
+  /// How many of the user's top categories the best sellers are taken from
+  pub recommendations_affinity_categories: u32,
+  /// How long the scored recommendations of a first page are kept for its next pages
+  pub recommendations_snapshot_ttl_secs: u64,
+  /// How many recorded product events can wait to be written, a full queue refuses new ones
+  pub product_events_queue_capacity: usize,
+  /// How many product events are written at most in one batch
+  pub product_events_batch_size: u32,
+  /// How long a product event waits at most before it's written
+  pub product_events_flush_interval_ms: u64,
+  /// How many events one `product_events_record` request can carry
+  pub product_events_max_per_request: u32,
+  /// The share (0 to 1) of the events of a type that are kept, by event type,
+  /// e.g. `view: 0.2`. The types left out are all kept.
+  pub product_events_sample_rates: HashMap<String, f64>,
+  /// How many related products are returned at most
+  pub related_products_max: u32,
+  /// How many candidates are scored at most for the related products, per source of candidates
+  pub related_products_candidates: u32,
+  /// The weights of the shared attribute values, the brand, the price proximity and the tags
+  /// in the score of a related product
+  pub related_products_attributes_weight: f64,
+  pub related_products_brand_weight: f64,
+  pub related_products_price_weight: f64,
+  pub related_products_tags_weight: f64,
+  /// The session roles allowed to moderate: approve, reject and publish the products,
+  /// and moderate the reviews
+  pub moderator_roles: Vec<String>,
+  /// The session roles allowed to reserve, commit and release stock, i.e. the order service
+  pub inventory_service_roles: Vec<String>,
+  /// A reservation neither committed nor released after this is released by the expirer
+  pub inventory_reservation_ttl_secs: u64,
+  /// How often the stale reservations are looked for
+  pub inventory_reservations_expirer_interval_secs: u64,
+}
+
+impl ProductsConfig {
+  /// Checks the settings the service can't safely run with, on startup
+  pub fn validate(&self) -> Result<(), String> {
+    if self.pagination_cursor_secret.chars().count() < PAGINATION_CURSOR_SECRET_MIN_LENGTH {
+      return Err(format!(
+        "pagination_cursor_secret must be at least {} characters, set it with {}",
+        PAGINATION_CURSOR_SECRET_MIN_LENGTH, PAGINATION_CURSOR_SECRET_ENV
+      ));
+    }
+
+    // the jobs tick on the intervals (a zero period panics), and the events writer batches
+    // out of a bounded queue
+    let positive = [
+      ("media_sweeper_interval_secs", self.media_sweeper_interval_secs),
+      ("exchange_rates_refresh_interval_secs", self.exchange_rates_refresh_interval_secs),
+      ("sales_expirer_interval_secs", self.sales_expirer_interval_secs),
+      ("best_selling_rankings_interval_secs", self.best_selling_rankings_interval_secs),
+      ("recommendations_similarity_interval_secs", self.recommendations_similarity_interval_secs),
+      ("product_events_queue_capacity", self.product_events_queue_capacity as u64),
+      ("product_events_batch_size", self.product_events_batch_size as u64),
+      ("product_events_flush_interval_ms", self.product_events_flush_interval_ms),
+      (
+        "inventory_reservations_expirer_interval_secs",
+        self.inventory_reservations_expirer_interval_secs,
+      ),
+    ];
+    if let Some((name, _)) = positive.iter().find(|(_, value)| *value == 0) {
+      return Err(format!("{} must be greater than 0", name));
+    }
+    Ok(())
+  }
+}
+
+impl fmt::Display for ProductsConfig {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{:?}", self)
+  }
+}
+
+impl Default for ProductsConfig {
+  fn default() -> Self {
+    ProductsConfig {
+      media_sweeper_interval_secs: 600,
+      media_upload_stale_after_secs: 3600,
+      media_upload_concurrency: 8,
+      media_upload_timeout_secs: 30,
+      image_derivatives: vec![
+        ImageDerivativeSpec { name: "thumbnail".into(), max_width: 160, max_height: 160 },
+        ImageDerivativeSpec { name: "card".into(), max_width: 480, max_height: 480 },
+        ImageDerivativeSpec { name: "zoom".into(), max_width: 1600, max_height: 1600 },
+      ],
+      image_jpeg_quality: 85,
+      video_max_size_mb: 100,
+      video_max_duration_secs: 120,
+      video_max_count_per_variant: 2,
+      video_accepted_formats: vec!["video/mp4".into(), "video/webm".into()],
+      video_accepted_codecs: ["h264", "h265", "vp8", "vp9", "av1"].map(String::from).to_vec(),
+      media_presigned_url_expiry_secs: 900,
+      media_presigned_max_files: 50,
+      media_staged_max_total_mb: 512,
+      pagination_cursor_secret: String::new(),
+      pagination_max_page_size: 100,
+      pagination_exact_total_max: 10_000,
+      exchange_rates_file: String::new(),
+      exchange_rates_refresh_interval_secs: 3600,
+      exchange_rates_max_age_secs: 86_400,
+      sales_expirer_interval_secs: 300,
+      display_variant_selection: VariantSelection::default(),
+      best_selling_rankings_interval_secs: 900,
+      best_selling_rankings_size: 500,
+      recommender: RecommenderStrategy::default(),
+      recommendations_history_days: 90,
+      recommendations_similarity_interval_secs: 3600,
+      recommendations_similar_per_product: 50,
+      recommendations_similarity_weight: 1.0,
+      recommendations_affinity_weight: 0.5,
+      recommendations_popularity_weight: 0.25,
+      recommendations_candidates: 200,
+      recommendations_affinity_categories: 3,
+      recommendations_snapshot_ttl_secs: 3600,
+      product_events_queue_capacity: 10_000,
+      product_events_batch_size: 500,
+      product_events_flush_interval_ms: 1000,
+      product_events_max_per_request: 50,
+      product_events_sample_rates: HashMap::new(),
+      related_products_max: 12,
+      related_products_candidates: 500,
+      related_products_attributes_weight: 1.0,
+      related_products_brand_weight: 0.5,
+      related_products_price_weight: 0.5,
+      related_products_tags_weight: 0.5,
+      moderator_roles: vec!["system_admin".into(), "system_moderator".into()],
+      inventory_service_roles: vec!["system_order_service".into()],
+      inventory_reservation_ttl_secs: 1800,
+      inventory_reservations_expirer_interval_secs: 60,
+    }
+  }
+}
+
 impl Default for Config {
   fn default() -> Self {
     Config {
@@ -22,6 +243,38 @@ impl Default for Config {
         service_grpc_url: "".to_string(),
         common_service_grpc_url: "".to_string(),
       },
+      products: ProductsConfig::default(),
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_products_config_validate() {
+    let mut cfg = ProductsConfig::default();
+    // no secret unless it's configured
+    assert!(cfg.validate().is_err());
+
+    cfg.pagination_cursor_secret = "short".into();
+    assert!(cfg.validate().is_err());
+
+    cfg.pagination_cursor_secret = "x".repeat(PAGINATION_CURSOR_SECRET_MIN_LENGTH);
+    assert!(cfg.validate().is_ok());
+
+    // nor a job that ticks without a period
+    let mut zero = cfg.clone();
+    zero.sales_expirer_interval_secs = 0;
+    assert_eq!(zero.validate(), Err("sales_expirer_interval_secs must be greater than 0".into()));
+
+    let mut zero = cfg.clone();
+    zero.product_events_flush_interval_ms = 0;
+    assert!(zero.validate().is_err());
+
+    let mut zero = cfg;
+    zero.product_events_batch_size = 0;
+    assert!(zero.validate().is_err());
+  }
+}
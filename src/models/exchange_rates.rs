@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use megacommerce_proto::{
+  BestSellingProductListItem, BigDiscountProductListItem, CategoryNavbarProductItem,
+  HeroProductListItem, NewlyAddedProductListItem, ProductDisplayPrice, ProductsCategoryItem,
+  RelatedProductListItem,
+};
+use rust_decimal::Decimal;
+
+use crate::{
+  data::currencies::{currency_get, Currency},
+  models::money::Money,
+};
+
+/// The rates of the currencies against a common base, e.g. `EUR -> 0.92` for 1 USD
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExchangeRates {
+  pub rates: HashMap<String, Decimal>,
+  /// When the rates were loaded, in milliseconds
+  pub fetched_at: u64,
+}
+
+impl ExchangeRates {
+  /// The amount in another currency, none without a rate for either currency
+  pub fn convert(&self, money: &Money, to: &'static Currency) -> Option<Money> {
+    if money.currency() == to {
+      return Some(*money);
+    }
+    let from_rate = self.rates.get(money.currency().code).filter(|r| !r.is_zero())?;
+    let to_rate = self.rates.get(to.code)?;
+    let amount = money.amount().checked_mul(*to_rate)?.checked_div(*from_rate)?;
+    Some(Money::from_decimal(amount, to))
+  }
+
+  /// The prices of an item, given in minor units of `currency_code`, in the display currency
+  pub fn display_price(
+    &self,
+    currency_code: &str,
+    price_cents: i64,
+    discount_price_cents: Option<i64>,
+    to: &'static Currency,
+  ) -> Option<ProductDisplayPrice> {
+    let from = currency_get(currency_code)?;
+    let convert = |units: i64| {
+      self.convert(&Money::from_minor_units(units, from), to)?.checked_minor_units().ok()
+    };
+
+    let discount_price_cents = match discount_price_cents {
+      Some(units) => Some(convert(units)?),
+      None => None,
+    };
+    Some(ProductDisplayPrice {
+      currency_code: to.code.to_string(),
+      price_cents: convert(price_cents)?,
+      discount_price_cents,
+    })
+  }
+}
+
+/// A list item whose prices can also be shown in the shopper's currency
+pub trait DisplayPriced {
+  fn currency_code(&self) -> &str;
+  /// The price and the discounted price, in minor units of the item's currency
+  fn prices(&self) -> (i64, Option<i64>);
+  fn set_display_price(&mut self, price: ProductDisplayPrice);
+}
+
+impl DisplayPriced for ProductsCategoryItem {
+  fn currency_code(&self) -> &str {
+    &self.currency_code
+  }
+
+  fn prices(&self) -> (i64, Option<i64>) {
+    (self.price_cents, self.discount_price_cents)
+  }
+
+  fn set_display_price(&mut self, price: ProductDisplayPrice) {
+    self.display_price = Some(price);
+  }
+}
+
+impl DisplayPriced for CategoryNavbarProductItem {
+  fn currency_code(&self) -> &str {
+    &self.currency_code
+  }
+
+  fn prices(&self) -> (i64, Option<i64>) {
+    (self.price_cents, self.discount_price_cents)
+  }
+
+  fn set_display_price(&mut self, price: ProductDisplayPrice) {
+    self.display_price = Some(price);
+  }
+}
+
+impl DisplayPriced for HeroProductListItem {
+  fn currency_code(&self) -> &str {
+    &self.currency_code
+  }
+
+  fn prices(&self) -> (i64, Option<i64>) {
+    (self.price_cents, self.discount_price_cents)
+  }
+
+  fn set_display_price(&mut self, price: ProductDisplayPrice) {
+    self.display_price = Some(price);
+  }
+}
+
+impl DisplayPriced for BigDiscountProductListItem {
+  fn currency_code(&self) -> &str {
+    &self.currency_code
+  }
+
+  fn prices(&self) -> (i64, Option<i64>) {
+    (self.price_cents, Some(self.discount_price_cents))
+  }
+
+  fn set_display_price(&mut self, price: ProductDisplayPrice) {
+    self.display_price = Some(price);
+  }
+}
+
+impl DisplayPriced for NewlyAddedProductListItem {
+  fn currency_code(&self) -> &str {
+    &self.currency_code
+  }
+
+  fn prices(&self) -> (i64, Option<i64>) {
+    (self.price_cents, self.sale_price_cents)
+  }
+
+  fn set_display_price(&mut self, price: ProductDisplayPrice) {
+    self.display_price = Some(price);
+  }
+}
+
+impl DisplayPriced for BestSellingProductListItem {
+  fn currency_code(&self) -> &str {
+    &self.currency_code
+  }
+
+  fn prices(&self) -> (i64, Option<i64>) {
+    (self.price_cents, self.sale_price_cents)
+  }
+
+  fn set_display_price(&mut self, price: ProductDisplayPrice) {
+    self.display_price = Some(price);
+  }
+}
+
+impl DisplayPriced for RelatedProductListItem {
+  fn currency_code(&self) -> &str {
+    &self.currency_code
+  }
+
+  fn prices(&self) -> (i64, Option<i64>) {
+    (self.price_cents, self.discount_price_cents)
+  }
+
+  fn set_display_price(&mut self, price: ProductDisplayPrice) {
+    self.display_price = Some(price);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::str::FromStr;
+
+  use super::*;
+
+  fn rates() -> ExchangeRates {
+    let rates = [("USD", "1"), ("EUR", "0.92"), ("JPY", "151.3"), ("KWD", "0.3071")];
+    ExchangeRates {
+      rates: rates.iter().map(|(c, r)| (c.to_string(), Decimal::from_str(r).unwrap())).collect(),
+      fetched_at: 0,
+    }
+  }
+
+  #[test]
+  fn test_exchange_rates_display_price() {
+    let jpy = currency_get("JPY").unwrap();
+    let price = rates().display_price("USD", 1999, Some(1499), jpy).unwrap();
+    assert_eq!(price.price_cents, 3024);
+    assert_eq!(price.discount_price_cents, Some(2268));
+
+    // through the base currency, into the 3 decimals of KWD
+    let kwd = currency_get("KWD").unwrap();
+    let price = rates().display_price("EUR", 10000, None, kwd).unwrap();
+    assert_eq!(price.price_cents, 33380);
+    assert_eq!(price.discount_price_cents, None);
+
+    let gbp = currency_get("GBP").unwrap();
+    assert_eq!(rates().display_price("USD", 1999, None, gbp), None);
+  }
+}
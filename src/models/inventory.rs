@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use megacommerce_proto::{InventoryStock, ProductOfferVariant};
+use serde::Deserialize;
+use serde_json::{from_value, Value};
+
+/// The available units at or under which a variant is low on stock, unless the
+/// seller set its own threshold
+pub const INVENTORY_LOW_STOCK_THRESHOLD: i64 = 5;
+pub const INVENTORY_IDEMPOTENCY_KEY_MAX_LENGTH: usize = 128;
+
+/// A reservation holds units for an order until it's committed (the units are sold)
+/// or released (the units are available again)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReservationStatus {
+  Reserved,
+  Committed,
+  Released,
+}
+
+impl ReservationStatus {
+  pub const ALL: [ReservationStatus; 3] = [Self::Reserved, Self::Committed, Self::Released];
+
+  pub const fn as_str(&self) -> &'static str {
+    match self {
+      Self::Reserved => "reserved",
+      Self::Committed => "committed",
+      Self::Released => "released",
+    }
+  }
+
+  pub fn from_str(value: &str) -> Option<Self> {
+    Self::ALL.into_iter().find(|s| s.as_str() == value)
+  }
+}
+
+/// The operations on a variant's stock, an idempotency key is bound to one of them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InventoryOperation {
+  Reserve { product_id: String, variant_id: String, quantity: u32 },
+  Commit { reservation_id: String },
+  Release { reservation_id: String },
+  Restock {
+    product_id: String,
+    variant_id: String,
+    quantity: u32,
+    low_stock_threshold: Option<u32>,
+  },
+}
+
+impl InventoryOperation {
+  /// Identifies the operation with its arguments, a key reused for a different
+  /// operation is a conflict rather than a retry
+  pub fn fingerprint(&self) -> String {
+    match self {
+      Self::Reserve { product_id, variant_id, quantity } => {
+        format!("reserve:{}:{}:{}", product_id, variant_id, quantity)
+      }
+      Self::Commit { reservation_id } => format!("commit:{}", reservation_id),
+      Self::Release { reservation_id } => format!("release:{}", reservation_id),
+      Self::Restock { product_id, variant_id, quantity, low_stock_threshold } => {
+        let threshold = low_stock_threshold.map(|t| t.to_string()).unwrap_or_default();
+        format!("restock:{}:{}:{}:{}", product_id, variant_id, quantity, threshold)
+      }
+    }
+  }
+}
+
+/// The result of an inventory operation, the refusals are not errors of the store
+#[derive(Debug, Clone, PartialEq)]
+pub enum InventoryOutcome {
+  Applied(InventoryStock),
+  /// The operation was already applied with the same idempotency key
+  Replayed(InventoryStock),
+  /// Fewer units are available than asked for, nothing was reserved
+  Insufficient(InventoryStock),
+  /// The idempotency key was used for a different operation
+  KeyConflict,
+  /// The reservation is already committed or released
+  Settled(ReservationStatus),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StockLevel {
+  InStock,
+  LowStock,
+  OutOfStock,
+}
+
+impl StockLevel {
+  pub fn of(available: i64, low_stock_threshold: i64) -> Self {
+    match available {
+      ..=0 => Self::OutOfStock,
+      n if n <= low_stock_threshold => Self::LowStock,
+      _ => Self::InStock,
+    }
+  }
+
+  /// Low stock is still in stock
+  pub fn in_stock(&self) -> bool {
+    *self != Self::OutOfStock
+  }
+
+  pub fn low_stock(&self) -> bool {
+    *self == Self::LowStock
+  }
+}
+
+/// The `inventory_items` row of a variant, as the list queries aggregate it per product
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub struct VariantInventory {
+  pub reserved: i64,
+  #[serde(default)]
+  pub sold: i64,
+  pub threshold: Option<i64>,
+}
+
+/// The inventory of the variants of a product, keyed by variant id. Products that never had
+/// a reservation have no rows.
+pub fn variants_inventory(value: Option<Value>) -> HashMap<String, VariantInventory> {
+  value.and_then(|value| from_value(value).ok()).unwrap_or_default()
+}
+
+/// The units of the variant that can still be ordered, the offer's quantity minus reservations
+pub fn variant_available(
+  variant: &ProductOfferVariant,
+  inventory: Option<&VariantInventory>,
+) -> i64 {
+  (variant.quantity as i64 - inventory.map(|i| i.reserved).unwrap_or(0)).max(0)
+}
+
+pub fn variant_stock_level(
+  variant: &ProductOfferVariant,
+  inventory: Option<&VariantInventory>,
+) -> StockLevel {
+  let threshold = inventory.and_then(|i| i.threshold).unwrap_or(INVENTORY_LOW_STOCK_THRESHOLD);
+  StockLevel::of(variant_available(variant, inventory), threshold)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_variant_stock_level() {
+    let variant = ProductOfferVariant { quantity: 12, ..Default::default() };
+    let reserved =
+      |reserved: i64, threshold: Option<i64>| VariantInventory { reserved, sold: 0, threshold };
+
+    assert_eq!(variant_stock_level(&variant, None), StockLevel::InStock);
+    assert_eq!(variant_stock_level(&variant, Some(&reserved(7, None))), StockLevel::LowStock);
+    assert_eq!(variant_stock_level(&variant, Some(&reserved(2, Some(10)))), StockLevel::LowStock);
+    assert_eq!(variant_stock_level(&variant, Some(&reserved(12, None))), StockLevel::OutOfStock);
+    // more reserved than the offer holds, e.g. the seller lowered the quantity
+    assert_eq!(variant_available(&variant, Some(&reserved(20, None))), 0);
+  }
+
+  #[test]
+  fn test_inventory_operation_fingerprint() {
+    let reserve = |quantity: u32| InventoryOperation::Reserve {
+      product_id: "p".into(),
+      variant_id: "v".into(),
+      quantity,
+    };
+    assert_eq!(reserve(2).fingerprint(), reserve(2).fingerprint());
+    assert_ne!(reserve(2).fingerprint(), reserve(3).fingerprint());
+  }
+}
@@ -1,5 +1,21 @@
 pub mod audit;
+pub mod best_selling;
 pub mod config;
+pub mod exchange_rates;
+pub mod inventory;
+pub mod money;
+pub mod pagination;
+pub mod permissions;
+pub mod price_quote;
 pub mod product_create;
+pub mod product_events;
+pub mod product_reviews;
+pub mod product_update;
 pub mod products;
+pub mod products_facets;
+pub mod products_search;
+pub mod recommendations;
+pub mod related_products;
+pub mod sellers;
 pub mod time;
+pub mod variant_selection;
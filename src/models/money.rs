@@ -0,0 +1,317 @@
+use std::{fmt, str::FromStr};
+
+use megacommerce_proto::{ProductOffer, ProductOfferVariant};
+use rust_decimal::{prelude::ToPrimitive, Decimal, RoundingStrategy};
+
+use crate::data::{
+  currencies::{currency_get, Currency},
+  locales::{number_locale_get, DigitGrouping},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoneyError {
+  UnknownCurrency,
+  InvalidAmount,
+  /// The amount in minor units doesn't fit an `i64`
+  TooLarge,
+}
+
+impl fmt::Display for MoneyError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let msg = match self {
+      Self::UnknownCurrency => "unknown currency",
+      Self::InvalidAmount => "invalid amount",
+      Self::TooLarge => "the amount is too large",
+    };
+    write!(f, "{}", msg)
+  }
+}
+
+impl std::error::Error for MoneyError {}
+
+/// An exact amount of a currency. Prices are kept as decimal strings, and are only
+/// converted to minor units (cents for most currencies) when they're sent out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Money {
+  amount: Decimal,
+  currency: &'static Currency,
+}
+
+impl Money {
+  /// Parses an amount such as "19.99", it may be more precise than the currency,
+  /// see `is_exact`
+  pub fn parse(amount: &str, currency_code: &str) -> Result<Self, MoneyError> {
+    let currency = currency_get(currency_code).ok_or(MoneyError::UnknownCurrency)?;
+    Self::parse_in(amount, currency)
+  }
+
+  pub fn parse_in(amount: &str, currency: &'static Currency) -> Result<Self, MoneyError> {
+    let amount = Decimal::from_str(amount.trim()).map_err(|_| MoneyError::InvalidAmount)?;
+    Ok(Self { amount, currency })
+  }
+
+  pub fn from_minor_units(units: i64, currency: &'static Currency) -> Self {
+    Self { amount: Decimal::new(units, currency.exponent), currency }
+  }
+
+  pub fn from_decimal(amount: Decimal, currency: &'static Currency) -> Self {
+    Self { amount, currency }
+  }
+
+  pub fn amount(&self) -> Decimal {
+    self.amount
+  }
+
+  pub fn currency(&self) -> &'static Currency {
+    self.currency
+  }
+
+  pub fn is_positive(&self) -> bool {
+    self.amount > Decimal::ZERO
+  }
+
+  /// Whether the amount is a whole number of minor units, e.g. 19.99 USD but not 19.999 USD
+  pub fn is_exact(&self) -> bool {
+    self.amount.normalize().scale() <= self.currency.exponent
+  }
+
+  /// The amount in minor units, rounded half away from zero
+  pub fn checked_minor_units(&self) -> Result<i64, MoneyError> {
+    let mut rounded = self
+      .amount
+      .round_dp_with_strategy(self.currency.exponent, RoundingStrategy::MidpointAwayFromZero);
+    rounded.rescale(self.currency.exponent);
+    i64::try_from(rounded.mantissa()).map_err(|_| MoneyError::TooLarge)
+  }
+
+  /// The amount in minor units, saturated for the amounts out of range, which
+  /// the validation rejects
+  pub fn minor_units(&self) -> i64 {
+    match self.checked_minor_units() {
+      Ok(units) => units,
+      Err(_) if self.amount.is_sign_negative() => i64::MIN,
+      Err(_) => i64::MAX,
+    }
+  }
+
+  pub fn to_f64(&self) -> f64 {
+    self.amount.to_f64().unwrap_or_default()
+  }
+
+  pub fn checked_sub(&self, other: &Money) -> Option<Money> {
+    if self.currency != other.currency {
+      return None;
+    }
+    Some(Self { amount: self.amount.checked_sub(other.amount)?, currency: self.currency })
+  }
+
+  /// How much cheaper `sale` is, in whole percents, 0 unless it's cheaper
+  pub fn discount_percentage(&self, sale: &Money) -> u32 {
+    if !self.is_positive() || sale.amount >= self.amount {
+      return 0;
+    }
+    let saved = (self.amount - sale.amount) / self.amount * Decimal::ONE_HUNDRED;
+    saved.round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero).to_u32().unwrap_or(0)
+  }
+
+  /// The amount for display in the language of an `Accept-Language` value, with the
+  /// currency's symbol and decimals, e.g. "$1,234.50" in English and "1.234,50 €" in German
+  pub fn format(&self, accept_language: &str) -> String {
+    let locale = number_locale_get(accept_language);
+    let plain = self.to_string();
+    let (sign, plain) = match plain.strip_prefix('-') {
+      Some(plain) => ("-", plain),
+      None => ("", plain.as_str()),
+    };
+    let (integer, fraction) = plain.split_once('.').unwrap_or((plain, ""));
+
+    let mut number = group_digits(integer, locale.group, locale.grouping);
+    if !fraction.is_empty() {
+      number.push_str(locale.decimal);
+      number.push_str(fraction);
+    }
+
+    let space = if locale.symbol_space { "\u{a0}" } else { "" };
+    let symbol = self.currency.symbol;
+    if locale.symbol_first {
+      format!("{}{}{}{}", sign, symbol, space, number)
+    } else {
+      format!("{}{}{}{}", sign, number, space, symbol)
+    }
+  }
+}
+
+fn group_digits(integer: &str, separator: &str, grouping: DigitGrouping) -> String {
+  let digits: Vec<char> = integer.chars().collect();
+  let mut groups: Vec<String> = vec![];
+  let mut end = digits.len();
+  let mut size = 3;
+  while end > 0 {
+    let start = end.saturating_sub(size);
+    groups.push(digits[start..end].iter().collect());
+    end = start;
+    if grouping == DigitGrouping::Indian {
+      size = 2;
+    }
+  }
+  groups.reverse();
+  groups.join(separator)
+}
+
+/// The amount with exactly the currency's decimals, e.g. "19.90", the way prices are stored
+impl fmt::Display for Money {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let mut amount = self
+      .amount
+      .round_dp_with_strategy(self.currency.exponent, RoundingStrategy::MidpointAwayFromZero);
+    amount.rescale(self.currency.exponent);
+    write!(f, "{}", amount)
+  }
+}
+
+/// When a sale price applies, in milliseconds. The start is inclusive and the end exclusive,
+/// an open end lasts until the seller removes the sale.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SaleWindow {
+  pub start: Option<u64>,
+  pub end: Option<u64>,
+}
+
+impl SaleWindow {
+  pub fn of(offer: &ProductOfferVariant) -> Self {
+    Self { start: offer.sale_price_start, end: offer.sale_price_end }
+  }
+
+  pub fn contains(&self, at: u64) -> bool {
+    self.start.is_none_or(|start| start <= at) && self.end.is_none_or(|end| at < end)
+  }
+}
+
+/// The price of an offer variant, along with its sale price if it's on sale
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VariantPrice {
+  pub price: Money,
+  pub sale_price: Option<Money>,
+}
+
+impl VariantPrice {
+  /// The effective price of the variant at `at` (in milliseconds), every read path goes
+  /// through it so a sale is never shown before it starts or after it ends
+  pub fn from_offer(
+    offer: &ProductOfferVariant,
+    currency_code: &str,
+    at: u64,
+  ) -> Result<Self, MoneyError> {
+    let sale_price = offer.sale_price.as_deref().filter(|_| offer.has_sale_price);
+    Ok(Self::parse(&offer.price, sale_price, currency_code)?.at(SaleWindow::of(offer), at))
+  }
+
+  /// A blank or zero sale price means the variant isn't on sale
+  pub fn parse(
+    price: &str,
+    sale_price: Option<&str>,
+    currency_code: &str,
+  ) -> Result<Self, MoneyError> {
+    let currency = currency_get(currency_code).ok_or(MoneyError::UnknownCurrency)?;
+    let price = Money::parse_in(price, currency)?;
+    let sale_price = match sale_price.map(str::trim) {
+      Some(sale_price) if !sale_price.is_empty() => {
+        Some(Money::parse_in(sale_price, currency)?).filter(Money::is_positive)
+      }
+      _ => None,
+    };
+    Ok(Self { price, sale_price })
+  }
+
+  /// Drops the sale price if the sale doesn't apply at `at`
+  pub fn at(self, window: SaleWindow, at: u64) -> Self {
+    match window.contains(at) {
+      true => self,
+      false => Self { sale_price: None, ..self },
+    }
+  }
+
+  /// The price the variant sells at
+  pub fn selling(&self) -> Money {
+    self.sale_price.unwrap_or(self.price)
+  }
+
+  pub fn discount_percentage(&self) -> Option<u32> {
+    self.sale_price.map(|sale_price| self.price.discount_percentage(&sale_price))
+  }
+}
+
+/// Removes the sale prices that don't apply at `at` from the offer, for the shoppers to
+/// only see the effective prices. The sale's dates are kept, e.g. for an upcoming sale.
+pub fn offer_sales_at(offer: &mut ProductOffer, at: u64) {
+  for variant in offer.offer.values_mut() {
+    if variant.has_sale_price && !SaleWindow::of(variant).contains(at) {
+      variant.has_sale_price = false;
+      variant.sale_price = None;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_money_minor_units() {
+    assert_eq!(Money::parse("19.99", "USD").unwrap().minor_units(), 1999);
+    assert_eq!(Money::parse("0.285", "USD").unwrap().minor_units(), 29);
+    assert_eq!(Money::parse("1500", "JPY").unwrap().minor_units(), 1500);
+    assert_eq!(Money::parse("1.234", "KWD").unwrap().minor_units(), 1234);
+    // far beyond what u32 cents could hold
+    assert_eq!(Money::parse("98765432.10", "USD").unwrap().minor_units(), 9_876_543_210);
+    assert_eq!(
+      Money::parse("99999999999999999999", "USD").unwrap().checked_minor_units(),
+      Err(MoneyError::TooLarge)
+    );
+    assert_eq!(Money::parse("1.5", "XXX"), Err(MoneyError::UnknownCurrency));
+    assert_eq!(Money::parse("1,5", "USD"), Err(MoneyError::InvalidAmount));
+  }
+
+  #[test]
+  fn test_money_precision_and_display() {
+    assert!(Money::parse("19.90", "USD").unwrap().is_exact());
+    assert!(Money::parse("19.900", "USD").unwrap().is_exact());
+    assert!(!Money::parse("19.999", "USD").unwrap().is_exact());
+    assert!(!Money::parse("100.5", "JPY").unwrap().is_exact());
+    assert_eq!(Money::parse("19.9", "USD").unwrap().to_string(), "19.90");
+    assert_eq!(Money::parse("3", "BHD").unwrap().to_string(), "3.000");
+    assert_eq!(Money::parse("1500", "JPY").unwrap().to_string(), "1500");
+  }
+
+  #[test]
+  fn test_money_format() {
+    let price = Money::parse("1234567.5", "USD").unwrap();
+    assert_eq!(price.format("en-US,en;q=0.9"), "$1,234,567.50");
+    assert_eq!(price.format("en-IN"), "$12,34,567.50");
+    assert_eq!(price.format("xx"), "$1,234,567.50");
+    let price = Money::parse("1234.5", "EUR").unwrap();
+    assert_eq!(price.format("de-DE"), "1.234,50\u{a0}€");
+    assert_eq!(price.format("fr"), "1\u{202f}234,50\u{a0}€");
+    assert_eq!(Money::parse("1500", "JPY").unwrap().format("ja"), "¥1,500");
+    assert_eq!(Money::parse("-5", "USD").unwrap().format("en"), "-$5.00");
+  }
+
+  #[test]
+  fn test_variant_price_sale_window() {
+    let prices = VariantPrice::parse("80", Some("60"), "USD").unwrap();
+    let window = SaleWindow { start: Some(1_000), end: Some(2_000) };
+    assert_eq!(prices.at(window, 999).sale_price, None);
+    assert_eq!(prices.at(window, 1_000).selling().to_string(), "60.00");
+    assert_eq!(prices.at(window, 1_999).discount_percentage(), Some(25));
+    assert_eq!(prices.at(window, 2_000).selling().to_string(), "80.00");
+    assert!(SaleWindow { start: Some(1_000), end: None }.contains(u64::MAX));
+  }
+
+  #[test]
+  fn test_money_discount_percentage() {
+    let price = Money::parse("80", "USD").unwrap();
+    assert_eq!(price.discount_percentage(&Money::parse("59.99", "USD").unwrap()), 25);
+    assert_eq!(price.discount_percentage(&Money::parse("90", "USD").unwrap()), 0);
+    assert_eq!(Money::parse("0", "USD").unwrap().discount_percentage(&price), 0);
+  }
+}
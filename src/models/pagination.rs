@@ -0,0 +1,154 @@
+use base64::{prelude::BASE64_URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PageDirection {
+  #[serde(rename = "n")]
+  Next,
+  #[serde(rename = "p")]
+  Previous,
+}
+
+/// The value of the sort column of an item, e.g. its price or creation time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PageCursorKey {
+  Int(i64),
+  Float(f64),
+}
+
+/// Where an item is in the sort order, the id breaks the ties of the sort key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PagePosition {
+  #[serde(rename = "k", skip_serializing_if = "Option::is_none", default)]
+  pub key: Option<PageCursorKey>,
+  #[serde(rename = "i")]
+  pub id: String,
+}
+
+/// The rows a list was computed into for its first page, e.g. the scored recommendations.
+/// It's pinned in the cursors, so the next pages are read from the same rows, whatever
+/// changed since in the data they were computed from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageSnapshot {
+  #[serde(rename = "i")]
+  pub id: String,
+}
+
+/// Points at the first (or last) item of a page, the next page starts after it
+/// and the previous one ends before it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageCursor {
+  /// The sort the cursor was issued for, it can't be used with another one
+  #[serde(rename = "s")]
+  pub sort: String,
+  #[serde(rename = "d")]
+  pub direction: PageDirection,
+  #[serde(rename = "p")]
+  pub position: PagePosition,
+  #[serde(rename = "w", skip_serializing_if = "Option::is_none", default)]
+  pub snapshot: Option<PageSnapshot>,
+}
+
+/// A page of a keyset paginated list, along with the positions the cursors are issued from
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeysetPage<T> {
+  pub items: Vec<T>,
+  /// Whether more rows follow the page in the direction it was read in
+  pub has_more: bool,
+  pub first: Option<PagePosition>,
+  pub last: Option<PagePosition>,
+  /// The rows the page was read from, the cursors issued from the page keep them
+  pub snapshot: Option<PageSnapshot>,
+}
+
+impl<T> Default for KeysetPage<T> {
+  fn default() -> Self {
+    Self { items: vec![], has_more: false, first: None, last: None, snapshot: None }
+  }
+}
+
+/// The total count of a list, the big ones are estimated by the query planner
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListTotal {
+  pub count: u64,
+  pub estimated: bool,
+}
+
+impl PageCursor {
+  /// Encodes the cursor as `<payload>.<signature>`, both base64url, so clients can pass it
+  /// back but can't forge or alter one
+  pub fn encode(&self, secret: &str) -> String {
+    let payload = BASE64_URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default());
+    let signature = cursor_mac(secret, &payload).finalize().into_bytes();
+    let signature = BASE64_URL_SAFE_NO_PAD.encode(signature);
+    format!("{}.{}", payload, signature)
+  }
+
+  /// Decodes a cursor issued by `encode`, none if it's malformed or its signature doesn't match
+  pub fn decode(cursor: &str, secret: &str) -> Option<Self> {
+    let (payload, signature) = cursor.split_once('.')?;
+    let signature = BASE64_URL_SAFE_NO_PAD.decode(signature).ok()?;
+    cursor_mac(secret, payload).verify_slice(&signature).ok()?;
+
+    let payload = BASE64_URL_SAFE_NO_PAD.decode(payload).ok()?;
+    serde_json::from_slice(&payload).ok()
+  }
+}
+
+fn cursor_mac(secret: &str, payload: &str) -> HmacSha256 {
+  // HMAC accepts keys of any length
+  let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
+  mac.update(payload.as_bytes());
+  mac
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn cursor() -> PageCursor {
+    PageCursor {
+      sort: "price:asc".into(),
+      direction: PageDirection::Next,
+      position: PagePosition {
+        key: Some(PageCursorKey::Float(19.99)),
+        id: "01JZ0000000000000000000000".into(),
+      },
+      snapshot: None,
+    }
+  }
+
+  #[test]
+  fn test_page_cursor_round_trip() {
+    let encoded = cursor().encode("secret");
+    assert_eq!(PageCursor::decode(&encoded, "secret"), Some(cursor()));
+
+    let mut by_date = cursor();
+    by_date.position.key = Some(PageCursorKey::Int(1_750_000_000_000));
+    let encoded = by_date.encode("secret");
+    assert_eq!(PageCursor::decode(&encoded, "secret"), Some(by_date));
+
+    let mut by_score = cursor();
+    by_score.snapshot = Some(PageSnapshot { id: "01JZ0000000000000000000001".into() });
+    let encoded = by_score.encode("secret");
+    assert_eq!(PageCursor::decode(&encoded, "secret"), Some(by_score));
+  }
+
+  #[test]
+  fn test_page_cursor_tampered() {
+    let encoded = cursor().encode("secret");
+    assert_eq!(PageCursor::decode(&encoded, "another"), None);
+
+    let (_, signature) = encoded.split_once('.').unwrap();
+    let mut forged = cursor();
+    forged.position.id = "01JZ0000000000000000000001".into();
+    let payload = BASE64_URL_SAFE_NO_PAD.encode(serde_json::to_vec(&forged).unwrap());
+    assert_eq!(PageCursor::decode(&format!("{}.{}", payload, signature), "secret"), None);
+    assert_eq!(PageCursor::decode("garbage", "secret"), None);
+  }
+}
@@ -0,0 +1,19 @@
+/// Whether one of the session's roles (space separated, as the sessions carry them)
+/// is one of the allowed roles
+pub fn roles_allow(session_roles: &str, allowed: &[String]) -> bool {
+  session_roles.split_whitespace().any(|role| allowed.iter().any(|a| a == role))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_roles_allow() {
+    let allowed = vec!["system_admin".to_string(), "system_moderator".to_string()];
+    assert!(roles_allow("system_user system_moderator", &allowed));
+    assert!(!roles_allow("system_user", &allowed));
+    assert!(!roles_allow("", &allowed));
+    assert!(!roles_allow("system_admin_like", &allowed));
+  }
+}
@@ -0,0 +1,100 @@
+use megacommerce_proto::ProductOfferVariant;
+
+use crate::models::money::{Money, MoneyError, VariantPrice};
+
+/// A variant of a published product, with the units reserved by orders
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariantStock {
+  pub variant: ProductOfferVariant,
+  pub currency_code: String,
+  pub reserved: i64,
+}
+
+impl VariantStock {
+  /// The units left to order, never negative
+  pub fn available(&self) -> i64 {
+    (self.variant.quantity as i64 - self.reserved).max(0)
+  }
+}
+
+/// The price of `quantity` units of a variant at a point in time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriceQuote {
+  pub prices: VariantPrice,
+  /// The unit price of the largest minimum-order tier the quantity reaches
+  pub tier: Option<(u32, Money)>,
+  /// The price each unit is charged, the lowest of the selling and the tier price
+  pub unit_price: Money,
+  pub total: Money,
+}
+
+/// Prices `quantity` units of the variant at `at` (in milliseconds). A sale that applies
+/// at `at` and a reached minimum-order tier both lower the unit price, the lowest one wins.
+pub fn price_quote(stock: &VariantStock, quantity: u32, at: u64) -> Result<PriceQuote, MoneyError> {
+  let variant = &stock.variant;
+  let prices = VariantPrice::from_offer(variant, &stock.currency_code, at)?;
+  let currency = prices.price.currency();
+
+  let mut tier: Option<(u32, Money)> = None;
+  if variant.has_minimum_orders {
+    for mo in variant.minimum_orders.iter().filter(|mo| mo.quantity <= quantity) {
+      if tier.is_none_or(|(q, _)| mo.quantity > q) {
+        tier = Some((mo.quantity, Money::parse_in(&mo.price, currency)?));
+      }
+    }
+  }
+
+  let selling = prices.selling();
+  let unit_price = match tier {
+    Some((_, tier_price)) if tier_price.amount() < selling.amount() => tier_price,
+    _ => selling,
+  };
+  let total = unit_price.amount().checked_mul(quantity.into()).ok_or(MoneyError::TooLarge)?;
+
+  Ok(PriceQuote { prices, tier, unit_price, total: Money::from_decimal(total, currency) })
+}
+
+#[cfg(test)]
+mod tests {
+  use megacommerce_proto::ProductOfferMinimumOrder;
+
+  use super::*;
+
+  fn stock() -> VariantStock {
+    let tier = |quantity: u32, price: &str| ProductOfferMinimumOrder {
+      quantity,
+      price: price.into(),
+      ..Default::default()
+    };
+    VariantStock {
+      variant: ProductOfferVariant {
+        price: "10.00".into(),
+        quantity: 100,
+        has_sale_price: true,
+        sale_price: Some("9.00".into()),
+        sale_price_start: Some(1_000),
+        sale_price_end: Some(2_000),
+        has_minimum_orders: true,
+        minimum_orders: vec![tier(10, "9.50"), tier(50, "8.00")],
+        ..Default::default()
+      },
+      currency_code: "USD".into(),
+      reserved: 30,
+    }
+  }
+
+  #[test]
+  fn test_price_quote_tiers() {
+    let quote = price_quote(&stock(), 5, 0).unwrap();
+    assert_eq!((quote.tier, quote.unit_price.to_string()), (None, "10.00".into()));
+
+    let quote = price_quote(&stock(), 20, 0).unwrap();
+    assert_eq!(quote.tier.map(|(q, _)| q), Some(10));
+    assert_eq!(quote.total.to_string(), "190.00");
+
+    // the sale is cheaper than the first tier, but not than the second
+    assert_eq!(price_quote(&stock(), 20, 1_500).unwrap().unit_price.to_string(), "9.00");
+    assert_eq!(price_quote(&stock(), 60, 1_500).unwrap().unit_price.to_string(), "8.00");
+    assert_eq!(stock().available(), 70);
+  }
+}
@@ -1,5 +1,6 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, str::FromStr, sync::Arc};
 
+use base64::{prelude::BASE64_STANDARD, Engine};
 use chrono::NaiveDate;
 use image::ImageFormat;
 use lazy_static::lazy_static;
@@ -32,7 +33,8 @@ use megacommerce_shared::{
     errors::{AppError, AppErrorError, AppErrorErrors},
     files::UnitSizeType,
     images::{
-      validate_base64_image, ImageValidationConfig, ImageValidationError, ImageValidationResult,
+      validate_base64_image, validate_image_bytes, ImageValidationConfig, ImageValidationError,
+      ImageValidationResult,
     },
     products::SubcategoryAttributeType,
   },
@@ -41,12 +43,14 @@ use megacommerce_shared::{
     time::{date_to_milliseconds, time_get_millis},
   },
 };
+use rust_decimal::Decimal;
 use serde_json::{json, Value};
 use tonic::Code;
 use ulid::Ulid;
 
 use crate::{
-  data::currencies::CURRENCY_LIST,
+  data::currencies::{currency_get, Currency},
+  models::money::Money,
   models::products::{
     product_id_is_validate, ProductCreateStepsNames, ProductFulfillmentType,
     ProductOfferingCondition, PRODUCT_BRAND_NAME_MAX_LENGTH, PRODUCT_BRAND_NAME_MIN_LENGTH,
@@ -59,10 +63,14 @@ use crate::{
     PRODUCT_TITLE_MAX_LENGTH, PRODUCT_TITLE_MIN_LENGTH, PRODUCT_VARIATION_TITLE_MAX_LENGTH,
     PRODUCT_VARIATION_TITLE_MIN_LENGTH,
   },
-  utils::slug::Slug,
+  utils::{
+    checksum::sha256_hex,
+    slug::Slug,
+    video::{video_probe, VideoInfo, VideoProbeError},
+  },
 };
 
-use super::products::ProductStatus;
+use super::{config::ProductsConfig, products::ProductStatus};
 
 struct ProductCreateOfferPricingSharedFields {
   pub sku: String,
@@ -96,11 +104,23 @@ lazy_static! {
     AppErrorError { id: "form.field.checkbox.checked.error".into(), params: None };
 }
 
+#[derive(Debug)]
+pub struct VideoValidationResult {
+  pub info: VideoInfo,
+  pub size_bytes: usize,
+  pub decoded_data: Vec<u8>,
+}
+
 #[derive(Debug)]
 pub struct ProductCreateIsValidResult {
   pub media_validation_results_no_variants: HashMap<String, ImageValidationResult>,
   pub media_validation_results_with_variants:
     HashMap<String, HashMap<String, ImageValidationResult>>,
+  pub media_videos_validation_results_no_variants: HashMap<String, VideoValidationResult>,
+  pub media_videos_validation_results_with_variants:
+    HashMap<String, HashMap<String, VideoValidationResult>>,
+  /// attachment id -> the verified SHA-256 checksum of its decoded bytes
+  pub media_checksums: HashMap<String, String>,
 }
 
 impl Default for ProductCreateIsValidResult {
@@ -108,6 +128,9 @@ impl Default for ProductCreateIsValidResult {
     Self {
       media_validation_results_no_variants: HashMap::new(),
       media_validation_results_with_variants: HashMap::new(),
+      media_videos_validation_results_no_variants: HashMap::new(),
+      media_videos_validation_results_with_variants: HashMap::new(),
+      media_checksums: HashMap::new(),
     }
   }
 }
@@ -117,6 +140,8 @@ pub fn products_create_is_valid(
   product: &ProductCreateRequest,
   subcategory_data: Option<ProductDataResponseSubcategory>,
   config: &Config,
+  products_config: &ProductsConfig,
+  staged: &mut HashMap<String, Vec<u8>>,
 ) -> Result<ProductCreateIsValidResult, AppError> {
   let mut result = ProductCreateIsValidResult { ..Default::default() };
 
@@ -147,9 +172,7 @@ pub fn products_create_is_valid(
     return Err(error_builder(ctx, errors));
   }
 
-  let media_validaiton = media_form_validation(media, &mut errors, config);
-  result.media_validation_results_with_variants = media_validaiton.0;
-  result.media_validation_results_no_variants = media_validaiton.1;
+  media_form_validation(media, &mut errors, config, products_config, staged, &mut result);
   if errors.len() > 0 {
     return Err(error_builder(ctx, errors));
   }
@@ -167,7 +190,7 @@ pub fn products_create_is_valid(
   Ok(result)
 }
 
-fn identity_form_validation(
+pub(super) fn identity_form_validation(
   identity: ProductCreateRequestIdentity,
   errors: &mut HashMap<String, AppErrorError>,
   subcategory_data: &Option<ProductDataResponseSubcategory>,
@@ -241,7 +264,7 @@ fn identity_form_validation(
   }
 }
 
-fn description_form_validation(
+pub(super) fn description_form_validation(
   description: ProductCreateRequestDescription,
   errors: &mut HashMap<String, AppErrorError>,
 ) {
@@ -437,24 +460,22 @@ fn details_without_variations_form_validation(
 
 // TODO:
 // handle resumeable uploading case (for big media files)
-// handle validating videos also
-// handle validating checksum of attachments
+/// The attachments referencing a staged upload (by `key`) are validated from their
+/// downloaded bytes in `staged`, the other ones from their inline (base64) data.
 fn media_form_validation(
   media: ProductCreateRequestMedia,
   errors: &mut HashMap<String, AppErrorError>,
   config: &Config,
-) -> (HashMap<String, HashMap<String, ImageValidationResult>>, HashMap<String, ImageValidationResult>)
-{
-  let mut validation_results_variants: HashMap<String, HashMap<String, ImageValidationResult>> =
-    HashMap::new();
-  let mut validation_results_no_variants: HashMap<String, ImageValidationResult> = HashMap::new();
-
+  products_config: &ProductsConfig,
+  staged: &mut HashMap<String, Vec<u8>>,
+  result: &mut ProductCreateIsValidResult,
+) {
   if media.media.is_none() {
     errors.insert(
       missing_form(&ProductCreateStepsNames::Details),
       AppErrorError { id: "products.media.form.missing".into(), params: None },
     );
-    return (validation_results_variants, validation_results_no_variants);
+    return;
   }
 
   let form = media.media.unwrap();
@@ -481,23 +502,42 @@ fn media_form_validation(
     min_width: cfg.product_image_min_width as u32,
     min_height: cfg.product_image_min_height as u32,
   };
+  let checksums = &mut result.media_checksums;
   match form {
     MediaWithVariants(m) => {
-      validation_results_variants =
-        media_with_variations_form_validation(m, errors, config, img_config);
+      result.media_validation_results_with_variants = media_with_variations_form_validation(
+        m.images, errors, checksums, staged, config, img_config,
+      );
+      result.media_videos_validation_results_with_variants =
+        media_videos_with_variations_form_validation(
+          m.videos,
+          errors,
+          checksums,
+          staged,
+          products_config,
+        );
     }
     MediaNoVariants(m) => {
-      validation_results_no_variants =
-        media_without_variations_form_validation(m, errors, config, img_config);
+      result.media_validation_results_no_variants = media_without_variations_form_validation(
+        m.images, errors, checksums, staged, config, img_config,
+      );
+      result.media_videos_validation_results_no_variants =
+        media_videos_without_variations_form_validation(
+          m.videos,
+          errors,
+          checksums,
+          staged,
+          products_config,
+        );
     }
   }
-
-  (validation_results_variants, validation_results_no_variants)
 }
 
 fn media_with_variations_form_validation(
-  forms: ProductCreateRequestMediaWithVariants,
+  images: HashMap<String, Attachments>,
   errors: &mut HashMap<String, AppErrorError>,
+  checksums: &mut HashMap<String, String>,
+  staged: &mut HashMap<String, Vec<u8>>,
   config: &Config,
   img_cfg: &ImageValidationConfig,
 ) -> HashMap<String, HashMap<String, ImageValidationResult>> {
@@ -508,12 +548,12 @@ fn media_with_variations_form_validation(
   let mut validation_results: HashMap<String, HashMap<String, ImageValidationResult>> =
     HashMap::new();
 
-  if forms.images.is_empty() {
+  if images.is_empty() {
     let err = AppErrorError { id: "products.media.missing_images".into(), params: None };
     field_error(errors, step, None, "count", err);
   }
 
-  for form in forms.images.iter() {
+  for form in images.iter() {
     if form.1.attachments.len() < min_count || form.1.attachments.len() > max_count {
       let params = Some(HashMap::from([
         ("Min".into(), Value::Number(min_count.into())),
@@ -529,12 +569,17 @@ fn media_with_variations_form_validation(
       return validation_results;
     }
     for attachment in form.1.attachments.iter() {
-      let validation_result = validate_image(cfg, img_cfg, errors, step, Some(form.0), attachment);
+      let validation_result =
+        validate_image(cfg, img_cfg, errors, step, Some(form.0), attachment, staged);
       if validation_result.is_some() {
-        validation_results
-          .entry(form.0.clone())
-          .or_insert_with(HashMap::new)
-          .insert(attachment.id.clone(), validation_result.unwrap());
+        let result = validation_result.unwrap();
+        let data = &result.decoded_data;
+        if validate_checksum(errors, checksums, step, Some(form.0), attachment, data) {
+          validation_results
+            .entry(form.0.clone())
+            .or_insert_with(HashMap::new)
+            .insert(attachment.id.clone(), result);
+        }
       }
     }
   }
@@ -543,8 +588,10 @@ fn media_with_variations_form_validation(
 }
 
 fn media_without_variations_form_validation(
-  form: ProductCreateRequestMediaWithoutVariants,
+  images: Vec<Attachment>,
   errors: &mut HashMap<String, AppErrorError>,
+  checksums: &mut HashMap<String, String>,
+  staged: &mut HashMap<String, Vec<u8>>,
   config: &Config,
   img_cfg: &ImageValidationConfig,
 ) -> HashMap<String, ImageValidationResult> {
@@ -554,12 +601,12 @@ fn media_without_variations_form_validation(
   let max_count = cfg.product_images_max_count_per_variant.clone() as usize;
   let mut validation_results: HashMap<String, ImageValidationResult> = HashMap::new();
 
-  if form.images.is_empty() {
+  if images.is_empty() {
     let err = AppErrorError { id: "products.media.missing_images".into(), params: None };
     field_error(errors, step, None, "count", err);
   }
 
-  if form.images.len() < min_count || form.images.len() > max_count {
+  if images.len() < min_count || images.len() > max_count {
     let params = Some(HashMap::from([
       ("Min".into(), Value::Number(min_count.into())),
       ("Max".into(), Value::Number(max_count.into())),
@@ -569,10 +616,13 @@ fn media_without_variations_form_validation(
     return validation_results;
   }
 
-  for attachment in form.images.iter() {
-    let validation_result = validate_image(cfg, img_cfg, errors, step, None, attachment);
+  for attachment in images.iter() {
+    let validation_result = validate_image(cfg, img_cfg, errors, step, None, attachment, staged);
     if validation_result.is_some() {
-      validation_results.insert(attachment.id.clone(), validation_result.unwrap());
+      let result = validation_result.unwrap();
+      if validate_checksum(errors, checksums, step, None, attachment, &result.decoded_data) {
+        validation_results.insert(attachment.id.clone(), result);
+      }
     }
   }
 
@@ -586,6 +636,7 @@ fn validate_image(
   step: &ProductCreateStepsNames,
   form_id: Option<&str>,
   attachment: &Attachment,
+  staged: &mut HashMap<String, Vec<u8>>,
 ) -> Option<ImageValidationResult> {
   let max_size = cfg.product_image_max_size_mb;
   let min_w_dim = cfg.product_image_min_width;
@@ -593,9 +644,11 @@ fn validate_image(
   let min_h_dim = cfg.product_image_min_height;
   let max_h_dim = cfg.product_image_max_height;
   let image_types = cfg.product_image_accepted_formats.join(", ");
-  let img_data = attachment.base64.as_str();
 
-  let result = validate_base64_image(&img_data, img_cfg);
+  let result = match staged.remove(&attachment.key) {
+    Some(data) => validate_image_bytes(data, img_cfg),
+    None => validate_base64_image(attachment.base64.as_str(), img_cfg),
+  };
   if result.is_err() {
     match result.as_ref().unwrap_err() {
       ImageValidationError::LargeImage(_) => {
@@ -646,7 +699,187 @@ fn validate_image(
   Some(result.unwrap())
 }
 
-fn offer_form_validation(
+fn media_videos_with_variations_form_validation(
+  videos: HashMap<String, Attachments>,
+  errors: &mut HashMap<String, AppErrorError>,
+  checksums: &mut HashMap<String, String>,
+  staged: &mut HashMap<String, Vec<u8>>,
+  cfg: &ProductsConfig,
+) -> HashMap<String, HashMap<String, VideoValidationResult>> {
+  let step = &ProductCreateStepsNames::Media;
+  let max_count = cfg.video_max_count_per_variant;
+  let mut validation_results: HashMap<String, HashMap<String, VideoValidationResult>> =
+    HashMap::new();
+
+  for form in videos.iter() {
+    if form.0.is_empty() {
+      errors.insert(missing_form_id(step), ERR_MISSIN_FID.clone());
+      return validation_results;
+    }
+    if form.1.attachments.len() > max_count {
+      let params = Some(HashMap::from([("Max".into(), Value::Number(max_count.into()))]));
+      let err = AppErrorError { id: "products.media.variant_videos.count".into(), params };
+      field_error(errors, step, Some(form.0), "videos_count", err);
+      return validation_results;
+    }
+
+    for attachment in form.1.attachments.iter() {
+      if let Some(result) = validate_video(cfg, errors, step, Some(form.0), attachment, staged) {
+        let data = &result.decoded_data;
+        if validate_checksum(errors, checksums, step, Some(form.0), attachment, data) {
+          validation_results
+            .entry(form.0.clone())
+            .or_insert_with(HashMap::new)
+            .insert(attachment.id.clone(), result);
+        }
+      }
+    }
+  }
+
+  validation_results
+}
+
+fn media_videos_without_variations_form_validation(
+  videos: Vec<Attachment>,
+  errors: &mut HashMap<String, AppErrorError>,
+  checksums: &mut HashMap<String, String>,
+  staged: &mut HashMap<String, Vec<u8>>,
+  cfg: &ProductsConfig,
+) -> HashMap<String, VideoValidationResult> {
+  let step = &ProductCreateStepsNames::Media;
+  let max_count = cfg.video_max_count_per_variant;
+  let mut validation_results: HashMap<String, VideoValidationResult> = HashMap::new();
+
+  if videos.len() > max_count {
+    let params = Some(HashMap::from([("Max".into(), Value::Number(max_count.into()))]));
+    let err = AppErrorError { id: "products.media.variant_videos.count".into(), params };
+    field_error(errors, step, None, "videos_count", err);
+    return validation_results;
+  }
+
+  for attachment in videos.iter() {
+    if let Some(result) = validate_video(cfg, errors, step, None, attachment, staged) {
+      if validate_checksum(errors, checksums, step, None, attachment, &result.decoded_data) {
+        validation_results.insert(attachment.id.clone(), result);
+      }
+    }
+  }
+
+  validation_results
+}
+
+fn validate_video(
+  cfg: &ProductsConfig,
+  errors: &mut HashMap<String, AppErrorError>,
+  step: &ProductCreateStepsNames,
+  form_id: Option<&str>,
+  attachment: &Attachment,
+  staged: &mut HashMap<String, Vec<u8>>,
+) -> Option<VideoValidationResult> {
+  let max_size_bytes = (cfg.video_max_size_mb * 1024 * 1024) as usize;
+  let video_types = cfg.video_accepted_formats.join(", ");
+  let video_codecs = cfg.video_accepted_codecs.join(", ");
+  let err_max_size = || {
+    let params = Some(HashMap::from([
+      ("Max".into(), Value::Number(cfg.video_max_size_mb.into())),
+      ("Unit".into(), Value::String(UnitSizeType::MB.as_str().to_string())),
+    ]));
+    AppErrorError { id: "video.max_size.error".to_string(), params }
+  };
+
+  let data = match staged.remove(&attachment.key) {
+    Some(data) => data,
+    None => {
+      // strip the data url prefix (data:video/mp4;base64,) if the client sent one
+      let encoded = attachment.base64.as_str();
+      let encoded = encoded.split_once("base64,").map(|(_, data)| data).unwrap_or(encoded);
+      // check the size before decoding, so a huge payload isn't decoded for nothing
+      if encoded.len() / 4 * 3 > max_size_bytes + 3 {
+        field_error(errors, step, form_id, &attachment.id, err_max_size());
+        return None;
+      }
+      match BASE64_STANDARD.decode(encoded) {
+        Ok(data) => data,
+        Err(_) => {
+          let err = AppErrorError { id: "video.data.invalid".to_string(), params: None };
+          field_error(errors, step, form_id, &attachment.id, err);
+          return None;
+        }
+      }
+    }
+  };
+  if data.len() > max_size_bytes {
+    field_error(errors, step, form_id, &attachment.id, err_max_size());
+    return None;
+  }
+
+  let info = match video_probe(&data) {
+    Ok(info) => info,
+    Err(VideoProbeError::UnknownContainer) => {
+      let params = Some(HashMap::from([("Types".into(), Value::String(video_types.clone()))]));
+      let err = AppErrorError { id: "video.type.unsupported".to_string(), params };
+      field_error(errors, step, form_id, &attachment.id, err);
+      return None;
+    }
+    Err(_) => {
+      let err = AppErrorError { id: "video.data.invalid".to_string(), params: None };
+      field_error(errors, step, form_id, &attachment.id, err);
+      return None;
+    }
+  };
+
+  if !cfg.video_accepted_formats.iter().any(|f| f == info.container.to_mime_type()) {
+    let params = Some(HashMap::from([("Types".into(), Value::String(video_types))]));
+    let err = AppErrorError { id: "video.type.unsupported".to_string(), params };
+    field_error(errors, step, form_id, &attachment.id, err);
+    return None;
+  }
+  if info.codecs.iter().any(|codec| !cfg.video_accepted_codecs.contains(codec)) {
+    let params = Some(HashMap::from([("Codecs".into(), Value::String(video_codecs))]));
+    let err = AppErrorError { id: "video.codec.unsupported".to_string(), params };
+    field_error(errors, step, form_id, &attachment.id, err);
+    return None;
+  }
+  if info.duration_ms > cfg.video_max_duration_secs * 1000 {
+    let max_duration = cfg.video_max_duration_secs;
+    let params = Some(HashMap::from([("Max".into(), Value::Number(max_duration.into()))]));
+    let err = AppErrorError { id: "video.duration.max.error".to_string(), params };
+    field_error(errors, step, form_id, &attachment.id, err);
+    return None;
+  }
+
+  Some(VideoValidationResult { info, size_bytes: data.len(), decoded_data: data })
+}
+
+/// Verifies the attachment's SHA-256 checksum against its decoded bytes,
+/// the verified checksum is recorded under the attachment id
+fn validate_checksum(
+  errors: &mut HashMap<String, AppErrorError>,
+  checksums: &mut HashMap<String, String>,
+  step: &ProductCreateStepsNames,
+  form_id: Option<&str>,
+  attachment: &Attachment,
+  data: &[u8],
+) -> bool {
+  let expected = attachment.checksum.trim().to_lowercase();
+  if expected.is_empty() {
+    let err = AppErrorError { id: "attachment.checksum.required".to_string(), params: None };
+    field_error(errors, step, form_id, &attachment.id, err);
+    return false;
+  }
+
+  let actual = sha256_hex(data);
+  if actual != expected {
+    let err = AppErrorError { id: "attachment.checksum.mismatch".to_string(), params: None };
+    field_error(errors, step, form_id, &attachment.id, err);
+    return false;
+  }
+
+  checksums.insert(attachment.id.clone(), actual);
+  true
+}
+
+pub(super) fn offer_form_validation(
   form: ProductCreateRequestOffer,
   errors: &mut HashMap<String, AppErrorError>,
 ) {
@@ -659,12 +892,13 @@ fn offer_form_validation(
     return;
   }
 
+  let currency = currency_get(&form.currency);
   match form.pricing.unwrap() {
-    OfferWithVariants(offer) => offer_with_variations_form_validation(offer, errors),
-    OfferNoVariants(offer) => offer_without_variations_form_validation(offer, errors),
+    OfferWithVariants(offer) => offer_with_variations_form_validation(offer, currency, errors),
+    OfferNoVariants(offer) => offer_without_variations_form_validation(offer, currency, errors),
   }
 
-  if CURRENCY_LIST.iter().find(|cur| cur.code == form.currency).is_none() {
+  if currency.is_none() {
     let err = AppErrorError { id: "products.currency_code.error".to_string(), params: None };
     field_error(errors, step, None, "currency", err);
   }
@@ -678,6 +912,7 @@ fn offer_form_validation(
 
 fn offer_with_variations_form_validation(
   form: ProductCreateRequestOfferWithVariants,
+  currency: Option<&'static Currency>,
   errors: &mut HashMap<String, AppErrorError>,
 ) {
   let step = &ProductCreateStepsNames::Offer;
@@ -700,12 +935,13 @@ fn offer_with_variations_form_validation(
       has_minimum_orders: form.has_minimum_orders,
       minimum_orders: form.minimum_orders.clone(),
     };
-    validate_offer_pricing(offer, step, Some(&form.id), errors);
+    validate_offer_pricing(offer, currency, step, Some(&form.id), errors);
   }
 }
 
 fn offer_without_variations_form_validation(
   form: ProductCreateRequestOfferWithoutVariants,
+  currency: Option<&'static Currency>,
   errors: &mut HashMap<String, AppErrorError>,
 ) {
   let step = &ProductCreateStepsNames::Offer;
@@ -723,11 +959,12 @@ fn offer_without_variations_form_validation(
     has_minimum_orders: form.has_minimum_orders,
     minimum_orders: form.minimum_orders,
   };
-  validate_offer_pricing(offer, step, None, errors);
+  validate_offer_pricing(offer, currency, step, None, errors);
 }
 
 fn validate_offer_pricing(
   offer: &ProductCreateOfferPricingSharedFields,
+  currency: Option<&'static Currency>,
   step: &ProductCreateStepsNames,
   form_id: Option<&str>,
   errors: &mut HashMap<String, AppErrorError>,
@@ -751,17 +988,7 @@ fn validate_offer_pricing(
     field_error(errors, step, form_id, "sku", err);
   }
 
-  let price = offer.price.parse::<f64>();
-  match price {
-    Ok(price) => {
-      if price <= 0.0 {
-        field_error(errors, step, form_id, "price", ERR_GT_0.clone());
-      }
-    }
-    Err(_) => {
-      field_error(errors, step, form_id, "price", ERR_INVALID_NUM.clone());
-    }
-  }
+  let price = validate_price(&offer.price, currency, step, form_id, "price", errors);
 
   if !ProductOfferingCondition::as_slice().contains(&offer.offering_condition.as_str()) {
     field_error(errors, step, form_id, "offering_condition", ERR_INVALID_INP.clone());
@@ -786,16 +1013,10 @@ fn validate_offer_pricing(
 
   let ls_price = offer.list_price.clone().unwrap_or_default();
   if !ls_price.is_empty() {
-    match ls_price.parse::<f64>() {
-      Ok(ls_price) => {
-        if ls_price <= price.clone().unwrap_or_default() {
-          let err = AppErrorError { id: "products.list_price.error".into(), params: None };
-          field_error(errors, step, form_id, "list_price", err);
-        }
-      }
-      Err(_) => {
-        field_error(errors, step, form_id, "list_price", ERR_INVALID_NUM.clone());
-      }
+    let ls_price = validate_price(&ls_price, currency, step, form_id, "list_price", errors);
+    if ls_price.is_some_and(|ls_price| ls_price <= price.unwrap_or_default()) {
+      let err = AppErrorError { id: "products.list_price.error".into(), params: None };
+      field_error(errors, step, form_id, "list_price", err);
     }
   }
 
@@ -804,16 +1025,10 @@ fn validate_offer_pricing(
     if sale_price.is_empty() {
       field_error(errors, step, form_id, "sale_price", ERR_REQUIRED.clone());
     } else {
-      match sale_price.parse::<f64>() {
-        Ok(sp) => {
-          if sp <= price.unwrap_or_default() {
-            let err = AppErrorError { id: "products.sale_price.lesser".into(), params: None };
-            field_error(errors, step, form_id, "sale_price", err);
-          }
-        }
-        Err(_) => {
-          field_error(errors, step, form_id, "sale_price", ERR_INVALID_NUM.clone());
-        }
+      let sp = validate_price(&sale_price, currency, step, form_id, "sale_price", errors);
+      if sp.is_some_and(|sp| sp <= price.unwrap_or_default()) {
+        let err = AppErrorError { id: "products.sale_price.lesser".into(), params: None };
+        field_error(errors, step, form_id, "sale_price", err);
       }
     }
 
@@ -878,23 +1093,46 @@ fn validate_offer_pricing(
         field_error(errors, step, form_id, &format!("minimum_orders.{}.quantity", mo.id), err);
       }
 
-      match mo.price.parse::<f64>() {
-        Ok(price) => {
-          if price <= 0.0 {
-            let f_name = &format!("minimum_orders.{}.price", mo.id);
-            field_error(errors, step, form_id, f_name, ERR_GT_0.clone());
-          }
-        }
-        Err(_) => {
-          let f_name = &format!("minimum_orders.{}.price", mo.id);
-          field_error(errors, step, form_id, f_name, ERR_INVALID_NUM.clone());
-        }
-      }
+      let f_name = &format!("minimum_orders.{}.price", mo.id);
+      validate_price(&mo.price, currency, step, form_id, f_name, errors);
     }
   }
 }
 
-fn validate_safety_form(
+/// Checks that a price is a positive amount of whole minor units of the offer's currency,
+/// e.g. no cents for JPY. The prices of an unknown currency, which is reported on its own,
+/// are only checked to be positive numbers.
+fn validate_price(
+  value: &str,
+  currency: Option<&'static Currency>,
+  step: &ProductCreateStepsNames,
+  form_id: Option<&str>,
+  field: &str,
+  errors: &mut HashMap<String, AppErrorError>,
+) -> Option<Decimal> {
+  let parsed = match currency {
+    Some(currency) => {
+      Money::parse_in(value, currency).ok().map(|money| (money.amount(), Some(money)))
+    }
+    None => Decimal::from_str(value.trim()).ok().map(|amount| (amount, None)),
+  };
+
+  let err = match parsed {
+    None => ERR_INVALID_NUM.clone(),
+    Some((amount, _)) if amount <= Decimal::ZERO => ERR_GT_0.clone(),
+    Some((_, Some(money))) if !money.is_exact() => {
+      let exponent = money.currency().exponent;
+      let params = Some(HashMap::from([("Max".into(), Value::Number(exponent.into()))]));
+      AppErrorError { id: "products.price.precision.error".into(), params }
+    }
+    Some((_, Some(money))) if money.checked_minor_units().is_err() => ERR_INVALID_NUM.clone(),
+    Some((amount, _)) => return Some(amount),
+  };
+  field_error(errors, step, form_id, field, err);
+  None
+}
+
+pub(super) fn validate_safety_form(
   form: ProductCreateRequestSafety,
   errors: &mut HashMap<String, AppErrorError>,
   sub: &Subcategory,
@@ -1134,7 +1372,7 @@ fn invalid_field_data(
   errors.insert(key, AppErrorError { id: "form.field.invalid_data".to_string(), params });
 }
 
-fn field_error(
+pub(super) fn field_error(
   errors: &mut HashMap<String, AppErrorError>,
   form_name: &ProductCreateStepsNames,
   form_id: Option<&str>,
@@ -1171,7 +1409,10 @@ fn missing_form(form_name: &ProductCreateStepsNames) -> String {
   format!("{}.form.missing", form_name.as_str())
 }
 
-fn error_builder(ctx: Arc<Context>, errors: HashMap<String, AppErrorError>) -> AppError {
+pub(super) fn error_builder(
+  ctx: Arc<Context>,
+  errors: HashMap<String, AppErrorError>,
+) -> AppError {
   // for err in errors.iter() {
   //   println!("field {}, has error {:#?}", err.0, err.1);
   // }
@@ -1284,19 +1525,22 @@ pub fn products_create_pre_save(
   let offer = products_create_pre_save_offer(&pro.offer, &variant_ids, &variant_id, created_at);
   let safety = products_create_pre_save_safety(&pro.safety);
 
+  // a product id reserved by the presigned media uploads is kept
+  let id = if pro.product_id.is_empty() { Ulid::new().to_string() } else { pro.product_id.clone() };
+
   let off = pro.offer.as_ref().unwrap();
   let product = Product {
-    id: ulid::Ulid::new().to_string(),
+    id,
     user_id: ctx.session().user_id().to_string(),
     title: identity.title.clone(),
     category: identity.category,
     subcategory: identity.subcategory,
     has_variations: identity.has_variations,
     brand_name: if identity.no_brand { None } else { Some(identity.brand_name) },
-    has_brand_name: identity.no_brand,
+    has_brand_name: !identity.no_brand,
     product_id: if identity.no_product_id { None } else { Some(identity.product_id) },
     product_id_type: if identity.no_product_id { None } else { Some(identity.product_id_type) },
-    has_product_id: identity.no_product_id,
+    has_product_id: !identity.no_product_id,
     description: description.description,
     bullet_points,
     currency_code: off.currency.clone(),
@@ -1399,15 +1643,22 @@ fn get_variant_id_and_title(
   (var_id, var_name)
 }
 
-fn products_create_pre_save_offer(
+pub(super) fn products_create_pre_save_offer(
   offer: &Option<ProductCreateRequestOffer>,
   variant_ids: &HashMap<String, String>,
   variant_id: &str,
   created_at: u64,
 ) -> ProductOffer {
-  let mut result = ProductOffer { offer: HashMap::new() };
+  let mut result = ProductOffer { offer: HashMap::new(), default_variant_id: None };
   let path = "products.models.products_create_pre_save_offer".to_string();
 
+  // the prices are stored with exactly the decimals of the currency, e.g. "19.90"
+  let currency = offer.as_ref().and_then(|offer| currency_get(&offer.currency));
+  let normalize = |price: &str| match currency.and_then(|c| Money::parse_in(price, c).ok()) {
+    Some(money) => money.to_string(),
+    None => price.to_string(),
+  };
+
   let get_min_orders =
     |mo: &Vec<ProductCreateRequestOfferMinimumOrder>| -> Vec<ProductOfferMinimumOrder> {
       mo.iter()
@@ -1415,7 +1666,7 @@ fn products_create_pre_save_offer(
           id: Ulid::new().to_string(),
           created_at,
           updated_at: None,
-          price: mo.price.clone(),
+          price: normalize(&mo.price),
           quantity: mo.quantity,
         })
         .collect()
@@ -1438,12 +1689,16 @@ fn products_create_pre_save_offer(
                   ProductOfferVariant {
                     sku: var.sku.clone(),
                     quantity: var.quantity,
-                    price: var.price.clone(),
+                    price: normalize(&var.price),
                     offering_condition: var.offering_condition.clone(),
                     condition_note: var.condition_note.clone(),
-                    list_price: var.list_price.clone(),
+                    list_price: var.list_price.as_deref().map(normalize),
                     has_sale_price: has_sale,
-                    sale_price: if has_sale { var.sale_price.clone() } else { None },
+                    sale_price: if has_sale {
+                      var.sale_price.as_deref().map(normalize)
+                    } else {
+                      None
+                    },
                     sale_price_start: if has_sale {
                       Some(date_to_milliseconds(&var.sale_price_start.clone().unwrap()).expect(
                         &format!("{}: failed to convert sale_price_start to miliseconds", path),
@@ -1474,12 +1729,16 @@ fn products_create_pre_save_offer(
                 ProductOfferVariant {
                   sku: var.sku.clone(),
                   quantity: var.quantity,
-                  price: var.price.clone(),
+                  price: normalize(&var.price),
                   offering_condition: var.offering_condition.clone(),
                   condition_note: var.condition_note.clone(),
-                  list_price: var.list_price.clone(),
+                  list_price: var.list_price.as_deref().map(normalize),
                   has_sale_price: has_sale,
-                  sale_price: if has_sale { var.sale_price.clone() } else { None },
+                  sale_price: if has_sale {
+                    var.sale_price.as_deref().map(normalize)
+                  } else {
+                    None
+                  },
                   sale_price_start: if has_sale {
                     Some(date_to_milliseconds(&var.sale_price_start.clone().unwrap()).expect(
                       &format!("{}: failed to convert sale_price_start to miliseconds", path),
@@ -1513,7 +1772,9 @@ fn products_create_pre_save_offer(
   return result;
 }
 
-fn products_create_pre_save_safety(safety: &Option<ProductCreateRequestSafety>) -> ProductSafety {
+pub(super) fn products_create_pre_save_safety(
+  safety: &Option<ProductCreateRequestSafety>,
+) -> ProductSafety {
   let mut result = ProductSafety { safety: HashMap::new() };
   let path = "products.models.products_create_pre_save_safety".to_string();
   match &safety {
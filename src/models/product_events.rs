@@ -0,0 +1,196 @@
+use std::{collections::HashMap, sync::Arc};
+
+use megacommerce_proto::{ProductEventInput, ProductEventsRecordRequest};
+use megacommerce_shared::models::{
+  context::Context,
+  errors::{AppError, AppErrorError, AppErrorErrors},
+};
+use serde_json::Value;
+use tonic::Code;
+use ulid::Ulid;
+
+pub const PRODUCT_EVENT_LIST_NAME_MAX_LENGTH: usize = 64;
+pub const PRODUCT_EVENT_VARIANT_ID_MAX_LENGTH: usize = 64;
+
+/// What a shopper did with a product, the recommendations and rankings learn from these
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProductEventType {
+  View,
+  /// A click on the product in a list, which carries its position there
+  Click,
+  AddToCart,
+  Wishlist,
+  Purchase,
+}
+
+impl ProductEventType {
+  pub const ALL: [ProductEventType; 5] =
+    [Self::View, Self::Click, Self::AddToCart, Self::Wishlist, Self::Purchase];
+
+  pub const fn as_str(&self) -> &'static str {
+    match self {
+      Self::View => "view",
+      Self::Click => "click",
+      Self::AddToCart => "add_to_cart",
+      Self::Wishlist => "wishlist",
+      Self::Purchase => "purchase",
+    }
+  }
+
+  pub fn from_str(value: &str) -> Option<Self> {
+    Self::ALL.into_iter().find(|t| t.as_str() == value)
+  }
+
+  /// Whether the clients may record the event. A purchase is only recorded by the service,
+  /// when the order's reservation is committed, so it can't be made up to sway the rankings.
+  pub const fn client_recordable(&self) -> bool {
+    !matches!(self, Self::Purchase)
+  }
+
+  /// How much the event tells about the user's interest in the product
+  pub const fn weight(&self) -> f64 {
+    match self {
+      Self::Click => 0.5,
+      Self::View => 1.0,
+      Self::AddToCart | Self::Wishlist => 2.0,
+      Self::Purchase => 3.0,
+    }
+  }
+}
+
+/// An event as it's stored in `product_events`, anonymous sessions have an empty user id
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProductEvent {
+  pub id: String,
+  pub user_id: String,
+  pub product_id: String,
+  pub variant_id: Option<String>,
+  pub event_type: ProductEventType,
+  /// The list a click came from, and the product's position in it (from 0)
+  pub list_name: Option<String>,
+  pub position: Option<u32>,
+  pub created_at: u64,
+}
+
+impl ProductEvent {
+  /// The event of a valid input, see `product_events_record_is_valid`
+  pub fn new(input: ProductEventInput, user_id: &str, now: u64) -> Option<Self> {
+    Some(Self {
+      id: Ulid::new().to_string(),
+      user_id: user_id.to_string(),
+      event_type: ProductEventType::from_str(&input.event_type)?,
+      product_id: input.product_id,
+      variant_id: input.variant_id.filter(|id| !id.is_empty()),
+      list_name: input.list_name.filter(|name| !name.is_empty()),
+      position: input.position,
+      created_at: now,
+    })
+  }
+}
+
+/// Whether an event of the type is kept, `draw` is uniform in [0, 1). A type without
+/// a sample rate is always kept.
+pub fn product_event_sampled(
+  rates: &HashMap<String, f64>,
+  event_type: ProductEventType,
+  draw: f64,
+) -> bool {
+  rates.get(event_type.as_str()).is_none_or(|rate| draw < *rate)
+}
+
+pub fn product_events_record_is_valid(
+  ctx: Arc<Context>,
+  req: &ProductEventsRecordRequest,
+  max_events: usize,
+) -> Result<(), AppError> {
+  let path = "products.models.product_events_record_is_valid";
+  let mut errors: HashMap<String, AppErrorError> = HashMap::new();
+  let max_params = |max: usize| -> Option<HashMap<String, Value>> {
+    Some(HashMap::from([("Max".into(), Value::Number(max.into()))]))
+  };
+
+  if req.events.is_empty() || req.events.len() > max_events {
+    let params = max_params(max_events);
+    let err = AppErrorError { id: "products.events.count.invalid".into(), params };
+    errors.insert("events".into(), err);
+  }
+
+  for (i, event) in req.events.iter().enumerate() {
+    let mut invalid = |field: &str, id: &str, params: Option<HashMap<String, Value>>| {
+      errors.insert(format!("events.{}.{}", i, field), AppErrorError { id: id.into(), params });
+    };
+
+    if Ulid::from_string(&event.product_id).is_err() {
+      invalid("product_id", "products.events.product_id.invalid", None);
+    }
+
+    let event_type = ProductEventType::from_str(&event.event_type);
+    match event_type {
+      None => invalid("event_type", "products.events.event_type.invalid", None),
+      Some(t) if !t.client_recordable() => {
+        invalid("event_type", "products.events.event_type.not_allowed", None)
+      }
+      Some(_) => {}
+    }
+    // only a click from a list has a position, and it must have one
+    if (event_type == Some(ProductEventType::Click)) != event.position.is_some() {
+      invalid("position", "products.events.position.invalid", None);
+    }
+
+    let max = PRODUCT_EVENT_VARIANT_ID_MAX_LENGTH;
+    if event.variant_id.as_ref().is_some_and(|id| id.len() > max) {
+      invalid("variant_id", "products.events.variant_id.length", max_params(max));
+    }
+    let max = PRODUCT_EVENT_LIST_NAME_MAX_LENGTH;
+    if event.list_name.as_ref().is_some_and(|name| name.chars().count() > max) {
+      invalid("list_name", "products.events.list_name.length", max_params(max));
+    }
+  }
+
+  if errors.len() > 0 {
+    let errors = Some(AppErrorErrors { errors_internal: Some(errors), ..Default::default() });
+    let id = "products.events.invalid";
+    return Err(AppError::new(ctx, path, id, None, "", Code::InvalidArgument.into(), errors));
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_product_event_type() {
+    assert_eq!(ProductEventType::from_str("add_to_cart"), Some(ProductEventType::AddToCart));
+    assert_eq!(ProductEventType::from_str("like"), None);
+    assert!(ProductEventType::Purchase.weight() > ProductEventType::View.weight());
+  }
+
+  #[test]
+  fn test_product_events_record_is_valid() {
+    let ctx = Arc::new(Context::default());
+    let event = |event_type: &str| ProductEventInput {
+      product_id: "01J9Z3Y1ZK6Q4Y8Y5W3V2T1S0R".into(),
+      event_type: event_type.into(),
+      ..Default::default()
+    };
+    let check = |events: Vec<ProductEventInput>| {
+      product_events_record_is_valid(ctx.clone(), &ProductEventsRecordRequest { events }, 10)
+    };
+
+    assert!(check(vec![event("view"), event("add_to_cart")]).is_ok());
+    // purchases are recorded when the order commits its reservation
+    assert!(check(vec![event("purchase")]).is_err());
+    assert!(check(vec![event("view"), event("purchase")]).is_err());
+    assert!(check(vec![event("like")]).is_err());
+  }
+
+  #[test]
+  fn test_product_event_sampled() {
+    let rates = HashMap::from([("view".to_string(), 0.25)]);
+    assert!(product_event_sampled(&rates, ProductEventType::View, 0.1));
+    assert!(!product_event_sampled(&rates, ProductEventType::View, 0.25));
+    assert!(product_event_sampled(&rates, ProductEventType::Purchase, 0.99));
+  }
+}
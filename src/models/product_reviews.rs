@@ -0,0 +1,136 @@
+use std::{collections::HashMap, sync::Arc};
+
+use megacommerce_proto::ProductReviewCreateRequest;
+use megacommerce_shared::models::{
+  context::Context,
+  errors::{AppError, AppErrorError, AppErrorErrors},
+};
+use serde_json::Value;
+use tonic::Code;
+
+pub const PRODUCT_REVIEW_RATING_MIN: u32 = 1;
+pub const PRODUCT_REVIEW_RATING_MAX: u32 = 5;
+pub const PRODUCT_REVIEW_TITLE_MAX_LENGTH: usize = 120;
+pub const PRODUCT_REVIEW_BODY_MIN_LENGTH: usize = 10;
+pub const PRODUCT_REVIEW_BODY_MAX_LENGTH: usize = 5000;
+
+/// A review is only shown, and counted in the product's rating, once it's approved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProductReviewStatus {
+  Pending,
+  Approved,
+  Rejected,
+}
+
+impl ProductReviewStatus {
+  pub const ALL: [ProductReviewStatus; 3] = [Self::Pending, Self::Approved, Self::Rejected];
+
+  pub const fn as_str(&self) -> &'static str {
+    match self {
+      Self::Pending => "pending",
+      Self::Approved => "approved",
+      Self::Rejected => "rejected",
+    }
+  }
+
+  pub fn from_str(value: &str) -> Option<Self> {
+    Self::ALL.into_iter().find(|s| s.as_str() == value)
+  }
+
+  /// A moderated review can be reconsidered, but never moved back to pending
+  pub fn can_transition_to(&self, next: ProductReviewStatus) -> bool {
+    match self {
+      Self::Pending => matches!(next, Self::Approved | Self::Rejected),
+      Self::Approved => next == Self::Rejected,
+      Self::Rejected => next == Self::Approved,
+    }
+  }
+
+  /// The change of the product's (rating sum, rating count) when a review with
+  /// `rating` moves from this status to `next`
+  pub fn rating_delta(&self, next: ProductReviewStatus, rating: i64) -> (i64, i64) {
+    match (*self == Self::Approved, next == Self::Approved) {
+      (false, true) => (rating, 1),
+      (true, false) => (-rating, -1),
+      _ => (0, 0),
+    }
+  }
+}
+
+/// The average rating rounded to one decimal, none until the product has a rating
+pub fn product_rating_average(sum: i64, count: i64) -> Option<f64> {
+  if count <= 0 {
+    return None;
+  }
+  Some((sum as f64 / count as f64 * 10.0).round() / 10.0)
+}
+
+/// The average rating in tenths (4.5 is 45), as the best sellers carry it, 0 without a rating
+pub fn product_rating_tenths(sum: i64, count: i64) -> u32 {
+  product_rating_average(sum, count).map(|avg| (avg * 10.0).round() as u32).unwrap_or(0)
+}
+
+pub fn product_review_create_is_valid(
+  ctx: Arc<Context>,
+  req: &ProductReviewCreateRequest,
+) -> Result<(), AppError> {
+  let path = "products.models.product_review_create_is_valid";
+  let mut errors: HashMap<String, AppErrorError> = HashMap::new();
+
+  if req.rating < PRODUCT_REVIEW_RATING_MIN || req.rating > PRODUCT_REVIEW_RATING_MAX {
+    let params = Some(HashMap::from([
+      ("Min".into(), Value::Number(PRODUCT_REVIEW_RATING_MIN.into())),
+      ("Max".into(), Value::Number(PRODUCT_REVIEW_RATING_MAX.into())),
+    ]));
+    let err = AppErrorError { id: "products.reviews.rating.invalid".into(), params };
+    errors.insert("rating".into(), err);
+  }
+
+  let title_len = req.title.trim().chars().count();
+  if title_len > PRODUCT_REVIEW_TITLE_MAX_LENGTH {
+    let params =
+      Some(HashMap::from([("Max".into(), Value::Number(PRODUCT_REVIEW_TITLE_MAX_LENGTH.into()))]));
+    let err = AppErrorError { id: "products.reviews.title.length".into(), params };
+    errors.insert("title".into(), err);
+  }
+
+  let body_len = req.body.trim().chars().count();
+  if body_len < PRODUCT_REVIEW_BODY_MIN_LENGTH || body_len > PRODUCT_REVIEW_BODY_MAX_LENGTH {
+    let params = Some(HashMap::from([
+      ("Min".into(), Value::Number(PRODUCT_REVIEW_BODY_MIN_LENGTH.into())),
+      ("Max".into(), Value::Number(PRODUCT_REVIEW_BODY_MAX_LENGTH.into())),
+    ]));
+    let err = AppErrorError { id: "products.reviews.body.length".into(), params };
+    errors.insert("body".into(), err);
+  }
+
+  if errors.len() > 0 {
+    let errors = Some(AppErrorErrors { errors_internal: Some(errors), ..Default::default() });
+    let id = "products.reviews.invalid";
+    return Err(AppError::new(ctx, path, id, None, "", Code::InvalidArgument.into(), errors));
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ProductReviewStatus::*;
+
+  #[test]
+  fn test_review_status_rating_delta() {
+    assert_eq!(Pending.rating_delta(Approved, 4), (4, 1));
+    assert_eq!(Approved.rating_delta(Rejected, 4), (-4, -1));
+    assert_eq!(Rejected.rating_delta(Approved, 2), (2, 1));
+    assert_eq!(Pending.rating_delta(Rejected, 5), (0, 0));
+    assert!(!Approved.can_transition_to(Pending));
+  }
+
+  #[test]
+  fn test_product_rating_average() {
+    assert_eq!(product_rating_average(0, 0), None);
+    assert_eq!(product_rating_average(14, 3), Some(4.7));
+    assert_eq!(product_rating_average(5, 1), Some(5.0));
+  }
+}
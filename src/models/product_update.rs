@@ -0,0 +1,412 @@
+use std::{collections::HashMap, sync::Arc};
+
+use megacommerce_proto::{
+  product_create_request_offer::{
+    Pricing,
+    Pricing::{WithVariants as OfferWithVariants, WithoutVariants as OfferNoVariants},
+  },
+  Product, ProductBulletPoint, ProductDataResponseSubcategory, ProductUpdateRequest,
+};
+use megacommerce_shared::{
+  models::{
+    context::Context,
+    errors::{AppError, AppErrorError},
+  },
+  utils::time::time_get_millis,
+};
+use serde_json::{json, Value};
+use ulid::Ulid;
+
+use crate::{
+  models::{
+    product_create::{
+      description_form_validation, error_builder, field_error, identity_form_validation,
+      offer_form_validation, products_create_pre_save_offer, products_create_pre_save_safety,
+      validate_safety_form, ERR_INVALID_INP, ERR_MISSIN_FID,
+    },
+    products::{ProductCreateStepsNames, ProductStatus},
+  },
+  utils::slug::Slug,
+};
+
+/// The key used to map the single variant of a product without variations
+/// when reusing the create pre-save offer builder.
+const MAIN_VARIANT_KEY: &str = "main";
+
+pub fn products_update_is_valid(
+  ctx: Arc<Context>,
+  req: &ProductUpdateRequest,
+  stored: &Product,
+  subcategory_data: Option<ProductDataResponseSubcategory>,
+) -> Result<(), AppError> {
+  let mut errors: HashMap<String, AppErrorError> = HashMap::new();
+
+  if req.identity.is_none()
+    && req.description.is_none()
+    && req.offer.is_none()
+    && req.safety.is_none()
+    && req.default_variant_id.is_none()
+  {
+    errors.insert(
+      "product.update".into(),
+      AppErrorError { id: "products.update.empty".into(), params: None },
+    );
+    return Err(error_builder(ctx, errors));
+  }
+
+  if let Some(identity) = req.identity.clone() {
+    let step = &ProductCreateStepsNames::Identity;
+    if identity.category != stored.category {
+      let err = AppErrorError { id: "products.category.immutable".into(), params: None };
+      field_error(&mut errors, step, None, "category", err);
+    }
+    if identity.subcategory != stored.subcategory {
+      let err = AppErrorError { id: "products.subcategory.immutable".into(), params: None };
+      field_error(&mut errors, step, None, "subcategory", err);
+    }
+    if identity.has_variations != stored.has_variations {
+      let err = AppErrorError { id: "products.has_variations.immutable".into(), params: None };
+      field_error(&mut errors, step, None, "has_variations", err);
+    }
+    identity_form_validation(identity, &mut errors, &subcategory_data);
+    if errors.len() > 0 {
+      return Err(error_builder(ctx, errors));
+    }
+  }
+
+  if let Some(description) = req.description.clone() {
+    description_form_validation(description, &mut errors);
+    if errors.len() > 0 {
+      return Err(error_builder(ctx, errors));
+    }
+  }
+
+  if let Some(offer) = req.offer.clone() {
+    offer_variants_match(&offer.pricing, stored, &mut errors);
+    if errors.len() > 0 {
+      return Err(error_builder(ctx, errors));
+    }
+    offer_form_validation(offer, &mut errors);
+    if errors.len() > 0 {
+      return Err(error_builder(ctx, errors));
+    }
+  }
+
+  // an empty id unsets the default, the lists fall back to their variant selection
+  if let Some(default_variant_id) = req.default_variant_id.as_deref() {
+    let stored_ids = stored.offer.as_ref().map(|o| &o.offer);
+    if !default_variant_id.is_empty()
+      && !stored_ids.is_some_and(|ids| ids.contains_key(default_variant_id))
+    {
+      let err = AppErrorError { id: "products.default_variant_id.invalid".into(), params: None };
+      field_error(&mut errors, &ProductCreateStepsNames::Offer, None, "default_variant_id", err);
+      return Err(error_builder(ctx, errors));
+    }
+  }
+
+  if let Some(safety) = req.safety.clone() {
+    let sub = subcategory_data.and_then(|s| s.data);
+    if sub.is_none() {
+      errors.insert(
+        "identity.subcategory".into(),
+        AppErrorError { id: "products.type.required".into(), params: None },
+      );
+      return Err(error_builder(ctx, errors));
+    }
+    validate_safety_form(safety, &mut errors, &sub.unwrap());
+    if errors.len() > 0 {
+      return Err(error_builder(ctx, errors));
+    }
+  }
+
+  Ok(())
+}
+
+/// The variants structure is decided at creation time (details and media are keyed by the
+/// stored variant ids), so an offer update must address exactly the stored variants.
+fn offer_variants_match(
+  pricing: &Option<Pricing>,
+  stored: &Product,
+  errors: &mut HashMap<String, AppErrorError>,
+) {
+  let step = &ProductCreateStepsNames::Offer;
+  let stored_ids: Vec<&String> =
+    stored.offer.as_ref().map(|o| o.offer.keys().collect()).unwrap_or_default();
+
+  match pricing {
+    Some(OfferWithVariants(form)) => {
+      if !stored.has_variations {
+        field_error(errors, step, None, "pricing", ERR_INVALID_INP.clone());
+        return;
+      }
+      for var in form.variants.iter() {
+        if !stored_ids.contains(&&var.id) {
+          field_error(errors, step, Some(&var.id), "id", ERR_MISSIN_FID.clone());
+        }
+      }
+      if form.variants.len() != stored_ids.len() {
+        let err = AppErrorError { id: "products.variations.count.immutable".into(), params: None };
+        field_error(errors, step, None, "variants", err);
+      }
+    }
+    Some(OfferNoVariants(_)) => {
+      if stored.has_variations || stored_ids.len() != 1 {
+        field_error(errors, step, None, "pricing", ERR_INVALID_INP.clone());
+      }
+    }
+    // offer_form_validation reports the missing pricing form
+    None => {}
+  }
+}
+
+/// Applies the validated steps of the update request on top of the stored product.
+/// The version is left untouched here, the store bumps it atomically. An approved or
+/// published product goes back to pending, the moderation has to review the edit.
+/// The variants keep their stored quantities, the stock is changed by `InventoryRestock`.
+pub fn products_update_pre_save(
+  _ctx: Arc<Context>,
+  req: &ProductUpdateRequest,
+  stored: &Product,
+) -> Result<Product, AppError> {
+  let updated_at = time_get_millis();
+  let mut pro = stored.clone();
+
+  if let Some(identity) = req.identity.clone() {
+    pro.slug = Slug::default().generate_slug(&identity.title);
+    pro.title = identity.title;
+    pro.brand_name = if identity.no_brand { None } else { Some(identity.brand_name) };
+    pro.has_brand_name = !identity.no_brand;
+    pro.product_id = if identity.no_product_id { None } else { Some(identity.product_id) };
+    pro.product_id_type =
+      if identity.no_product_id { None } else { Some(identity.product_id_type) };
+    pro.has_product_id = !identity.no_product_id;
+  }
+
+  if let Some(description) = req.description.clone() {
+    pro.description = description.description;
+    pro.bullet_points = description
+      .bullet_points
+      .iter()
+      .map(|bp| ProductBulletPoint {
+        id: Ulid::new().to_string(),
+        created_at: updated_at,
+        text: bp.bullet_point.clone(),
+        updated_at: None,
+      })
+      .collect();
+  }
+
+  if let Some(offer) = req.offer.as_ref() {
+    let mut variant_ids: HashMap<String, String> = HashMap::new();
+    for id in stored.offer.as_ref().map(|o| o.offer.keys()).into_iter().flatten() {
+      variant_ids.insert(id.clone(), id.clone());
+      if !stored.has_variations {
+        variant_ids.insert(MAIN_VARIANT_KEY.to_string(), id.clone());
+      }
+    }
+
+    pro.offer = Some(products_create_pre_save_offer(
+      &req.offer,
+      &variant_ids,
+      MAIN_VARIANT_KEY,
+      updated_at,
+    ));
+    // the stock only changes through the inventory operations (restock, commit), an edit
+    // of the offer keeps the stored quantities, whatever the form sent
+    if let (Some(updated), Some(stored)) = (pro.offer.as_mut(), stored.offer.as_ref()) {
+      for (id, variant) in updated.offer.iter_mut() {
+        variant.quantity = stored.offer.get(id).map(|v| v.quantity).unwrap_or_default();
+      }
+    }
+    pro.currency_code = offer.currency.clone();
+    pro.fulfillment_type = offer.fulfillment_type.clone();
+    pro.processing_time = offer.processing_time;
+  }
+
+  // the rebuilt offer starts without a default, keep the stored one unless it's changed
+  let stored_default = stored.offer.as_ref().and_then(|o| o.default_variant_id.clone());
+  if let Some(offer) = pro.offer.as_mut() {
+    offer.default_variant_id = match req.default_variant_id.as_deref() {
+      Some("") => None,
+      Some(id) => Some(id.to_string()),
+      None => stored_default,
+    };
+  }
+
+  if req.safety.is_some() {
+    pro.safety = Some(products_create_pre_save_safety(&req.safety));
+  }
+
+  if ProductStatus::from_str(&stored.status).is_some_and(|s| s.requires_review_after_edit()) {
+    pro.status = ProductStatus::Pending.as_string();
+  }
+
+  pro.updated_at = Some(updated_at);
+  Ok(pro)
+}
+
+pub fn products_update_auditable_v1(req: &ProductUpdateRequest) -> Value {
+  json!({
+    "product_id": req.product_id,
+    "version": req.version,
+    "identity": req.identity,
+    "description": req.description,
+    "offer": req.offer,
+    "safety": req.safety,
+    "default_variant_id": req.default_variant_id,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use megacommerce_proto::{
+    ProductCreateRequestDescription, ProductCreateRequestIdentity, ProductCreateRequestOffer,
+    ProductCreateRequestOfferWithVariants, ProductCreateRequestOfferWithoutVariants, ProductOffer,
+    ProductOfferVariant,
+  };
+
+  use super::*;
+
+  fn stored(status: ProductStatus, variant_ids: &[&str]) -> Product {
+    let variant =
+      |price: &str| ProductOfferVariant { price: price.into(), quantity: 7, ..Default::default() };
+    Product {
+      id: "01J9Z3Y1ZK6Q4Y8Y5W3V2T1S0R".into(),
+      status: status.as_string(),
+      version: 3,
+      currency_code: "USD".into(),
+      has_variations: variant_ids.len() > 1,
+      offer: Some(ProductOffer {
+        offer: variant_ids.iter().map(|id| (id.to_string(), variant("10.00"))).collect(),
+        default_variant_id: Some(variant_ids[0].into()),
+      }),
+      ..Default::default()
+    }
+  }
+
+  fn offer(pricing: Pricing) -> ProductCreateRequestOffer {
+    ProductCreateRequestOffer {
+      currency: "USD".into(),
+      processing_time: 2,
+      pricing: Some(pricing),
+      ..Default::default()
+    }
+  }
+
+  fn with_variants(ids: &[&str]) -> Pricing {
+    let mut form = ProductCreateRequestOfferWithVariants::default();
+    for id in ids {
+      form.variants.push(Default::default());
+      form.variants.last_mut().unwrap().id = id.to_string();
+    }
+    OfferWithVariants(form)
+  }
+
+  #[test]
+  fn test_offer_variants_match() {
+    let check = |pricing: Pricing, stored: &Product| {
+      let mut errors = HashMap::new();
+      offer_variants_match(&Some(pricing), stored, &mut errors);
+      let mut keys: Vec<String> = errors.into_keys().collect();
+      keys.sort();
+      keys
+    };
+    let variants = stored(ProductStatus::Draft, &["a", "b"]);
+    let single = stored(ProductStatus::Draft, &["a"]);
+
+    assert!(check(with_variants(&["b", "a"]), &variants).is_empty());
+    assert_eq!(check(with_variants(&["a", "c"]), &variants), vec!["offer.c.id"]);
+    assert_eq!(check(with_variants(&["a"]), &variants), vec!["offer.variants"]);
+    assert_eq!(check(with_variants(&["a"]), &single), vec!["offer.pricing"]);
+
+    let no_variants = OfferNoVariants(ProductCreateRequestOfferWithoutVariants::default());
+    assert!(check(no_variants.clone(), &single).is_empty());
+    assert_eq!(check(no_variants, &variants), vec!["offer.pricing"]);
+  }
+
+  #[test]
+  fn test_products_update_pre_save() {
+    let ctx = Arc::new(Context::default());
+    let stored = stored(ProductStatus::Draft, &["a"]);
+
+    // only the sent steps change, the offer and its default are kept
+    let req = ProductUpdateRequest {
+      description: Some(ProductCreateRequestDescription {
+        description: "a new description".into(),
+        ..Default::default()
+      }),
+      ..Default::default()
+    };
+    let pro = products_update_pre_save(ctx.clone(), &req, &stored).unwrap();
+    assert_eq!(pro.description, "a new description");
+    assert_eq!(pro.offer, stored.offer);
+    assert_eq!((pro.version, pro.status.as_str()), (3, "draft"));
+    assert!(pro.updated_at.is_some());
+
+    // the single variant keeps its stored id, and the default is unset on request
+    let pricing = OfferNoVariants(ProductCreateRequestOfferWithoutVariants {
+      price: "12.5".into(),
+      quantity: 1000,
+      ..Default::default()
+    });
+    let req = ProductUpdateRequest {
+      offer: Some(offer(pricing)),
+      default_variant_id: Some("".into()),
+      ..Default::default()
+    };
+    let pro = products_update_pre_save(ctx.clone(), &req, &stored).unwrap();
+    let offer = pro.offer.unwrap();
+    assert_eq!(offer.offer.keys().collect::<Vec<_>>(), vec!["a"]);
+    assert_eq!(offer.offer["a"].price, "12.50");
+    // the sent quantity is ignored, the stock is restocked instead
+    assert_eq!(offer.offer["a"].quantity, 7);
+    assert_eq!((offer.default_variant_id, pro.processing_time), (None, 2));
+  }
+
+  #[test]
+  fn test_products_update_pre_save_identity() {
+    let ctx = Arc::new(Context::default());
+    let stored = stored(ProductStatus::Draft, &["a"]);
+    let identity = |no_brand: bool, no_product_id: bool| {
+      let req = ProductUpdateRequest {
+        identity: Some(ProductCreateRequestIdentity {
+          title: "a new title".into(),
+          brand_name: "brand".into(),
+          no_brand,
+          product_id: "012345678905".into(),
+          product_id_type: "upc".into(),
+          no_product_id,
+          ..Default::default()
+        }),
+        ..Default::default()
+      };
+      products_update_pre_save(ctx.clone(), &req, &stored).unwrap()
+    };
+
+    let pro = identity(false, false);
+    assert_eq!((pro.has_brand_name, pro.brand_name.as_deref()), (true, Some("brand")));
+    assert_eq!((pro.has_product_id, pro.product_id.as_deref()), (true, Some("012345678905")));
+
+    let pro = identity(true, true);
+    assert_eq!((pro.has_brand_name, pro.brand_name), (false, None));
+    assert_eq!((pro.has_product_id, pro.product_id, pro.product_id_type), (false, None, None));
+  }
+
+  #[test]
+  fn test_products_update_pre_save_review() {
+    let ctx = Arc::new(Context::default());
+    let req = ProductUpdateRequest {
+      description: Some(ProductCreateRequestDescription::default()),
+      ..Default::default()
+    };
+    let status = |status: ProductStatus| {
+      let pro = products_update_pre_save(ctx.clone(), &req, &stored(status, &["a"])).unwrap();
+      pro.status
+    };
+
+    assert_eq!(status(ProductStatus::Published), "pending");
+    assert_eq!(status(ProductStatus::Approved), "pending");
+    assert_eq!(status(ProductStatus::Paused), "paused");
+    assert_eq!(status(ProductStatus::Rejected), "rejected");
+  }
+}
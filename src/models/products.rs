@@ -1,4 +1,5 @@
 use lazy_static::lazy_static;
+use megacommerce_proto::{ProductMedia, ProductMediaImage};
 use regex::Regex;
 
 pub static PRODUCT_TITLE_MIN_LENGTH: usize = 5;
@@ -26,6 +27,9 @@ pub static PRODUCT_MEDIA_MAX_ALLOWED_DIRECT_UPLOAD_SIZE_BYTES: usize = 1024 * 10
 pub static PRODUCT_IMAGE_ACCEPTED_TYPES: [&str; 4] =
   ["image/png", "image/webp", "image/jpeg", "image/jpg"];
 pub static PRODUCT_ID_TYPES: [&str; 4] = ["upc", "ean", "isbn", "gtin"];
+pub const PRODUCT_IMAGE_DERIVATIVE_CARD: &str = "card";
+pub const PRODUCT_SEARCH_QUERY_MIN_LENGTH: usize = 2;
+pub const PRODUCT_SEARCH_QUERY_MAX_LENGTH: usize = 100;
 
 pub enum ProductOfferingCondition {
   New,
@@ -78,21 +82,96 @@ impl ProductFulfillmentType {
   }
 }
 
-#[derive(Debug)]
+/// The lifecycle of a product listing:
+///
+/// draft -> pending -> approved | rejected -> published <-> paused -> archived
+///
+/// A rejected product goes back to draft (or straight to pending after fixing it),
+/// and every non archived state can be archived.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ProductStatus {
   Draft,
   Pending,
+  Approved,
+  Rejected,
   Published,
+  Paused,
+  Archived,
 }
 
 impl ProductStatus {
+  pub const ALL: [ProductStatus; 7] = [
+    Self::Draft,
+    Self::Pending,
+    Self::Approved,
+    Self::Rejected,
+    Self::Published,
+    Self::Paused,
+    Self::Archived,
+  ];
+
+  pub const fn as_str(&self) -> &'static str {
+    match self {
+      Self::Draft => "draft",
+      Self::Pending => "pending",
+      Self::Approved => "approved",
+      Self::Rejected => "rejected",
+      Self::Published => "published",
+      Self::Paused => "paused",
+      Self::Archived => "archived",
+    }
+  }
+
   pub fn as_string(&self) -> String {
+    self.as_str().to_string()
+  }
+
+  pub fn from_str(value: &str) -> Option<Self> {
+    Self::ALL.into_iter().find(|s| s.as_str() == value)
+  }
+
+  /// The states this state is allowed to move to.
+  pub fn transitions(&self) -> &'static [ProductStatus] {
     match self {
-      ProductStatus::Draft => "draft".to_string(),
-      ProductStatus::Pending => "pending".to_string(),
-      ProductStatus::Published => "published".to_string(),
+      Self::Draft => &[Self::Pending, Self::Archived],
+      Self::Pending => &[Self::Approved, Self::Rejected, Self::Draft, Self::Archived],
+      Self::Approved => &[Self::Published, Self::Archived],
+      Self::Rejected => &[Self::Draft, Self::Pending, Self::Archived],
+      Self::Published => &[Self::Paused, Self::Archived],
+      Self::Paused => &[Self::Published, Self::Archived],
+      Self::Archived => &[],
+    }
+  }
+
+  pub fn can_transition_to(&self, next: ProductStatus) -> bool {
+    self.transitions().contains(&next)
+  }
+
+  /// Moving to these states must be justified, the reason is shown to the supplier.
+  pub fn requires_reason(&self) -> bool {
+    matches!(self, Self::Rejected | Self::Paused)
+  }
+
+  /// Whether moving from `from` to this state is a moderation decision, the supplier can't
+  /// make it. Resuming a paused product isn't one, the moderation published it before.
+  pub fn is_moderation(&self, from: ProductStatus) -> bool {
+    match (from, self) {
+      (Self::Paused, Self::Published) => false,
+      _ => matches!(self, Self::Approved | Self::Rejected | Self::Published),
     }
   }
+
+  /// Whether the session may move a product from `from` to this state: a moderator may make
+  /// any transition, the supplier only the supplier ones (submit, withdraw, pause, resume,
+  /// archive) on their own products, anybody else none.
+  pub fn can_be_set_by(&self, from: ProductStatus, is_owner: bool, is_moderator: bool) -> bool {
+    is_moderator || (is_owner && !self.is_moderation(from))
+  }
+
+  /// The states the moderation has already signed off, an edit sends them back to review
+  pub fn requires_review_after_edit(&self) -> bool {
+    matches!(self, Self::Approved | Self::Published)
+  }
 }
 
 pub enum ProductCreateStepsNames {
@@ -225,6 +304,49 @@ pub fn product_id_is_validate(id_type: &str, id_value: &str) -> bool {
   }
 }
 
+/// The url list endpoints show for an image, falls back to the original for
+/// images that were uploaded before derivatives existed
+pub fn product_image_card_url(image: &ProductMediaImage) -> String {
+  match image.derivatives.get(PRODUCT_IMAGE_DERIVATIVE_CARD) {
+    Some(card) => card.url.clone(),
+    None => image.url.clone(),
+  }
+}
+
+/// The card url of the variant's first image by media id, the images are a map so picking
+/// any of them would show a different one on each process. Empty if the variant has none.
+pub fn variant_card_image(media: &ProductMedia, variant_id: &str) -> String {
+  media
+    .media
+    .get(variant_id)
+    .and_then(|variant| variant.images.iter().min_by_key(|(id, _)| *id))
+    .map(|(_, image)| product_image_card_url(image))
+    .unwrap_or_default()
+}
+
+/// Where the client uploads a file with a presigned url before the product is created,
+/// the objects under it are only read (and then released) by the product creation
+pub fn product_media_staging_prefix(user_id: &str, product_id: &str) -> String {
+  format!("uploads/{}/{}/", user_id, product_id)
+}
+
+pub fn product_media_staging_key(user_id: &str, product_id: &str, checksum: &str) -> String {
+  format!("{}{}", product_media_staging_prefix(user_id, product_id), checksum)
+}
+
+/// Every storage key the product's media references (originals, derivatives and videos)
+pub fn product_media_keys(media: &ProductMedia) -> Vec<String> {
+  let mut keys = vec![];
+  for variant in media.media.values() {
+    for image in variant.images.values() {
+      keys.push(image.url.clone());
+      keys.extend(image.derivatives.values().map(|d| d.url.clone()));
+    }
+    keys.extend(variant.videos.values().map(|v| v.url.clone()));
+  }
+  keys
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -253,6 +375,46 @@ mod tests {
     assert!(product_id_is_validate("gtin", "96385074"));
   }
 
+  #[test]
+  fn status_round_trip() {
+    for status in ProductStatus::ALL {
+      assert_eq!(ProductStatus::from_str(status.as_str()), Some(status));
+    }
+    assert_eq!(ProductStatus::from_str("deleted"), None);
+  }
+
+  #[test]
+  fn status_transitions() {
+    assert!(ProductStatus::Draft.can_transition_to(ProductStatus::Pending));
+    assert!(ProductStatus::Pending.can_transition_to(ProductStatus::Approved));
+    assert!(ProductStatus::Approved.can_transition_to(ProductStatus::Published));
+    assert!(ProductStatus::Published.can_transition_to(ProductStatus::Paused));
+    assert!(ProductStatus::Paused.can_transition_to(ProductStatus::Published));
+    assert!(!ProductStatus::Pending.can_transition_to(ProductStatus::Published));
+    assert!(!ProductStatus::Draft.can_transition_to(ProductStatus::Published));
+    assert!(ProductStatus::Archived.transitions().is_empty());
+  }
+
+  #[test]
+  fn status_update_permissions() {
+    use ProductStatus::*;
+
+    // the supplier can't moderate their own product
+    assert!(!Approved.can_be_set_by(Pending, true, false));
+    assert!(!Rejected.can_be_set_by(Pending, true, false));
+    assert!(!Published.can_be_set_by(Approved, true, false));
+    assert!(Pending.can_be_set_by(Draft, true, false));
+    assert!(Archived.can_be_set_by(Published, true, false));
+    // but pauses and resumes it
+    assert!(Paused.can_be_set_by(Published, true, false));
+    assert!(Published.can_be_set_by(Paused, true, false));
+    // nor can anybody else touch it
+    assert!(!Paused.can_be_set_by(Published, false, false));
+    assert!(!Published.can_be_set_by(Paused, false, false));
+    assert!(!Archived.can_be_set_by(Draft, false, false));
+    assert!(Published.can_be_set_by(Approved, false, true));
+  }
+
   #[test]
   fn invalid_examples() {
     assert!(!product_id_is_validate("upc", "1234567890123")); // wrong length
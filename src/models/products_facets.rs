@@ -0,0 +1,185 @@
+use std::collections::{BTreeMap, HashMap};
+
+use megacommerce_proto::{ProductDetails, Subcategory};
+use megacommerce_shared::{
+  models::{errors::AppErrorError, products::SubcategoryAttributeType},
+  utils::grpc::{grpc_deserialize_any, AnyValue},
+};
+use serde_json::Value;
+
+/// The max values a single facet can be filtered by
+pub const PRODUCT_FACET_MAX_VALUES: usize = 20;
+
+/// A subcategory attribute the products can be filtered and counted by,
+/// only the select and boolean attributes have a closed set of values to facet on
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProductFacetSchema {
+  pub name: String,
+  pub options: Vec<String>,
+}
+
+/// A validated facet filter, a product matches if it has any of the values
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProductFacetFilter {
+  pub name: String,
+  pub values: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProductFacetCount {
+  pub name: String,
+  pub value: String,
+  pub count: u64,
+}
+
+fn attribute_options(typ: &str, string_array: &[String]) -> Option<Vec<String>> {
+  match SubcategoryAttributeType::from_str(typ) {
+    SubcategoryAttributeType::Select => Some(string_array.to_vec()),
+    SubcategoryAttributeType::Boolean => Some(vec!["true".into(), "false".into()]),
+    _ => None,
+  }
+}
+
+/// Merges the facetable attributes of the subcategories, sorted by name.
+/// Attributes with the same name in different subcategories share one facet.
+pub fn product_facets_schema<'a>(
+  subcategories: impl Iterator<Item = &'a Subcategory>,
+) -> Vec<ProductFacetSchema> {
+  let mut facets: BTreeMap<String, Vec<String>> = BTreeMap::new();
+  for sub in subcategories {
+    for (name, attr) in sub.attributes.iter() {
+      if let Some(options) = attribute_options(&attr.r#type, &attr.string_array) {
+        let merged = facets.entry(name.clone()).or_default();
+        for option in options.into_iter() {
+          if !merged.contains(&option) {
+            merged.push(option);
+          }
+        }
+      }
+    }
+  }
+
+  facets.into_iter().map(|(name, options)| ProductFacetSchema { name, options }).collect()
+}
+
+/// Checks the requested facet filters against the schema, the errors are keyed by the facet name
+pub fn product_facet_filters_validate(
+  schema: &[ProductFacetSchema],
+  filters: &HashMap<String, Vec<String>>,
+) -> Result<Vec<ProductFacetFilter>, HashMap<String, AppErrorError>> {
+  let mut errors: HashMap<String, AppErrorError> = HashMap::new();
+  let mut validated: Vec<ProductFacetFilter> = vec![];
+
+  for (name, values) in filters.iter() {
+    let facet = match schema.iter().find(|f| &f.name == name) {
+      Some(facet) => facet,
+      None => {
+        let err = AppErrorError { id: "products.facet.unknown".into(), params: None };
+        errors.insert(name.clone(), err);
+        continue;
+      }
+    };
+    if values.is_empty() {
+      continue;
+    }
+    if values.len() > PRODUCT_FACET_MAX_VALUES {
+      let params = Some(HashMap::from([(
+        "Max".to_string(),
+        Value::Number(PRODUCT_FACET_MAX_VALUES.into()),
+      )]));
+      errors.insert(name.clone(), AppErrorError { id: "products.facet.values.max".into(), params });
+      continue;
+    }
+    if values.iter().any(|v| !facet.options.contains(v)) {
+      let err = AppErrorError { id: "products.facet.value.invalid".into(), params: None };
+      errors.insert(name.clone(), err);
+      continue;
+    }
+
+    let mut unique: Vec<String> = vec![];
+    for value in values.iter() {
+      if !unique.contains(value) {
+        unique.push(value.clone());
+      }
+    }
+    validated.push(ProductFacetFilter { name: name.clone(), values: unique });
+  }
+
+  if errors.len() > 0 {
+    return Err(errors);
+  }
+
+  validated.sort_by(|a, b| a.name.cmp(&b.name));
+  Ok(validated)
+}
+
+/// The (name, value) pairs of the facetable attributes the product has,
+/// over its shared attributes and all of its variants
+pub fn product_attribute_values(
+  details: &ProductDetails,
+  sub: &Subcategory,
+) -> Vec<(String, String)> {
+  let forms = std::iter::once(&details.shared)
+    .chain(details.details.values().map(|variant| &variant.variant_data));
+
+  let mut values: Vec<(String, String)> = vec![];
+  for form in forms {
+    for (name, any) in form.iter() {
+      let facetable = match sub.attributes.get(name) {
+        Some(attr) => attribute_options(&attr.r#type, &attr.string_array).is_some(),
+        None => false,
+      };
+      if !facetable {
+        continue;
+      }
+
+      let value = match grpc_deserialize_any(any) {
+        AnyValue::String(value) => value,
+        AnyValue::Bool(value) => value.to_string(),
+        _ => continue,
+      };
+      let pair = (name.clone(), value);
+      if !values.contains(&pair) {
+        values.push(pair);
+      }
+    }
+  }
+
+  values
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn schema() -> Vec<ProductFacetSchema> {
+    vec![
+      ProductFacetSchema { name: "color".into(), options: vec!["red".into(), "blue".into()] },
+      ProductFacetSchema { name: "size".into(), options: vec!["m".into(), "l".into()] },
+    ]
+  }
+
+  #[test]
+  fn test_facet_filters_validate() {
+    let filters = HashMap::from([
+      ("size".to_string(), vec!["l".to_string()]),
+      ("color".to_string(), vec!["red".to_string(), "red".to_string()]),
+    ]);
+    let validated = product_facet_filters_validate(&schema(), &filters).unwrap();
+    assert_eq!(validated.len(), 2);
+    let color = ProductFacetFilter { name: "color".into(), values: vec!["red".into()] };
+    assert_eq!(validated[0], color);
+    assert_eq!(validated[1].name, "size");
+  }
+
+  #[test]
+  fn test_facet_filters_validate_errors() {
+    let filters = HashMap::from([
+      ("material".to_string(), vec!["wool".to_string()]),
+      ("color".to_string(), vec!["green".to_string()]),
+    ]);
+    let errors = product_facet_filters_validate(&schema(), &filters).unwrap_err();
+    assert_eq!(errors["material"].id, "products.facet.unknown");
+    assert_eq!(errors["color"].id, "products.facet.value.invalid");
+  }
+}
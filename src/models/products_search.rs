@@ -0,0 +1,216 @@
+use std::{collections::HashMap, sync::Arc};
+
+use megacommerce_proto::{ProductsSearchRequest, SortDirection};
+use megacommerce_shared::models::{
+  context::Context,
+  errors::{AppError, AppErrorError, AppErrorErrors},
+};
+use serde_json::Value;
+use tonic::Code;
+
+use crate::{
+  data::currencies::{currency_get, Currency},
+  models::{
+    money::Money,
+    products::{PRODUCT_SEARCH_QUERY_MAX_LENGTH, PRODUCT_SEARCH_QUERY_MIN_LENGTH},
+  },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProductsSearchSort {
+  /// Text rank weighted by the sales
+  #[default]
+  Relevance,
+  PriceAsc,
+  PriceDesc,
+  Newest,
+  Oldest,
+}
+
+impl ProductsSearchSort {
+  pub fn from_sort_by(name: &str, direction: SortDirection) -> Option<Self> {
+    match (name, direction) {
+      ("relevance", _) => Some(Self::Relevance),
+      ("price", SortDirection::Asc) => Some(Self::PriceAsc),
+      ("price", _) => Some(Self::PriceDesc),
+      ("created_at", SortDirection::Asc) => Some(Self::Oldest),
+      ("created_at", _) => Some(Self::Newest),
+      _ => None,
+    }
+  }
+
+  /// The name the pagination cursors of the sort are issued for
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Relevance => "relevance",
+      Self::PriceAsc => "price:asc",
+      Self::PriceDesc => "price:desc",
+      Self::Newest => "created_at:desc",
+      Self::Oldest => "created_at:asc",
+    }
+  }
+}
+
+/// A price range of a products search, the products are stored with their own currency,
+/// so only the products priced in `currency` are matched
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProductsSearchPriceRange {
+  pub currency: &'static Currency,
+  pub min: Option<Money>,
+  pub max: Option<Money>,
+}
+
+/// The validated (and normalized) filters of a products search
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProductsSearchFilters {
+  pub query: String,
+  pub category_id: Option<String>,
+  pub subcategory_ids: Vec<String>,
+  pub price_range: Option<ProductsSearchPriceRange>,
+  pub on_sale: bool,
+  pub sort: ProductsSearchSort,
+}
+
+pub fn products_search_is_valid(
+  ctx: Arc<Context>,
+  req: &ProductsSearchRequest,
+) -> Result<ProductsSearchFilters, AppError> {
+  let path = "products.models.products_search_is_valid";
+  let mut errors: HashMap<String, AppErrorError> = HashMap::new();
+
+  if req.pagination.is_none() {
+    let id = "request.pagination.invalid";
+    return Err(AppError::new(ctx, path, id, None, "", Code::InvalidArgument.into(), None));
+  }
+
+  let query = search_query_normalize(&req.query);
+  let query_len = query.chars().count();
+  if query_len < PRODUCT_SEARCH_QUERY_MIN_LENGTH || query_len > PRODUCT_SEARCH_QUERY_MAX_LENGTH {
+    let params = Some(HashMap::from([
+      ("Min".into(), Value::Number(PRODUCT_SEARCH_QUERY_MIN_LENGTH.into())),
+      ("Max".into(), Value::Number(PRODUCT_SEARCH_QUERY_MAX_LENGTH.into())),
+    ]));
+    let err = AppErrorError { id: "products.search.query.length".into(), params };
+    errors.insert("query".into(), err);
+  }
+
+  if req.category_id.is_empty() && !req.subcategory_ids.is_empty() {
+    let err = AppErrorError { id: "products.search.category.required".into(), params: None };
+    errors.insert("category_id".into(), err);
+  }
+
+  let price_range =
+    match search_price_range(&req.price_currency_code, req.min_price_cents, req.max_price_cents) {
+      Ok(range) => range,
+      Err((field, id)) => {
+        errors.insert(field.into(), AppErrorError { id: id.into(), params: None });
+        None
+      }
+    };
+
+  let sort = match req.pagination.as_ref().and_then(|p| p.sort_by.first()) {
+    Some(sort) => match ProductsSearchSort::from_sort_by(&sort.name, sort.direction()) {
+      Some(sort) => sort,
+      None => {
+        let err = AppErrorError { id: "products.search.sort.invalid".into(), params: None };
+        errors.insert("sort_by".into(), err);
+        ProductsSearchSort::default()
+      }
+    },
+    None => ProductsSearchSort::default(),
+  };
+
+  if errors.len() > 0 {
+    let errors = Some(AppErrorErrors { errors_internal: Some(errors), ..Default::default() });
+    let id = "products.search.invalid";
+    return Err(AppError::new(ctx, path, id, None, "", Code::InvalidArgument.into(), errors));
+  }
+
+  Ok(ProductsSearchFilters {
+    query,
+    category_id: if req.category_id.is_empty() { None } else { Some(req.category_id.clone()) },
+    subcategory_ids: req.subcategory_ids.clone(),
+    price_range,
+    on_sale: req.on_sale,
+    sort,
+  })
+}
+
+/// Builds the price range from the bounds in the minor units of `currency_code`,
+/// on failure returns the invalid field and the error id
+pub fn search_price_range(
+  currency_code: &str,
+  min_units: Option<u32>,
+  max_units: Option<u32>,
+) -> Result<Option<ProductsSearchPriceRange>, (&'static str, &'static str)> {
+  if min_units.is_none() && max_units.is_none() {
+    return Ok(None);
+  }
+  if let (Some(min), Some(max)) = (min_units, max_units) {
+    if min > max {
+      return Err(("min_price_cents", "products.search.price_range.invalid"));
+    }
+  }
+
+  let currency = currency_get(currency_code)
+    .ok_or(("price_currency_code", "products.search.price_currency.invalid"))?;
+  let money = |units: u32| Money::from_minor_units(units as i64, currency);
+  Ok(Some(ProductsSearchPriceRange {
+    currency,
+    min: min_units.map(money),
+    max: max_units.map(money),
+  }))
+}
+
+/// Trims the query and collapses its inner whitespace
+pub fn search_query_normalize(query: &str) -> String {
+  query.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_search_query_normalize() {
+    assert_eq!(search_query_normalize("  red \t running\n shoes "), "red running shoes");
+    assert_eq!(search_query_normalize("   "), "");
+  }
+
+  #[test]
+  fn test_search_sort() {
+    let sort = |name: &str, dir: SortDirection| ProductsSearchSort::from_sort_by(name, dir);
+    assert_eq!(sort("price", SortDirection::Asc), Some(ProductsSearchSort::PriceAsc));
+    assert_eq!(sort("price", SortDirection::Desc), Some(ProductsSearchSort::PriceDesc));
+    assert_eq!(sort("created_at", SortDirection::Desc), Some(ProductsSearchSort::Newest));
+    assert_eq!(sort("relevance", SortDirection::Asc), Some(ProductsSearchSort::Relevance));
+    assert_eq!(sort("title", SortDirection::Asc), None);
+  }
+
+  #[test]
+  fn test_search_price_range() {
+    assert_eq!(search_price_range("", None, None), Ok(None));
+
+    let range = search_price_range("USD", Some(1999), Some(5000)).unwrap().unwrap();
+    assert_eq!(range.currency.code, "USD");
+    assert_eq!(range.min.unwrap().to_string(), "19.99");
+    assert_eq!(range.max.unwrap().to_string(), "50.00");
+
+    // the bounds are in the currency's own minor units
+    let range = search_price_range("JPY", Some(1999), None).unwrap().unwrap();
+    assert_eq!(range.min.unwrap().to_string(), "1999");
+    assert!(range.max.is_none());
+
+    let range = search_price_range("KWD", None, Some(1999)).unwrap().unwrap();
+    assert_eq!(range.max.unwrap().to_string(), "1.999");
+
+    assert_eq!(
+      search_price_range("", Some(100), None),
+      Err(("price_currency_code", "products.search.price_currency.invalid"))
+    );
+    assert_eq!(
+      search_price_range("USD", Some(500), Some(100)),
+      Err(("min_price_cents", "products.search.price_range.invalid"))
+    );
+  }
+}
@@ -0,0 +1,35 @@
+use serde::Deserialize;
+
+/// How the "products to like" are picked
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecommenderStrategy {
+  /// The newest products first, the same list for everyone
+  #[default]
+  Baseline,
+  /// Scored by the similarity to the products the user recently saw or bought, the affinity
+  /// to their categories and the popularity, the popularity alone for anonymous sessions
+  Personalized,
+}
+
+/// How much each signal counts in the score of a personalized recommendation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecommendationWeights {
+  /// Of the co-view/co-purchase similarity to the user's recent products
+  pub similarity: f64,
+  /// Of the share of the user's recent events in the product's category
+  pub affinity: f64,
+  /// Of the inverse rank in the monthly best selling ranking
+  pub popularity: f64,
+}
+
+/// Bounds the products scored for a page of recommendations: the most similar products,
+/// the best sellers of the user's top categories and the overall best sellers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecommendationCandidates {
+  /// How many products each of the sources contributes at most
+  pub per_source: i64,
+  /// How many of the user's categories (by their share of the events) the best sellers of
+  /// are candidates
+  pub top_categories: i64,
+}
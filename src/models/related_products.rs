@@ -0,0 +1,148 @@
+use std::{cmp::Ordering, collections::HashSet};
+
+/// How much each signal counts in the score of a related product, every signal is in [0, 1]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RelatedProductsWeights {
+  /// Of the share of the product's attribute values the candidate has too
+  pub attributes: f64,
+  /// Of having the same brand
+  pub brand: f64,
+  /// Of the closeness of the lowest variant prices, `1 - |a - b| / max(a, b)`
+  pub price: f64,
+  /// Of the overlap (Jaccard) of the tags
+  pub tags: f64,
+}
+
+/// What a product is compared on, its brand and tags are lowercased
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RelatedSignals {
+  pub id: String,
+  pub brand: Option<String>,
+  pub currency_code: String,
+  /// The lowest regular price of the variants
+  pub price: Option<f64>,
+  pub tags: Vec<String>,
+  /// The share of the source product's attribute values this product has too, none for
+  /// the source itself
+  pub attributes: Option<f64>,
+}
+
+/// `1 - |a - b| / max(a, b)`, 1 for the same price and towards 0 as they grow apart
+pub fn price_proximity(a: f64, b: f64) -> f64 {
+  if a <= 0.0 || b <= 0.0 {
+    return 0.0;
+  }
+  1.0 - (a - b).abs() / a.max(b)
+}
+
+/// The Jaccard index of the two sets of tags
+pub fn tags_overlap(a: &[String], b: &[String]) -> f64 {
+  let a: HashSet<&String> = a.iter().collect();
+  let b: HashSet<&String> = b.iter().collect();
+  let union = a.union(&b).count();
+  if union == 0 {
+    return 0.0;
+  }
+  a.intersection(&b).count() as f64 / union as f64
+}
+
+impl RelatedProductsWeights {
+  /// The weighted sum of the signals of the candidate, compared to the source product.
+  /// Prices are only compared within the same currency.
+  pub fn score(&self, source: &RelatedSignals, candidate: &RelatedSignals) -> f64 {
+    let attributes = candidate.attributes.unwrap_or(0.0);
+    let brand = match (&source.brand, &candidate.brand) {
+      (Some(a), Some(b)) if a == b => 1.0,
+      _ => 0.0,
+    };
+    let price = match (source.price, candidate.price) {
+      (Some(a), Some(b)) if source.currency_code == candidate.currency_code => {
+        price_proximity(a, b)
+      }
+      _ => 0.0,
+    };
+    let tags = tags_overlap(&source.tags, &candidate.tags);
+
+    attributes * self.attributes + brand * self.brand + price * self.price + tags * self.tags
+  }
+}
+
+/// The ids of the `limit` best scored candidates, the ties are broken by id (descending)
+/// so the same product always gets the same list
+pub fn related_ranked(
+  weights: &RelatedProductsWeights,
+  source: &RelatedSignals,
+  candidates: &[RelatedSignals],
+  limit: usize,
+) -> Vec<String> {
+  let mut scored: Vec<(f64, &String)> =
+    candidates.iter().map(|c| (weights.score(source, c), &c.id)).collect();
+  scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal).then(b.1.cmp(a.1)));
+  scored.into_iter().take(limit).map(|(_, id)| id.clone()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn tags(names: &[&str]) -> Vec<String> {
+    names.iter().map(|n| n.to_string()).collect()
+  }
+
+  fn signals(id: &str, brand: Option<&str>, price: f64, tag_names: &[&str]) -> RelatedSignals {
+    RelatedSignals {
+      id: id.into(),
+      brand: brand.map(|b| b.into()),
+      currency_code: "USD".into(),
+      price: Some(price),
+      tags: tags(tag_names),
+      attributes: None,
+    }
+  }
+
+  #[test]
+  fn test_related_signals() {
+    assert_eq!(price_proximity(50.0, 50.0), 1.0);
+    assert_eq!(price_proximity(50.0, 100.0), 0.5);
+    assert_eq!(price_proximity(0.0, 100.0), 0.0);
+
+    assert_eq!(tags_overlap(&tags(&["a", "b"]), &tags(&["b", "c"])), 1.0 / 3.0);
+    assert_eq!(tags_overlap(&tags(&["a"]), &tags(&["a"])), 1.0);
+    assert_eq!(tags_overlap(&[], &[]), 0.0);
+  }
+
+  #[test]
+  fn test_related_score() {
+    let weights = RelatedProductsWeights { attributes: 1.0, brand: 0.5, price: 0.5, tags: 0.5 };
+    let source = signals("s", Some("acme"), 100.0, &["red", "cotton"]);
+
+    let mut same = signals("a", Some("acme"), 100.0, &["red", "cotton"]);
+    same.attributes = Some(1.0);
+    assert_eq!(weights.score(&source, &same), 2.5);
+
+    // another brand, half the price and no shared tag or attribute
+    let other = signals("b", Some("other"), 50.0, &["blue"]);
+    assert_eq!(weights.score(&source, &other), 0.25);
+
+    // prices in another currency aren't compared
+    let mut euros = signals("c", None, 100.0, &[]);
+    euros.currency_code = "EUR".into();
+    assert_eq!(weights.score(&source, &euros), 0.0);
+  }
+
+  #[test]
+  fn test_related_ranked() {
+    let weights = RelatedProductsWeights { attributes: 1.0, brand: 0.5, price: 0.5, tags: 0.5 };
+    let source = signals("s", Some("acme"), 100.0, &["red"]);
+    let candidates = vec![
+      signals("01A", None, 10.0, &[]),
+      signals("01B", Some("acme"), 100.0, &["red"]),
+      signals("01C", None, 10.0, &[]),
+      RelatedSignals { attributes: Some(0.5), ..signals("01D", None, 100.0, &[]) },
+    ];
+
+    let ranked = related_ranked(&weights, &source, &candidates, 3);
+    // the equal scores of 01A and 01C are ordered by id
+    assert_eq!(ranked, vec!["01B", "01D", "01C"]);
+  }
+}
@@ -0,0 +1,70 @@
+use std::collections::BTreeSet;
+
+use megacommerce_proto::{
+  CategoryNavbarProductItem, ProductDetailsResponseData, ProductsCategoryItem,
+};
+
+/// The public profile of the seller (the user) behind a product
+#[derive(Debug, Clone, PartialEq)]
+pub struct SellerProfile {
+  pub id: String,
+  pub display_name: String,
+  /// The average rating rounded to one decimal, none until the seller has a rating
+  pub rating: Option<f64>,
+}
+
+/// The distinct sellers of a page, so each is looked up once
+pub fn seller_ids<'a>(ids: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+  let ids: BTreeSet<&str> = ids.into_iter().filter(|id| !id.is_empty()).collect();
+  ids.into_iter().map(String::from).collect()
+}
+
+/// A product showing its seller, whose profile is filled in after the product is read
+pub trait SoldBy {
+  fn seller_id(&self) -> &str;
+  fn set_seller(&mut self, seller: &SellerProfile);
+}
+
+impl SoldBy for ProductsCategoryItem {
+  fn seller_id(&self) -> &str {
+    &self.seller_id
+  }
+
+  fn set_seller(&mut self, seller: &SellerProfile) {
+    self.sold_by = seller.display_name.clone();
+    self.seller_rating = seller.rating;
+  }
+}
+
+impl SoldBy for CategoryNavbarProductItem {
+  fn seller_id(&self) -> &str {
+    &self.seller_id
+  }
+
+  fn set_seller(&mut self, seller: &SellerProfile) {
+    self.sold_by = seller.display_name.clone();
+    self.seller_rating = seller.rating;
+  }
+}
+
+impl SoldBy for ProductDetailsResponseData {
+  fn seller_id(&self) -> &str {
+    &self.supplier_id
+  }
+
+  fn set_seller(&mut self, seller: &SellerProfile) {
+    self.sold_by = seller.display_name.clone();
+    self.seller_rating = seller.rating;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_seller_ids() {
+    let ids = seller_ids(["b", "a", "", "b", "a"]);
+    assert_eq!(ids, vec!["a".to_string(), "b".to_string()]);
+  }
+}
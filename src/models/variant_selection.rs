@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use megacommerce_proto::{ProductOffer, ProductOfferVariant};
+use serde::Deserialize;
+
+use crate::models::{
+  inventory::{variant_stock_level, StockLevel, VariantInventory},
+  money::VariantPrice,
+};
+
+/// How the variant a product is shown with in the lists is chosen. Whatever the policy,
+/// a variant left to order wins over an out of stock one, then the cheapest one, then the
+/// smallest variant id, so a product shows the same image and price on every list.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VariantSelection {
+  #[default]
+  CheapestInStock,
+  /// The variant the seller set as `default_variant_id`
+  SellerDefault,
+  /// The variant with the most units sold
+  BestSeller,
+}
+
+/// The variant a product is shown with, with its prices and stock at the time of the selection
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DisplayVariant<'a> {
+  pub id: &'a str,
+  pub variant: &'a ProductOfferVariant,
+  pub prices: VariantPrice,
+  pub level: StockLevel,
+}
+
+/// Picks the display variant of the offer at `now` (in milliseconds), `stock` is the product's
+/// `variants_inventory`. Variants with an unparsable price are never picked, none is returned
+/// if no variant has a valid price.
+pub fn display_variant<'a>(
+  policy: VariantSelection,
+  offer: &'a ProductOffer,
+  stock: &HashMap<String, VariantInventory>,
+  currency_code: &str,
+  now: u64,
+) -> Option<DisplayVariant<'a>> {
+  let default_id = offer.default_variant_id.as_deref();
+  offer
+    .offer
+    .iter()
+    .filter_map(|(id, variant)| {
+      let inventory = stock.get(id);
+      let prices = VariantPrice::from_offer(variant, currency_code, now).ok()?;
+      let level = variant_stock_level(variant, inventory);
+      let sold = inventory.map(|i| i.sold).unwrap_or(0);
+      Some((DisplayVariant { id, variant, prices, level }, sold))
+    })
+    .min_by_key(|(display, sold)| {
+      let preference = match policy {
+        VariantSelection::CheapestInStock => 0,
+        VariantSelection::SellerDefault => i64::from(default_id != Some(display.id)),
+        VariantSelection::BestSeller => -sold,
+      };
+      (!display.level.in_stock(), preference, display.prices.selling().amount(), display.id)
+    })
+    .map(|(display, _)| display)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn offer() -> (ProductOffer, HashMap<String, VariantInventory>) {
+    let variant = |price: &str| ProductOfferVariant {
+      price: price.into(),
+      quantity: 10,
+      ..Default::default()
+    };
+    let offer = ProductOffer {
+      offer: HashMap::from([
+        ("a".into(), variant("30.00")),
+        ("b".into(), variant("10.00")),
+        ("c".into(), variant("20.00")),
+        ("d".into(), variant("20.00")),
+      ]),
+      default_variant_id: Some("c".into()),
+    };
+    let inventory = |reserved: i64, sold: i64| VariantInventory { reserved, sold, threshold: None };
+    let stock = HashMap::from([("a".into(), inventory(0, 40)), ("b".into(), inventory(10, 90))]);
+    (offer, stock)
+  }
+
+  #[test]
+  fn test_display_variant() {
+    let (offer, stock) = offer();
+    let pick = |policy| display_variant(policy, &offer, &stock, "USD", 0).map(|d| d.id);
+
+    // "b" is the cheapest but sold out, "c" and "d" tie on the price
+    assert_eq!(pick(VariantSelection::CheapestInStock), Some("c"));
+    assert_eq!(pick(VariantSelection::SellerDefault), Some("c"));
+    assert_eq!(pick(VariantSelection::BestSeller), Some("a"));
+
+    let (mut offer, stock) = offer();
+    offer.default_variant_id = Some("d".into());
+    let picked = display_variant(VariantSelection::SellerDefault, &offer, &stock, "USD", 0);
+    assert_eq!(picked.map(|d| d.id), Some("d"));
+  }
+}
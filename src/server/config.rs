@@ -3,7 +3,10 @@ use std::{env, fs};
 
 use megacommerce_shared::models::errors::{BoxedErr, ErrorType, InternalError};
 
-use crate::{models::config::Config, server::Server};
+use crate::{
+  models::config::{Config, PAGINATION_CURSOR_SECRET_ENV},
+  server::Server,
+};
 
 impl Server {
   pub(crate) async fn init_service_config(&self) -> Result<(), Box<dyn Error>> {
@@ -23,9 +26,20 @@ impl Server {
     let yaml_string = fs::read_to_string(format!("config.{}.yaml", env_mode))
       .map_err(|e| ie(Box::new(e), "failed to load service config file"))?;
 
-    let parsed_config: Config = serde_yaml::from_str(&yaml_string)
+    let mut parsed_config: Config = serde_yaml::from_str(&yaml_string)
       .map_err(|e| ie(Box::new(e), "failed to parse service config file"))?;
 
+    // the secrets come from the environment (or the secret store that fills it)
+    if let Ok(secret) = env::var(PAGINATION_CURSOR_SECRET_ENV) {
+      if !secret.is_empty() {
+        parsed_config.products.pagination_cursor_secret = secret;
+      }
+    }
+    parsed_config.products.validate().map_err(|msg| {
+      let err = Box::new(std::io::Error::new(std::io::ErrorKind::InvalidInput, msg));
+      ie(err, "invalid products config")
+    })?;
+
     let mut config = self.service_config.lock().await;
     *config = parsed_config;
 
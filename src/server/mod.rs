@@ -5,6 +5,7 @@ pub mod object_storage;
 
 use std::error::Error;
 use std::sync::Arc;
+use std::time::Duration;
 
 use megacommerce_proto::Config as SharedConfig;
 use megacommerce_shared::models::errors::{BoxedErr, ErrorType, InternalError};
@@ -16,10 +17,23 @@ use tokio::sync::{Mutex, RwLock};
 
 use crate::common::main::{Common, CommonArgs};
 use crate::controller::{Controller, ControllerArgs};
+use crate::jobs::{
+  BestSellingRanker, BestSellingRankerArgs, ExchangeRatesRefresher, ExchangeRatesRefresherArgs,
+  MediaSweeper, MediaSweeperArgs, ProductEventsWriter, ProductEventsWriterArgs,
+  ProductSimilaritiesBuilder, ProductSimilaritiesBuilderArgs, ReservationsExpirer,
+  ReservationsExpirerArgs, SalesExpirer, SalesExpirerArgs,
+};
+use crate::models::recommendations::{
+  RecommendationCandidates, RecommendationWeights, RecommenderStrategy,
+};
 use crate::models::config::Config as ServiceConfig;
 use crate::server::object_storage::ObjectStorage;
 use crate::store::cache::{Cache, CacheArgs};
 use crate::store::database::dbstore::{ProductsStoreImpl, ProductsStoreImplArgs};
+use crate::store::exchange_rates::{
+  DbExchangeRates, ExchangeRatesCache, ExchangeRatesProvider, FileExchangeRates,
+};
+use crate::store::recommender::{BaselineRecommender, PersonalizedRecommender, ProductsRecommender};
 
 pub struct Server {
   pub(crate) errors: mpsc::Sender<InternalError>,
@@ -90,9 +104,118 @@ impl Server {
     let cache =
       Arc::new(Cache::new(cache_args).await.map_err(|e| mk_err("failed to initialize cache", e))?);
 
-    let store_args = ProductsStoreImplArgs { db: self.db() };
+    let products_cfg = self.service_config.lock().await.products.clone();
+    let variant_selection = products_cfg.display_variant_selection;
+    let store_args = ProductsStoreImplArgs { db: self.db(), variant_selection };
     let store = Arc::new(ProductsStoreImpl::new(store_args));
 
+    let sweeper = MediaSweeper::new(MediaSweeperArgs {
+      store: store.clone(),
+      storage: self.object_storage(),
+      errors: self.errors.clone(),
+      interval: Duration::from_secs(products_cfg.media_sweeper_interval_secs),
+      stale_after: Duration::from_secs(products_cfg.media_upload_stale_after_secs),
+    });
+    spawn(async move {
+      sweeper.run().await;
+    });
+
+    let expirer = SalesExpirer::new(SalesExpirerArgs {
+      store: store.clone(),
+      errors: self.errors.clone(),
+      interval: Duration::from_secs(products_cfg.sales_expirer_interval_secs),
+    });
+    spawn(async move {
+      expirer.run().await;
+    });
+
+    let reservations_expirer = ReservationsExpirer::new(ReservationsExpirerArgs {
+      store: store.clone(),
+      errors: self.errors.clone(),
+      interval: Duration::from_secs(products_cfg.inventory_reservations_expirer_interval_secs),
+      ttl: Duration::from_secs(products_cfg.inventory_reservation_ttl_secs),
+    });
+    spawn(async move {
+      reservations_expirer.run().await;
+    });
+
+    let ranker = BestSellingRanker::new(BestSellingRankerArgs {
+      store: store.clone(),
+      errors: self.errors.clone(),
+      interval: Duration::from_secs(products_cfg.best_selling_rankings_interval_secs),
+      size: products_cfg.best_selling_rankings_size as i64,
+    });
+    spawn(async move {
+      ranker.run().await;
+    });
+
+    let history = Duration::from_secs(products_cfg.recommendations_history_days as u64 * 86_400);
+    let recommender: Arc<dyn ProductsRecommender> = match products_cfg.recommender {
+      RecommenderStrategy::Baseline => Arc::new(BaselineRecommender { store: store.clone() }),
+      RecommenderStrategy::Personalized => {
+        let similarities = ProductSimilaritiesBuilder::new(ProductSimilaritiesBuilderArgs {
+          store: store.clone(),
+          errors: self.errors.clone(),
+          interval: Duration::from_secs(products_cfg.recommendations_similarity_interval_secs),
+          history,
+          per_product: products_cfg.recommendations_similar_per_product as i64,
+          snapshot_ttl: Duration::from_secs(products_cfg.recommendations_snapshot_ttl_secs),
+        });
+        spawn(async move {
+          similarities.run().await;
+        });
+
+        let weights = RecommendationWeights {
+          similarity: products_cfg.recommendations_similarity_weight,
+          affinity: products_cfg.recommendations_affinity_weight,
+          popularity: products_cfg.recommendations_popularity_weight,
+        };
+        let candidates = RecommendationCandidates {
+          per_source: products_cfg.recommendations_candidates as i64,
+          top_categories: products_cfg.recommendations_affinity_categories as i64,
+        };
+        let fallback = BaselineRecommender { store: store.clone() };
+        Arc::new(PersonalizedRecommender {
+          store: store.clone(),
+          fallback,
+          weights,
+          candidates,
+          history,
+        })
+      }
+    };
+
+    // a request's events are queued all at once, so the queue holds at least one request
+    let max_per_request = products_cfg.product_events_max_per_request as usize;
+    let capacity = products_cfg.product_events_queue_capacity.max(max_per_request).max(1);
+    let (product_events, events) = mpsc::channel(capacity);
+    let events_writer = ProductEventsWriter::new(ProductEventsWriterArgs {
+      store: store.clone(),
+      errors: self.errors.clone(),
+      events,
+      batch_size: products_cfg.product_events_batch_size as usize,
+      flush_interval: Duration::from_millis(products_cfg.product_events_flush_interval_ms),
+    });
+    spawn(async move {
+      events_writer.run().await;
+    });
+
+    let rates_file = &products_cfg.exchange_rates_file;
+    let rates_provider: Arc<dyn ExchangeRatesProvider> = match rates_file.is_empty() {
+      true => Arc::new(DbExchangeRates { db: self.db() }),
+      false => Arc::new(FileExchangeRates { path: rates_file.into() }),
+    };
+    let max_age = Duration::from_secs(products_cfg.exchange_rates_max_age_secs);
+    let exchange_rates = Arc::new(ExchangeRatesCache::new(rates_provider, max_age));
+    let refresher = ExchangeRatesRefresher::new(ExchangeRatesRefresherArgs {
+      rates: exchange_rates.clone(),
+      errors: self.errors.clone(),
+      interval: Duration::from_secs(products_cfg.exchange_rates_refresh_interval_secs),
+    });
+    spawn(async move {
+      refresher.run().await;
+    });
+
     let cfg = self.config().get().await.localization.clone().unwrap_or_default();
     match self.common.as_mut().unwrap().translations_get().await {
       Ok(res) => {
@@ -107,8 +230,16 @@ impl Server {
       Err(err) => return Err(err),
     }
 
-    let ctr_args =
-      ControllerArgs { cfg: self.config(), cache, store, storage: self.object_storage() };
+    let ctr_args = ControllerArgs {
+      cfg: self.config(),
+      products_cfg,
+      cache,
+      store,
+      storage: self.object_storage(),
+      exchange_rates,
+      recommender,
+      product_events,
+    };
     let controller = Controller::new(ctr_args);
     controller.run().await
   }
@@ -1,12 +1,31 @@
+use std::{collections::HashMap, time::Duration};
+
 use aws_config::{BehaviorVersion, Region};
 use aws_credential_types::Credentials;
-use aws_sdk_s3::{config::SharedCredentialsProvider, primitives::ByteStream, Client};
+use aws_sdk_s3::{
+  config::SharedCredentialsProvider, presigning::PresigningConfig, primitives::ByteStream, Client,
+};
 use megacommerce_proto::{Config, ConfigFile};
 use megacommerce_shared::models::{
   errors::{BoxedErr, ErrorType, InternalError},
   r_lock::RLock,
 };
 
+/// The metadata of a stored object, as returned by a HEAD request
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectHead {
+  pub size: u64,
+  pub content_type: String,
+}
+
+/// A presigned PUT request the client sends the object with directly to the bucket
+#[derive(Debug, Clone)]
+pub struct PresignedUpload {
+  pub url: String,
+  /// The signed headers, the client must send them as they are
+  pub headers: HashMap<String, String>,
+}
+
 #[derive(Debug)]
 pub struct ObjectStorage {
   client: Client,
@@ -94,6 +113,70 @@ impl ObjectStorage {
     Ok(data.to_vec())
   }
 
+  /// Downloads the bytes in [start, end] (inclusive) only, e.g. to sniff the header of a file
+  pub async fn download_file_range(
+    &self,
+    key: &str,
+    start: u64,
+    end: u64,
+  ) -> Result<Vec<u8>, BoxedErr> {
+    let full_key = format!("{}{}", self.config_file.amazon_s3_path_prefix(), key);
+    let response = self
+      .client
+      .get_object()
+      .bucket(self.config_file.amazon_s3_bucket())
+      .key(full_key)
+      .range(format!("bytes={}-{}", start, end))
+      .send()
+      .await?;
+    let data = response.body.collect().await?.into_bytes();
+    Ok(data.to_vec())
+  }
+
+  /// Returns None if the object doesn't exist
+  pub async fn file_head(&self, key: &str) -> Result<Option<ObjectHead>, BoxedErr> {
+    let full_key = format!("{}{}", self.config_file.amazon_s3_path_prefix(), key);
+    let result = self
+      .client
+      .head_object()
+      .bucket(self.config_file.amazon_s3_bucket())
+      .key(full_key)
+      .send()
+      .await;
+
+    match result {
+      Ok(head) => Ok(Some(ObjectHead {
+        size: head.content_length().unwrap_or_default().max(0) as u64,
+        content_type: head.content_type().unwrap_or_default().to_string(),
+      })),
+      Err(err) if err.as_service_error().map(|e| e.is_not_found()).unwrap_or(false) => Ok(None),
+      Err(err) => Err(Box::new(err)),
+    }
+  }
+
+  /// Presigns a PUT of exactly `content_length` bytes of `content_type` to the key,
+  /// the bucket rejects the upload if the client sends anything else
+  pub async fn presigned_upload(
+    &self,
+    key: &str,
+    content_type: &str,
+    content_length: u64,
+    expires_in: Duration,
+  ) -> Result<PresignedUpload, BoxedErr> {
+    let request = self
+      .client
+      .put_object()
+      .bucket(self.config_file.amazon_s3_bucket())
+      .key(format!("{}{}", self.config_file.amazon_s3_path_prefix(), key))
+      .content_type(content_type)
+      .content_length(content_length as i64)
+      .presigned(PresigningConfig::expires_in(expires_in)?)
+      .await?;
+
+    let headers = request.headers().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    Ok(PresignedUpload { url: request.uri().to_string(), headers })
+  }
+
   pub async fn delete_file(&self, key: &str) -> Result<(), Box<dyn std::error::Error>> {
     let full_key = format!("{}{}", self.config_file.amazon_s3_path_prefix(), key);
 
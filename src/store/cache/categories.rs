@@ -9,7 +9,10 @@ use megacommerce_shared::{models::errors::BoxedErr, store::errors::handle_db_err
 use serde_json::from_value;
 use sqlx::query;
 
-use crate::store::cache::Cache;
+use crate::{
+  models::products_facets::{product_facets_schema, ProductFacetSchema},
+  store::cache::Cache,
+};
 
 impl Cache {
   pub fn category_data(&self, category_name: &str) -> Option<Arc<Category>> {
@@ -35,6 +38,26 @@ impl Cache {
     })
   }
 
+  /// The facets of the given subcategories (all of the category's ones if empty),
+  /// an unknown category or subcategory has none
+  pub fn category_facets(
+    &self,
+    category_id: &str,
+    subcategory_ids: &[String],
+  ) -> Vec<ProductFacetSchema> {
+    let subs_guard = match self.subcategories_data.get(category_id) {
+      Some(subs) => subs,
+      None => return vec![],
+    };
+
+    let subs: Vec<Arc<Subcategory>> = subs_guard
+      .iter()
+      .filter(|sub| subcategory_ids.is_empty() || subcategory_ids.contains(sub.key()))
+      .map(|sub| sub.value().clone())
+      .collect();
+    product_facets_schema(subs.iter().map(|sub| sub.as_ref()))
+  }
+
   pub(super) async fn categories_init(&self) -> Result<(), BoxedErr> {
     let db = &*self.db.get().await;
     let rows = query!("SELECT id, name, image, subcategories, translations FROM categories")
@@ -3,22 +3,89 @@ pub mod dbstore;
 use megacommerce_proto::{
   BestSellingProductListItem, BigDiscountProductListItem, CategoryNavbarResponseData,
   HeroProductsResponseData, NewlyAddedProductListItem, Product, ProductDetailsResponseData,
-  ProductListItem, ProductsCategoryItem, ProductSnapshot, ProductSnapshotRequest,
-  ProductToLikeListItem,
+  ProductListItem, ProductRating, ProductReview, ProductsCategoryItem, ProductSnapshot,
+  ProductSnapshotRequest, ProductToLikeListItem, RelatedProductListItem,
 };
 use megacommerce_shared::{models::context::Context, store::errors::DBError};
-use std::{fmt, sync::Arc};
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use crate::models::{
+  best_selling::BestSellingWindow,
+  inventory::{InventoryOutcome, ReservationStatus},
+  pagination::{KeysetPage, ListTotal, PageCursor},
+  price_quote::VariantStock,
+  product_events::ProductEvent,
+  product_reviews::ProductReviewStatus,
+  products::ProductStatus,
+  products_facets::{ProductFacetCount, ProductFacetFilter},
+  products_search::ProductsSearchFilters,
+  recommendations::{RecommendationCandidates, RecommendationWeights},
+  related_products::RelatedProductsWeights,
+  sellers::SellerProfile,
+};
+use crate::server::object_storage::ObjectStorage;
 
 #[tonic::async_trait]
 pub trait ProductsStore: fmt::Debug + Send + Sync {
-  async fn product_create(&self, ctx: Arc<Context>, product: &Product) -> Result<(), DBError>;
+  async fn product_create(
+    &self,
+    ctx: Arc<Context>,
+    product: &Product,
+    attributes: &[(String, String)],
+  ) -> Result<(), DBError>;
+  async fn product_get(&self, ctx: Arc<Context>, id: &str) -> Result<Product, DBError>;
+  async fn product_update(
+    &self,
+    ctx: Arc<Context>,
+    product: &Product,
+    expected_version: u32,
+    previous_status: &str,
+  ) -> Result<u32, DBError>;
+  async fn product_status_update(
+    &self,
+    ctx: Arc<Context>,
+    id: &str,
+    from: ProductStatus,
+    to: ProductStatus,
+    reason: &str,
+  ) -> Result<(), DBError>;
+  async fn product_media_uploads_add(
+    &self,
+    ctx: Arc<Context>,
+    product_id: &str,
+    keys: &[String],
+  ) -> Result<(), DBError>;
+  async fn product_media_uploads_recorded(
+    &self,
+    ctx: Arc<Context>,
+    product_id: &str,
+    keys: &[String],
+  ) -> Result<Vec<String>, DBError>;
+  async fn product_media_uploads_reserved(
+    &self,
+    ctx: Arc<Context>,
+    product_id: &str,
+    prefix: &str,
+  ) -> Result<bool, DBError>;
+  async fn product_media_uploads_stale(
+    &self,
+    ctx: Arc<Context>,
+    before: u64,
+    limit: i64,
+  ) -> Result<Vec<(String, String)>, DBError>;
+  async fn product_media_uploads_release(
+    &self,
+    ctx: Arc<Context>,
+    product_id: &str,
+    keys: &[String],
+    storage: &ObjectStorage,
+  ) -> Result<(), DBError>;
   async fn products_to_like(
     &self,
     ctx: Arc<Context>,
-    page: u32,
-    last_id: &str,
+    cursor: Option<&PageCursor>,
     limit: i64,
-  ) -> Result<Vec<ProductToLikeListItem>, DBError>;
+  ) -> Result<KeysetPage<ProductToLikeListItem>, DBError>;
   async fn product_snapshot(
     &self,
     ctx: Arc<Context>,
@@ -53,17 +120,172 @@ pub trait ProductsStore: fmt::Debug + Send + Sync {
     ctx: Arc<Context>,
     category_id: &str,
     subcategory_ids: &[String],
-    page: u32,
-    last_id: &str,
+    cursor: Option<&PageCursor>,
     limit: i64,
     sort_by: Option<&str>,
     sort_direction: Option<&str>,
-  ) -> Result<Vec<ProductsCategoryItem>, DBError>;
+    facets: &[ProductFacetFilter],
+    in_stock: bool,
+  ) -> Result<KeysetPage<ProductsCategoryItem>, DBError>;
+  async fn products_category_facets(
+    &self,
+    ctx: Arc<Context>,
+    category_id: &str,
+    subcategory_ids: &[String],
+    names: &[String],
+    filters: &[ProductFacetFilter],
+    in_stock: bool,
+  ) -> Result<Vec<ProductFacetCount>, DBError>;
+  async fn products_category_total(
+    &self,
+    ctx: Arc<Context>,
+    category_id: &str,
+    subcategory_ids: &[String],
+    facets: &[ProductFacetFilter],
+    in_stock: bool,
+    exact_max: u64,
+  ) -> Result<ListTotal, DBError>;
+  async fn products_search(
+    &self,
+    ctx: Arc<Context>,
+    filters: &ProductsSearchFilters,
+    cursor: Option<&PageCursor>,
+    limit: i64,
+  ) -> Result<KeysetPage<ProductsCategoryItem>, DBError>;
+  async fn products_search_total(
+    &self,
+    ctx: Arc<Context>,
+    filters: &ProductsSearchFilters,
+    exact_max: u64,
+  ) -> Result<ListTotal, DBError>;
   async fn products_list(
     &self,
     ctx: Arc<Context>,
-    page: u32,
-    last_id: &str,
+    cursor: Option<&PageCursor>,
+    limit: i64,
+  ) -> Result<KeysetPage<ProductListItem>, DBError>;
+  async fn product_review_create(
+    &self,
+    ctx: Arc<Context>,
+    review: &ProductReview,
+  ) -> Result<ProductReview, DBError>;
+  async fn product_review_get(&self, ctx: Arc<Context>, id: &str) -> Result<ProductReview, DBError>;
+  async fn product_reviews_list(
+    &self,
+    ctx: Arc<Context>,
+    product_id: &str,
+    verified_only: bool,
+    cursor: Option<&PageCursor>,
+    limit: i64,
+  ) -> Result<KeysetPage<ProductReview>, DBError>;
+  async fn product_review_moderate(
+    &self,
+    ctx: Arc<Context>,
+    id: &str,
+    from: ProductReviewStatus,
+    to: ProductReviewStatus,
+    reason: &str,
+  ) -> Result<(), DBError>;
+  async fn product_rating(
+    &self,
+    ctx: Arc<Context>,
+    product_id: &str,
+  ) -> Result<ProductRating, DBError>;
+  async fn seller_profiles(
+    &self,
+    ctx: Arc<Context>,
+    ids: &[String],
+  ) -> Result<HashMap<String, SellerProfile>, DBError>;
+  async fn products_sales_expire(
+    &self,
+    ctx: Arc<Context>,
+    now: u64,
+    limit: i64,
+  ) -> Result<u64, DBError>;
+  async fn product_variant_stock(
+    &self,
+    ctx: Arc<Context>,
+    product_id: &str,
+    variant_id: &str,
+  ) -> Result<VariantStock, DBError>;
+  async fn inventory_reserve(
+    &self,
+    ctx: Arc<Context>,
+    key: &str,
+    product_id: &str,
+    variant_id: &str,
+    quantity: u32,
+    user_id: &str,
+  ) -> Result<InventoryOutcome, DBError>;
+  async fn inventory_settle(
+    &self,
+    ctx: Arc<Context>,
+    key: &str,
+    reservation_id: &str,
+    to: ReservationStatus,
+  ) -> Result<InventoryOutcome, DBError>;
+  async fn inventory_restock(
+    &self,
+    ctx: Arc<Context>,
+    key: &str,
+    product_id: &str,
+    variant_id: &str,
+    quantity: u32,
+    low_stock_threshold: Option<u32>,
+  ) -> Result<InventoryOutcome, DBError>;
+  async fn inventory_reservations_expire(
+    &self,
+    ctx: Arc<Context>,
+    before: u64,
+    limit: i64,
+  ) -> Result<u64, DBError>;
+  async fn best_selling_rankings_refresh(
+    &self,
+    ctx: Arc<Context>,
+    now: u64,
+    size: i64,
+  ) -> Result<u64, DBError>;
+  async fn best_selling_ranking(
+    &self,
+    ctx: Arc<Context>,
+    window: BestSellingWindow,
+    category_id: Option<&str>,
+    cursor: Option<&PageCursor>,
+    limit: i64,
+  ) -> Result<KeysetPage<BestSellingProductListItem>, DBError>;
+  async fn product_similarities_refresh(
+    &self,
+    ctx: Arc<Context>,
+    since: u64,
+    now: u64,
+    per_product: i64,
+  ) -> Result<u64, DBError>;
+  async fn product_recommendation_snapshots_expire(
+    &self,
+    ctx: Arc<Context>,
+    before: u64,
+  ) -> Result<u64, DBError>;
+  async fn products_recommended(
+    &self,
+    ctx: Arc<Context>,
+    user_id: &str,
+    since: u64,
+    weights: RecommendationWeights,
+    candidates: RecommendationCandidates,
+    cursor: Option<&PageCursor>,
+    limit: i64,
+  ) -> Result<KeysetPage<ProductToLikeListItem>, DBError>;
+  async fn product_events_insert(
+    &self,
+    ctx: Arc<Context>,
+    events: &[ProductEvent],
+  ) -> Result<u64, DBError>;
+  async fn related_products(
+    &self,
+    ctx: Arc<Context>,
+    product_id: &str,
+    weights: RelatedProductsWeights,
+    pool: i64,
     limit: i64,
-  ) -> Result<Vec<ProductListItem>, DBError>;
+  ) -> Result<Vec<RelatedProductListItem>, DBError>;
 }
@@ -1,31 +1,54 @@
 mod best_selling_products;
+mod best_selling_rankings;
 mod big_discount_products;
 mod category_navbar;
 mod hero_products;
+mod inventory;
+mod job_lock;
+mod keyset;
+mod list_total;
 mod newly_added_products;
 mod product_create;
 mod product_details;
+mod product_events;
+mod product_get;
+mod product_media_uploads;
+mod product_recommendations;
+mod product_reviews;
+mod product_sales;
 mod product_snapshot;
+mod product_status_update;
+mod product_update;
+mod product_variant_stock;
 mod products_category;
+mod products_category_facets;
 mod products_list;
+mod products_search;
 mod products_to_like;
+mod related_products;
 mod router;
+mod seller_profiles;
 
 use megacommerce_shared::models::r_lock::RLock;
 use sqlx::{Pool, Postgres};
 
+use crate::models::variant_selection::VariantSelection;
+
 #[derive(Debug)]
 pub struct ProductsStoreImpl {
   pub(crate) db: RLock<Pool<Postgres>>,
+  /// How the lists choose the variant a product is shown with
+  pub(crate) variant_selection: VariantSelection,
 }
 
 #[derive(Debug)]
 pub struct ProductsStoreImplArgs {
   pub db: RLock<Pool<Postgres>>,
+  pub variant_selection: VariantSelection,
 }
 
 impl ProductsStoreImpl {
   pub fn new(args: ProductsStoreImplArgs) -> Self {
-    Self { db: args.db }
+    Self { db: args.db, variant_selection: args.variant_selection }
   }
 }
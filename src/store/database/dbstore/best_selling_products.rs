@@ -1,89 +1,22 @@
 use std::sync::Arc;
 
-use megacommerce_proto::{
-  BestSellingProductListItem, ProductMedia, ProductMediaImage, ProductOffer, ProductOfferVariant,
-};
-use megacommerce_shared::{
-  models::{
-    context::Context,
-    errors::{BoxedErr, ErrorType},
-  },
-  store::errors::DBError,
+use megacommerce_proto::BestSellingProductListItem;
+use megacommerce_shared::{models::context::Context, store::errors::DBError};
+
+use crate::{
+  models::best_selling::BestSellingWindow,
+  store::database::dbstore::{best_selling_rankings::best_selling_ranking, ProductsStoreImpl},
 };
-use rand::Rng;
-use serde_json::from_value;
 
-use crate::store::database::dbstore::ProductsStoreImpl;
+/// How many products the home page shows
+const BEST_SELLING_PRODUCTS_LIMIT: i64 = 6;
 
-// TODO: fetch the actual rating
+/// The first products of the weekly ranking over all the categories
 pub(super) async fn best_selling_products(
   s: &ProductsStoreImpl,
-  _: Arc<Context>,
+  ctx: Arc<Context>,
 ) -> Result<Vec<BestSellingProductListItem>, DBError> {
-  let path = "products.store.best_selling_products".to_string();
-  let de = |err: BoxedErr, msg: &str, err_type: Option<ErrorType>| {
-    DBError::new(
-      err_type.unwrap_or(ErrorType::DBSelectError),
-      err,
-      msg.to_string(),
-      &path,
-      "".to_string(),
-    )
-  };
-
-  let db = &*s.db.get().await;
-
-  let rows = sqlx::query!(
-    r#"
-    SELECT
-      ii.quantity_reserved,
-      p.id,
-      p.title,
-      p.offer,
-      p.media
-    FROM
-      inventory_items AS ii
-    INNER JOIN products AS p ON ii.product_id = p.id
-    ORDER BY ii.quantity_reserved DESC LIMIT 6
-  "#
-  )
-  .fetch_all(db)
-  .await
-  .map_err(|err| de(Box::new(err), "failed to select inventory items", None))?;
-
-  let mut best_sellers = Vec::new();
-
-  for row in rows {
-    let offer_data: ProductOffer = from_value(row.offer).map_err(|err| {
-      de(Box::new(err), "failed to deserialize product's offer", Some(ErrorType::JsonUnmarshal))
-    })?;
-    let media: ProductMedia = from_value(row.media).map_err(|err| {
-      de(Box::new(err), "failed to deserialize product's media", Some(ErrorType::JsonUnmarshal))
-    })?;
-
-    let offer_vec: Vec<(String, ProductOfferVariant)> = offer_data.offer.into_iter().collect();
-    let (variant_id, offer) = offer_vec.get(0).unwrap();
-
-    let images_data: Vec<(String, ProductMediaImage)> =
-      media.media.get(variant_id).unwrap().images.clone().into_iter().collect();
-    let (_, image) = images_data.get(0).unwrap();
-
-    let price =
-      offer.price.parse::<f64>().expect(&format!("{}: Should convert price string to f64", path));
-    best_sellers.push(BestSellingProductListItem {
-      id: row.id,
-      variant_id: variant_id.to_string(),
-      title: row.title,
-      image: image.url.clone(),
-      price_cents: (price * 100.0).round() as i64,
-      sale_price_cents: offer
-        .sale_price
-        .as_ref()
-        .and_then(|sp| sp.parse::<f64>().ok().and_then(|f| Some((f * 100.0).round() as i64))),
-      rating: rand::rng().random_range(10..49),
-      sold_count: row.quantity_reserved as u32,
-    });
-  }
-
-  Ok(best_sellers)
+  let window = BestSellingWindow::default();
+  let page = best_selling_ranking(s, ctx, window, None, None, BEST_SELLING_PRODUCTS_LIMIT).await?;
+  Ok(page.items)
 }
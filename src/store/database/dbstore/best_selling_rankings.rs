@@ -0,0 +1,204 @@
+use std::sync::Arc;
+
+use megacommerce_proto::{BestSellingProductListItem, ProductMedia, ProductOffer};
+use megacommerce_shared::{
+  models::{
+    context::Context,
+    errors::{BoxedErr, ErrorType},
+  },
+  store::errors::DBError,
+  utils::time::time_get_millis,
+};
+use serde_json::{from_value, Value};
+use sqlx::{FromRow, QueryBuilder};
+
+use crate::{
+  models::{
+    best_selling::BestSellingWindow,
+    inventory::variants_inventory,
+    pagination::{KeysetPage, PageCursor, PageCursorKey, PagePosition},
+    product_reviews::product_rating_tenths,
+    products::variant_card_image,
+    variant_selection::display_variant,
+  },
+  store::database::dbstore::{
+    inventory::VARIANTS_INVENTORY_SQL,
+    job_lock::job_try_lock,
+    keyset::{keyset_page, KeysetOrder},
+    ProductsStoreImpl,
+  },
+};
+
+/// The ranking of all the categories, stored under an empty category
+const ALL_CATEGORIES: &str = "";
+
+#[derive(FromRow)]
+struct RankingRow {
+  id: String,
+  title: String,
+  offer: Value,
+  media: Value,
+  rating_sum: i64,
+  rating_count: i64,
+  currency_code: String,
+  units_sold: i64,
+  rank: i64,
+  stock: Option<Value>,
+}
+
+/// Recomputes the rankings of every window from the sales events, overall and per category,
+/// keeping the first `size` products of each. All the rankings are replaced in a single
+/// transaction, so readers see either the previous or the new ones, never a mix of both.
+/// Another replica refreshing at the same time skips its refresh and returns 0.
+pub(super) async fn best_selling_rankings_refresh(
+  s: &ProductsStoreImpl,
+  _ctx: Arc<Context>,
+  now: u64,
+  size: i64,
+) -> Result<u64, DBError> {
+  let path = "products.store.best_selling_rankings_refresh";
+  let de = |err: BoxedErr, err_type: ErrorType, msg: &str| {
+    DBError::new(err_type, err, msg, path, "".to_string())
+  };
+
+  let db = &*s.db.get().await;
+  let mut ranked = 0;
+  let mut tx = db.begin().await.map_err(|err| {
+    de(Box::new(err), ErrorType::DBConnectionError, "failed to begin transaction")
+  })?;
+
+  let locked = job_try_lock(&mut *tx, path).await.map_err(|err| {
+    de(Box::new(err), ErrorType::DBSelectError, "failed to lock the rankings refresh")
+  })?;
+  if !locked {
+    return Ok(0);
+  }
+
+  for window in BestSellingWindow::ALL {
+    sqlx::query("DELETE FROM best_selling_rankings WHERE time_window = $1")
+      .bind(window.as_str())
+      .execute(&mut *tx)
+      .await
+      .map_err(|err| {
+        de(Box::new(err), ErrorType::DBDeleteError, "failed to delete the previous rankings")
+      })?;
+
+    let inserted = sqlx::query(
+      r#"
+      WITH sold AS (
+        SELECT e.product_id, p.category, SUM(e.quantity)::BIGINT AS units_sold
+        FROM sales_events AS e
+        INNER JOIN products AS p ON p.id = e.product_id AND p.status = 'published'
+        WHERE e.created_at >= $2
+        GROUP BY e.product_id, p.category
+      ), ranked AS (
+        SELECT
+          scoped.category,
+          scoped.product_id,
+          scoped.units_sold,
+          ROW_NUMBER() OVER (
+            PARTITION BY scoped.category ORDER BY scoped.units_sold DESC, scoped.product_id DESC
+          ) AS rank
+        FROM (
+          SELECT $3 AS category, product_id, units_sold FROM sold
+          UNION ALL
+          SELECT category, product_id, units_sold FROM sold
+        ) AS scoped
+      )
+      INSERT INTO best_selling_rankings (
+        time_window, category, product_id, units_sold, rank, computed_at
+      )
+      SELECT $1, category, product_id, units_sold, rank, $4 FROM ranked WHERE rank <= $5
+      "#,
+    )
+    .bind(window.as_str())
+    .bind(window.since(now) as i64)
+    .bind(ALL_CATEGORIES)
+    .bind(now as i64)
+    .bind(size)
+    .execute(&mut *tx)
+    .await
+    .map_err(|err| de(Box::new(err), ErrorType::DBInsertError, "failed to insert the rankings"))?;
+    ranked += inserted.rows_affected();
+  }
+
+  tx.commit()
+    .await
+    .map_err(|err| de(Box::new(err), ErrorType::DBUpdateError, "failed to commit transaction"))?;
+
+  Ok(ranked)
+}
+
+/// A page of the products ranked by their units sold over the window, in the category or
+/// over all of them. The products are in the order of the last refresh of the rankings.
+pub(super) async fn best_selling_ranking(
+  s: &ProductsStoreImpl,
+  _ctx: Arc<Context>,
+  window: BestSellingWindow,
+  category_id: Option<&str>,
+  cursor: Option<&PageCursor>,
+  limit: i64,
+) -> Result<KeysetPage<BestSellingProductListItem>, DBError> {
+  let path = "products.store.best_selling_ranking";
+  let db = &*s.db.get().await;
+
+  let order = KeysetOrder { key: Some("r.rank"), id: "r.product_id", descending: false };
+
+  let mut query_builder = QueryBuilder::new(format!(
+    r#"
+    SELECT
+      p.id,
+      p.title,
+      p.offer,
+      p.media,
+      p.rating_sum,
+      p.rating_count::BIGINT AS rating_count,
+      p.currency_code,
+      r.units_sold,
+      r.rank,
+      {VARIANTS_INVENTORY_SQL} AS stock
+    FROM best_selling_rankings AS r
+    INNER JOIN products AS p ON p.id = r.product_id
+    WHERE p.status = 'published' AND r.time_window = "#
+  ));
+  query_builder.push_bind(window.as_str());
+  query_builder.push(" AND r.category = ");
+  query_builder.push_bind(category_id.unwrap_or(ALL_CATEGORIES).to_string());
+  order.push_condition(&mut query_builder, cursor);
+  order.push_order_by(&mut query_builder, cursor);
+  // one more row than asked for, which tells if there's a next page
+  query_builder.push(" LIMIT ");
+  query_builder.push_bind(limit + 1);
+
+  let rows = query_builder.build_query_as::<RankingRow>().fetch_all(db).await.map_err(|err| {
+    let msg = "failed to select the best selling products";
+    DBError::new(ErrorType::DBSelectError, Box::new(err), msg, path, "".to_string())
+  })?;
+
+  let (policy, now) = (s.variant_selection, time_get_millis());
+  let position = |row: &RankingRow| PagePosition {
+    key: Some(PageCursorKey::Int(row.rank)),
+    id: row.id.clone(),
+  };
+  let item = |row: RankingRow| {
+    let offer: ProductOffer = from_value(row.offer).ok()?;
+    let media: ProductMedia = from_value(row.media).ok()?;
+    let stock = variants_inventory(row.stock);
+    let display = display_variant(policy, &offer, &stock, &row.currency_code, now)?;
+
+    Some(BestSellingProductListItem {
+      id: row.id,
+      variant_id: display.id.to_string(),
+      title: row.title,
+      image: variant_card_image(&media, display.id),
+      price_cents: display.prices.price.minor_units(),
+      sale_price_cents: display.prices.sale_price.map(|p| p.minor_units()),
+      rating: product_rating_tenths(row.rating_sum, row.rating_count),
+      sold_count: row.units_sold as u32,
+      currency_code: row.currency_code,
+      display_price: None,
+    })
+  };
+
+  Ok(keyset_page(rows, cursor, limit, position, item))
+}
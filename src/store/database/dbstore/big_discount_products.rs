@@ -1,6 +1,5 @@
 use std::sync::Arc;
 
-use bigdecimal::num_traits::ToPrimitive;
 use megacommerce_proto::{BigDiscountProductListItem, ProductMedia};
 use megacommerce_shared::{
   models::{
@@ -8,10 +7,33 @@ use megacommerce_shared::{
     errors::{BoxedErr, ErrorType},
   },
   store::errors::DBError,
+  utils::time::time_get_millis,
 };
-use serde_json::from_value;
+use serde_json::{from_value, Value};
+use sqlx::FromRow;
 
-use crate::store::database::dbstore::ProductsStoreImpl;
+use crate::{
+  models::{
+    money::{SaleWindow, VariantPrice},
+    products::product_image_card_url,
+  },
+  store::database::dbstore::ProductsStoreImpl,
+};
+
+#[derive(FromRow)]
+struct BigDiscountRow {
+  id: String,
+  title: String,
+  media: Option<Value>,
+  variant_id: String,
+  price: Option<String>,
+  sale_price: Option<String>,
+  has_sale_price: Option<bool>,
+  sale_price_start: Option<i64>,
+  sale_price_end: Option<i64>,
+  currency_code: String,
+  sold_count: i64,
+}
 
 pub(super) async fn big_discount_products(
   s: &ProductsStoreImpl,
@@ -30,24 +52,34 @@ pub(super) async fn big_discount_products(
 
   let db = &*s.db.get().await;
 
-  let rows = sqlx::query!(
+  let rows: Vec<BigDiscountRow> = sqlx::query_as(
     r#"
       WITH variants AS (
         SELECT
           p.id,
           p.title,
           p.media,
+          p.currency_code,
           variant.key as variant_id,
-          (variant.value ->> 'price')::numeric as price,
-          (variant.value ->> 'sale_price')::numeric as sale_price,
-          ((variant.value ->> 'price')::numeric - (variant.value ->> 'sale_price')::numeric) / (variant.value ->> 'price')::numeric as discount_percentage
+          variant.value ->> 'price' as price,
+          variant.value ->> 'sale_price' as sale_price,
+          (variant.value ->> 'has_sale_price')::boolean as has_sale_price,
+          (variant.value ->> 'sale_price_start')::bigint as sale_price_start,
+          (variant.value ->> 'sale_price_end')::bigint as sale_price_end,
+          ((variant.value ->> 'price')::numeric - (variant.value ->> 'sale_price')::numeric)
+            / (variant.value ->> 'price')::numeric as discount_percentage
         FROM products p, 
         jsonb_each(p.offer -> 'offer') as variant
         WHERE 
-              (variant.value ->> 'has_sale_price')::boolean = true 
-              AND (
-                (variant.value ->> 'sale_price_end') IS NULL 
-                OR (variant.value ->> 'sale_price_end')::bigint > EXTRACT(EPOCH FROM NOW()) * 1000
+              (variant.value ->> 'has_sale_price')::boolean = true
+              AND (variant.value ->> 'sale_price')::numeric > 0
+              AND COALESCE(
+                (variant.value ->> 'sale_price_start')::bigint <= EXTRACT(EPOCH FROM NOW()) * 1000,
+                TRUE
+              )
+              AND COALESCE(
+                (variant.value ->> 'sale_price_end')::bigint > EXTRACT(EPOCH FROM NOW()) * 1000,
+                TRUE
               )
       )
       SELECT
@@ -57,31 +89,35 @@ pub(super) async fn big_discount_products(
           v.variant_id,
           v.price,
           v.sale_price,
-          v.discount_percentage,
-          COALESCE(ii.quantity_reserved, 0) AS sold_count
+          v.has_sale_price,
+          v.sale_price_start,
+          v.sale_price_end,
+          v.currency_code,
+          COALESCE(ii.quantity_sold, 0)::BIGINT AS sold_count
       FROM variants AS v
       LEFT JOIN inventory_items AS ii ON ii.variant_id = v.variant_id
       ORDER BY v.discount_percentage DESC
       LIMIT 6
-    "#
+    "#,
   )
   .fetch_all(db)
   .await
   .map_err(|err| de(Box::new(err), "failed to select big discount products", None))?;
 
   let mut big_discount_products = Vec::new();
+  let now = time_get_millis();
 
   for row in rows {
     let media: ProductMedia = from_value(row.media.unwrap_or_default()).map_err(|err| {
       de(Box::new(err), "failed to deserialize product's media", Some(ErrorType::JsonUnmarshal))
     })?;
 
-    let variant_id = row.variant_id.unwrap_or_default();
+    let variant_id = row.variant_id;
 
     // Get the first image for this variant
     let image_url = if let Some(variant_media) = media.media.get(&variant_id) {
       if let Some((_, first_image)) = variant_media.images.iter().next() {
-        first_image.url.clone()
+        product_image_card_url(first_image)
       } else {
         "".to_string()
       }
@@ -89,23 +125,30 @@ pub(super) async fn big_discount_products(
       "".to_string()
     };
 
-    let price = row.price.unwrap_or_default().to_f64().unwrap_or(0.0);
-    let sale_price = row.sale_price.unwrap_or_default().to_f64().unwrap_or(0.0);
-    let price_cents = (price * 100.0).round() as u32;
-    let discount_price_cents = (sale_price * 100.0).round() as u32;
-
-    let discount_percentage =
-      (row.discount_percentage.unwrap_or_default().to_f64().unwrap_or(0.0) * 100.0).round() as u32;
+    let currency_code = row.currency_code;
+    let price = row.price.unwrap_or_default();
+    let sale_price = row.sale_price.as_deref().filter(|_| row.has_sale_price.unwrap_or_default());
+    let window = SaleWindow {
+      start: row.sale_price_start.map(|start| start as u64),
+      end: row.sale_price_end.map(|end| end as u64),
+    };
+    let prices = VariantPrice::parse(&price, sale_price, &currency_code)
+      .map(|prices| prices.at(window, now))
+      .map_err(|err| {
+        de(Box::new(err), "failed to parse product's price", Some(ErrorType::InvalidNumber))
+      })?;
 
     big_discount_products.push(BigDiscountProductListItem {
-      id: row.id.unwrap_or_default(),
+      id: row.id,
       variant_id: variant_id.to_string(),
-      title: row.title.unwrap_or_default(),
+      title: row.title,
       image: image_url,
-      price_cents,
-      discount_price_cents,
-      discount_percentage,
-      sold_count: row.sold_count.unwrap_or_default() as u32,
+      price_cents: prices.price.minor_units(),
+      discount_price_cents: prices.sale_price.map(|p| p.minor_units()).unwrap_or(0),
+      discount_percentage: prices.discount_percentage().unwrap_or(0),
+      sold_count: row.sold_count as u32,
+      currency_code,
+      display_price: None,
     });
   }
 
@@ -3,7 +3,6 @@ use std::{io::ErrorKind, sync::Arc};
 
 use megacommerce_proto::{
   CategoryNavbarProductItem, CategoryNavbarResponseData, ProductMedia, ProductOffer,
-  ProductOfferVariant,
 };
 use megacommerce_shared::{
   models::{
@@ -11,10 +10,32 @@ use megacommerce_shared::{
     errors::{BoxedErr, ErrorType},
   },
   store::errors::DBError,
+  utils::time::time_get_millis,
 };
-use serde_json::from_value;
+use serde_json::{from_value, Value};
+use sqlx::FromRow;
 
-use crate::store::database::dbstore::ProductsStoreImpl;
+use crate::{
+  models::{
+    best_selling::BestSellingWindow, inventory::variants_inventory, products::variant_card_image,
+    variant_selection::display_variant,
+  },
+  store::database::dbstore::{inventory::VARIANTS_INVENTORY_SQL, ProductsStoreImpl},
+};
+
+/// How many products the navbar of a subcategory shows
+const NAVBAR_PRODUCTS_MAX: i64 = 6;
+
+#[derive(FromRow)]
+struct NavbarRow {
+  id: String,
+  user_id: String,
+  title: String,
+  media: Value,
+  offer: Value,
+  currency_code: String,
+  stock: Option<Value>,
+}
 
 pub(super) async fn category_navbar(
   s: &ProductsStoreImpl,
@@ -85,33 +106,55 @@ pub(super) async fn category_navbar(
       )
     })?;
 
-  // Fetch random products from the category/subcategory (max 6)
-  // For now, using random ordering since ML integration is deferred
-  let product_rows = sqlx::query!(
+  // The subcategory's best sellers of the week, then its newest products, both read from
+  // bounded index scans, so the same products show until the rankings or the catalog change
+  let product_rows: Vec<NavbarRow> = sqlx::query_as(&format!(
     r#"
-    SELECT 
+    WITH picked AS (
+      (
+        SELECT r.product_id AS id, 0 AS source, r.rank AS position
+        FROM best_selling_rankings AS r
+        INNER JOIN products AS p ON p.id = r.product_id
+        WHERE r.time_window = $3 AND r.category = $1
+          AND p.subcategory = $2 AND p.status = 'published'
+        ORDER BY r.rank
+        LIMIT $4
+      )
+      UNION ALL
+      (
+        SELECT p.id, 1 AS source, 0::BIGINT AS position
+        FROM products AS p
+        WHERE p.category = $1 AND p.subcategory = $2 AND p.status = 'published'
+        ORDER BY p.id DESC
+        LIMIT $4
+      )
+    ), deduplicated AS (
+      SELECT DISTINCT ON (id) id, source, position FROM picked ORDER BY id, source, position
+    )
+    SELECT
       p.id,
+      p.user_id,
       p.title,
       p.media,
-      p.offer
-    FROM 
-      products AS p
-    WHERE 
-      p.category = $1 
-      AND p.subcategory = $2 
-      AND p.status = 'published'
-    ORDER BY 
-      RANDOM()
-    LIMIT 6
-    "#,
-    category_id,
-    subcategory_id
-  )
+      p.offer,
+      p.currency_code,
+      {VARIANTS_INVENTORY_SQL} AS stock
+    FROM deduplicated AS d
+    INNER JOIN products AS p ON p.id = d.id
+    ORDER BY d.source, d.position, d.id DESC
+    LIMIT $4
+    "#
+  ))
+  .bind(category_id)
+  .bind(subcategory_id)
+  .bind(BestSellingWindow::default().as_str())
+  .bind(NAVBAR_PRODUCTS_MAX)
   .fetch_all(db)
   .await
   .map_err(|err| de(Box::new(err), "failed to select products by category/subcategory", None))?;
 
   let mut recommended_products = Vec::new();
+  let (policy, now) = (s.variant_selection, time_get_millis());
 
   for row in product_rows {
     let offer_data: ProductOffer = from_value(row.offer).map_err(|err| {
@@ -121,34 +164,29 @@ pub(super) async fn category_navbar(
       de(Box::new(err), "failed to deserialize product's media", Some(ErrorType::JsonUnmarshal))
     })?;
 
-    // Get first variant (or random variant if multiple)
-    let offer_vec: Vec<(String, ProductOfferVariant)> = offer_data.offer.into_iter().collect();
-    let (variant_id, offer) = offer_vec.get(0).unwrap();
-
-    let images_data: Vec<(String, _)> =
-      media.media.get(variant_id).unwrap().images.clone().into_iter().collect();
-    let (_, image) = images_data.get(0).unwrap();
-
-    let price =
-      offer.price.parse::<f64>().expect(&format!("{}: Should convert price string to f64", path));
-    let sale_price = offer.sale_price.as_ref().and_then(|sp| sp.parse::<f64>().ok()).unwrap_or(0.0);
-
-    let price_cents = (price * 100.0).round() as u32;
-    let discount_price_cents = (sale_price * 100.0).round() as u32;
-
-    let discount_percentage =
-      if sale_price > 0.0 { ((price - sale_price) / price * 100.0).round() as u32 } else { 0 };
+    let stock = variants_inventory(row.stock);
+    let display = match display_variant(policy, &offer_data, &stock, &row.currency_code, now) {
+      Some(display) => display,
+      // no variant with a valid price, nothing to show
+      None => continue,
+    };
+    let (prices, level) = (display.prices, display.level);
 
-    // TODO: fetch actual seller name from users table
     recommended_products.push(CategoryNavbarProductItem {
       id: row.id,
-      variant_id: variant_id.to_string(),
+      variant_id: display.id.to_string(),
       title: row.title,
-      image: image.url.clone(),
-      price_cents,
-      discount_price_cents: if sale_price > 0.0 { Some(discount_price_cents) } else { None },
-      discount_percentage: if sale_price > 0.0 { Some(discount_percentage) } else { None },
-      sold_by: "Megacommerce".to_string(),
+      image: variant_card_image(&media, display.id),
+      price_cents: prices.price.minor_units(),
+      discount_price_cents: prices.sale_price.map(|p| p.minor_units()),
+      discount_percentage: prices.discount_percentage(),
+      seller_id: row.user_id,
+      sold_by: String::new(),
+      seller_rating: None,
+      currency_code: row.currency_code,
+      display_price: None,
+      in_stock: level.in_stock(),
+      low_stock: level.low_stock(),
     });
   }
 
@@ -11,11 +11,19 @@ use megacommerce_shared::{
     errors::{BoxedErr, ErrorType},
   },
   store::errors::DBError,
+  utils::time::time_get_millis,
 };
 use serde_json::{from_value, Value};
 use sqlx::FromRow;
 
-use crate::store::database::dbstore::ProductsStoreImpl;
+use crate::{
+  models::{
+    inventory::{variant_stock_level, variants_inventory},
+    money::VariantPrice,
+    products::variant_card_image,
+  },
+  store::database::dbstore::{inventory::VARIANTS_INVENTORY_SQL, ProductsStoreImpl},
+};
 
 #[derive(FromRow)]
 struct ProductRow {
@@ -23,6 +31,8 @@ struct ProductRow {
   title: String,
   media: Value,
   offer: Value,
+  currency_code: String,
+  stock: Option<Value>,
 }
 
 struct ProductAndVariantID<'a> {
@@ -35,6 +45,7 @@ struct ProductAndVariantID<'a> {
 fn process_slider_rows(
   rows: Vec<ProductRow>,
   slider_ids: &[ProductAndVariantID],
+  now: u64,
   de: impl Fn(BoxedErr, ErrorType, &str) -> DBError,
 ) -> Result<Vec<HeroProductListItem>, DBError> {
   // using  a HashMap for efficient O(1) lookups of variant_id by product_id
@@ -74,35 +85,28 @@ fn process_slider_rows(
       de(Box::new(err), ErrorType::JsonUnmarshal, "failed to deserialize product's media")
     })?;
 
-    let image = media
-      .media
-      .get(variant_id)
-      .and_then(|variant_media| variant_media.images.values().next())
-      .map(|img| img.url.clone())
-      .unwrap_or_default();
-
-    let price = offer_variant.price.parse::<f64>().unwrap_or_default();
-    let sale_price = offer_variant
-      .sale_price
-      .as_ref()
-      .map(|s| s.parse::<f64>().unwrap_or_default())
-      .unwrap_or(0.0);
+    let image = variant_card_image(&media, variant_id);
 
-    let price_cents = (price * 100.0).round() as u32;
-    let discount_price_cents = (sale_price * 100.0).round() as u32;
+    let prices = VariantPrice::from_offer(offer_variant, &pro.currency_code, now).map_err(|err| {
+      de(Box::new(err), ErrorType::InvalidNumber, "failed to parse the product's price")
+    })?;
 
-    // Corrected discount calculation
-    let discount_percentage =
-      if sale_price > 0.0 { ((price - sale_price) / price * 100.0).round() as u32 } else { 0 };
+    // the slider is curated, an out of stock variant stays on it but shows as such
+    let stock = variants_inventory(pro.stock);
+    let level = variant_stock_level(offer_variant, stock.get(variant_id));
 
     products.push(HeroProductListItem {
       id: pro.id,
       variant_id: variant_id.to_string(),
       title: pro.title,
       image,
-      price_cents,
-      discount_price_cents: Some(discount_price_cents),
-      discount_percentage: Some(discount_percentage),
+      price_cents: prices.price.minor_units(),
+      discount_price_cents: Some(prices.sale_price.map(|p| p.minor_units()).unwrap_or(0)),
+      discount_percentage: Some(prices.discount_percentage().unwrap_or(0)),
+      currency_code: pro.currency_code,
+      display_price: None,
+      in_stock: level.in_stock(),
+      low_stock: level.low_stock(),
     });
   }
 
@@ -147,27 +151,33 @@ pub(super) async fn hero_products(
     .collect();
 
   // Fetch product data for both sliders
-  let welcome_rows: Vec<ProductRow> =
-    sqlx::query_as("SELECT id, title, media, offer FROM products WHERE id = ANY($1::TEXT[])")
-      .bind(&welcome_slider_ids.iter().map(|row| row.product_id).collect::<Vec<&str>>())
-      .fetch_all(db)
-      .await
-      .map_err(|err| {
-        de(Box::new(err), ErrorType::DBSelectError, "failed to select welcome_slider products")
-      })?;
+  let products_query = format!(
+    r#"
+    SELECT p.id, p.title, p.media, p.offer, p.currency_code, {VARIANTS_INVENTORY_SQL} AS stock
+    FROM products AS p
+    WHERE p.id = ANY($1::TEXT[])
+    "#
+  );
+  let welcome_rows: Vec<ProductRow> = sqlx::query_as(&products_query)
+    .bind(&welcome_slider_ids.iter().map(|row| row.product_id).collect::<Vec<&str>>())
+    .fetch_all(db)
+    .await
+    .map_err(|err| {
+      de(Box::new(err), ErrorType::DBSelectError, "failed to select welcome_slider products")
+    })?;
 
-  let category_rows: Vec<ProductRow> =
-    sqlx::query_as("SELECT id, title, media, offer FROM products WHERE id = ANY($1::TEXT[])")
-      .bind(&category_slider_ids.iter().map(|row| row.product_id).collect::<Vec<&str>>())
-      .fetch_all(db)
-      .await
-      .map_err(|err| {
-        de(Box::new(err), ErrorType::DBSelectError, "failed to select category_slider products")
-      })?;
+  let category_rows: Vec<ProductRow> = sqlx::query_as(&products_query)
+    .bind(&category_slider_ids.iter().map(|row| row.product_id).collect::<Vec<&str>>())
+    .fetch_all(db)
+    .await
+    .map_err(|err| {
+      de(Box::new(err), ErrorType::DBSelectError, "failed to select category_slider products")
+    })?;
 
   // Process rows using the helper function
-  let welcome_products = process_slider_rows(welcome_rows, &welcome_slider_ids, &de)?;
-  let category_products = process_slider_rows(category_rows, &category_slider_ids, &de)?;
+  let now = time_get_millis();
+  let welcome_products = process_slider_rows(welcome_rows, &welcome_slider_ids, now, &de)?;
+  let category_products = process_slider_rows(category_rows, &category_slider_ids, now, &de)?;
 
   // Construct the final response
   let result = HeroProductsResponseData {
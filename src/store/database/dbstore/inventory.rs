@@ -0,0 +1,677 @@
+use std::sync::Arc;
+
+use megacommerce_proto::InventoryStock;
+use megacommerce_shared::{
+  models::{
+    context::Context,
+    errors::{BoxedErr, ErrorType},
+  },
+  store::errors::DBError,
+  utils::time::time_get_millis,
+};
+use sqlx::PgConnection;
+use ulid::Ulid;
+
+use crate::{
+  models::{
+    inventory::{
+      InventoryOperation, InventoryOutcome, ReservationStatus, StockLevel, VariantInventory,
+      INVENTORY_LOW_STOCK_THRESHOLD,
+    },
+    product_events::ProductEventType,
+  },
+  store::database::dbstore::ProductsStoreImpl,
+};
+
+/// The `inventory_items` rows of the product aliased `p`, as a jsonb object of variant ids to
+/// `{"reserved", "sold", "threshold"}`, see `variants_inventory`
+pub(super) const VARIANTS_INVENTORY_SQL: &str = r#"
+  (SELECT jsonb_object_agg(
+    si.variant_id,
+    jsonb_build_object(
+      'reserved', si.quantity_reserved,
+      'sold', si.quantity_sold,
+      'threshold', si.low_stock_threshold
+    )
+  ) FROM inventory_items AS si WHERE si.product_id = p.id)"#;
+
+/// The condition of the product aliased `p` having a variant with units left to order
+pub(super) const IN_STOCK_SQL: &str = r#"
+  EXISTS (
+    SELECT 1 FROM jsonb_each(p.offer->'offer') AS sv
+    LEFT JOIN inventory_items AS si ON si.product_id = p.id AND si.variant_id = sv.key
+    WHERE (sv.value->>'quantity')::BIGINT - COALESCE(si.quantity_reserved, 0) > 0
+  )"#;
+
+enum KeyClaim {
+  New,
+  /// The key was used for the same operation, with the variant and reservation it applied to
+  Replay(String, String, Option<String>),
+  Conflict,
+}
+
+fn no_rows(msg: &str, path: &str) -> DBError {
+  let err = std::io::Error::new(std::io::ErrorKind::NotFound, msg.to_string());
+  DBError::new(ErrorType::NoRows, Box::new(err), msg, path, "".to_string())
+}
+
+fn stock_of(
+  product_id: &str,
+  variant_id: &str,
+  on_hand: i64,
+  inventory: &VariantInventory,
+  reservation_id: Option<String>,
+) -> InventoryStock {
+  let available = (on_hand - inventory.reserved).max(0);
+  let threshold = inventory.threshold.unwrap_or(INVENTORY_LOW_STOCK_THRESHOLD);
+  let level = StockLevel::of(available, threshold);
+  InventoryStock {
+    product_id: product_id.to_string(),
+    variant_id: variant_id.to_string(),
+    quantity: on_hand,
+    reserved: inventory.reserved,
+    available,
+    low_stock_threshold: threshold,
+    in_stock: level.in_stock(),
+    low_stock: level.low_stock(),
+    reservation_id,
+    replayed: false,
+  }
+}
+
+/// Claims the idempotency key for the operation. A concurrent claim of the same key waits
+/// for this transaction, and then sees the key as used.
+async fn key_claim(
+  conn: &mut PgConnection,
+  key: &str,
+  operation: &InventoryOperation,
+  now: i64,
+) -> Result<KeyClaim, sqlx::Error> {
+  let fingerprint = operation.fingerprint();
+  let claimed = sqlx::query(
+    r#"
+    INSERT INTO inventory_operations (idempotency_key, fingerprint, created_at)
+    VALUES ($1, $2, $3)
+    ON CONFLICT (idempotency_key) DO NOTHING
+    "#,
+  )
+  .bind(key)
+  .bind(&fingerprint)
+  .bind(now)
+  .execute(&mut *conn)
+  .await?;
+  if claimed.rows_affected() == 1 {
+    return Ok(KeyClaim::New);
+  }
+
+  let (used_for, product_id, variant_id, reservation_id): (
+    String,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+  ) = sqlx::query_as(
+    r#"
+    SELECT fingerprint, product_id, variant_id, reservation_id
+    FROM inventory_operations WHERE idempotency_key = $1
+    "#,
+  )
+  .bind(key)
+  .fetch_one(&mut *conn)
+  .await?;
+
+  match (used_for == fingerprint, product_id, variant_id) {
+    (true, Some(product_id), Some(variant_id)) => {
+      Ok(KeyClaim::Replay(product_id, variant_id, reservation_id))
+    }
+    _ => Ok(KeyClaim::Conflict),
+  }
+}
+
+/// Records what the claimed key applied to, for the retries to answer with
+async fn key_applied(
+  conn: &mut PgConnection,
+  key: &str,
+  product_id: &str,
+  variant_id: &str,
+  reservation_id: Option<&str>,
+) -> Result<(), sqlx::Error> {
+  sqlx::query(
+    r#"
+    UPDATE inventory_operations SET product_id = $2, variant_id = $3, reservation_id = $4
+    WHERE idempotency_key = $1
+    "#,
+  )
+  .bind(key)
+  .bind(product_id)
+  .bind(variant_id)
+  .bind(reservation_id)
+  .execute(&mut *conn)
+  .await?;
+  Ok(())
+}
+
+/// Locks the product row, then the variant's inventory row (created on first use), always in
+/// this order so concurrent operations on a variant queue up instead of overselling.
+/// Returns the offer's quantity and the inventory, none if the variant doesn't exist.
+async fn variant_lock(
+  conn: &mut PgConnection,
+  product_id: &str,
+  variant_id: &str,
+  now: i64,
+) -> Result<Option<(i64, VariantInventory)>, sqlx::Error> {
+  let on_hand: Option<Option<i64>> = sqlx::query_scalar(
+    "SELECT (offer->'offer'->$2->>'quantity')::BIGINT FROM products WHERE id = $1 FOR UPDATE",
+  )
+  .bind(product_id)
+  .bind(variant_id)
+  .fetch_optional(&mut *conn)
+  .await?;
+  let on_hand = match on_hand.flatten() {
+    Some(on_hand) => on_hand,
+    None => return Ok(None),
+  };
+
+  sqlx::query(
+    r#"
+    INSERT INTO inventory_items (
+      product_id, variant_id, quantity_reserved, quantity_sold, updated_at
+    ) VALUES ($1, $2, 0, 0, $3)
+    ON CONFLICT (product_id, variant_id) DO NOTHING
+    "#,
+  )
+  .bind(product_id)
+  .bind(variant_id)
+  .bind(now)
+  .execute(&mut *conn)
+  .await?;
+
+  let (reserved, threshold): (i64, Option<i64>) = sqlx::query_as(
+    r#"
+    SELECT quantity_reserved::BIGINT, low_stock_threshold::BIGINT FROM inventory_items
+    WHERE product_id = $1 AND variant_id = $2
+    FOR UPDATE
+    "#,
+  )
+  .bind(product_id)
+  .bind(variant_id)
+  .fetch_one(&mut *conn)
+  .await?;
+
+  Ok(Some((on_hand, VariantInventory { reserved, threshold, ..Default::default() })))
+}
+
+/// The current stock of the variant, for answering a replayed operation
+async fn variant_stock(
+  conn: &mut PgConnection,
+  product_id: &str,
+  variant_id: &str,
+  reservation_id: Option<String>,
+) -> Result<InventoryStock, sqlx::Error> {
+  let (on_hand, reserved, threshold): (Option<i64>, Option<i64>, Option<i64>) = sqlx::query_as(
+    r#"
+    SELECT
+      (p.offer->'offer'->$2->>'quantity')::BIGINT,
+      ii.quantity_reserved::BIGINT,
+      ii.low_stock_threshold::BIGINT
+    FROM products AS p
+    LEFT JOIN inventory_items AS ii ON ii.product_id = p.id AND ii.variant_id = $2
+    WHERE p.id = $1
+    "#,
+  )
+  .bind(product_id)
+  .bind(variant_id)
+  .fetch_one(&mut *conn)
+  .await?;
+
+  let reserved = reserved.unwrap_or(0);
+  let inventory = VariantInventory { reserved, threshold, ..Default::default() };
+  Ok(stock_of(product_id, variant_id, on_hand.unwrap_or(0), &inventory, reservation_id))
+}
+
+/// Sets the offer's quantity of the variant, and bumps the product's version so an edit of
+/// the offer as it was can't overwrite the stock
+async fn offer_quantity_set(
+  conn: &mut PgConnection,
+  product_id: &str,
+  variant_id: &str,
+  quantity: i64,
+  now: i64,
+) -> Result<(), sqlx::Error> {
+  sqlx::query(
+    r#"
+    UPDATE products SET
+      offer = jsonb_set(offer, ARRAY['offer', $2, 'quantity'], to_jsonb($3::BIGINT)),
+      updated_at = $4,
+      version = version + 1
+    WHERE id = $1
+    "#,
+  )
+  .bind(product_id)
+  .bind(variant_id)
+  .bind(quantity)
+  .bind(now)
+  .execute(&mut *conn)
+  .await?;
+  Ok(())
+}
+
+/// Reserves units of the variant for an order of `user_id` (empty for a guest), if that
+/// many are available
+pub(super) async fn inventory_reserve(
+  s: &ProductsStoreImpl,
+  _ctx: Arc<Context>,
+  key: &str,
+  product_id: &str,
+  variant_id: &str,
+  quantity: u32,
+  user_id: &str,
+) -> Result<InventoryOutcome, DBError> {
+  let path = "products.store.inventory_reserve";
+  let de = |err: BoxedErr, err_type: ErrorType, msg: &str| {
+    DBError::new(err_type, err, msg, path, "".to_string())
+  };
+  let select_err = |err: sqlx::Error| de(Box::new(err), ErrorType::DBSelectError, "failed to lock");
+  let update_err =
+    |err: sqlx::Error| de(Box::new(err), ErrorType::DBUpdateError, "failed to reserve the units");
+
+  let now = time_get_millis() as i64;
+  let db = &*s.db.get().await;
+  let mut tx = db
+    .begin()
+    .await
+    .map_err(|err| de(Box::new(err), ErrorType::DBConnectionError, "failed to begin transaction"))?;
+
+  let operation = InventoryOperation::Reserve {
+    product_id: product_id.into(),
+    variant_id: variant_id.into(),
+    quantity,
+  };
+  match key_claim(&mut tx, key, &operation, now).await.map_err(select_err)? {
+    KeyClaim::New => {}
+    KeyClaim::Conflict => return Ok(InventoryOutcome::KeyConflict),
+    KeyClaim::Replay(product_id, variant_id, reservation_id) => {
+      let stock = variant_stock(&mut tx, &product_id, &variant_id, reservation_id).await;
+      return Ok(InventoryOutcome::Replayed(stock.map_err(select_err)?));
+    }
+  }
+
+  let (on_hand, inventory) = match variant_lock(&mut tx, product_id, variant_id, now).await {
+    Ok(Some(locked)) => locked,
+    Ok(None) => return Err(no_rows("the variant is not found", path)),
+    Err(err) => return Err(select_err(err)),
+  };
+  if on_hand - inventory.reserved < quantity as i64 {
+    // dropping the transaction frees the key, the order can retry it once restocked
+    let stock = stock_of(product_id, variant_id, on_hand, &inventory, None);
+    return Ok(InventoryOutcome::Insufficient(stock));
+  }
+
+  let reservation_id = Ulid::new().to_string();
+  sqlx::query(
+    r#"
+    INSERT INTO inventory_reservations (
+      id, product_id, variant_id, quantity, status, user_id, created_at, updated_at
+    ) VALUES ($1, $2, $3, $4, $5, $6, $7, $7)
+    "#,
+  )
+  .bind(&reservation_id)
+  .bind(product_id)
+  .bind(variant_id)
+  .bind(quantity as i64)
+  .bind(ReservationStatus::Reserved.as_str())
+  .bind(user_id)
+  .bind(now)
+  .execute(&mut *tx)
+  .await
+  .map_err(update_err)?;
+
+  sqlx::query(
+    r#"
+    UPDATE inventory_items SET quantity_reserved = quantity_reserved + $3, updated_at = $4
+    WHERE product_id = $1 AND variant_id = $2
+    "#,
+  )
+  .bind(product_id)
+  .bind(variant_id)
+  .bind(quantity as i64)
+  .bind(now)
+  .execute(&mut *tx)
+  .await
+  .map_err(update_err)?;
+
+  key_applied(&mut tx, key, product_id, variant_id, Some(&reservation_id))
+    .await
+    .map_err(update_err)?;
+  tx.commit()
+    .await
+    .map_err(|err| de(Box::new(err), ErrorType::DBUpdateError, "failed to commit transaction"))?;
+
+  let inventory =
+    VariantInventory { reserved: inventory.reserved + quantity as i64, ..inventory };
+  Ok(InventoryOutcome::Applied(stock_of(
+    product_id,
+    variant_id,
+    on_hand,
+    &inventory,
+    Some(reservation_id),
+  )))
+}
+
+/// Commits a reservation, its units leave the offer's quantity and count as sold (and the
+/// buyer's purchase is recorded), or releases it, its units are available again. `to` is
+/// either `Committed` or `Released`.
+pub(super) async fn inventory_settle(
+  s: &ProductsStoreImpl,
+  _ctx: Arc<Context>,
+  key: &str,
+  reservation_id: &str,
+  to: ReservationStatus,
+) -> Result<InventoryOutcome, DBError> {
+  let path = "products.store.inventory_settle";
+  let de = |err: BoxedErr, err_type: ErrorType, msg: &str| {
+    DBError::new(err_type, err, msg, path, "".to_string())
+  };
+  let select_err = |err: sqlx::Error| de(Box::new(err), ErrorType::DBSelectError, "failed to lock");
+  let update_err = |err: sqlx::Error| {
+    de(Box::new(err), ErrorType::DBUpdateError, "failed to settle the reservation")
+  };
+
+  let now = time_get_millis() as i64;
+  let db = &*s.db.get().await;
+  let mut tx = db
+    .begin()
+    .await
+    .map_err(|err| de(Box::new(err), ErrorType::DBConnectionError, "failed to begin transaction"))?;
+
+  let operation = match to {
+    ReservationStatus::Committed => {
+      InventoryOperation::Commit { reservation_id: reservation_id.into() }
+    }
+    _ => InventoryOperation::Release { reservation_id: reservation_id.into() },
+  };
+  match key_claim(&mut tx, key, &operation, now).await.map_err(select_err)? {
+    KeyClaim::New => {}
+    KeyClaim::Conflict => return Ok(InventoryOutcome::KeyConflict),
+    KeyClaim::Replay(product_id, variant_id, reservation_id) => {
+      let stock = variant_stock(&mut tx, &product_id, &variant_id, reservation_id).await;
+      return Ok(InventoryOutcome::Replayed(stock.map_err(select_err)?));
+    }
+  }
+
+  let reservation: Option<(String, String, i64, String, String)> = sqlx::query_as(
+    r#"
+    SELECT product_id, variant_id, quantity, status, user_id FROM inventory_reservations
+    WHERE id = $1
+    FOR UPDATE
+    "#,
+  )
+  .bind(reservation_id)
+  .fetch_optional(&mut *tx)
+  .await
+  .map_err(select_err)?;
+
+  let (product_id, variant_id, quantity, status, user_id) = match reservation {
+    Some(reservation) => reservation,
+    None => return Err(no_rows("the reservation is not found", path)),
+  };
+  match ReservationStatus::from_str(&status) {
+    Some(ReservationStatus::Reserved) => {}
+    Some(status) => return Ok(InventoryOutcome::Settled(status)),
+    None => return Err(no_rows("the reservation status is unknown", path)),
+  }
+
+  let (mut on_hand, inventory) = match variant_lock(&mut tx, &product_id, &variant_id, now).await {
+    Ok(Some(locked)) => locked,
+    Ok(None) => return Err(no_rows("the variant is not found", path)),
+    Err(err) => return Err(select_err(err)),
+  };
+
+  let sold = if to == ReservationStatus::Committed { quantity } else { 0 };
+  sqlx::query(
+    r#"
+    UPDATE inventory_items SET
+      quantity_reserved = GREATEST(quantity_reserved - $3, 0),
+      quantity_sold = quantity_sold + $4,
+      updated_at = $5
+    WHERE product_id = $1 AND variant_id = $2
+    "#,
+  )
+  .bind(&product_id)
+  .bind(&variant_id)
+  .bind(quantity)
+  .bind(sold)
+  .bind(now)
+  .execute(&mut *tx)
+  .await
+  .map_err(update_err)?;
+
+  if sold > 0 {
+    on_hand = (on_hand - sold).max(0);
+    offer_quantity_set(&mut tx, &product_id, &variant_id, on_hand, now)
+      .await
+      .map_err(update_err)?;
+
+    // the sale is what the best selling rankings are computed from
+    sqlx::query(
+      r#"
+      INSERT INTO sales_events (id, product_id, variant_id, quantity, created_at)
+      VALUES ($1, $2, $3, $4, $5)
+      "#,
+    )
+    .bind(reservation_id)
+    .bind(&product_id)
+    .bind(&variant_id)
+    .bind(sold)
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(update_err)?;
+
+    // and the purchase is what the recommendations learn from, the clients can't report it
+    sqlx::query(
+      r#"
+      INSERT INTO product_events (id, user_id, product_id, variant_id, event_type, created_at)
+      VALUES ($1, $2, $3, $4, $5, $6)
+      "#,
+    )
+    .bind(Ulid::new().to_string())
+    .bind(&user_id)
+    .bind(&product_id)
+    .bind(&variant_id)
+    .bind(ProductEventType::Purchase.as_str())
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(update_err)?;
+  }
+
+  sqlx::query("UPDATE inventory_reservations SET status = $2, updated_at = $3 WHERE id = $1")
+    .bind(reservation_id)
+    .bind(to.as_str())
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(update_err)?;
+
+  key_applied(&mut tx, key, &product_id, &variant_id, Some(reservation_id))
+    .await
+    .map_err(update_err)?;
+  tx.commit()
+    .await
+    .map_err(|err| de(Box::new(err), ErrorType::DBUpdateError, "failed to commit transaction"))?;
+
+  let reserved = (inventory.reserved - quantity).max(0);
+  let inventory = VariantInventory { reserved, ..inventory };
+  let reservation_id = Some(reservation_id.to_string());
+  Ok(InventoryOutcome::Applied(stock_of(
+    &product_id,
+    &variant_id,
+    on_hand,
+    &inventory,
+    reservation_id,
+  )))
+}
+
+/// Adds units to the variant's offer quantity, and sets its low stock threshold if given
+pub(super) async fn inventory_restock(
+  s: &ProductsStoreImpl,
+  _ctx: Arc<Context>,
+  key: &str,
+  product_id: &str,
+  variant_id: &str,
+  quantity: u32,
+  low_stock_threshold: Option<u32>,
+) -> Result<InventoryOutcome, DBError> {
+  let path = "products.store.inventory_restock";
+  let de = |err: BoxedErr, err_type: ErrorType, msg: &str| {
+    DBError::new(err_type, err, msg, path, "".to_string())
+  };
+  let select_err = |err: sqlx::Error| de(Box::new(err), ErrorType::DBSelectError, "failed to lock");
+  let update_err =
+    |err: sqlx::Error| de(Box::new(err), ErrorType::DBUpdateError, "failed to restock");
+
+  let now = time_get_millis() as i64;
+  let db = &*s.db.get().await;
+  let mut tx = db
+    .begin()
+    .await
+    .map_err(|err| de(Box::new(err), ErrorType::DBConnectionError, "failed to begin transaction"))?;
+
+  let operation = InventoryOperation::Restock {
+    product_id: product_id.into(),
+    variant_id: variant_id.into(),
+    quantity,
+    low_stock_threshold,
+  };
+  match key_claim(&mut tx, key, &operation, now).await.map_err(select_err)? {
+    KeyClaim::New => {}
+    KeyClaim::Conflict => return Ok(InventoryOutcome::KeyConflict),
+    KeyClaim::Replay(product_id, variant_id, _) => {
+      let stock = variant_stock(&mut tx, &product_id, &variant_id, None).await;
+      return Ok(InventoryOutcome::Replayed(stock.map_err(select_err)?));
+    }
+  }
+
+  let (on_hand, mut inventory) = match variant_lock(&mut tx, product_id, variant_id, now).await {
+    Ok(Some(locked)) => locked,
+    Ok(None) => return Err(no_rows("the variant is not found", path)),
+    Err(err) => return Err(select_err(err)),
+  };
+
+  let on_hand = on_hand + quantity as i64;
+  offer_quantity_set(&mut tx, product_id, variant_id, on_hand, now).await.map_err(update_err)?;
+
+  if let Some(threshold) = low_stock_threshold {
+    sqlx::query(
+      r#"
+      UPDATE inventory_items SET low_stock_threshold = $3, updated_at = $4
+      WHERE product_id = $1 AND variant_id = $2
+      "#,
+    )
+    .bind(product_id)
+    .bind(variant_id)
+    .bind(threshold as i64)
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(update_err)?;
+    inventory.threshold = Some(threshold as i64);
+  }
+
+  key_applied(&mut tx, key, product_id, variant_id, None).await.map_err(update_err)?;
+  tx.commit()
+    .await
+    .map_err(|err| de(Box::new(err), ErrorType::DBUpdateError, "failed to commit transaction"))?;
+
+  Ok(InventoryOutcome::Applied(stock_of(product_id, variant_id, on_hand, &inventory, None)))
+}
+
+/// Releases up to `limit` reservations that are still reserved since before `before`
+/// (millis), e.g. the order was abandoned, and returns how many were released. The
+/// reservations being settled are skipped, and the products are locked before their
+/// variants, in the same order as the other operations.
+pub(super) async fn inventory_reservations_expire(
+  s: &ProductsStoreImpl,
+  _ctx: Arc<Context>,
+  before: u64,
+  limit: i64,
+) -> Result<u64, DBError> {
+  let path = "products.store.inventory_reservations_expire";
+  let de = |err: BoxedErr, err_type: ErrorType, msg: &str| {
+    DBError::new(err_type, err, msg, path, "".to_string())
+  };
+  let select_err = |err: sqlx::Error| de(Box::new(err), ErrorType::DBSelectError, "failed to lock");
+  let update_err = |err: sqlx::Error| {
+    de(Box::new(err), ErrorType::DBUpdateError, "failed to release the stale reservations")
+  };
+
+  let now = time_get_millis() as i64;
+  let db = &*s.db.get().await;
+  let mut tx = db
+    .begin()
+    .await
+    .map_err(|err| de(Box::new(err), ErrorType::DBConnectionError, "failed to begin transaction"))?;
+
+  let mut stale: Vec<(String, String, String, i64)> = sqlx::query_as(
+    r#"
+    SELECT id, product_id, variant_id, quantity FROM inventory_reservations
+    WHERE status = $1 AND created_at < $2
+    ORDER BY created_at
+    LIMIT $3
+    FOR UPDATE SKIP LOCKED
+    "#,
+  )
+  .bind(ReservationStatus::Reserved.as_str())
+  .bind(before as i64)
+  .bind(limit)
+  .fetch_all(&mut *tx)
+  .await
+  .map_err(select_err)?;
+  if stale.is_empty() {
+    return Ok(0);
+  }
+
+  stale.sort_by(|a, b| (&a.1, &a.2).cmp(&(&b.1, &b.2)));
+  let product_ids: Vec<&str> =
+    stale.iter().map(|(_, product_id, _, _)| product_id.as_str()).collect();
+  sqlx::query("SELECT id FROM products WHERE id = ANY($1) ORDER BY id FOR UPDATE")
+    .bind(&product_ids)
+    .execute(&mut *tx)
+    .await
+    .map_err(select_err)?;
+
+  for (_, product_id, variant_id, quantity) in stale.iter() {
+    sqlx::query(
+      r#"
+      UPDATE inventory_items SET
+        quantity_reserved = GREATEST(quantity_reserved - $3, 0),
+        updated_at = $4
+      WHERE product_id = $1 AND variant_id = $2
+      "#,
+    )
+    .bind(product_id)
+    .bind(variant_id)
+    .bind(quantity)
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(update_err)?;
+  }
+
+  let ids: Vec<&str> = stale.iter().map(|(id, _, _, _)| id.as_str()).collect();
+  let released = sqlx::query(
+    "UPDATE inventory_reservations SET status = $2, updated_at = $3 WHERE id = ANY($1)",
+  )
+  .bind(&ids)
+  .bind(ReservationStatus::Released.as_str())
+  .bind(now)
+  .execute(&mut *tx)
+  .await
+  .map_err(update_err)?;
+
+  tx.commit()
+    .await
+    .map_err(|err| de(Box::new(err), ErrorType::DBUpdateError, "failed to commit transaction"))?;
+
+  Ok(released.rows_affected())
+}
@@ -0,0 +1,11 @@
+use sqlx::PgConnection;
+
+/// Takes the transaction scoped lock of a job, the jobs run on every replica but the ones
+/// rebuilding a table must not run on two of them at once. Returns false if another replica
+/// holds it, the job then skips its run.
+pub(super) async fn job_try_lock(conn: &mut PgConnection, job: &str) -> Result<bool, sqlx::Error> {
+  sqlx::query_scalar::<_, bool>("SELECT pg_try_advisory_xact_lock(hashtextextended($1, 0))")
+    .bind(job)
+    .fetch_one(&mut *conn)
+    .await
+}
@@ -0,0 +1,96 @@
+use sqlx::{Postgres, QueryBuilder};
+
+use crate::models::pagination::{KeysetPage, PageCursor, PageCursorKey, PageDirection, PagePosition};
+
+/// The order of a keyset paginated query: its sort key (none to sort by the id only),
+/// then the id as the tie breaker, both in the same direction
+#[derive(Debug, Clone, Copy)]
+pub(super) struct KeysetOrder {
+  pub key: Option<&'static str>,
+  pub id: &'static str,
+  pub descending: bool,
+}
+
+impl KeysetOrder {
+  /// A previous page is read in the reverse order, walking backwards from the cursor
+  fn descending_for(&self, cursor: Option<&PageCursor>) -> bool {
+    match cursor.map(|c| c.direction) {
+      Some(PageDirection::Previous) => !self.descending,
+      _ => self.descending,
+    }
+  }
+
+  /// Appends ` AND (key, id) < (..)` (or `>`), which only keeps the rows after the cursor.
+  /// A cursor without a key is compared on the id alone, binding a NULL key instead would
+  /// never match a row and end the list there.
+  pub(super) fn push_condition(
+    &self,
+    qb: &mut QueryBuilder<Postgres>,
+    cursor: Option<&PageCursor>,
+  ) {
+    let cursor = match cursor {
+      Some(cursor) => cursor,
+      None => return,
+    };
+    let key = self.key.zip(cursor.position.key.as_ref());
+
+    qb.push(" AND (");
+    if let Some((column, _)) = key {
+      qb.push(column);
+      qb.push(", ");
+    }
+    qb.push(self.id);
+    qb.push(if self.descending_for(Some(cursor)) { ") < (" } else { ") > (" });
+    match key {
+      Some((_, PageCursorKey::Int(value))) => {
+        qb.push_bind(*value);
+        qb.push(", ");
+      }
+      Some((_, PageCursorKey::Float(value))) => {
+        qb.push_bind(*value);
+        qb.push(", ");
+      }
+      None => {}
+    }
+    qb.push_bind(cursor.position.id.clone());
+    qb.push(")");
+  }
+
+  pub(super) fn push_order_by(&self, qb: &mut QueryBuilder<Postgres>, cursor: Option<&PageCursor>) {
+    let direction = if self.descending_for(cursor) { " DESC" } else { " ASC" };
+    qb.push(" ORDER BY ");
+    if let Some(key) = self.key {
+      qb.push(key);
+      qb.push(direction);
+      qb.push(", ");
+    }
+    qb.push(self.id);
+    qb.push(direction);
+  }
+}
+
+/// Builds the page out of the `limit + 1` rows read, the extra one only tells that more follow.
+/// The rows are put in the requested order (a previous page is read reversed), and the
+/// positions are taken from them, so the rows dropped by `item` don't shift the cursors.
+/// The snapshot of the cursor carries over to the page.
+pub(super) fn keyset_page<R, T>(
+  mut rows: Vec<R>,
+  cursor: Option<&PageCursor>,
+  limit: i64,
+  position: impl Fn(&R) -> PagePosition,
+  item: impl FnMut(R) -> Option<T>,
+) -> KeysetPage<T> {
+  let has_more = rows.len() as i64 > limit;
+  rows.truncate(limit.max(0) as usize);
+  if cursor.is_some_and(|c| c.direction == PageDirection::Previous) {
+    rows.reverse();
+  }
+
+  KeysetPage {
+    has_more,
+    first: rows.first().map(&position),
+    last: rows.last().map(&position),
+    items: rows.into_iter().filter_map(item).collect(),
+    snapshot: cursor.and_then(|c| c.snapshot.clone()),
+  }
+}
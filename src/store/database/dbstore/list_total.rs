@@ -0,0 +1,29 @@
+use serde_json::Value;
+use sqlx::{Pool, Postgres, QueryBuilder};
+
+use crate::models::pagination::ListTotal;
+
+/// Counts the rows of the query `push_query` appends. The planner's estimate is taken first
+/// and the rows are only counted when there are up to `exact_max` of them, since counting
+/// a big list means visiting every one of its rows.
+pub(super) async fn list_total<'a>(
+  db: &Pool<Postgres>,
+  exact_max: u64,
+  push_query: impl Fn(&mut QueryBuilder<'a, Postgres>),
+) -> Result<ListTotal, sqlx::Error> {
+  let mut explain = QueryBuilder::new("EXPLAIN (FORMAT JSON) ");
+  push_query(&mut explain);
+  let plan: Value = explain.build_query_scalar().fetch_one(db).await?;
+
+  let estimate = plan[0]["Plan"]["Plan Rows"].as_f64().unwrap_or(0.0).max(0.0) as u64;
+  if estimate > exact_max {
+    return Ok(ListTotal { count: estimate, estimated: true });
+  }
+
+  let mut count = QueryBuilder::new("SELECT COUNT(*) FROM (");
+  push_query(&mut count);
+  count.push(") AS listed");
+  let count: i64 = count.build_query_scalar().fetch_one(db).await?;
+
+  Ok(ListTotal { count: count as u64, estimated: false })
+}
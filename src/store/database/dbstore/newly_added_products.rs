@@ -1,20 +1,36 @@
 use std::sync::Arc;
 
-use bigdecimal::num_traits::ToPrimitive;
-use megacommerce_proto::{NewlyAddedProductListItem, ProductMedia};
+use megacommerce_proto::{NewlyAddedProductListItem, ProductMedia, ProductOffer};
 use megacommerce_shared::{
   models::{
     context::Context,
     errors::{BoxedErr, ErrorType},
   },
   store::errors::DBError,
+  utils::time::time_get_millis,
 };
-use serde_json::from_value;
+use serde_json::{from_value, Value};
+use sqlx::FromRow;
 
 use crate::{
-  models::time::format_human_readable_time, store::database::dbstore::ProductsStoreImpl,
+  models::{
+    inventory::variants_inventory, products::variant_card_image,
+    time::format_human_readable_time, variant_selection::display_variant,
+  },
+  store::database::dbstore::{inventory::VARIANTS_INVENTORY_SQL, ProductsStoreImpl},
 };
 
+#[derive(FromRow)]
+struct ProductRow {
+  id: String,
+  title: String,
+  media: Value,
+  offer: Value,
+  currency_code: String,
+  created_at: i64,
+  stock: Option<Value>,
+}
+
 pub(super) async fn newly_added_products(
   s: &ProductsStoreImpl,
   ctx: Arc<Context>,
@@ -32,84 +48,61 @@ pub(super) async fn newly_added_products(
 
   let db = &*s.db.get().await;
 
-  let rows = sqlx::query!(
+  let rows: Vec<ProductRow> = sqlx::query_as(&format!(
     r#"
-      WITH variants AS (
-        SELECT
+      SELECT
           p.id,
           p.title,
           p.media,
-          p.created_at,
-          variant.key as variant_id,
-          (variant.value ->> 'price')::numeric as price,
-          (variant.value ->> 'sale_price')::numeric as sale_price,
-          ROW_NUMBER() OVER (
-            PARTITION BY p.id ORDER BY (variant.value ->> 'price')::numeric DESC
-          ) as rn
-        FROM products p, 
-        jsonb_each(p.offer -> 'offer') as variant
-      )
-      SELECT
-          v.id,
-          v.title,
-          v.media,
-          v.variant_id,
-          v.price,
-          v.sale_price,
-          v.created_at
-      FROM variants AS v
-      WHERE v.rn = 1
-      ORDER BY v.created_at DESC
+          p.offer,
+          p.currency_code,
+          p.created_at::BIGINT AS created_at,
+          {VARIANTS_INVENTORY_SQL} AS stock
+      FROM products AS p
+      ORDER BY p.created_at DESC, p.id DESC
       LIMIT 6
     "#
-  )
+  ))
   .fetch_all(db)
   .await
   .map_err(|err| de(Box::new(err), "failed to select newly added products", None))?;
 
   let mut newly_added_products = Vec::new();
+  let (policy, now) = (s.variant_selection, time_get_millis());
 
   for row in rows {
-    let media: ProductMedia = from_value(row.media.unwrap_or_default()).map_err(|err| {
+    let offer: ProductOffer = from_value(row.offer).map_err(|err| {
+      de(Box::new(err), "failed to deserialize product's offer", Some(ErrorType::JsonUnmarshal))
+    })?;
+    let media: ProductMedia = from_value(row.media).map_err(|err| {
       de(Box::new(err), "failed to deserialize product's media", Some(ErrorType::JsonUnmarshal))
     })?;
 
-    let variant_id = row.variant_id.unwrap_or_default();
-
-    // Get the first image for this variant
-    let image_url = if let Some(variant_media) = media.media.get(&variant_id) {
-      if let Some((_, first_image)) = variant_media.images.iter().next() {
-        first_image.url.clone()
-      } else {
-        "".to_string()
-      }
-    } else {
-      "".to_string()
+    let stock = variants_inventory(row.stock);
+    let display = match display_variant(policy, &offer, &stock, &row.currency_code, now) {
+      Some(display) => display,
+      // no variant with a valid price, nothing to show
+      None => continue,
     };
-
-    let price = row.price.unwrap_or_default().to_f64().unwrap_or(0.0);
-    let sale_price = row.sale_price.unwrap_or_default().to_f64().unwrap_or(0.0);
-    let price_cents = (price * 100.0).round() as u32;
-    let discount_price_cents = (sale_price * 100.0).round() as u32;
-
-    // Calculate discount percentage if sale price exists
-    let discount_percentage =
-      if sale_price > 0.0 { ((price - sale_price) / price * 100.0).round() as u32 } else { 0 };
+    let (prices, level) = (display.prices, display.level);
 
     // Format the created_at timestamp
-    let created_at_ms = row.created_at.unwrap_or_default() as i64;
     let timezone = &ctx.timezone;
-    let created_at = format_human_readable_time(&ctx.accept_language, created_at_ms, timezone);
+    let created_at = format_human_readable_time(&ctx.accept_language, row.created_at, timezone);
 
     newly_added_products.push(NewlyAddedProductListItem {
-      id: row.id.unwrap_or_default(),
-      variant_id: variant_id.to_string(),
-      title: row.title.unwrap_or_default(),
-      image: image_url,
-      price_cents,
-      sale_price_cents: Some(discount_price_cents),
-      discount_percentage: Some(discount_percentage),
+      id: row.id,
+      variant_id: display.id.to_string(),
+      title: row.title,
+      image: variant_card_image(&media, display.id),
+      price_cents: prices.price.minor_units(),
+      sale_price_cents: Some(prices.sale_price.map(|p| p.minor_units()).unwrap_or(0)),
+      discount_percentage: Some(prices.discount_percentage().unwrap_or(0)),
       created_at,
+      currency_code: row.currency_code,
+      display_price: None,
+      in_stock: level.in_stock(),
+      low_stock: level.low_stock(),
     });
   }
 
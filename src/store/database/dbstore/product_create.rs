@@ -6,12 +6,13 @@ use megacommerce_shared::models::errors::{BoxedErr, ErrorType};
 use megacommerce_shared::store::errors::DBError;
 use serde_json::{to_value, Value};
 
-use crate::store::database::dbstore::ProductsStoreImpl;
+use crate::{models::products::product_media_keys, store::database::dbstore::ProductsStoreImpl};
 
 pub(super) async fn product_create(
   s: &ProductsStoreImpl,
   _: Arc<Context>,
   pro: &Product,
+  attributes: &[(String, String)],
 ) -> Result<(), DBError> {
   let mk_err = |msg: &str, err: BoxedErr, typ: Option<ErrorType>| DBError {
     err_type: typ.unwrap_or(ErrorType::JsonMarshal),
@@ -43,6 +44,9 @@ pub(super) async fn product_create(
     .map_err(|e| mk_err("failed to serialize the products metadata", Box::new(e), None))?;
 
   let db = &*s.db.get().await;
+  let mut tx = db.begin().await.map_err(|e| {
+    mk_err("failed to begin transaction", Box::new(e), Some(ErrorType::DBConnectionError))
+  })?;
 
   sqlx::query(
     r#"
@@ -89,9 +93,59 @@ pub(super) async fn product_create(
   .bind(pro.created_at as i64)
   .bind(pro.published_at.map(|t| t as i64))
   .bind(pro.updated_at.map(|t| t as i64))
-  .execute(db)
+  .execute(&mut *tx)
   .await
   .map_err(|e| mk_err("failed to insert a product", Box::new(e), Some(ErrorType::DBInsertError)))?;
 
+  // the objects may be shared with other products, keep track of who references them
+  let media_keys = pro.media.as_ref().map(product_media_keys).unwrap_or_default();
+  sqlx::query(
+    r#"
+    INSERT INTO product_media_objects (key, product_id)
+    SELECT DISTINCT UNNEST($1::text[]), $2
+    ON CONFLICT DO NOTHING
+    "#,
+  )
+  .bind(&media_keys)
+  .bind(&pro.id)
+  .execute(&mut *tx)
+  .await
+  .map_err(|e| {
+    mk_err("failed to insert the media references", Box::new(e), Some(ErrorType::DBInsertError))
+  })?;
+
+  // the facetable attribute values, which the category facets are filtered and counted by
+  let (names, values): (Vec<String>, Vec<String>) = attributes.iter().cloned().unzip();
+  sqlx::query(
+    r#"
+    INSERT INTO product_attributes (product_id, name, value)
+    SELECT $1, UNNEST($2::text[]), UNNEST($3::text[])
+    ON CONFLICT DO NOTHING
+    "#,
+  )
+  .bind(&pro.id)
+  .bind(&names)
+  .bind(&values)
+  .execute(&mut *tx)
+  .await
+  .map_err(|e| {
+    mk_err("failed to insert the product attributes", Box::new(e), Some(ErrorType::DBInsertError))
+  })?;
+
+  // the media is referenced by the product now, it's no longer a pending upload.
+  // other pending keys of the product (e.g. staged direct uploads) are released by the caller
+  sqlx::query("DELETE FROM product_media_uploads WHERE product_id = $1 AND key = ANY($2)")
+    .bind(&pro.id)
+    .bind(&media_keys)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+      mk_err("failed to clear pending uploads", Box::new(e), Some(ErrorType::DBDeleteError))
+    })?;
+
+  tx.commit().await.map_err(|e| {
+    mk_err("failed to commit a product", Box::new(e), Some(ErrorType::DBInsertError))
+  })?;
+
   Ok(())
 }
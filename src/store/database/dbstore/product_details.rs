@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use megacommerce_proto::{ProductBulletPoint, ProductDetailsResponseData, ProductTag};
 use megacommerce_shared::{
@@ -7,10 +7,11 @@ use megacommerce_shared::{
     errors::{BoxedErr, ErrorType},
   },
   store::errors::DBError,
+  utils::time::time_get_millis,
 };
 use serde_json::from_value;
 
-use crate::store::database::dbstore::ProductsStoreImpl;
+use crate::{models::money::offer_sales_at, store::database::dbstore::ProductsStoreImpl};
 
 pub(super) async fn product_details(
   s: &ProductsStoreImpl,
@@ -50,8 +51,9 @@ pub(super) async fn product_details(
   let media: megacommerce_proto::ProductMedia = from_value(row.media)
     .map_err(|err| de(Box::new(err), ErrorType::JsonUnmarshal, "failed to deserialize media"))?;
 
-  let offer: megacommerce_proto::ProductOffer = from_value(row.offer)
+  let mut offer: megacommerce_proto::ProductOffer = from_value(row.offer)
     .map_err(|err| de(Box::new(err), ErrorType::JsonUnmarshal, "failed to deserialize offer"))?;
+  offer_sales_at(&mut offer, time_get_millis());
 
   let safety: megacommerce_proto::ProductSafety = from_value(row.safety)
     .map_err(|err| de(Box::new(err), ErrorType::JsonUnmarshal, "failed to deserialize safety"))?;
@@ -75,6 +77,8 @@ pub(super) async fn product_details(
   Ok(ProductDetailsResponseData {
     id: row.id,
     supplier_id: row.user_id,
+    sold_by: String::new(),
+    seller_rating: None,
     title: row.title,
     category: row.category,
     subcategory: row.subcategory,
@@ -93,5 +97,6 @@ pub(super) async fn product_details(
     safety: Some(safety),
     tags,
     metadata,
+    display_prices: HashMap::new(),
   })
 }
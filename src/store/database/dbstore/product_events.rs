@@ -0,0 +1,69 @@
+use std::sync::Arc;
+
+use megacommerce_shared::{
+  models::{context::Context, errors::ErrorType},
+  store::errors::DBError,
+};
+
+use crate::{models::product_events::ProductEvent, store::database::dbstore::ProductsStoreImpl};
+
+/// Writes a batch of events in one statement. The events of products that don't exist
+/// (anymore) are skipped rather than failing the batch, and so are the ids already written.
+pub(super) async fn product_events_insert(
+  s: &ProductsStoreImpl,
+  _ctx: Arc<Context>,
+  events: &[ProductEvent],
+) -> Result<u64, DBError> {
+  let path = "products.store.product_events_insert";
+  let db = &*s.db.get().await;
+
+  let mut ids = Vec::with_capacity(events.len());
+  let mut user_ids = Vec::with_capacity(events.len());
+  let mut product_ids = Vec::with_capacity(events.len());
+  let mut variant_ids = Vec::with_capacity(events.len());
+  let mut event_types = Vec::with_capacity(events.len());
+  let mut list_names = Vec::with_capacity(events.len());
+  let mut positions = Vec::with_capacity(events.len());
+  let mut created_at = Vec::with_capacity(events.len());
+  for event in events {
+    ids.push(event.id.clone());
+    user_ids.push(event.user_id.clone());
+    product_ids.push(event.product_id.clone());
+    variant_ids.push(event.variant_id.clone());
+    event_types.push(event.event_type.as_str());
+    list_names.push(event.list_name.clone());
+    positions.push(event.position.map(|p| p as i32));
+    created_at.push(event.created_at as i64);
+  }
+
+  let inserted = sqlx::query(
+    r#"
+    INSERT INTO product_events (
+      id, user_id, product_id, variant_id, event_type, list_name, position, created_at
+    )
+    SELECT e.id, e.user_id, e.product_id, e.variant_id, e.event_type, e.list_name, e.position,
+      e.created_at
+    FROM UNNEST($1::TEXT[], $2::TEXT[], $3::TEXT[], $4::TEXT[], $5::TEXT[], $6::TEXT[],
+      $7::INTEGER[], $8::BIGINT[])
+      AS e(id, user_id, product_id, variant_id, event_type, list_name, position, created_at)
+    INNER JOIN products AS p ON p.id = e.product_id
+    ON CONFLICT (id) DO NOTHING
+    "#,
+  )
+  .bind(ids)
+  .bind(user_ids)
+  .bind(product_ids)
+  .bind(variant_ids)
+  .bind(event_types)
+  .bind(list_names)
+  .bind(positions)
+  .bind(created_at)
+  .execute(db)
+  .await
+  .map_err(|err| {
+    let msg = "failed to insert the product events";
+    DBError::new(ErrorType::DBInsertError, Box::new(err), msg, path, "".to_string())
+  })?;
+
+  Ok(inserted.rows_affected())
+}
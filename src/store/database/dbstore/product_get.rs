@@ -0,0 +1,134 @@
+use std::sync::Arc;
+
+use megacommerce_proto::{
+  Product, ProductBulletPoint, ProductDetails, ProductMedia, ProductMetadata, ProductOffer,
+  ProductSafety, ProductTag,
+};
+use megacommerce_shared::{
+  models::{
+    context::Context,
+    errors::{BoxedErr, ErrorType},
+  },
+  store::errors::DBError,
+};
+use serde_json::{from_value, Value};
+use sqlx::FromRow;
+
+use crate::store::database::dbstore::ProductsStoreImpl;
+
+#[derive(FromRow)]
+struct ProductRow {
+  id: String,
+  user_id: String,
+  title: String,
+  category: String,
+  subcategory: String,
+  has_variations: bool,
+  brand_name: Option<String>,
+  has_brand_name: bool,
+  product_id: Option<String>,
+  has_product_id: bool,
+  product_id_type: Option<String>,
+  description: String,
+  bullet_points: Value,
+  currency_code: String,
+  fulfillment_type: String,
+  processing_time: i64,
+  details: Value,
+  media: Value,
+  offer: Value,
+  safety: Value,
+  tags: Value,
+  metadata: Option<Value>,
+  ar_enabled: bool,
+  slug: String,
+  status: String,
+  version: i16,
+  schema_version: i16,
+  created_at: i64,
+  published_at: Option<i64>,
+  updated_at: Option<i64>,
+}
+
+pub(super) async fn product_get(
+  s: &ProductsStoreImpl,
+  _ctx: Arc<Context>,
+  id: &str,
+) -> Result<Product, DBError> {
+  let path = "products.store.product_get";
+  let de = |err: BoxedErr, err_type: ErrorType, msg: &str| {
+    DBError::new(err_type, err, msg, path, "".to_string())
+  };
+
+  let db = &*s.db.get().await;
+
+  let row: ProductRow = sqlx::query_as(
+    r#"
+    SELECT
+      id, user_id, title, category, subcategory, has_variations, brand_name,
+      has_brand_name, product_id, has_product_id, product_id_type, description,
+      bullet_points, currency_code, fulfillment_type, processing_time, details,
+      media, offer, safety, tags, metadata, ar_enabled, slug, status, version,
+      schema_version, created_at, published_at, updated_at
+    FROM products
+    WHERE id = $1
+    "#,
+  )
+  .bind(id)
+  .fetch_optional(db)
+  .await
+  .map_err(|err| de(Box::new(err), ErrorType::DBSelectError, "failed to select a product"))?
+  .ok_or_else(|| {
+    let err = std::io::Error::new(std::io::ErrorKind::NotFound, "the product is not found");
+    de(Box::new(err), ErrorType::NoRows, "the product is not found")
+  })?;
+
+  let unmarshal = |err: serde_json::Error, field: &str| -> DBError {
+    de(Box::new(err), ErrorType::JsonUnmarshal, &format!("failed to deserialize {}", field))
+  };
+
+  let bullet_points: Vec<ProductBulletPoint> =
+    from_value(row.bullet_points).map_err(|e| unmarshal(e, "bullet_points"))?;
+  let details: ProductDetails = from_value(row.details).map_err(|e| unmarshal(e, "details"))?;
+  let media: Option<ProductMedia> = from_value(row.media).map_err(|e| unmarshal(e, "media"))?;
+  let offer: ProductOffer = from_value(row.offer).map_err(|e| unmarshal(e, "offer"))?;
+  let safety: ProductSafety = from_value(row.safety).map_err(|e| unmarshal(e, "safety"))?;
+  let tags: Vec<ProductTag> = from_value(row.tags).map_err(|e| unmarshal(e, "tags"))?;
+  let metadata: Option<ProductMetadata> = match row.metadata {
+    Some(value) => Some(from_value(value).map_err(|e| unmarshal(e, "metadata"))?),
+    None => None,
+  };
+
+  Ok(Product {
+    id: row.id,
+    user_id: row.user_id,
+    title: row.title,
+    category: row.category,
+    subcategory: row.subcategory,
+    has_variations: row.has_variations,
+    brand_name: row.brand_name,
+    has_brand_name: row.has_brand_name,
+    product_id: row.product_id,
+    has_product_id: row.has_product_id,
+    product_id_type: row.product_id_type,
+    description: row.description,
+    bullet_points,
+    currency_code: row.currency_code,
+    fulfillment_type: row.fulfillment_type,
+    processing_time: row.processing_time as u64,
+    details: Some(details),
+    media,
+    offer: Some(offer),
+    safety: Some(safety),
+    tags,
+    metadata,
+    ar_enabled: row.ar_enabled,
+    slug: row.slug,
+    status: row.status,
+    version: row.version as u32,
+    schema_version: row.schema_version as u32,
+    created_at: row.created_at as u64,
+    published_at: row.published_at.map(|t| t as u64),
+    updated_at: row.updated_at.map(|t| t as u64),
+  })
+}
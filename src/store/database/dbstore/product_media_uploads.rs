@@ -0,0 +1,218 @@
+use std::sync::Arc;
+
+use megacommerce_shared::{
+  models::{
+    context::Context,
+    errors::{BoxedErr, ErrorType},
+  },
+  store::errors::DBError,
+  utils::time::time_get_millis,
+};
+
+use crate::{server::object_storage::ObjectStorage, store::database::dbstore::ProductsStoreImpl};
+
+/// Takes a transaction scoped lock on every key, always in the same order so two
+/// transactions can't deadlock on them. Recording a key and releasing it both hold it.
+const KEYS_LOCK_SQL: &str = r#"
+  SELECT pg_advisory_xact_lock(hashtextextended(k, 0))
+  FROM (SELECT DISTINCT UNNEST($1::text[]) AS k ORDER BY k) AS keys
+"#;
+
+/// Records the object keys that are about to be uploaded for a product, the rows are
+/// removed in the same transaction that inserts the product (or by the compensation
+/// after a failure), so whatever is left here belongs to an interrupted creation.
+/// A key being released by `product_media_uploads_release` is recorded once its object is
+/// deleted, so the caller uploads it again rather than finding it in the bucket.
+pub(super) async fn product_media_uploads_add(
+  s: &ProductsStoreImpl,
+  _: Arc<Context>,
+  product_id: &str,
+  keys: &[String],
+) -> Result<(), DBError> {
+  let path = "products.store.product_media_uploads_add";
+  let de = |err: BoxedErr, err_type: ErrorType, msg: &str| {
+    DBError::new(err_type, err, msg, path, "".to_string())
+  };
+
+  let db = &*s.db.get().await;
+  let mut tx = db.begin().await.map_err(|err| {
+    de(Box::new(err), ErrorType::DBConnectionError, "failed to begin transaction")
+  })?;
+
+  sqlx::query(KEYS_LOCK_SQL)
+    .bind(keys)
+    .execute(&mut *tx)
+    .await
+    .map_err(|err| de(Box::new(err), ErrorType::DBSelectError, "failed to lock the media keys"))?;
+
+  sqlx::query(
+    r#"
+    INSERT INTO product_media_uploads (key, product_id, created_at)
+    SELECT DISTINCT UNNEST($1::text[]), $2, $3
+    ON CONFLICT DO NOTHING
+    "#,
+  )
+  .bind(keys)
+  .bind(product_id)
+  .bind(time_get_millis() as i64)
+  .execute(&mut *tx)
+  .await
+  .map_err(|err| de(Box::new(err), ErrorType::DBInsertError, "failed to record pending uploads"))?;
+
+  tx.commit()
+    .await
+    .map_err(|err| de(Box::new(err), ErrorType::DBInsertError, "failed to commit transaction"))?;
+
+  Ok(())
+}
+
+/// Returns the keys of the given ones that are recorded as pending uploads of the product
+pub(super) async fn product_media_uploads_recorded(
+  s: &ProductsStoreImpl,
+  _: Arc<Context>,
+  product_id: &str,
+  keys: &[String],
+) -> Result<Vec<String>, DBError> {
+  let path = "products.store.product_media_uploads_recorded";
+  let db = &*s.db.get().await;
+
+  sqlx::query_scalar::<_, String>(
+    "SELECT key FROM product_media_uploads WHERE product_id = $1 AND key = ANY($2)",
+  )
+  .bind(product_id)
+  .bind(keys)
+  .fetch_all(db)
+  .await
+  .map_err(|err| {
+    let msg = "failed to get the recorded pending uploads";
+    DBError::new(ErrorType::DBSelectError, Box::new(err), msg, path, "".to_string())
+  })
+}
+
+/// Whether an upload staged under `prefix` is recorded for the product, i.e. the product id
+/// was reserved by `product_media_upload_urls` for the user the prefix belongs to
+pub(super) async fn product_media_uploads_reserved(
+  s: &ProductsStoreImpl,
+  _: Arc<Context>,
+  product_id: &str,
+  prefix: &str,
+) -> Result<bool, DBError> {
+  let path = "products.store.product_media_uploads_reserved";
+  let db = &*s.db.get().await;
+
+  sqlx::query_scalar::<_, bool>(
+    r#"
+    SELECT EXISTS (
+      SELECT 1 FROM product_media_uploads WHERE product_id = $1 AND starts_with(key, $2)
+    )
+    "#,
+  )
+  .bind(product_id)
+  .bind(prefix)
+  .fetch_one(db)
+  .await
+  .map_err(|err| {
+    let msg = "failed to check the reserved product id";
+    DBError::new(ErrorType::DBSelectError, Box::new(err), msg, path, "".to_string())
+  })
+}
+
+/// Returns up to `limit` (product_id, key) pairs of pending uploads that were
+/// recorded before `before` (millis)
+pub(super) async fn product_media_uploads_stale(
+  s: &ProductsStoreImpl,
+  _: Arc<Context>,
+  before: u64,
+  limit: i64,
+) -> Result<Vec<(String, String)>, DBError> {
+  let path = "products.store.product_media_uploads_stale";
+  let db = &*s.db.get().await;
+
+  sqlx::query_as::<_, (String, String)>(
+    r#"
+    SELECT product_id, key FROM product_media_uploads
+    WHERE created_at < $1
+    ORDER BY created_at
+    LIMIT $2
+    "#,
+  )
+  .bind(before as i64)
+  .bind(limit)
+  .fetch_all(db)
+  .await
+  .map_err(|err| {
+    let msg = "failed to get stale pending uploads";
+    DBError::new(ErrorType::DBSelectError, Box::new(err), msg, path, "".to_string())
+  })
+}
+
+/// Deletes the objects of the product's pending uploads that nothing else references,
+/// then the pending uploads themselves. Keys are content addressed, so the same object may
+/// be shared by several products: only the keys that no stored product and no other pending
+/// creation references are deleted from the bucket. The references are checked and the
+/// objects deleted while holding the keys' locks, so a creation recording one of the keys at
+/// the same time waits for the object to be gone and uploads it again. The keys whose object
+/// fails to be deleted stay pending for the sweeper.
+pub(super) async fn product_media_uploads_release(
+  s: &ProductsStoreImpl,
+  _: Arc<Context>,
+  product_id: &str,
+  keys: &[String],
+  storage: &ObjectStorage,
+) -> Result<(), DBError> {
+  let path = "products.store.product_media_uploads_release";
+  let de = |err: BoxedErr, err_type: ErrorType, msg: &str| {
+    DBError::new(err_type, err, msg, path, "".to_string())
+  };
+
+  let db = &*s.db.get().await;
+  let mut tx = db.begin().await.map_err(|err| {
+    de(Box::new(err), ErrorType::DBConnectionError, "failed to begin transaction")
+  })?;
+
+  sqlx::query(KEYS_LOCK_SQL)
+    .bind(keys)
+    .execute(&mut *tx)
+    .await
+    .map_err(|err| de(Box::new(err), ErrorType::DBSelectError, "failed to lock the media keys"))?;
+
+  let unreferenced = sqlx::query_scalar::<_, String>(
+    r#"
+    SELECT DISTINCT k FROM UNNEST($1::text[]) AS k
+    WHERE NOT EXISTS (SELECT 1 FROM product_media_objects o WHERE o.key = k)
+      AND NOT EXISTS (
+        SELECT 1 FROM product_media_uploads u WHERE u.key = k AND u.product_id <> $2
+      )
+    "#,
+  )
+  .bind(keys)
+  .bind(product_id)
+  .fetch_all(&mut *tx)
+  .await
+  .map_err(|err| {
+    de(Box::new(err), ErrorType::DBSelectError, "failed to get the unreferenced media keys")
+  })?;
+
+  let mut failed: Vec<String> = vec![];
+  for key in unreferenced.into_iter() {
+    if storage.delete_file(&key).await.is_err() {
+      failed.push(key);
+    }
+  }
+
+  let released: Vec<String> = keys.iter().filter(|k| !failed.contains(k)).cloned().collect();
+  sqlx::query("DELETE FROM product_media_uploads WHERE product_id = $1 AND key = ANY($2)")
+    .bind(product_id)
+    .bind(&released)
+    .execute(&mut *tx)
+    .await
+    .map_err(|err| {
+      de(Box::new(err), ErrorType::DBDeleteError, "failed to delete pending uploads")
+    })?;
+
+  tx.commit()
+    .await
+    .map_err(|err| de(Box::new(err), ErrorType::DBDeleteError, "failed to commit transaction"))?;
+
+  Ok(())
+}
@@ -0,0 +1,347 @@
+use std::sync::Arc;
+
+use megacommerce_proto::ProductToLikeListItem;
+use megacommerce_shared::{
+  models::{
+    context::Context,
+    errors::{BoxedErr, ErrorType},
+  },
+  store::errors::DBError,
+  utils::time::time_get_millis,
+};
+use sqlx::{Postgres, QueryBuilder};
+use ulid::Ulid;
+
+use crate::{
+  models::{
+    pagination::{KeysetPage, PageCursor, PageCursorKey, PagePosition, PageSnapshot},
+    product_events::ProductEventType,
+    recommendations::{RecommendationCandidates, RecommendationWeights},
+  },
+  store::database::dbstore::{
+    inventory::VARIANTS_INVENTORY_SQL,
+    job_lock::job_try_lock,
+    keyset::{keyset_page, KeysetOrder},
+    products_to_like::{products_to_like_item, ProductRow},
+    ProductsStoreImpl,
+  },
+};
+
+/// `(CASE <column> WHEN 'view' THEN 1.0 ... END)`, the weight of an event of the type in the column
+fn event_weight_sql(column: &str) -> String {
+  let cases: String = ProductEventType::ALL
+    .iter()
+    .map(|t| format!(" WHEN '{}' THEN {:.2}", t.as_str(), t.weight()))
+    .collect();
+  format!("(CASE {column}{cases} ELSE 0 END)")
+}
+
+/// Recomputes the item-to-item similarities out of the events since `since`: the cosine of
+/// the products' vectors of weighted events per user, so products viewed or bought by the same
+/// users are similar. Only the `per_product` most similar products of each product are kept,
+/// and the previous similarities are replaced in a single transaction. Another replica
+/// refreshing at the same time skips its refresh and returns 0.
+pub(super) async fn product_similarities_refresh(
+  s: &ProductsStoreImpl,
+  _ctx: Arc<Context>,
+  since: u64,
+  now: u64,
+  per_product: i64,
+) -> Result<u64, DBError> {
+  let path = "products.store.product_similarities_refresh";
+  let de = |err: BoxedErr, err_type: ErrorType, msg: &str| {
+    DBError::new(err_type, err, msg, path, "".to_string())
+  };
+
+  let db = &*s.db.get().await;
+  let mut tx = db.begin().await.map_err(|err| {
+    de(Box::new(err), ErrorType::DBConnectionError, "failed to begin transaction")
+  })?;
+
+  let locked = job_try_lock(&mut *tx, path).await.map_err(|err| {
+    de(Box::new(err), ErrorType::DBSelectError, "failed to lock the similarities refresh")
+  })?;
+  if !locked {
+    return Ok(0);
+  }
+
+  sqlx::query("DELETE FROM product_similarities").execute(&mut *tx).await.map_err(|err| {
+    de(Box::new(err), ErrorType::DBDeleteError, "failed to delete the previous similarities")
+  })?;
+
+  let weight = event_weight_sql("e.event_type");
+  let inserted = sqlx::query(&format!(
+    r#"
+    WITH weighted AS (
+      SELECT e.user_id, e.product_id, SUM({weight})::DOUBLE PRECISION AS weight
+      FROM product_events AS e
+      WHERE e.created_at >= $1 AND e.user_id <> ''
+      GROUP BY e.user_id, e.product_id
+    ), norms AS (
+      SELECT product_id, SQRT(SUM(weight * weight)) AS norm FROM weighted GROUP BY product_id
+    ), pairs AS (
+      SELECT a.product_id, b.product_id AS similar_product_id, SUM(a.weight * b.weight) AS dot
+      FROM weighted AS a
+      INNER JOIN weighted AS b ON b.user_id = a.user_id AND b.product_id <> a.product_id
+      GROUP BY a.product_id, b.product_id
+    ), scored AS (
+      SELECT
+        pairs.product_id,
+        pairs.similar_product_id,
+        pairs.dot / (na.norm * nb.norm) AS score
+      FROM pairs
+      INNER JOIN norms AS na ON na.product_id = pairs.product_id
+      INNER JOIN norms AS nb ON nb.product_id = pairs.similar_product_id
+      WHERE na.norm > 0 AND nb.norm > 0
+    ), ranked AS (
+      SELECT
+        scored.*,
+        ROW_NUMBER() OVER (
+          PARTITION BY product_id ORDER BY score DESC, similar_product_id DESC
+        ) AS rank
+      FROM scored
+    )
+    INSERT INTO product_similarities (product_id, similar_product_id, score, computed_at)
+    SELECT product_id, similar_product_id, score, $2 FROM ranked WHERE rank <= $3
+    "#
+  ))
+  .bind(since as i64)
+  .bind(now as i64)
+  .bind(per_product)
+  .execute(&mut *tx)
+  .await
+  .map_err(|err| de(Box::new(err), ErrorType::DBInsertError, "failed to insert the similarities"))?;
+
+  tx.commit().await.map_err(|err| {
+    de(Box::new(err), ErrorType::DBUpdateError, "failed to commit transaction")
+  })?;
+
+  Ok(inserted.rows_affected())
+}
+
+/// Deletes the recommendations snapshots stored before `before` (millis), their cursors
+/// end the list from then on
+pub(super) async fn product_recommendation_snapshots_expire(
+  s: &ProductsStoreImpl,
+  _ctx: Arc<Context>,
+  before: u64,
+) -> Result<u64, DBError> {
+  let path = "products.store.product_recommendation_snapshots_expire";
+  let db = &*s.db.get().await;
+
+  let deleted = sqlx::query("DELETE FROM product_recommendation_snapshots WHERE created_at < $1")
+    .bind(before as i64)
+    .execute(db)
+    .await
+    .map_err(|err| {
+      let msg = "failed to delete the expired recommendations snapshots";
+      DBError::new(ErrorType::DBDeleteError, Box::new(err), msg, path, "".to_string())
+    })?;
+
+  Ok(deleted.rows_affected())
+}
+
+/// A page of the products recommended to the user, by the weighted sum of the similarity to
+/// the products they had events with since `since`, the share of those events in the
+/// product's category and the product's popularity (the inverse of its monthly best selling
+/// rank). The products they bought in that time are left out. An anonymous user (an empty id)
+/// has no events, so their recommendations are the most popular products.
+/// Only the candidates are scored: the most similar products, the best sellers of the user's
+/// top categories and the overall best sellers, and only the page is read in full.
+/// The first page stores the scored products as a snapshot, and the next pages (whose cursors
+/// carry it) are read from it, so the similarities, the rankings and the events changing in
+/// between don't move the scores the cursors point at. A snapshot is kept for
+/// `recommendations_snapshot_ttl_secs`, paging past that ends the list.
+pub(super) async fn products_recommended(
+  s: &ProductsStoreImpl,
+  ctx: Arc<Context>,
+  user_id: &str,
+  since: u64,
+  weights: RecommendationWeights,
+  candidates: RecommendationCandidates,
+  cursor: Option<&PageCursor>,
+  limit: i64,
+) -> Result<KeysetPage<ProductToLikeListItem>, DBError> {
+  let path = "products.store.products_recommended";
+  let db = &*s.db.get().await;
+  let lang = &ctx.accept_language;
+
+  let order = KeysetOrder { key: Some("score"), id: "id", descending: true };
+
+  let mut query_builder = QueryBuilder::new("WITH ");
+  let snapshot = match cursor.and_then(|c| c.snapshot.clone()) {
+    Some(snapshot) => {
+      query_builder.push(
+        r#"ranked AS (
+      SELECT rs.product_id AS id, rs.score FROM product_recommendation_snapshots AS rs
+      INNER JOIN products AS p ON p.id = rs.product_id AND p.status = 'published'
+      WHERE rs.snapshot_id = "#,
+      );
+      query_builder.push_bind(snapshot.id.clone());
+      query_builder.push(")");
+      snapshot
+    }
+    None => {
+      let snapshot = PageSnapshot { id: Ulid::new().to_string() };
+      push_recommendations_scored(&mut query_builder, user_id, since, weights, candidates);
+      query_builder.push(
+        r#", ranked AS (
+      INSERT INTO product_recommendation_snapshots (snapshot_id, product_id, score, created_at)
+      SELECT "#,
+      );
+      query_builder.push_bind(snapshot.id.clone());
+      query_builder.push(", id, score, ");
+      query_builder.push_bind(time_get_millis() as i64);
+      query_builder.push(" FROM scored RETURNING product_id AS id, score)");
+      snapshot
+    }
+  };
+
+  query_builder.push(
+    r#", page AS (
+      SELECT id, score FROM ranked
+      WHERE TRUE"#,
+  );
+  order.push_condition(&mut query_builder, cursor);
+  order.push_order_by(&mut query_builder, cursor);
+  // one more row than asked for, which tells if there's a next page
+  query_builder.push(" LIMIT ");
+  query_builder.push_bind(limit + 1);
+  query_builder.push(format!(
+    r#"
+    )
+    SELECT
+      p.id,
+      p.title,
+      p.media,
+      p.offer,
+      p.currency_code,
+      p.rating_sum,
+      p.rating_count::BIGINT AS rating_count,
+      (
+        SELECT COALESCE(SUM(ii.quantity_sold), 0) FROM inventory_items AS ii
+        WHERE ii.product_id = p.id
+      )::BIGINT AS sold_count,
+      {VARIANTS_INVENTORY_SQL} AS stock,
+      page.score
+    FROM page
+    INNER JOIN products AS p ON p.id = page.id"#
+  ));
+  order.push_order_by(&mut query_builder, cursor);
+
+  let rows = query_builder.build_query_as::<ProductRow>().fetch_all(db).await.map_err(|err| {
+    let msg = "failed to select the recommended products";
+    DBError::new(ErrorType::DBSelectError, Box::new(err), msg, path, "".to_string())
+  })?;
+
+  let position = |row: &ProductRow| PagePosition {
+    key: Some(PageCursorKey::Float(row.score)),
+    id: row.id.clone(),
+  };
+  let page = keyset_page(rows, cursor, limit, position, Some);
+  let (policy, now) = (s.variant_selection, time_get_millis());
+  let products = page
+    .items
+    .into_iter()
+    .map(|row| products_to_like_item(row, policy, now, lang, path))
+    .collect::<Result<Vec<ProductToLikeListItem>, DBError>>()?;
+
+  Ok(KeysetPage {
+    items: products,
+    has_more: page.has_more,
+    first: page.first,
+    last: page.last,
+    snapshot: Some(snapshot),
+  })
+}
+
+/// Pushes the `history ... scored` CTEs, the candidates of the user's recommendations with
+/// their score (`id`, `score`)
+fn push_recommendations_scored(
+  query_builder: &mut QueryBuilder<Postgres>,
+  user_id: &str,
+  since: u64,
+  weights: RecommendationWeights,
+  candidates: RecommendationCandidates,
+) {
+  let weight = event_weight_sql("h.event_type");
+  query_builder.push(
+    r#"history AS (
+      SELECT e.product_id, e.event_type FROM product_events AS e
+      WHERE e.user_id <> '' AND e.user_id = "#,
+  );
+  query_builder.push_bind(user_id.to_string());
+  query_builder.push(" AND e.created_at >= ");
+  query_builder.push_bind(since as i64);
+  query_builder.push(format!(
+    r#"
+    ), seen AS (
+      SELECT h.product_id, SUM({weight})::DOUBLE PRECISION AS weight
+      FROM history AS h GROUP BY h.product_id
+    ), similar AS (
+      SELECT ps.similar_product_id AS product_id, SUM(ps.score * seen.weight) AS score
+      FROM product_similarities AS ps
+      INNER JOIN seen ON seen.product_id = ps.product_id
+      GROUP BY ps.similar_product_id
+    ), affinity AS (
+      SELECT hp.category, COUNT(*)::DOUBLE PRECISION / SUM(COUNT(*)) OVER () AS share
+      FROM history AS h
+      INNER JOIN products AS hp ON hp.id = h.product_id
+      GROUP BY hp.category
+    ), candidates AS (
+      (SELECT product_id FROM similar ORDER BY score DESC, product_id LIMIT "#
+  ));
+  query_builder.push_bind(candidates.per_source);
+  query_builder.push(
+    r#")
+      UNION
+      SELECT r.product_id FROM best_selling_rankings AS r
+      INNER JOIN (
+        SELECT category FROM affinity ORDER BY share DESC, category LIMIT "#,
+  );
+  query_builder.push_bind(candidates.top_categories);
+  query_builder.push(
+    r#"
+      ) AS top ON top.category = r.category
+      WHERE r.time_window = '30d' AND r.rank <= "#,
+  );
+  query_builder.push_bind(candidates.per_source);
+  query_builder.push(
+    r#"
+      UNION
+      SELECT r.product_id FROM best_selling_rankings AS r
+      WHERE r.time_window = '30d' AND r.category = '' AND r.rank <= "#,
+  );
+  query_builder.push_bind(candidates.per_source);
+  query_builder.push(
+    r#"
+    ), scored AS (
+      SELECT
+        p.id,
+        (
+          COALESCE(similar.score / NULLIF((SELECT SUM(weight) FROM seen), 0), 0) * "#,
+  );
+  query_builder.push_bind(weights.similarity);
+  query_builder.push(" + COALESCE(affinity.share, 0) * ");
+  query_builder.push_bind(weights.affinity);
+  query_builder.push(" + COALESCE(1.0 / popular.rank, 0) * ");
+  query_builder.push_bind(weights.popularity);
+  query_builder.push(
+    r#"
+        )::DOUBLE PRECISION AS score
+      FROM candidates AS c
+      INNER JOIN products AS p ON p.id = c.product_id
+      LEFT JOIN similar ON similar.product_id = p.id
+      LEFT JOIN affinity ON affinity.category = p.category
+      LEFT JOIN best_selling_rankings AS popular
+        ON popular.product_id = p.id AND popular.time_window = '30d' AND popular.category = ''
+      WHERE p.status = 'published' AND NOT EXISTS (
+        SELECT 1 FROM history AS h WHERE h.product_id = p.id AND h.event_type = "#,
+  );
+  query_builder.push_bind(ProductEventType::Purchase.as_str());
+  query_builder.push(
+    r#"
+      )
+    )"#,
+  );
+}
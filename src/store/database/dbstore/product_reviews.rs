@@ -0,0 +1,267 @@
+use std::sync::Arc;
+
+use megacommerce_proto::{ProductRating, ProductReview};
+use megacommerce_shared::{
+  models::{
+    context::Context,
+    errors::{BoxedErr, ErrorType},
+  },
+  store::errors::DBError,
+  utils::time::time_get_millis,
+};
+use sqlx::{FromRow, QueryBuilder};
+
+use crate::{
+  models::{
+    pagination::{KeysetPage, PageCursor, PagePosition},
+    product_reviews::{product_rating_average, ProductReviewStatus},
+  },
+  store::database::dbstore::{
+    keyset::{keyset_page, KeysetOrder},
+    ProductsStoreImpl,
+  },
+};
+
+#[derive(FromRow)]
+struct ProductReviewRow {
+  id: String,
+  product_id: String,
+  user_id: String,
+  rating: i16,
+  title: String,
+  body: String,
+  verified_purchase: bool,
+  status: String,
+  created_at: i64,
+}
+
+impl From<ProductReviewRow> for ProductReview {
+  fn from(row: ProductReviewRow) -> Self {
+    ProductReview {
+      id: row.id,
+      product_id: row.product_id,
+      user_id: row.user_id,
+      rating: row.rating as u32,
+      title: row.title,
+      body: row.body,
+      verified_purchase: row.verified_purchase,
+      status: row.status,
+      created_at: row.created_at as u64,
+    }
+  }
+}
+
+fn no_rows(msg: &str, path: &str) -> DBError {
+  let err = std::io::Error::new(std::io::ErrorKind::NotFound, msg.to_string());
+  DBError::new(ErrorType::NoRows, Box::new(err), msg, path, "".to_string())
+}
+
+/// Inserts a pending review, flagged as a verified purchase if the user has ordered the product.
+/// A user reviews a product once, a second review results in a `NoRows` error.
+pub(super) async fn product_review_create(
+  s: &ProductsStoreImpl,
+  _ctx: Arc<Context>,
+  review: &ProductReview,
+) -> Result<ProductReview, DBError> {
+  let path = "products.store.product_review_create";
+  let db = &*s.db.get().await;
+
+  let row = sqlx::query_as::<_, ProductReviewRow>(
+    r#"
+    INSERT INTO product_reviews (
+      id, product_id, user_id, rating, title, body, verified_purchase, status, created_at
+    )
+    SELECT $1, $2, $3, $4, $5, $6,
+      EXISTS (SELECT 1 FROM order_lines AS ol WHERE ol.product_id = $2 AND ol.user_id = $3),
+      $7, $8
+    ON CONFLICT (product_id, user_id) DO NOTHING
+    RETURNING id, product_id, user_id, rating, title, body, verified_purchase, status, created_at
+    "#,
+  )
+  .bind(&review.id)
+  .bind(&review.product_id)
+  .bind(&review.user_id)
+  .bind(review.rating as i16)
+  .bind(&review.title)
+  .bind(&review.body)
+  .bind(ProductReviewStatus::Pending.as_str())
+  .bind(review.created_at as i64)
+  .fetch_optional(db)
+  .await
+  .map_err(|err| {
+    let msg = "failed to insert the product review";
+    DBError::new(ErrorType::DBInsertError, Box::new(err), msg, path, "".to_string())
+  })?;
+
+  match row {
+    Some(row) => Ok(row.into()),
+    None => Err(no_rows("the user has already reviewed the product", path)),
+  }
+}
+
+pub(super) async fn product_review_get(
+  s: &ProductsStoreImpl,
+  _ctx: Arc<Context>,
+  id: &str,
+) -> Result<ProductReview, DBError> {
+  let path = "products.store.product_review_get";
+  let db = &*s.db.get().await;
+
+  let row = sqlx::query_as::<_, ProductReviewRow>(
+    r#"
+    SELECT id, product_id, user_id, rating, title, body, verified_purchase, status, created_at
+    FROM product_reviews WHERE id = $1
+    "#,
+  )
+  .bind(id)
+  .fetch_optional(db)
+  .await
+  .map_err(|err| {
+    let msg = "failed to select the product review";
+    DBError::new(ErrorType::DBSelectError, Box::new(err), msg, path, "".to_string())
+  })?;
+
+  row.map(ProductReview::from).ok_or_else(|| no_rows("the review is not found", path))
+}
+
+/// The approved reviews of the product, newest first
+pub(super) async fn product_reviews_list(
+  s: &ProductsStoreImpl,
+  _ctx: Arc<Context>,
+  product_id: &str,
+  verified_only: bool,
+  cursor: Option<&PageCursor>,
+  limit: i64,
+) -> Result<KeysetPage<ProductReview>, DBError> {
+  let path = "products.store.product_reviews_list";
+  let db = &*s.db.get().await;
+
+  let order = KeysetOrder { key: None, id: "id", descending: true };
+  let mut query_builder = QueryBuilder::new(
+    r#"
+    SELECT id, product_id, user_id, rating, title, body, verified_purchase, status, created_at
+    FROM product_reviews
+    WHERE product_id = "#,
+  );
+  query_builder.push_bind(product_id);
+  query_builder.push(" AND status = ");
+  query_builder.push_bind(ProductReviewStatus::Approved.as_str());
+  if verified_only {
+    query_builder.push(" AND verified_purchase");
+  }
+  order.push_condition(&mut query_builder, cursor);
+  order.push_order_by(&mut query_builder, cursor);
+  // one more row than asked for, which tells if there's a next page
+  query_builder.push(" LIMIT ");
+  query_builder.push_bind(limit + 1);
+
+  let rows =
+    query_builder.build_query_as::<ProductReviewRow>().fetch_all(db).await.map_err(|err| {
+      let msg = "failed to select the product reviews";
+      DBError::new(ErrorType::DBSelectError, Box::new(err), msg, path, "".to_string())
+    })?;
+
+  let position = |row: &ProductReviewRow| PagePosition { key: None, id: row.id.clone() };
+  Ok(keyset_page(rows, cursor, limit, position, |row| Some(ProductReview::from(row))))
+}
+
+/// Moves the review from `from` to `to`, and applies the change to the product's rating
+/// in the same transaction. The update is guarded by the current status, so a concurrent
+/// moderation results in a `NoRows` error instead of counting the review twice.
+pub(super) async fn product_review_moderate(
+  s: &ProductsStoreImpl,
+  ctx: Arc<Context>,
+  id: &str,
+  from: ProductReviewStatus,
+  to: ProductReviewStatus,
+  reason: &str,
+) -> Result<(), DBError> {
+  let path = "products.store.product_review_moderate";
+  let de = |err: BoxedErr, err_type: ErrorType, msg: &str| {
+    DBError::new(err_type, err, msg, path, "".to_string())
+  };
+
+  let now = time_get_millis() as i64;
+  let db = &*s.db.get().await;
+  let mut tx = db
+    .begin()
+    .await
+    .map_err(|err| de(Box::new(err), ErrorType::DBConnectionError, "failed to begin transaction"))?;
+
+  let moderated: Option<(String, i16)> = sqlx::query_as(
+    r#"
+    UPDATE product_reviews SET
+      status = $3,
+      moderation_reason = $4,
+      moderated_by = $5,
+      updated_at = $6
+    WHERE id = $1 AND status = $2
+    RETURNING product_id, rating
+    "#,
+  )
+  .bind(id)
+  .bind(from.as_str())
+  .bind(to.as_str())
+  .bind(reason)
+  .bind(ctx.session().user_id())
+  .bind(now)
+  .fetch_optional(&mut *tx)
+  .await
+  .map_err(|err| de(Box::new(err), ErrorType::DBUpdateError, "failed to moderate the review"))?;
+
+  let (product_id, rating) = match moderated {
+    Some(moderated) => moderated,
+    None => return Err(no_rows("the review status has changed", path)),
+  };
+
+  let (sum, count) = from.rating_delta(to, rating as i64);
+  if count != 0 {
+    sqlx::query(
+      r#"
+      UPDATE products SET
+        rating_sum = rating_sum + $2,
+        rating_count = rating_count + $3
+      WHERE id = $1
+      "#,
+    )
+    .bind(&product_id)
+    .bind(sum)
+    .bind(count)
+    .execute(&mut *tx)
+    .await
+    .map_err(|err| {
+      de(Box::new(err), ErrorType::DBUpdateError, "failed to update the product rating")
+    })?;
+  }
+
+  tx.commit()
+    .await
+    .map_err(|err| de(Box::new(err), ErrorType::DBUpdateError, "failed to commit transaction"))?;
+
+  Ok(())
+}
+
+pub(super) async fn product_rating(
+  s: &ProductsStoreImpl,
+  _ctx: Arc<Context>,
+  product_id: &str,
+) -> Result<ProductRating, DBError> {
+  let path = "products.store.product_rating";
+  let db = &*s.db.get().await;
+
+  let row: Option<(i64, i64)> =
+    sqlx::query_as("SELECT rating_sum, rating_count::BIGINT FROM products WHERE id = $1")
+      .bind(product_id)
+      .fetch_optional(db)
+      .await
+      .map_err(|err| {
+        let msg = "failed to select the product rating";
+        DBError::new(ErrorType::DBSelectError, Box::new(err), msg, path, "".to_string())
+      })?;
+
+  let (sum, count) = row.ok_or_else(|| no_rows("the product is not found", path))?;
+  Ok(ProductRating {
+    average: product_rating_average(sum, count).unwrap_or_default(),
+    count: count as u32,
+  })
+}
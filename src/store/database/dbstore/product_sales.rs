@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use megacommerce_shared::{
+  models::{context::Context, errors::ErrorType},
+  store::errors::DBError,
+};
+
+use crate::store::database::dbstore::ProductsStoreImpl;
+
+/// The SQL condition of a variant's sale price applying right now, the counterpart of
+/// `VariantPrice::from_offer` for the queries that filter or sort by price.
+/// `variant` is the variant's jsonb value, e.g. `v.value`.
+pub(super) fn sale_active_sql(variant: &str) -> String {
+  let now = "EXTRACT(EPOCH FROM NOW()) * 1000";
+  format!(
+    "(COALESCE(({variant}->>'has_sale_price')::BOOLEAN, FALSE) \
+     AND COALESCE(({variant}->>'sale_price')::NUMERIC > 0, FALSE) \
+     AND COALESCE(({variant}->>'sale_price_start')::BIGINT <= {now}, TRUE) \
+     AND COALESCE(({variant}->>'sale_price_end')::BIGINT > {now}, TRUE))"
+  )
+}
+
+/// Takes the sales that ended before `now` off the variants of up to `limit` products, their
+/// sale price and dates are removed and `has_sale_price` is unset. The version is bumped, so an
+/// edit made on the offer as it was is rejected. Returns the number of updated products.
+pub(super) async fn products_sales_expire(
+  s: &ProductsStoreImpl,
+  _ctx: Arc<Context>,
+  now: u64,
+  limit: i64,
+) -> Result<u64, DBError> {
+  let path = "products.store.products_sales_expire";
+  let db = &*s.db.get().await;
+
+  let result = sqlx::query(
+    r#"
+    WITH expired AS (
+      SELECT p.id FROM products AS p
+      WHERE EXISTS (
+        SELECT 1 FROM jsonb_each(p.offer->'offer') AS v
+        WHERE (v.value->>'has_sale_price')::BOOLEAN
+          AND (v.value->>'sale_price_end')::BIGINT <= $1
+      )
+      LIMIT $2
+      FOR UPDATE SKIP LOCKED
+    )
+    UPDATE products AS p SET
+      offer = jsonb_set(p.offer, '{offer}', (
+        SELECT jsonb_object_agg(v.key, CASE
+          WHEN (v.value->>'has_sale_price')::BOOLEAN
+            AND (v.value->>'sale_price_end')::BIGINT <= $1
+          THEN (v.value - 'sale_price' - 'sale_price_start' - 'sale_price_end')
+            || '{"has_sale_price": false}'::JSONB
+          ELSE v.value
+        END)
+        FROM jsonb_each(p.offer->'offer') AS v
+      )),
+      updated_at = $1,
+      version = p.version + 1
+    FROM expired
+    WHERE p.id = expired.id
+    "#,
+  )
+  .bind(now as i64)
+  .bind(limit)
+  .execute(db)
+  .await
+  .map_err(|err| {
+    let msg = "failed to expire the ended sales";
+    DBError::new(ErrorType::DBUpdateError, Box::new(err), msg, path, "".to_string())
+  })?;
+
+  Ok(result.rows_affected())
+}
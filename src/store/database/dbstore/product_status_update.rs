@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use megacommerce_shared::{
+  models::{
+    context::Context,
+    errors::{BoxedErr, ErrorType},
+  },
+  store::errors::DBError,
+  utils::time::time_get_millis,
+};
+use ulid::Ulid;
+
+use crate::{models::products::ProductStatus, store::database::dbstore::ProductsStoreImpl};
+
+/// Moves the product from `from` to `to` and records the transition with its reason.
+/// The update is guarded by the current status, so a concurrent transition results
+/// in a `NoRows` error instead of silently overriding it.
+pub(super) async fn product_status_update(
+  s: &ProductsStoreImpl,
+  ctx: Arc<Context>,
+  id: &str,
+  from: ProductStatus,
+  to: ProductStatus,
+  reason: &str,
+) -> Result<(), DBError> {
+  let path = "products.store.product_status_update";
+  let de = |err: BoxedErr, err_type: ErrorType, msg: &str| {
+    DBError::new(err_type, err, msg, path, "".to_string())
+  };
+
+  let now = time_get_millis() as i64;
+  let db = &*s.db.get().await;
+  let mut tx = db
+    .begin()
+    .await
+    .map_err(|err| de(Box::new(err), ErrorType::DBConnectionError, "failed to begin transaction"))?;
+
+  let result = sqlx::query(
+    r#"
+    UPDATE products SET
+      status = $3,
+      updated_at = $4,
+      published_at = CASE WHEN $3 = 'published'
+        THEN COALESCE(published_at, $4) ELSE published_at END,
+      version = version + 1
+    WHERE id = $1 AND status = $2
+    "#,
+  )
+  .bind(id)
+  .bind(from.as_str())
+  .bind(to.as_str())
+  .bind(now)
+  .execute(&mut *tx)
+  .await
+  .map_err(|err| de(Box::new(err), ErrorType::DBUpdateError, "failed to update product status"))?;
+
+  if result.rows_affected() == 0 {
+    let err = std::io::Error::new(std::io::ErrorKind::NotFound, "the product status has changed");
+    return Err(de(Box::new(err), ErrorType::NoRows, "the product status has changed"));
+  }
+
+  sqlx::query(
+    r#"
+    INSERT INTO product_status_transitions (
+      id, product_id, from_status, to_status, reason, user_id, created_at
+    ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+    "#,
+  )
+  .bind(Ulid::new().to_string())
+  .bind(id)
+  .bind(from.as_str())
+  .bind(to.as_str())
+  .bind(reason)
+  .bind(ctx.session().user_id())
+  .bind(now)
+  .execute(&mut *tx)
+  .await
+  .map_err(|err| {
+    de(Box::new(err), ErrorType::DBInsertError, "failed to insert product status transition")
+  })?;
+
+  tx.commit()
+    .await
+    .map_err(|err| de(Box::new(err), ErrorType::DBUpdateError, "failed to commit transaction"))?;
+
+  Ok(())
+}
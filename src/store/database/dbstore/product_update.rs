@@ -0,0 +1,115 @@
+use std::sync::Arc;
+
+use megacommerce_proto::Product;
+use megacommerce_shared::models::context::Context;
+use megacommerce_shared::models::errors::{BoxedErr, ErrorType};
+use megacommerce_shared::store::errors::DBError;
+use serde_json::{to_value, Value};
+use ulid::Ulid;
+
+use crate::store::database::dbstore::ProductsStoreImpl;
+
+/// Writes the editable columns of the product if the stored version still equals
+/// `expected_version`, and returns the bumped version. A stale version (or a missing
+/// product) results in a `NoRows` error. When the edit moves the product out of
+/// `previous_status` (back to review), the transition is recorded in the same transaction.
+pub(super) async fn product_update(
+  s: &ProductsStoreImpl,
+  ctx: Arc<Context>,
+  pro: &Product,
+  expected_version: u32,
+  previous_status: &str,
+) -> Result<u32, DBError> {
+  let mk_err = |msg: &str, err: BoxedErr, typ: Option<ErrorType>| DBError {
+    err_type: typ.unwrap_or(ErrorType::JsonMarshal),
+    err,
+    msg: msg.into(),
+    path: "products.store.product_update".into(),
+    details: "".into(),
+  };
+
+  let bullet_points: Value = to_value(&pro.bullet_points)
+    .map_err(|e| mk_err("failed to serialize the product's bullet_points", Box::new(e), None))?;
+  let offer: Value = to_value(pro.offer.as_ref())
+    .map_err(|e| mk_err("failed to serialize the product's offer", Box::new(e), None))?;
+  let safety: Value = to_value(pro.safety.as_ref())
+    .map_err(|e| mk_err("failed to serialize the product's safety", Box::new(e), None))?;
+
+  let db = &*s.db.get().await;
+  let mut tx = db.begin().await.map_err(|e| {
+    mk_err("failed to begin transaction", Box::new(e), Some(ErrorType::DBConnectionError))
+  })?;
+
+  let version: Option<i16> = sqlx::query_scalar(
+    r#"
+    UPDATE products SET
+        title = $3, brand_name = $4, has_brand_name = $5, product_id = $6,
+        has_product_id = $7, product_id_type = $8, description = $9, bullet_points = $10,
+        currency_code = $11, fulfillment_type = $12, processing_time = $13, offer = $14,
+        safety = $15, slug = $16, updated_at = $17, status = $18, version = version + 1
+    WHERE id = $1 AND version = $2
+    RETURNING version
+  "#,
+  )
+  .bind(&pro.id)
+  .bind(expected_version as i32)
+  .bind(&pro.title)
+  .bind(&pro.brand_name)
+  .bind(&pro.has_brand_name)
+  .bind(&pro.product_id)
+  .bind(&pro.has_product_id)
+  .bind(&pro.product_id_type)
+  .bind(&pro.description)
+  .bind(&bullet_points)
+  .bind(&pro.currency_code)
+  .bind(&pro.fulfillment_type)
+  .bind(pro.processing_time as i64)
+  .bind(&offer)
+  .bind(&safety)
+  .bind(&pro.slug)
+  .bind(pro.updated_at.map(|t| t as i64))
+  .bind(&pro.status)
+  .fetch_optional(&mut *tx)
+  .await
+  .map_err(|e| mk_err("failed to update a product", Box::new(e), Some(ErrorType::DBUpdateError)))?;
+
+  let version = match version {
+    Some(v) => v as u32,
+    None => {
+      return Err(mk_err(
+        "the product is not found or its version is stale",
+        Box::new(std::io::Error::new(std::io::ErrorKind::NotFound, "stale product version")),
+        Some(ErrorType::NoRows),
+      ))
+    }
+  };
+
+  if pro.status != previous_status {
+    sqlx::query(
+      r#"
+      INSERT INTO product_status_transitions (
+        id, product_id, from_status, to_status, reason, user_id, created_at
+      ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+      "#,
+    )
+    .bind(Ulid::new().to_string())
+    .bind(&pro.id)
+    .bind(previous_status)
+    .bind(&pro.status)
+    .bind("the product was edited, it's reviewed again")
+    .bind(ctx.session().user_id())
+    .bind(pro.updated_at.map(|t| t as i64))
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| {
+      let msg = "failed to insert product status transition";
+      mk_err(msg, Box::new(e), Some(ErrorType::DBInsertError))
+    })?;
+  }
+
+  tx.commit().await.map_err(|e| {
+    mk_err("failed to commit transaction", Box::new(e), Some(ErrorType::DBUpdateError))
+  })?;
+
+  Ok(version)
+}
@@ -0,0 +1,61 @@
+use std::sync::Arc;
+
+use megacommerce_proto::ProductOfferVariant;
+use megacommerce_shared::{
+  models::{
+    context::Context,
+    errors::{BoxedErr, ErrorType},
+  },
+  store::errors::DBError,
+};
+use serde_json::{from_value, Value};
+
+use crate::{models::price_quote::VariantStock, store::database::dbstore::ProductsStoreImpl};
+
+/// The offer of a variant of a published product, along with its reserved units.
+/// A missing (or unpublished) product or variant results in a `NoRows` error.
+pub(super) async fn product_variant_stock(
+  s: &ProductsStoreImpl,
+  _ctx: Arc<Context>,
+  product_id: &str,
+  variant_id: &str,
+) -> Result<VariantStock, DBError> {
+  let path = "products.store.product_variant_stock";
+  let de = |err: BoxedErr, err_type: ErrorType, msg: &str| {
+    DBError::new(err_type, err, msg, path, "".to_string())
+  };
+
+  let db = &*s.db.get().await;
+  let row: Option<(Option<Value>, String, i64)> = sqlx::query_as(
+    r#"
+    SELECT
+      p.offer -> 'offer' -> $2,
+      p.currency_code,
+      COALESCE((
+        SELECT SUM(ii.quantity_reserved) FROM inventory_items AS ii
+        WHERE ii.product_id = p.id AND ii.variant_id = $2
+      ), 0)::BIGINT
+    FROM products AS p
+    WHERE p.id = $1 AND p.status = 'published'
+    "#,
+  )
+  .bind(product_id)
+  .bind(variant_id)
+  .fetch_optional(db)
+  .await
+  .map_err(|err| de(Box::new(err), ErrorType::DBSelectError, "failed to select the variant"))?;
+
+  let (variant, currency_code, reserved) = match row {
+    Some((Some(variant), currency_code, reserved)) => (variant, currency_code, reserved),
+    _ => {
+      let msg = "the product or its variant is not found";
+      let err = std::io::Error::new(std::io::ErrorKind::NotFound, msg);
+      return Err(de(Box::new(err), ErrorType::NoRows, msg));
+    }
+  };
+
+  let variant: ProductOfferVariant = from_value(variant).map_err(|err| {
+    de(Box::new(err), ErrorType::JsonUnmarshal, "failed to deserialize the variant's offer")
+  })?;
+  Ok(VariantStock { variant, currency_code, reserved })
+}
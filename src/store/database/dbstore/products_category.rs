@@ -1,4 +1,4 @@
-use sqlx::{FromRow, QueryBuilder};
+use sqlx::{FromRow, Postgres, QueryBuilder};
 use std::sync::Arc;
 
 use megacommerce_proto::{ProductMedia, ProductOffer, ProductsCategoryItem};
@@ -8,19 +8,60 @@ use megacommerce_shared::{
     errors::{BoxedErr, ErrorType},
   },
   store::errors::DBError,
+  utils::time::time_get_millis,
 };
 use serde_json::from_value;
 
-use crate::store::database::dbstore::ProductsStoreImpl;
+use crate::{
+  models::{
+    inventory::variants_inventory,
+    pagination::{KeysetPage, ListTotal, PageCursor, PageCursorKey, PagePosition},
+    product_reviews::product_rating_average,
+    products::variant_card_image,
+    products_facets::ProductFacetFilter,
+    variant_selection::{display_variant, VariantSelection},
+  },
+  store::database::dbstore::{
+    inventory::{IN_STOCK_SQL, VARIANTS_INVENTORY_SQL},
+    keyset::{keyset_page, KeysetOrder},
+    list_total::list_total,
+    product_sales::sale_active_sql,
+    products_category_facets::push_facet_exists,
+    ProductsStoreImpl,
+  },
+};
 
 #[derive(FromRow)]
-struct ProductCategoryRow {
+pub(super) struct ProductCategoryRow {
   id: String,
+  user_id: String,
   title: String,
   media: serde_json::Value,
   offer: serde_json::Value,
+  currency_code: String,
   created_at: i64,
   sold_count: i64,
+  rating_sum: i64,
+  rating_count: i64,
+  min_price: f64,
+  /// The `inventory_items` rows of the product's variants, see `variants_inventory`
+  stock: Option<serde_json::Value>,
+  /// The relevance of a search match, not selected by the other queries
+  #[sqlx(default)]
+  score: Option<f64>,
+}
+
+impl ProductCategoryRow {
+  /// The position of the row in an order by `min_price`, `created_at` or `score`
+  pub(super) fn position(&self, order: &KeysetOrder) -> PagePosition {
+    let key = match order.key {
+      Some("min_price") => Some(PageCursorKey::Float(self.min_price)),
+      Some("created_at") => Some(PageCursorKey::Int(self.created_at)),
+      Some("score") => self.score.map(PageCursorKey::Float),
+      _ => None,
+    };
+    PagePosition { key, id: self.id.clone() }
+  }
 }
 
 pub(super) async fn products_category(
@@ -28,12 +69,13 @@ pub(super) async fn products_category(
   _ctx: Arc<Context>,
   category_id: &str,
   subcategory_ids: &[String],
-  page: u32,
-  last_id: &str,
+  cursor: Option<&PageCursor>,
   limit: i64,
   sort_by: Option<&str>,
   sort_direction: Option<&str>,
-) -> Result<Vec<ProductsCategoryItem>, DBError> {
+  facets: &[ProductFacetFilter],
+  in_stock: bool,
+) -> Result<KeysetPage<ProductsCategoryItem>, DBError> {
   let path = "products.store.products_category".to_string();
   let de = |err: BoxedErr, msg: &str, err_type: Option<ErrorType>| {
     DBError::new(
@@ -47,149 +89,174 @@ pub(super) async fn products_category(
 
   let db = &*s.db.get().await;
 
-  let order_clause = match (sort_by, sort_direction) {
-    (Some("price"), Some("asc")) => "min_price ASC, id ASC",
-    (Some("price"), Some("desc")) | (Some("price"), _) => "min_price DESC, id DESC",
-    (Some("created_at"), Some("asc")) => "created_at ASC, id ASC",
-    (Some("created_at"), Some("desc")) | (Some("created_at"), _) => "created_at DESC, id DESC",
-    _ => "created_at DESC, id DESC",
+  let (key, descending) = match (sort_by, sort_direction) {
+    (Some("price"), Some("asc")) => ("min_price", false),
+    (Some("price"), _) => ("min_price", true),
+    (Some("created_at"), Some("asc")) => ("created_at", false),
+    _ => ("created_at", true),
   };
+  let order = KeysetOrder { key: Some(key), id: "id", descending };
 
   // --- Start of the QueryBuilder implementation ---
-  let mut query_builder = QueryBuilder::new(
+  let sale_active = sale_active_sql("value");
+  let mut query_builder = QueryBuilder::new(format!(
     r#"
     WITH product_variants AS (
         SELECT
             p.id,
+            p.user_id,
             p.title,
             p.media,
             p.offer,
+            p.currency_code,
             p.created_at,
-            COALESCE(SUM(ii.quantity_reserved), 0)::BIGINT as sold_count,
-            (SELECT MIN(
+            p.rating_sum,
+            p.rating_count::BIGINT as rating_count,
+            COALESCE(SUM(ii.quantity_sold), 0)::BIGINT as sold_count,
+            {VARIANTS_INVENTORY_SQL} as stock,
+            COALESCE((SELECT MIN(
                 CASE
-                    WHEN (value->>'sale_price') IS NOT NULL AND (value->>'sale_price')::numeric > 0
+                    WHEN {sale_active}
                     THEN (value->>'sale_price')::numeric
                     ELSE (value->>'price')::numeric
                 END
-            ) FROM jsonb_each(p.offer->'offer'))::FLOAT8 as min_price
+            ) FROM jsonb_each(p.offer->'offer')), 0)::FLOAT8 as min_price
         FROM products AS p
         LEFT JOIN inventory_items AS ii ON ii.product_id = p.id
         WHERE
-            p.category = 
-    "#,
-  );
-
-  query_builder.push_bind(category_id);
-
-  query_builder.push(" AND p.status = 'published'");
-
-  if !subcategory_ids.is_empty() {
-    query_builder.push(" AND p.subcategory = ANY(");
-    query_builder.push_bind(subcategory_ids);
-    query_builder.push("::TEXT[])");
-  }
+    "#
+  ));
 
-  if page > 1 {
-    query_builder.push(" AND p.id < ");
-    query_builder.push_bind(last_id);
-  }
+  push_category_filters(&mut query_builder, category_id, subcategory_ids, facets, in_stock);
 
   query_builder.push(
     r#"
-        GROUP BY p.id, p.title, p.media, p.offer, p.created_at, min_price
+        GROUP BY p.id, p.user_id, p.title, p.media, p.offer, p.currency_code, p.created_at,
+            min_price
     )
     SELECT
         id,
+        user_id,
         title,
         media,
         offer,
+        currency_code,
         created_at,
+        rating_sum,
+        rating_count,
         sold_count,
+        stock,
         min_price
     FROM product_variants
-    ORDER BY 
+    WHERE TRUE
     "#,
   );
 
-  query_builder.push(order_clause);
+  // the sort key is computed, so the keyset applies to the outer query
+  order.push_condition(&mut query_builder, cursor);
+  order.push_order_by(&mut query_builder, cursor);
 
+  // one more row than asked for, which tells if there's a next page
   query_builder.push(" LIMIT ");
-  query_builder.push_bind(limit);
+  query_builder.push_bind(limit + 1);
 
   let rows =
     query_builder.build_query_as::<ProductCategoryRow>().fetch_all(db).await.map_err(|err| {
       de(Box::new(err), "failed to fetch products from database", Some(ErrorType::DBSelectError))
     })?;
 
-  // --- The rest of your post-processing logic remains the same ---
-  let products: Vec<ProductsCategoryItem> = rows
-    .into_iter()
-    .filter_map(|row| {
-      let offer_data: ProductOffer = from_value(row.offer)
-        .map_err(|err| de(Box::new(err), "failed to deserialize offer", None))
-        .ok()?;
-
-      let media: ProductMedia = from_value(row.media)
-        .map_err(|err| de(Box::new(err), "failed to deserialize media", None))
-        .ok()?;
-
-      let (variant_id, offer_variant) = offer_data.offer.into_iter().min_by(|(_, a), (_, b)| {
-        let price_a =
-          if a.has_sale_price { a.sale_price.as_deref().unwrap_or(&a.price) } else { &a.price };
-
-        let price_b =
-          if b.has_sale_price { b.sale_price.as_deref().unwrap_or(&b.price) } else { &b.price };
-
-        price_a.partial_cmp(price_b).unwrap_or(std::cmp::Ordering::Equal)
-      })?;
-
-      let image_url = media
-        .media
-        .get(&variant_id)
-        .and_then(|vm| vm.images.values().next())
-        .map(|img| img.url.clone())
-        .unwrap_or_default();
-
-      let price_str = if offer_variant.has_sale_price {
-        offer_variant.sale_price.as_deref().unwrap_or(&offer_variant.price)
-      } else {
-        &offer_variant.price
-      };
-
-      let price = price_str.parse::<f64>().ok()?;
-      let price_cents = (price * 100.0).round() as u32;
-
-      let (discount_price_cents, discount_percentage) =
-        if let Some(sale_price_str) = &offer_variant.sale_price {
-          if let (Ok(original_price), Ok(sale_price)) =
-            (offer_variant.price.parse::<f64>(), sale_price_str.parse::<f64>())
-          {
-            let disc_cents = (sale_price * 100.0).round() as u32;
-            let disc_pct = ((original_price - sale_price) / original_price * 100.0).round() as u32;
-            (Some(disc_cents), Some(disc_pct))
-          } else {
-            (None, None)
-          }
-        } else {
-          (None, None)
-        };
-
-      Some(ProductsCategoryItem {
-        id: row.id,
-        variant_id: variant_id.to_string(),
-        title: row.title,
-        image: image_url,
-        price_cents,
-        discount_price_cents,
-        discount_percentage,
-        sold_by: "Megacommerce".to_string(),
-        rating: None,
-        sold_count: Some(row.sold_count as u32),
-        created_at: row.created_at as u64,
-      })
-    })
-    .collect();
-
-  Ok(products)
+  let now = time_get_millis();
+  let item = |row| products_category_item(row, s.variant_selection, now);
+  Ok(keyset_page(rows, cursor, limit, |row| row.position(&order), item))
+}
+
+/// Estimates (or counts, for the small ones) the products the category list pages through
+pub(super) async fn products_category_total(
+  s: &ProductsStoreImpl,
+  _ctx: Arc<Context>,
+  category_id: &str,
+  subcategory_ids: &[String],
+  facets: &[ProductFacetFilter],
+  in_stock: bool,
+  exact_max: u64,
+) -> Result<ListTotal, DBError> {
+  let path = "products.store.products_category_total";
+  let db = &*s.db.get().await;
+
+  list_total(db, exact_max, |query_builder| {
+    query_builder.push("SELECT p.id FROM products AS p WHERE ");
+    push_category_filters(query_builder, category_id, subcategory_ids, facets, in_stock);
+  })
+  .await
+  .map_err(|err| {
+    let msg = "failed to count the category products";
+    DBError::new(ErrorType::DBSelectError, Box::new(err), msg, path, "".to_string())
+  })
+}
+
+/// Appends the conditions of the category's published products (aliased `p`), only the ones
+/// with a variant left to order if `in_stock`
+fn push_category_filters<'a>(
+  query_builder: &mut QueryBuilder<'a, Postgres>,
+  category_id: &'a str,
+  subcategory_ids: &'a [String],
+  facets: &[ProductFacetFilter],
+  in_stock: bool,
+) {
+  query_builder.push(" p.category = ");
+  query_builder.push_bind(category_id);
+
+  query_builder.push(" AND p.status = 'published'");
+
+  if !subcategory_ids.is_empty() {
+    query_builder.push(" AND p.subcategory = ANY(");
+    query_builder.push_bind(subcategory_ids);
+    query_builder.push("::TEXT[])");
+  }
+
+  for facet in facets.iter() {
+    query_builder.push(" AND ");
+    push_facet_exists(query_builder, facet);
+  }
+
+  if in_stock {
+    query_builder.push(" AND ");
+    query_builder.push(IN_STOCK_SQL);
+  }
+}
+
+/// Builds the list item out of the display variant of the product at `now`, rows with an
+/// undecodable offer or media are skipped. The seller's name and rating are
+/// left to the caller, which looks up the sellers of the whole page at once.
+pub(super) fn products_category_item(
+  row: ProductCategoryRow,
+  policy: VariantSelection,
+  now: u64,
+) -> Option<ProductsCategoryItem> {
+  let offer_data: ProductOffer = from_value(row.offer).ok()?;
+  let media: ProductMedia = from_value(row.media).ok()?;
+
+  let stock = variants_inventory(row.stock);
+  let display = display_variant(policy, &offer_data, &stock, &row.currency_code, now)?;
+  let (prices, level) = (display.prices, display.level);
+
+  Some(ProductsCategoryItem {
+    id: row.id,
+    variant_id: display.id.to_string(),
+    title: row.title,
+    image: variant_card_image(&media, display.id),
+    price_cents: prices.selling().minor_units(),
+    discount_price_cents: prices.sale_price.map(|p| p.minor_units()),
+    discount_percentage: prices.discount_percentage(),
+    seller_id: row.user_id,
+    sold_by: String::new(),
+    seller_rating: None,
+    rating: product_rating_average(row.rating_sum, row.rating_count),
+    sold_count: Some(row.sold_count as u32),
+    created_at: row.created_at as u64,
+    currency_code: row.currency_code,
+    display_price: None,
+    in_stock: level.in_stock(),
+    low_stock: level.low_stock(),
+  })
 }
@@ -0,0 +1,97 @@
+use sqlx::{Postgres, QueryBuilder};
+use std::sync::Arc;
+
+use megacommerce_shared::{
+  models::{context::Context, errors::ErrorType},
+  store::errors::DBError,
+};
+
+use crate::{
+  models::products_facets::{ProductFacetCount, ProductFacetFilter},
+  store::database::dbstore::{inventory::IN_STOCK_SQL, ProductsStoreImpl},
+};
+
+/// Appends an `EXISTS (...)` condition, which matches the products (aliased `p`) that have
+/// any of the filter values in `product_attributes`
+pub(super) fn push_facet_exists(
+  query_builder: &mut QueryBuilder<Postgres>,
+  facet: &ProductFacetFilter,
+) {
+  query_builder.push("EXISTS (SELECT 1 FROM product_attributes AS fa WHERE fa.product_id = p.id");
+  query_builder.push(" AND fa.name = ");
+  query_builder.push_bind(facet.name.clone());
+  query_builder.push(" AND fa.value = ANY(");
+  query_builder.push_bind(facet.values.clone());
+  query_builder.push("::TEXT[]))");
+}
+
+/// Counts the published products of the category per (facet, value). The count of a facet's
+/// values applies every other facet filter but its own, so picking a value doesn't hide
+/// the alternatives of the same facet. With `in_stock`, only the products with a variant
+/// left to order are counted, as the listing shows them.
+pub(super) async fn products_category_facets(
+  s: &ProductsStoreImpl,
+  _ctx: Arc<Context>,
+  category_id: &str,
+  subcategory_ids: &[String],
+  names: &[String],
+  filters: &[ProductFacetFilter],
+  in_stock: bool,
+) -> Result<Vec<ProductFacetCount>, DBError> {
+  let path = "products.store.products_category_facets";
+  if names.is_empty() {
+    return Ok(vec![]);
+  }
+
+  let db = &*s.db.get().await;
+  let mut query_builder = QueryBuilder::new(
+    r#"
+    SELECT pa.name, pa.value, COUNT(DISTINCT p.id)::BIGINT AS count
+    FROM products AS p
+    JOIN product_attributes AS pa ON pa.product_id = p.id
+    WHERE p.status = 'published' AND p.category = 
+    "#,
+  );
+  query_builder.push_bind(category_id);
+
+  if !subcategory_ids.is_empty() {
+    query_builder.push(" AND p.subcategory = ANY(");
+    query_builder.push_bind(subcategory_ids);
+    query_builder.push("::TEXT[])");
+  }
+
+  query_builder.push(" AND pa.name = ANY(");
+  query_builder.push_bind(names);
+  query_builder.push("::TEXT[])");
+
+  for facet in filters.iter() {
+    query_builder.push(" AND (pa.name = ");
+    query_builder.push_bind(facet.name.clone());
+    query_builder.push(" OR ");
+    push_facet_exists(&mut query_builder, facet);
+    query_builder.push(")");
+  }
+
+  if in_stock {
+    query_builder.push(" AND ");
+    query_builder.push(IN_STOCK_SQL);
+  }
+
+  query_builder.push(" GROUP BY pa.name, pa.value");
+
+  let rows = query_builder
+    .build_query_as::<(String, String, i64)>()
+    .fetch_all(db)
+    .await
+    .map_err(|err| {
+      let msg = "failed to count the category facets";
+      DBError::new(ErrorType::DBSelectError, Box::new(err), msg, path, "".to_string())
+    })?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(|(name, value, count)| ProductFacetCount { name, value, count: count as u64 })
+      .collect(),
+  )
+}
@@ -7,19 +7,32 @@ use megacommerce_shared::{
     errors::{BoxedErr, ErrorType},
   },
   store::errors::DBError,
+  utils::time::time_get_millis,
 };
-use serde_json::from_value;
-use sqlx::Row;
+use serde_json::{from_value, Value};
+use sqlx::{postgres::PgRow, QueryBuilder, Row};
 
-use crate::store::database::dbstore::ProductsStoreImpl;
+use crate::{
+  models::{
+    inventory::variants_inventory,
+    money::Money,
+    pagination::{KeysetPage, PageCursor, PagePosition},
+    products::variant_card_image,
+    variant_selection::display_variant,
+  },
+  store::database::dbstore::{
+    inventory::VARIANTS_INVENTORY_SQL,
+    keyset::{keyset_page, KeysetOrder},
+    ProductsStoreImpl,
+  },
+};
 
 pub(super) async fn products_list(
   s: &ProductsStoreImpl,
   ctx: Arc<Context>,
-  page: u32,
-  last_id: &str,
+  cursor: Option<&PageCursor>,
   limit: i64,
-) -> Result<Vec<ProductListItem>, DBError> {
+) -> Result<KeysetPage<ProductListItem>, DBError> {
   let path = "products.store.products_list";
   let de = |err: BoxedErr, msg: &str, err_type: Option<ErrorType>| -> DBError {
     DBError::new(
@@ -34,9 +47,9 @@ pub(super) async fn products_list(
   let db = &*s.db.get().await;
   let user_id = ctx.session().user_id.clone();
 
-  let where_clause = if page > 1 { " AND p.id < $2 " } else { "" };
+  let order = KeysetOrder { key: None, id: "p.id", descending: true };
 
-  let sql = format!(
+  let mut query_builder = QueryBuilder::new(format!(
     r#"
         SELECT
             p.id,
@@ -45,26 +58,30 @@ pub(super) async fn products_list(
             p.created_at,
             p.offer,
             p.media,
-            p.currency_code
+            p.currency_code,
+            {VARIANTS_INVENTORY_SQL} AS stock
         FROM products AS p
-        WHERE p.user_id = $1 {}
-        ORDER BY p.id DESC
-        LIMIT ${}
-        "#,
-    where_clause,
-    if page > 1 { "3" } else { "2" }
-  );
-
-  let rows = if page > 1 {
-    sqlx::query(&sql).bind(user_id).bind(last_id).bind(limit).fetch_all(db).await
-  } else {
-    sqlx::query(&sql).bind(user_id).bind(limit).fetch_all(db).await
-  }
-  .map_err(|err| de(Box::new(err), "failed to fetch products from database", None))?;
-
+        WHERE p.user_id = "#
+  ));
+  query_builder.push_bind(user_id);
+  order.push_condition(&mut query_builder, cursor);
+  order.push_order_by(&mut query_builder, cursor);
+  // one more row than asked for, which tells if there's a next page
+  query_builder.push(" LIMIT ");
+  query_builder.push_bind(limit + 1);
+
+  let rows = query_builder
+    .build()
+    .fetch_all(db)
+    .await
+    .map_err(|err| de(Box::new(err), "failed to fetch products from database", None))?;
+
+  let position = |row: &PgRow| PagePosition { key: None, id: row.get("id") };
+  let page = keyset_page(rows, cursor, limit, position, Some);
   let mut products = Vec::new();
+  let (policy, now) = (s.variant_selection, time_get_millis());
 
-  for row in rows {
+  for row in page.items {
     let offer_data: ProductOffer = from_value(row.get("offer")).map_err(|err| {
       de(Box::new(err), "failed to deserialize product's offer", Some(ErrorType::JsonUnmarshal))
     })?;
@@ -72,31 +89,22 @@ pub(super) async fn products_list(
       de(Box::new(err), "failed to deserialize product's media", Some(ErrorType::JsonUnmarshal))
     })?;
 
-    // Get first variant
-    let offer_vec: Vec<(String, _)> = offer_data.offer.into_iter().collect();
-    let (variant_id, offer_variant) = match offer_vec.get(0) {
-      Some((vid, ov)) => (vid.clone(), ov),
-      None => {
-        continue;
-      }
+    // the same variant the storefront shows the product with
+    let currency_code: String = row.get("currency_code");
+    let stock = variants_inventory(row.get::<Option<Value>, _>("stock"));
+    let display = match display_variant(policy, &offer_data, &stock, &currency_code, now) {
+      Some(display) => display,
+      None => continue,
     };
+    let (offer_variant, image_url) = (display.variant, variant_card_image(&media, display.id));
 
-    // Get first image for this variant
-    let image_url = if let Some(variant_media) = media.media.get(&variant_id) {
-      if let Some((_, image)) = variant_media.images.iter().next() {
-        image.url.clone()
-      } else {
-        "".to_string()
-      }
-    } else {
-      "".to_string()
-    };
+    let amount = |value: &str| Money::parse(value, &currency_code).ok().map(|m| m.to_f64());
 
-    let price = offer_variant.price.parse::<f64>().unwrap_or(0.0);
+    let price = amount(&offer_variant.price).unwrap_or(0.0);
 
-    let list_price = offer_variant.list_price.as_ref().and_then(|lp| lp.parse::<f64>().ok());
+    let list_price = offer_variant.list_price.as_deref().and_then(amount);
 
-    let sale_price = offer_variant.sale_price.as_ref().and_then(|sp| sp.parse::<f64>().ok());
+    let sale_price = offer_variant.sale_price.as_deref().and_then(amount);
 
     products.push(ProductListItem {
       id: row.get("id"),
@@ -106,11 +114,17 @@ pub(super) async fn products_list(
       price,
       list_price,
       sale_price,
-      currency_code: row.get("currency_code"),
+      currency_code,
       quantity: offer_variant.quantity as i32,
       image: image_url,
     });
   }
 
-  Ok(products)
+  Ok(KeysetPage {
+    items: products,
+    has_more: page.has_more,
+    first: page.first,
+    last: page.last,
+    snapshot: page.snapshot,
+  })
 }
@@ -0,0 +1,185 @@
+use sqlx::{Postgres, QueryBuilder};
+use std::sync::Arc;
+
+use megacommerce_proto::ProductsCategoryItem;
+use megacommerce_shared::{
+  models::{context::Context, errors::ErrorType},
+  store::errors::DBError,
+  utils::time::time_get_millis,
+};
+
+use crate::{
+  models::{
+    pagination::{KeysetPage, ListTotal, PageCursor},
+    products_search::{ProductsSearchFilters, ProductsSearchSort},
+  },
+  store::database::dbstore::{
+    inventory::VARIANTS_INVENTORY_SQL,
+    keyset::{keyset_page, KeysetOrder},
+    list_total::list_total,
+    product_sales::sale_active_sql,
+    products_category::{products_category_item, ProductCategoryRow},
+    ProductsStoreImpl,
+  },
+};
+
+/// Full text search over the title, brand, description and bullet points of the published
+/// products (the generated `search_vector` column). By default the matches are ranked by
+/// their text rank (`score`), weighted by the sold quantity so popular products win close ties.
+pub(super) async fn products_search(
+  s: &ProductsStoreImpl,
+  _ctx: Arc<Context>,
+  filters: &ProductsSearchFilters,
+  cursor: Option<&PageCursor>,
+  limit: i64,
+) -> Result<KeysetPage<ProductsCategoryItem>, DBError> {
+  let path = "products.store.products_search";
+  let db = &*s.db.get().await;
+
+  let (key, descending) = match filters.sort {
+    ProductsSearchSort::Relevance => ("score", true),
+    ProductsSearchSort::PriceAsc => ("min_price", false),
+    ProductsSearchSort::PriceDesc => ("min_price", true),
+    ProductsSearchSort::Newest => ("created_at", true),
+    ProductsSearchSort::Oldest => ("created_at", false),
+  };
+  let order = KeysetOrder { key: Some(key), id: "id", descending };
+
+  let mut query_builder = QueryBuilder::new("");
+  push_search_matches(&mut query_builder, filters);
+
+  order.push_condition(&mut query_builder, cursor);
+  order.push_order_by(&mut query_builder, cursor);
+  // one more row than asked for, which tells if there's a next page
+  query_builder.push(" LIMIT ");
+  query_builder.push_bind(limit + 1);
+
+  let rows =
+    query_builder.build_query_as::<ProductCategoryRow>().fetch_all(db).await.map_err(|err| {
+      let msg = "failed to search the products";
+      DBError::new(ErrorType::DBSelectError, Box::new(err), msg, path, "".to_string())
+    })?;
+
+  let now = time_get_millis();
+  let item = |row| products_category_item(row, s.variant_selection, now);
+  Ok(keyset_page(rows, cursor, limit, |row| row.position(&order), item))
+}
+
+/// Estimates (or counts, for the few matches) the products the search pages through
+pub(super) async fn products_search_total(
+  s: &ProductsStoreImpl,
+  _ctx: Arc<Context>,
+  filters: &ProductsSearchFilters,
+  exact_max: u64,
+) -> Result<ListTotal, DBError> {
+  let path = "products.store.products_search_total";
+  let db = &*s.db.get().await;
+
+  list_total(db, exact_max, |query_builder| push_search_matches(query_builder, filters))
+    .await
+    .map_err(|err| {
+      let msg = "failed to count the search matches";
+      DBError::new(ErrorType::DBSelectError, Box::new(err), msg, path, "".to_string())
+    })
+}
+
+/// Appends the query of the filtered matches, without any order
+fn push_search_matches<'a>(
+  query_builder: &mut QueryBuilder<'a, Postgres>,
+  filters: &'a ProductsSearchFilters,
+) {
+  let sale_active = sale_active_sql("value");
+  // the exact amount is filtered on, the float one only orders the keyset
+  let min_price = format!(
+    r#"COALESCE((SELECT MIN(
+                CASE
+                    WHEN {sale_active}
+                    THEN (value->>'sale_price')::numeric
+                    ELSE (value->>'price')::numeric
+                END
+            ) FROM jsonb_each(p.offer->'offer')), 0)"#
+  );
+  query_builder.push(format!(
+    r#"
+    WITH matches AS (
+        SELECT
+            p.id,
+            p.user_id,
+            p.title,
+            p.media,
+            p.offer,
+            p.currency_code,
+            p.created_at,
+            p.rating_sum,
+            p.rating_count::BIGINT as rating_count,
+            COALESCE(SUM(ii.quantity_sold), 0)::BIGINT as sold_count,
+            {VARIANTS_INVENTORY_SQL} as stock,
+            {min_price} as min_price_amount,
+            {min_price}::FLOAT8 as min_price,
+            (ts_rank(p.search_vector, q.query)
+                * (1 + LN(1 + COALESCE(SUM(ii.quantity_sold), 0))))::FLOAT8 as score
+        FROM products AS p
+        CROSS JOIN websearch_to_tsquery('english', 
+    "#
+  ));
+  query_builder.push_bind(&filters.query);
+  query_builder.push(
+    r#") AS q(query)
+        LEFT JOIN inventory_items AS ii ON ii.product_id = p.id
+        WHERE p.status = 'published' AND p.search_vector @@ q.query
+    "#,
+  );
+
+  if let Some(category_id) = &filters.category_id {
+    query_builder.push(" AND p.category = ");
+    query_builder.push_bind(category_id);
+  }
+
+  if !filters.subcategory_ids.is_empty() {
+    query_builder.push(" AND p.subcategory = ANY(");
+    query_builder.push_bind(&filters.subcategory_ids);
+    query_builder.push("::TEXT[])");
+  }
+
+  if let Some(range) = &filters.price_range {
+    query_builder.push(" AND p.currency_code = ");
+    query_builder.push_bind(range.currency.code);
+  }
+
+  if filters.on_sale {
+    query_builder.push(format!(
+      r#"
+        AND EXISTS (
+            SELECT 1 FROM jsonb_each(p.offer->'offer') AS v
+            WHERE {}
+        )
+      "#,
+      sale_active_sql("v.value")
+    ));
+  }
+
+  query_builder.push(
+    r#"
+        GROUP BY p.id, q.query
+    )
+    SELECT
+        id, user_id, title, media, offer, currency_code, created_at, rating_sum, rating_count,
+        sold_count, stock, min_price, score
+    FROM matches
+    WHERE TRUE
+    "#,
+  );
+
+  if let Some(range) = &filters.price_range {
+    if let Some(min) = &range.min {
+      query_builder.push(" AND min_price_amount >= ");
+      query_builder.push_bind(min.to_string());
+      query_builder.push("::NUMERIC");
+    }
+    if let Some(max) = &range.max {
+      query_builder.push(" AND min_price_amount <= ");
+      query_builder.push_bind(max.to_string());
+      query_builder.push("::NUMERIC");
+    }
+  }
+}
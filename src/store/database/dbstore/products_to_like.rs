@@ -1,144 +1,174 @@
 use std::io::{Error, ErrorKind};
 
-use bigdecimal::ToPrimitive;
-use megacommerce_proto::{
-  ProductMedia, ProductOffer, ProductOfferVariant, ProductPrice, ProductToLikeListItem,
-};
+use megacommerce_proto::{ProductMedia, ProductOffer, ProductPrice, ProductToLikeListItem};
 use megacommerce_shared::{
   models::{
     context::Context,
     errors::{BoxedErr, ErrorType},
   },
   store::errors::DBError,
+  utils::time::time_get_millis,
 };
-use rand::Rng;
 use serde_json::from_value;
-use sqlx::FromRow;
+use sqlx::{FromRow, QueryBuilder};
 use std::sync::Arc;
 
-use crate::store::database::dbstore::ProductsStoreImpl;
+use crate::{
+  models::{
+    inventory::variants_inventory,
+    pagination::{KeysetPage, PageCursor, PagePosition},
+    product_reviews::product_rating_average,
+    products::variant_card_image,
+    variant_selection::{display_variant, VariantSelection},
+  },
+  store::database::dbstore::{
+    inventory::VARIANTS_INVENTORY_SQL,
+    keyset::{keyset_page, KeysetOrder},
+    ProductsStoreImpl,
+  },
+};
 
 // Helper struct for type-safe database row mapping
 #[derive(FromRow)]
-struct ProductRow {
-  id: String,
+pub(super) struct ProductRow {
+  pub(super) id: String,
   title: String,
   media: serde_json::Value,
   offer: serde_json::Value,
+  currency_code: String,
+  rating_sum: i64,
+  rating_count: i64,
   sold_count: i64,
+  stock: Option<serde_json::Value>,
+  /// The recommendation score, only selected by `products_recommended`
+  #[sqlx(default)]
+  pub(super) score: f64,
 }
 
 pub(super) async fn products_to_like(
   s: &ProductsStoreImpl,
-  _ctx: Arc<Context>,
-  page: u32,
-  last_id: &str,
+  ctx: Arc<Context>,
+  cursor: Option<&PageCursor>,
   limit: i64,
-) -> Result<Vec<ProductToLikeListItem>, DBError> {
+) -> Result<KeysetPage<ProductToLikeListItem>, DBError> {
   let path = "products.store.products_to_like";
-  let de = |err: BoxedErr, msg: &str, err_type: Option<ErrorType>| -> DBError {
-    DBError::new(
-      err_type.unwrap_or(ErrorType::DBSelectError),
-      err,
-      msg.to_string(),
-      path,
-      "".to_string(),
-    )
-  };
+  let de = products_to_like_err(path);
 
   let db = &*s.db.get().await;
+  let lang = &ctx.accept_language;
 
-  let where_clause = if page > 1 { " WHERE p.id < $1 " } else { "" };
-  let limit_clause = if page > 1 { " LIMIT $2 " } else { " LIMIT $1 " };
+  let order = KeysetOrder { key: None, id: "p.id", descending: true };
 
   // Use a more robust query with aggregation to avoid duplicate products
-  let sql = format!(
+  let mut query_builder = QueryBuilder::new(format!(
     r#"
         SELECT
             p.id,
             p.title,
             p.media,
             p.offer,
-            COALESCE(SUM(ii.quantity_reserved), 0)::BIGINT as sold_count
+            p.currency_code,
+            p.rating_sum,
+            p.rating_count::BIGINT as rating_count,
+            COALESCE(SUM(ii.quantity_sold), 0)::BIGINT as sold_count,
+            {VARIANTS_INVENTORY_SQL} as stock
         FROM products AS p
         LEFT JOIN inventory_items AS ii ON ii.product_id = p.id
-        {}
-        GROUP BY p.id, p.title, p.media, p.offer
-        ORDER BY p.id DESC
-        {}
+        WHERE TRUE
         "#,
-    where_clause, limit_clause,
-  );
-
-  let query_builder = sqlx::query_as::<_, ProductRow>(&sql);
+  ));
+  order.push_condition(&mut query_builder, cursor);
+  query_builder.push(" GROUP BY p.id, p.title, p.media, p.offer, p.currency_code");
+  order.push_order_by(&mut query_builder, cursor);
+  // one more row than asked for, which tells if there's a next page
+  query_builder.push(" LIMIT ");
+  query_builder.push_bind(limit + 1);
 
   let rows =
-    if page > 1 { query_builder.bind(last_id).bind(limit) } else { query_builder.bind(limit) }
-      .fetch_all(db)
-      .await
-      .map_err(|err| {
-        de(Box::new(err), "failed to fetch products from database", Some(ErrorType::DBSelectError))
-      })?;
-
-  let products: Vec<ProductToLikeListItem> = rows
+    query_builder.build_query_as::<ProductRow>().fetch_all(db).await.map_err(|err| {
+      de(Box::new(err), "failed to fetch products from database", Some(ErrorType::DBSelectError))
+    })?;
+
+  let position = |row: &ProductRow| PagePosition { key: None, id: row.id.clone() };
+  let page = keyset_page(rows, cursor, limit, position, Some);
+  let (policy, now) = (s.variant_selection, time_get_millis());
+  let products = page
+    .items
     .into_iter()
-    .map(|row| {
-      let offer_data: ProductOffer = from_value(row.offer)
-        .map_err(|err| de(Box::new(err), "failed to deserialize product's offer", None))?;
-      let media: ProductMedia = from_value(row.media)
-        .map_err(|err| de(Box::new(err), "failed to deserialize product's media", None))?;
-
-      let offer_vec: Vec<(String, ProductOfferVariant)> = offer_data.offer.into_iter().collect();
-      let (variant_id, offer_variant) = offer_vec.into_iter().next().ok_or_else(|| {
-        de(
-          Box::new(Error::new(ErrorKind::NotFound, "Product has no variants")),
-          "No variant found",
-          Some(ErrorType::NotFound),
-        )
-      })?;
-
-      let image_url = media
-        .media
-        .get(&variant_id)
-        .and_then(|variant_media| variant_media.images.values().next())
-        .map(|img| img.url.clone())
-        .unwrap_or_default();
-
-      let price = offer_variant
-        .price
-        .parse::<f64>()
-        .map_err(|err| de(Box::new(err), "failed to parse price", None))?;
-      let price_cents = (price * 100.0).round() as u32;
-      let formatted_price = format!("${:.2}", price);
-
-      let mut product_price =
-        ProductPrice { amount: price, formatted: formatted_price, ..Default::default() };
-
-      if let Some(sale_price_str) = &offer_variant.sale_price {
-        let sale_price = sale_price_str.parse::<f64>().map_err(|err| {
-          de(Box::new(err), "failed to parse sale price", Some(ErrorType::InvalidNumber))
-        })?;
-        let discount_price_cents = (sale_price * 100.0).round() as u32;
-        let save_amount_cents = price_cents - discount_price_cents;
-        let save_percentage = ((price - sale_price) / price * 100.0).round() as u32;
-
-        product_price.discount_price = Some(sale_price);
-        product_price.save_amount = Some(format!("${:.2}", save_amount_cents as f64 / 100.0));
-        product_price.save_percentage = Some(format!("{}%", save_percentage));
-      }
-
-      Ok(ProductToLikeListItem {
-        id: row.id,
-        variant_id: variant_id.to_string(),
-        title: row.title,
-        image: image_url,
-        price: Some(product_price),
-        rating: (rand::rng().random_range(10..49) / 10).to_f64(),
-        sold: Some(row.sold_count as i32),
-        meta: vec![],
-      })
-    })
+    .map(|row| products_to_like_item(row, policy, now, lang, path))
     .collect::<Result<Vec<ProductToLikeListItem>, DBError>>()?;
 
-  Ok(products)
+  Ok(KeysetPage {
+    items: products,
+    has_more: page.has_more,
+    first: page.first,
+    last: page.last,
+    snapshot: page.snapshot,
+  })
+}
+
+fn products_to_like_err(
+  path: &str,
+) -> impl Fn(BoxedErr, &str, Option<ErrorType>) -> DBError + '_ {
+  move |err: BoxedErr, msg: &str, err_type: Option<ErrorType>| {
+    DBError::new(
+      err_type.unwrap_or(ErrorType::DBSelectError),
+      err,
+      msg.to_string(),
+      path,
+      "".to_string(),
+    )
+  }
+}
+
+/// Builds the list item out of the row, shown with its display variant at `now`
+pub(super) fn products_to_like_item(
+  row: ProductRow,
+  policy: VariantSelection,
+  now: u64,
+  lang: &str,
+  path: &str,
+) -> Result<ProductToLikeListItem, DBError> {
+  let de = products_to_like_err(path);
+  let offer_data: ProductOffer = from_value(row.offer)
+    .map_err(|err| de(Box::new(err), "failed to deserialize product's offer", None))?;
+  let media: ProductMedia = from_value(row.media)
+    .map_err(|err| de(Box::new(err), "failed to deserialize product's media", None))?;
+
+  let stock = variants_inventory(row.stock);
+  let display = display_variant(policy, &offer_data, &stock, &row.currency_code, now);
+  let display = display.ok_or_else(|| {
+    de(
+      Box::new(Error::new(ErrorKind::NotFound, "Product has no variant with a valid price")),
+      "No variant found",
+      Some(ErrorType::NotFound),
+    )
+  })?;
+  let (prices, level) = (display.prices, display.level);
+  let price = prices.price;
+
+  let mut product_price = ProductPrice {
+    amount: price.to_f64(),
+    formatted: price.format(lang),
+    ..Default::default()
+  };
+
+  if let Some(sale_price) = prices.sale_price {
+    product_price.discount_price = Some(sale_price.to_f64());
+    product_price.save_amount = price.checked_sub(&sale_price).map(|saved| saved.format(lang));
+    product_price.save_percentage = Some(format!("{}%", price.discount_percentage(&sale_price)));
+  }
+
+  Ok(ProductToLikeListItem {
+    id: row.id,
+    variant_id: display.id.to_string(),
+    title: row.title,
+    image: variant_card_image(&media, display.id),
+    price: Some(product_price),
+    rating: product_rating_average(row.rating_sum, row.rating_count),
+    sold: Some(row.sold_count as i32),
+    meta: vec![],
+    in_stock: level.in_stock(),
+    low_stock: level.low_stock(),
+  })
 }
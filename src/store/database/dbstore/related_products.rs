@@ -0,0 +1,238 @@
+use std::{
+  collections::HashMap,
+  io::{Error, ErrorKind},
+  sync::Arc,
+};
+
+use megacommerce_proto::{ProductMedia, ProductOffer, RelatedProductListItem};
+use megacommerce_shared::{
+  models::{
+    context::Context,
+    errors::{BoxedErr, ErrorType},
+  },
+  store::errors::DBError,
+  utils::time::time_get_millis,
+};
+use serde_json::{from_value, Value};
+use sqlx::FromRow;
+
+use crate::{
+  models::{
+    inventory::variants_inventory,
+    product_reviews::product_rating_average,
+    products::variant_card_image,
+    related_products::{related_ranked, RelatedProductsWeights, RelatedSignals},
+    variant_selection::display_variant,
+  },
+  store::database::dbstore::{inventory::VARIANTS_INVENTORY_SQL, ProductsStoreImpl},
+};
+
+/// The lowest regular price of the variants of `p`
+const MIN_PRICE_SQL: &str = r#"
+  (SELECT MIN((v.value->>'price')::NUMERIC) FROM jsonb_each(p.offer->'offer') AS v)
+  ::DOUBLE PRECISION"#;
+
+/// The lowercased names of the tags of `p`
+const TAG_NAMES_SQL: &str = r#"
+  ARRAY(
+    SELECT DISTINCT LOWER(t->>'name') FROM jsonb_array_elements(COALESCE(p.tags, '[]')) AS t
+    WHERE t->>'name' IS NOT NULL
+  )"#;
+
+#[derive(FromRow)]
+struct SignalsRow {
+  id: String,
+  brand: Option<String>,
+  currency_code: String,
+  price: Option<f64>,
+  tags: Vec<String>,
+  attributes: Option<f64>,
+}
+
+impl From<SignalsRow> for RelatedSignals {
+  fn from(row: SignalsRow) -> Self {
+    Self {
+      id: row.id,
+      brand: row.brand,
+      currency_code: row.currency_code,
+      price: row.price,
+      tags: row.tags,
+      attributes: row.attributes,
+    }
+  }
+}
+
+#[derive(FromRow)]
+struct SourceRow {
+  category: String,
+  subcategory: String,
+  #[sqlx(flatten)]
+  signals: SignalsRow,
+}
+
+#[derive(FromRow)]
+struct RelatedRow {
+  id: String,
+  title: String,
+  media: Value,
+  offer: Value,
+  currency_code: String,
+  rating_sum: i64,
+  rating_count: i64,
+  stock: Option<Value>,
+}
+
+/// The products of the same subcategory most similar to the product, by the weighted sum of
+/// their shared attribute values, brand, price proximity and tags, see `related_ranked`.
+/// Only a bounded pool of candidates is scored: the newest `pool` published products of the
+/// subcategory, and up to `pool` more of them sharing an attribute value with the product.
+/// Only the best scored ones are then read in full.
+pub(super) async fn related_products(
+  s: &ProductsStoreImpl,
+  _ctx: Arc<Context>,
+  product_id: &str,
+  weights: RelatedProductsWeights,
+  pool: i64,
+  limit: i64,
+) -> Result<Vec<RelatedProductListItem>, DBError> {
+  let path = "products.store.related_products";
+  let de = |err: BoxedErr, err_type: ErrorType, msg: &str| {
+    DBError::new(err_type, err, msg, path, "".to_string())
+  };
+
+  let db = &*s.db.get().await;
+
+  let source: SourceRow = sqlx::query_as(&format!(
+    r#"
+    SELECT
+      p.category,
+      p.subcategory,
+      p.id,
+      LOWER(NULLIF(TRIM(p.brand_name), '')) AS brand,
+      p.currency_code,
+      {MIN_PRICE_SQL} AS price,
+      {TAG_NAMES_SQL} AS tags,
+      NULL::DOUBLE PRECISION AS attributes
+    FROM products AS p
+    WHERE p.id = $1 AND p.status = 'published'
+    "#
+  ))
+  .bind(product_id)
+  .fetch_optional(db)
+  .await
+  .map_err(|err| de(Box::new(err), ErrorType::DBSelectError, "failed to select the product"))?
+  .ok_or_else(|| {
+    let err = Box::new(Error::new(ErrorKind::NotFound, "the product is not found"));
+    de(err, ErrorType::NoRows, "the product is not found")
+  })?;
+
+  let candidates: Vec<SignalsRow> = sqlx::query_as(&format!(
+    r#"
+    WITH source_attributes AS (
+      SELECT name, value FROM product_attributes WHERE product_id = $1
+    ), candidates AS (
+      (
+        SELECT p.id FROM products AS p
+        WHERE p.category = $2 AND p.subcategory = $3 AND p.status = 'published' AND p.id <> $1
+        ORDER BY p.id DESC
+        LIMIT $4
+      )
+      UNION
+      (
+        SELECT DISTINCT pa.product_id FROM product_attributes AS pa
+        INNER JOIN source_attributes AS sa ON sa.name = pa.name AND sa.value = pa.value
+        INNER JOIN products AS p ON p.id = pa.product_id
+        WHERE p.category = $2 AND p.subcategory = $3 AND p.status = 'published' AND p.id <> $1
+        ORDER BY pa.product_id DESC
+        LIMIT $4
+      )
+    )
+    SELECT
+      p.id,
+      LOWER(NULLIF(TRIM(p.brand_name), '')) AS brand,
+      p.currency_code,
+      {MIN_PRICE_SQL} AS price,
+      {TAG_NAMES_SQL} AS tags,
+      (
+        SELECT COUNT(*) FROM product_attributes AS pa
+        INNER JOIN source_attributes AS sa ON sa.name = pa.name AND sa.value = pa.value
+        WHERE pa.product_id = p.id
+      )::DOUBLE PRECISION / NULLIF((SELECT COUNT(*) FROM source_attributes), 0) AS attributes
+    FROM candidates AS c
+    INNER JOIN products AS p ON p.id = c.id
+    "#
+  ))
+  .bind(product_id)
+  .bind(&source.category)
+  .bind(&source.subcategory)
+  .bind(pool)
+  .fetch_all(db)
+  .await
+  .map_err(|err| {
+    de(Box::new(err), ErrorType::DBSelectError, "failed to select the related candidates")
+  })?;
+
+  let source = RelatedSignals::from(source.signals);
+  let candidates: Vec<RelatedSignals> = candidates.into_iter().map(Into::into).collect();
+  let ranked = related_ranked(&weights, &source, &candidates, limit.max(0) as usize);
+  if ranked.is_empty() {
+    return Ok(vec![]);
+  }
+
+  let rows: Vec<RelatedRow> = sqlx::query_as(&format!(
+    r#"
+    SELECT
+      p.id,
+      p.title,
+      p.media,
+      p.offer,
+      p.currency_code,
+      p.rating_sum,
+      p.rating_count::BIGINT AS rating_count,
+      {VARIANTS_INVENTORY_SQL} AS stock
+    FROM products AS p
+    WHERE p.id = ANY($1)
+    "#
+  ))
+  .bind(&ranked)
+  .fetch_all(db)
+  .await
+  .map_err(|err| {
+    de(Box::new(err), ErrorType::DBSelectError, "failed to select the related products")
+  })?;
+
+  // back in the order of the scores
+  let mut rows: HashMap<String, RelatedRow> =
+    rows.into_iter().map(|row| (row.id.clone(), row)).collect();
+  let rows: Vec<RelatedRow> = ranked.iter().filter_map(|id| rows.remove(id)).collect();
+
+  let (policy, now) = (s.variant_selection, time_get_millis());
+  let products = rows
+    .into_iter()
+    .filter_map(|row| {
+      let offer: ProductOffer = from_value(row.offer).ok()?;
+      let media: ProductMedia = from_value(row.media).ok()?;
+      let stock = variants_inventory(row.stock);
+      // no variant with a valid price, nothing to show
+      let display = display_variant(policy, &offer, &stock, &row.currency_code, now)?;
+      let (prices, level) = (display.prices, display.level);
+
+      Some(RelatedProductListItem {
+        id: row.id,
+        variant_id: display.id.to_string(),
+        title: row.title,
+        image: variant_card_image(&media, display.id),
+        price_cents: prices.price.minor_units(),
+        discount_price_cents: prices.sale_price.map(|p| p.minor_units()),
+        discount_percentage: prices.discount_percentage(),
+        rating: product_rating_average(row.rating_sum, row.rating_count),
+        currency_code: row.currency_code,
+        display_price: None,
+        in_stock: level.in_stock(),
+        low_stock: level.low_stock(),
+      })
+    })
+    .collect();
+
+  Ok(products)
+}
@@ -1,38 +1,142 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use megacommerce_proto::{
   BestSellingProductListItem, BigDiscountProductListItem, CategoryNavbarResponseData,
   HeroProductsResponseData, NewlyAddedProductListItem, Product, ProductDetailsResponseData,
-  ProductListItem, ProductsCategoryItem, ProductSnapshot, ProductSnapshotRequest,
-  ProductToLikeListItem,
+  ProductListItem, ProductRating, ProductReview, ProductsCategoryItem, ProductSnapshot,
+  ProductSnapshotRequest, ProductToLikeListItem, RelatedProductListItem,
 };
 use megacommerce_shared::{models::context::Context, store::errors::DBError};
 
+use crate::models::{
+  best_selling::BestSellingWindow,
+  inventory::{InventoryOutcome, ReservationStatus},
+  pagination::{KeysetPage, ListTotal, PageCursor},
+  price_quote::VariantStock,
+  product_events::ProductEvent,
+  product_reviews::ProductReviewStatus,
+  products::ProductStatus,
+  products_facets::{ProductFacetCount, ProductFacetFilter},
+  products_search::ProductsSearchFilters,
+  recommendations::{RecommendationCandidates, RecommendationWeights},
+  related_products::RelatedProductsWeights,
+  sellers::SellerProfile,
+};
+use crate::server::object_storage::ObjectStorage;
 use crate::store::database::{
   dbstore::{
-    best_selling_products::best_selling_products, big_discount_products::big_discount_products,
+    best_selling_products::best_selling_products,
+    best_selling_rankings::{best_selling_ranking, best_selling_rankings_refresh},
+    big_discount_products::big_discount_products,
     category_navbar::category_navbar, hero_products::hero_products,
+    inventory::{
+      inventory_reservations_expire, inventory_reserve, inventory_restock, inventory_settle,
+    },
     newly_added_products::newly_added_products, product_create::product_create,
-    product_details::product_details, product_snapshot::product_snapshot,
-    products_category::products_category, products_list::products_list,
-    products_to_like::products_to_like, ProductsStoreImpl,
+    product_details::product_details, product_events::product_events_insert,
+    product_get::product_get,
+    product_media_uploads::{
+      product_media_uploads_add, product_media_uploads_recorded, product_media_uploads_release,
+      product_media_uploads_reserved, product_media_uploads_stale,
+    },
+    product_recommendations::{
+      product_recommendation_snapshots_expire, product_similarities_refresh, products_recommended,
+    },
+    product_reviews::{
+      product_rating, product_review_create, product_review_get, product_review_moderate,
+      product_reviews_list,
+    },
+    product_sales::products_sales_expire,
+    product_snapshot::product_snapshot, product_status_update::product_status_update,
+    product_update::product_update, product_variant_stock::product_variant_stock,
+    products_category::{products_category, products_category_total},
+    products_category_facets::products_category_facets, products_list::products_list,
+    products_search::{products_search, products_search_total}, products_to_like::products_to_like,
+    related_products::related_products, seller_profiles::seller_profiles, ProductsStoreImpl,
   },
   ProductsStore,
 };
 
 #[tonic::async_trait]
 impl ProductsStore for ProductsStoreImpl {
-  async fn product_create(&self, ctx: Arc<Context>, product: &Product) -> Result<(), DBError> {
-    product_create(self, ctx, product).await
+  async fn product_create(
+    &self,
+    ctx: Arc<Context>,
+    product: &Product,
+    attributes: &[(String, String)],
+  ) -> Result<(), DBError> {
+    product_create(self, ctx, product, attributes).await
+  }
+  async fn product_get(&self, ctx: Arc<Context>, id: &str) -> Result<Product, DBError> {
+    product_get(self, ctx, id).await
+  }
+  async fn product_update(
+    &self,
+    ctx: Arc<Context>,
+    product: &Product,
+    expected_version: u32,
+    previous_status: &str,
+  ) -> Result<u32, DBError> {
+    product_update(self, ctx, product, expected_version, previous_status).await
+  }
+  async fn product_status_update(
+    &self,
+    ctx: Arc<Context>,
+    id: &str,
+    from: ProductStatus,
+    to: ProductStatus,
+    reason: &str,
+  ) -> Result<(), DBError> {
+    product_status_update(self, ctx, id, from, to, reason).await
+  }
+  async fn product_media_uploads_add(
+    &self,
+    ctx: Arc<Context>,
+    product_id: &str,
+    keys: &[String],
+  ) -> Result<(), DBError> {
+    product_media_uploads_add(self, ctx, product_id, keys).await
+  }
+  async fn product_media_uploads_recorded(
+    &self,
+    ctx: Arc<Context>,
+    product_id: &str,
+    keys: &[String],
+  ) -> Result<Vec<String>, DBError> {
+    product_media_uploads_recorded(self, ctx, product_id, keys).await
+  }
+  async fn product_media_uploads_reserved(
+    &self,
+    ctx: Arc<Context>,
+    product_id: &str,
+    prefix: &str,
+  ) -> Result<bool, DBError> {
+    product_media_uploads_reserved(self, ctx, product_id, prefix).await
+  }
+  async fn product_media_uploads_stale(
+    &self,
+    ctx: Arc<Context>,
+    before: u64,
+    limit: i64,
+  ) -> Result<Vec<(String, String)>, DBError> {
+    product_media_uploads_stale(self, ctx, before, limit).await
+  }
+  async fn product_media_uploads_release(
+    &self,
+    ctx: Arc<Context>,
+    product_id: &str,
+    keys: &[String],
+    storage: &ObjectStorage,
+  ) -> Result<(), DBError> {
+    product_media_uploads_release(self, ctx, product_id, keys, storage).await
   }
   async fn products_to_like(
     &self,
     ctx: Arc<Context>,
-    page: u32,
-    last_id: &str,
+    cursor: Option<&PageCursor>,
     limit: i64,
-  ) -> Result<Vec<ProductToLikeListItem>, DBError> {
-    products_to_like(self, ctx, page, last_id, limit).await
+  ) -> Result<KeysetPage<ProductToLikeListItem>, DBError> {
+    products_to_like(self, ctx, cursor, limit).await
   }
   async fn product_snapshot(
     &self,
@@ -82,21 +186,241 @@ impl ProductsStore for ProductsStoreImpl {
     ctx: Arc<Context>,
     category_id: &str,
     subcategory_ids: &[String],
-    page: u32,
-    last_id: &str,
+    cursor: Option<&PageCursor>,
     limit: i64,
     sort_by: Option<&str>,
     sort_direction: Option<&str>,
-  ) -> Result<Vec<ProductsCategoryItem>, DBError> {
-    products_category(self, ctx, category_id, subcategory_ids, page, last_id, limit, sort_by, sort_direction).await
+    facets: &[ProductFacetFilter],
+    in_stock: bool,
+  ) -> Result<KeysetPage<ProductsCategoryItem>, DBError> {
+    products_category(
+      self,
+      ctx,
+      category_id,
+      subcategory_ids,
+      cursor,
+      limit,
+      sort_by,
+      sort_direction,
+      facets,
+      in_stock,
+    )
+    .await
+  }
+  async fn products_category_facets(
+    &self,
+    ctx: Arc<Context>,
+    category_id: &str,
+    subcategory_ids: &[String],
+    names: &[String],
+    filters: &[ProductFacetFilter],
+    in_stock: bool,
+  ) -> Result<Vec<ProductFacetCount>, DBError> {
+    products_category_facets(self, ctx, category_id, subcategory_ids, names, filters, in_stock)
+      .await
+  }
+  async fn products_category_total(
+    &self,
+    ctx: Arc<Context>,
+    category_id: &str,
+    subcategory_ids: &[String],
+    facets: &[ProductFacetFilter],
+    in_stock: bool,
+    exact_max: u64,
+  ) -> Result<ListTotal, DBError> {
+    products_category_total(self, ctx, category_id, subcategory_ids, facets, in_stock, exact_max)
+      .await
+  }
+  async fn products_search(
+    &self,
+    ctx: Arc<Context>,
+    filters: &ProductsSearchFilters,
+    cursor: Option<&PageCursor>,
+    limit: i64,
+  ) -> Result<KeysetPage<ProductsCategoryItem>, DBError> {
+    products_search(self, ctx, filters, cursor, limit).await
+  }
+  async fn products_search_total(
+    &self,
+    ctx: Arc<Context>,
+    filters: &ProductsSearchFilters,
+    exact_max: u64,
+  ) -> Result<ListTotal, DBError> {
+    products_search_total(self, ctx, filters, exact_max).await
   }
   async fn products_list(
     &self,
     ctx: Arc<Context>,
-    page: u32,
-    last_id: &str,
+    cursor: Option<&PageCursor>,
+    limit: i64,
+  ) -> Result<KeysetPage<ProductListItem>, DBError> {
+    products_list(self, ctx, cursor, limit).await
+  }
+  async fn product_review_create(
+    &self,
+    ctx: Arc<Context>,
+    review: &ProductReview,
+  ) -> Result<ProductReview, DBError> {
+    product_review_create(self, ctx, review).await
+  }
+  async fn product_review_get(
+    &self,
+    ctx: Arc<Context>,
+    id: &str,
+  ) -> Result<ProductReview, DBError> {
+    product_review_get(self, ctx, id).await
+  }
+  async fn product_reviews_list(
+    &self,
+    ctx: Arc<Context>,
+    product_id: &str,
+    verified_only: bool,
+    cursor: Option<&PageCursor>,
+    limit: i64,
+  ) -> Result<KeysetPage<ProductReview>, DBError> {
+    product_reviews_list(self, ctx, product_id, verified_only, cursor, limit).await
+  }
+  async fn product_review_moderate(
+    &self,
+    ctx: Arc<Context>,
+    id: &str,
+    from: ProductReviewStatus,
+    to: ProductReviewStatus,
+    reason: &str,
+  ) -> Result<(), DBError> {
+    product_review_moderate(self, ctx, id, from, to, reason).await
+  }
+  async fn product_rating(
+    &self,
+    ctx: Arc<Context>,
+    product_id: &str,
+  ) -> Result<ProductRating, DBError> {
+    product_rating(self, ctx, product_id).await
+  }
+  async fn seller_profiles(
+    &self,
+    ctx: Arc<Context>,
+    ids: &[String],
+  ) -> Result<HashMap<String, SellerProfile>, DBError> {
+    seller_profiles(self, ctx, ids).await
+  }
+  async fn products_sales_expire(
+    &self,
+    ctx: Arc<Context>,
+    now: u64,
+    limit: i64,
+  ) -> Result<u64, DBError> {
+    products_sales_expire(self, ctx, now, limit).await
+  }
+  async fn product_variant_stock(
+    &self,
+    ctx: Arc<Context>,
+    product_id: &str,
+    variant_id: &str,
+  ) -> Result<VariantStock, DBError> {
+    product_variant_stock(self, ctx, product_id, variant_id).await
+  }
+  async fn inventory_reserve(
+    &self,
+    ctx: Arc<Context>,
+    key: &str,
+    product_id: &str,
+    variant_id: &str,
+    quantity: u32,
+    user_id: &str,
+  ) -> Result<InventoryOutcome, DBError> {
+    inventory_reserve(self, ctx, key, product_id, variant_id, quantity, user_id).await
+  }
+  async fn inventory_settle(
+    &self,
+    ctx: Arc<Context>,
+    key: &str,
+    reservation_id: &str,
+    to: ReservationStatus,
+  ) -> Result<InventoryOutcome, DBError> {
+    inventory_settle(self, ctx, key, reservation_id, to).await
+  }
+  async fn inventory_restock(
+    &self,
+    ctx: Arc<Context>,
+    key: &str,
+    product_id: &str,
+    variant_id: &str,
+    quantity: u32,
+    low_stock_threshold: Option<u32>,
+  ) -> Result<InventoryOutcome, DBError> {
+    inventory_restock(self, ctx, key, product_id, variant_id, quantity, low_stock_threshold)
+      .await
+  }
+  async fn inventory_reservations_expire(
+    &self,
+    ctx: Arc<Context>,
+    before: u64,
+    limit: i64,
+  ) -> Result<u64, DBError> {
+    inventory_reservations_expire(self, ctx, before, limit).await
+  }
+  async fn best_selling_rankings_refresh(
+    &self,
+    ctx: Arc<Context>,
+    now: u64,
+    size: i64,
+  ) -> Result<u64, DBError> {
+    best_selling_rankings_refresh(self, ctx, now, size).await
+  }
+  async fn best_selling_ranking(
+    &self,
+    ctx: Arc<Context>,
+    window: BestSellingWindow,
+    category_id: Option<&str>,
+    cursor: Option<&PageCursor>,
+    limit: i64,
+  ) -> Result<KeysetPage<BestSellingProductListItem>, DBError> {
+    best_selling_ranking(self, ctx, window, category_id, cursor, limit).await
+  }
+  async fn product_similarities_refresh(
+    &self,
+    ctx: Arc<Context>,
+    since: u64,
+    now: u64,
+    per_product: i64,
+  ) -> Result<u64, DBError> {
+    product_similarities_refresh(self, ctx, since, now, per_product).await
+  }
+  async fn product_recommendation_snapshots_expire(
+    &self,
+    ctx: Arc<Context>,
+    before: u64,
+  ) -> Result<u64, DBError> {
+    product_recommendation_snapshots_expire(self, ctx, before).await
+  }
+  async fn products_recommended(
+    &self,
+    ctx: Arc<Context>,
+    user_id: &str,
+    since: u64,
+    weights: RecommendationWeights,
+    candidates: RecommendationCandidates,
+    cursor: Option<&PageCursor>,
+    limit: i64,
+  ) -> Result<KeysetPage<ProductToLikeListItem>, DBError> {
+    products_recommended(self, ctx, user_id, since, weights, candidates, cursor, limit).await
+  }
+  async fn product_events_insert(
+    &self,
+    ctx: Arc<Context>,
+    events: &[ProductEvent],
+  ) -> Result<u64, DBError> {
+    product_events_insert(self, ctx, events).await
+  }
+  async fn related_products(
+    &self,
+    ctx: Arc<Context>,
+    product_id: &str,
+    weights: RelatedProductsWeights,
+    pool: i64,
     limit: i64,
-  ) -> Result<Vec<ProductListItem>, DBError> {
-    products_list(self, ctx, page, last_id, limit).await
+  ) -> Result<Vec<RelatedProductListItem>, DBError> {
+    related_products(self, ctx, product_id, weights, pool, limit).await
   }
 }
@@ -0,0 +1,50 @@
+use std::{collections::HashMap, sync::Arc};
+
+use megacommerce_shared::{
+  models::{context::Context, errors::ErrorType},
+  store::errors::DBError,
+};
+
+use crate::{
+  models::{product_reviews::product_rating_average, sellers::SellerProfile},
+  store::database::dbstore::ProductsStoreImpl,
+};
+
+/// The profiles of the sellers, keyed by their id, in one query for a whole page.
+/// Sellers without a profile are left out.
+pub(super) async fn seller_profiles(
+  s: &ProductsStoreImpl,
+  _ctx: Arc<Context>,
+  ids: &[String],
+) -> Result<HashMap<String, SellerProfile>, DBError> {
+  let path = "products.store.seller_profiles";
+  if ids.is_empty() {
+    return Ok(HashMap::new());
+  }
+
+  let db = &*s.db.get().await;
+  let rows: Vec<(String, String, i64, i64)> = sqlx::query_as(
+    r#"
+    SELECT user_id, display_name, rating_sum, rating_count::BIGINT
+    FROM sellers
+    WHERE user_id = ANY($1::TEXT[])
+    "#,
+  )
+  .bind(ids)
+  .fetch_all(db)
+  .await
+  .map_err(|err| {
+    let msg = "failed to select the seller profiles";
+    DBError::new(ErrorType::DBSelectError, Box::new(err), msg, path, "".to_string())
+  })?;
+
+  Ok(
+    rows
+      .into_iter()
+      .map(|(id, display_name, rating_sum, rating_count)| {
+        let rating = product_rating_average(rating_sum, rating_count);
+        (id.clone(), SellerProfile { id, display_name, rating })
+      })
+      .collect(),
+  )
+}
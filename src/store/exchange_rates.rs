@@ -0,0 +1,94 @@
+use std::{collections::HashMap, fmt, path::PathBuf, str::FromStr, sync::Arc, time::Duration};
+
+use megacommerce_shared::{
+  models::{errors::BoxedErr, r_lock::RLock},
+  utils::time::time_get_millis,
+};
+use parking_lot::RwLock;
+use rust_decimal::Decimal;
+use sqlx::{Pool, Postgres};
+
+use crate::models::exchange_rates::ExchangeRates;
+
+/// Where the exchange rates come from
+#[tonic::async_trait]
+pub trait ExchangeRatesProvider: fmt::Debug + Send + Sync {
+  async fn exchange_rates(&self) -> Result<ExchangeRates, BoxedErr>;
+}
+
+fn rates_parse(rates: Vec<(String, String)>) -> Result<ExchangeRates, BoxedErr> {
+  let mut parsed = HashMap::with_capacity(rates.len());
+  for (code, rate) in rates.into_iter() {
+    let rate = Decimal::from_str(rate.trim())
+      .map_err(|err| format!("invalid exchange rate of {}: {}", code, err))?;
+    if rate <= Decimal::ZERO {
+      return Err(format!("the exchange rate of {} must be greater than 0", code).into());
+    }
+    parsed.insert(code.to_uppercase(), rate);
+  }
+  Ok(ExchangeRates { rates: parsed, fetched_at: time_get_millis() })
+}
+
+/// Reads the rates from the `exchange_rates` table, which is kept up to date outside the service
+#[derive(Debug)]
+pub struct DbExchangeRates {
+  pub db: RLock<Pool<Postgres>>,
+}
+
+#[tonic::async_trait]
+impl ExchangeRatesProvider for DbExchangeRates {
+  async fn exchange_rates(&self) -> Result<ExchangeRates, BoxedErr> {
+    let db = &*self.db.get().await;
+    let rows: Vec<(String, String)> =
+      sqlx::query_as("SELECT currency_code, rate::TEXT FROM exchange_rates")
+        .fetch_all(db)
+        .await?;
+    rates_parse(rows)
+  }
+}
+
+/// Reads the rates from a JSON object of currency codes to rates, e.g. `{"EUR": "0.92"}`
+#[derive(Debug)]
+pub struct FileExchangeRates {
+  pub path: PathBuf,
+}
+
+#[tonic::async_trait]
+impl ExchangeRatesProvider for FileExchangeRates {
+  async fn exchange_rates(&self) -> Result<ExchangeRates, BoxedErr> {
+    let content = tokio::fs::read_to_string(&self.path).await?;
+    let rates: HashMap<String, String> = serde_json::from_str(&content)?;
+    rates_parse(rates.into_iter().collect())
+  }
+}
+
+/// The last rates loaded from the provider, see `ExchangeRatesRefresher`
+#[derive(Debug)]
+pub struct ExchangeRatesCache {
+  provider: Arc<dyn ExchangeRatesProvider>,
+  rates: RwLock<Arc<ExchangeRates>>,
+  max_age: Duration,
+}
+
+impl ExchangeRatesCache {
+  pub fn new(provider: Arc<dyn ExchangeRatesProvider>, max_age: Duration) -> Self {
+    Self { provider, rates: RwLock::new(Arc::new(ExchangeRates::default())), max_age }
+  }
+
+  /// Replaces the rates, the current ones are kept if the provider fails
+  pub async fn refresh(&self) -> Result<(), BoxedErr> {
+    let rates = self.provider.exchange_rates().await?;
+    *self.rates.write() = Arc::new(rates);
+    Ok(())
+  }
+
+  /// The rates, none if they were never loaded or are older than the max age
+  pub fn get(&self) -> Option<Arc<ExchangeRates>> {
+    let rates = self.rates.read().clone();
+    let age = time_get_millis().saturating_sub(rates.fetched_at);
+    if rates.rates.is_empty() || age > self.max_age.as_millis() as u64 {
+      return None;
+    }
+    Some(rates)
+  }
+}
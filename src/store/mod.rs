@@ -1,2 +1,4 @@
 pub mod cache;
 pub mod database;
+pub mod exchange_rates;
+pub mod recommender;
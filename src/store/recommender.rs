@@ -0,0 +1,94 @@
+use std::{fmt, sync::Arc, time::Duration};
+
+use megacommerce_proto::ProductToLikeListItem;
+use megacommerce_shared::{
+  models::context::Context, store::errors::DBError, utils::time::time_get_millis,
+};
+
+use crate::{
+  models::{
+    pagination::{KeysetPage, PageCursor},
+    recommendations::{RecommendationCandidates, RecommendationWeights},
+  },
+  store::database::ProductsStore,
+};
+
+/// Picks the "products to like" of the session in the context
+#[tonic::async_trait]
+pub trait ProductsRecommender: fmt::Debug + Send + Sync {
+  /// The sort the pagination cursors of the recommendations are issued for
+  fn sort(&self) -> &'static str;
+  async fn products_to_like(
+    &self,
+    ctx: Arc<Context>,
+    cursor: Option<&PageCursor>,
+    limit: i64,
+  ) -> Result<KeysetPage<ProductToLikeListItem>, DBError>;
+}
+
+/// The newest products first, the same for every session
+#[derive(Debug)]
+pub struct BaselineRecommender {
+  pub store: Arc<dyn ProductsStore + Send + Sync>,
+}
+
+#[tonic::async_trait]
+impl ProductsRecommender for BaselineRecommender {
+  fn sort(&self) -> &'static str {
+    "id:desc"
+  }
+
+  async fn products_to_like(
+    &self,
+    ctx: Arc<Context>,
+    cursor: Option<&PageCursor>,
+    limit: i64,
+  ) -> Result<KeysetPage<ProductToLikeListItem>, DBError> {
+    self.store.products_to_like(ctx, cursor, limit).await
+  }
+}
+
+/// Scores the products by the user's events over the `history`, see `products_recommended`.
+/// The similarities it reads are kept up to date by `ProductSimilaritiesBuilder`.
+/// On a cold start (no rankings nor similarities yet) nothing is scored, the `fallback`
+/// lists the products instead.
+#[derive(Debug)]
+pub struct PersonalizedRecommender {
+  pub store: Arc<dyn ProductsStore + Send + Sync>,
+  pub fallback: BaselineRecommender,
+  pub weights: RecommendationWeights,
+  pub candidates: RecommendationCandidates,
+  pub history: Duration,
+}
+
+#[tonic::async_trait]
+impl ProductsRecommender for PersonalizedRecommender {
+  fn sort(&self) -> &'static str {
+    "score:desc"
+  }
+
+  async fn products_to_like(
+    &self,
+    ctx: Arc<Context>,
+    cursor: Option<&PageCursor>,
+    limit: i64,
+  ) -> Result<KeysetPage<ProductToLikeListItem>, DBError> {
+    // the scored pages carry their snapshot, a cursor without one was issued by the fallback
+    if cursor.is_some_and(|c| c.snapshot.is_none()) {
+      return self.fallback.products_to_like(ctx, cursor, limit).await;
+    }
+
+    // an anonymous session has no user id, it gets the popular products only
+    let user_id = ctx.session().user_id().to_string();
+    let since = time_get_millis().saturating_sub(self.history.as_millis() as u64);
+    let (weights, candidates) = (self.weights, self.candidates);
+    let page = self
+      .store
+      .products_recommended(ctx.clone(), &user_id, since, weights, candidates, cursor, limit)
+      .await?;
+    if cursor.is_none() && page.items.is_empty() {
+      return self.fallback.products_to_like(ctx, None, limit).await;
+    }
+    Ok(page)
+  }
+}
@@ -0,0 +1,163 @@
+use std::io::Cursor;
+
+use image::{
+  codecs::{jpeg::JpegEncoder, webp::WebPEncoder},
+  imageops::FilterType,
+  DynamicImage, ImageDecoder, ImageReader, ImageResult,
+};
+use serde::Deserialize;
+
+pub const WEBP_MIME_TYPE: &str = "image/webp";
+pub const JPEG_MIME_TYPE: &str = "image/jpeg";
+
+/// A named size the original image is scaled down to (e.g. thumbnail, card, zoom)
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct ImageDerivativeSpec {
+  pub name: String,
+  pub max_width: u32,
+  pub max_height: u32,
+}
+
+impl ImageDerivativeSpec {
+  /// The key of this derivative of the image stored under `key`. The keys are content
+  /// addressed, so everything the derivative's bytes depend on is part of it: a changed
+  /// spec (or quality) builds new objects instead of reusing the ones built from the old one.
+  pub fn derivative_key(&self, key: &str, jpeg_quality: u8) -> String {
+    format!("{}_{}_{}x{}_q{}", key, self.name, self.max_width, self.max_height, jpeg_quality)
+  }
+}
+
+#[derive(Debug)]
+pub struct EncodedImage {
+  pub mime_type: &'static str,
+  pub width: u32,
+  pub height: u32,
+  pub data: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct ImageDerivatives {
+  /// The original image, re-encoded without its metadata
+  pub original: EncodedImage,
+  /// The encoded derivatives with the specs they're built from, in the order of the specs
+  pub derivatives: Vec<(ImageDerivativeSpec, EncodedImage)>,
+}
+
+/// Decodes the image, applies its EXIF orientation and re-encodes it (and every derivative).
+/// Re-encoding from the decoded pixels is what drops the EXIF/GPS metadata. Photographic
+/// (opaque) images are encoded as JPEG at `jpeg_quality`, a lossless encoding would make
+/// them bigger than the uploads. Only the images with transparency, which JPEG can't keep,
+/// are encoded as lossless WebP.
+///
+/// This is CPU bound, run it on a blocking thread.
+pub fn image_derivatives(
+  data: &[u8],
+  specs: &[ImageDerivativeSpec],
+  jpeg_quality: u8,
+) -> ImageResult<ImageDerivatives> {
+  let mut decoder = ImageReader::new(Cursor::new(data)).with_guessed_format()?.into_decoder()?;
+  let orientation = decoder.orientation()?;
+  let mut img = DynamicImage::from_decoder(decoder)?;
+  img.apply_orientation(orientation);
+
+  let original = image_encode(&img, jpeg_quality)?;
+  let mut derivatives = Vec::with_capacity(specs.len());
+  for spec in specs.iter() {
+    let (width, height) =
+      fit_dimensions(img.width(), img.height(), spec.max_width, spec.max_height);
+    let encoded = if width == img.width() && height == img.height() {
+      image_encode(&img, jpeg_quality)?
+    } else {
+      image_encode(&img.resize_exact(width, height, FilterType::Lanczos3), jpeg_quality)?
+    };
+    derivatives.push((spec.clone(), encoded));
+  }
+
+  Ok(ImageDerivatives { original, derivatives })
+}
+
+/// Scales (width, height) down to fit within (max_width, max_height) keeping the aspect ratio,
+/// images are never scaled up.
+pub fn fit_dimensions(width: u32, height: u32, max_width: u32, max_height: u32) -> (u32, u32) {
+  if width <= max_width && height <= max_height {
+    return (width, height);
+  }
+
+  let ratio = f64::min(max_width as f64 / width as f64, max_height as f64 / height as f64);
+  let scaled = |v: u32| ((v as f64 * ratio).round() as u32).max(1);
+  (scaled(width), scaled(height))
+}
+
+fn image_encode(img: &DynamicImage, jpeg_quality: u8) -> ImageResult<EncodedImage> {
+  let mut data: Vec<u8> = vec![];
+  // both encoders only accept 8 bit pixels
+  let (img, mime_type) = if img.color().has_alpha() {
+    let img = DynamicImage::ImageRgba8(img.to_rgba8());
+    img.write_with_encoder(WebPEncoder::new_lossless(&mut data))?;
+    (img, WEBP_MIME_TYPE)
+  } else {
+    let img = DynamicImage::ImageRgb8(img.to_rgb8());
+    img.write_with_encoder(JpegEncoder::new_with_quality(&mut data, jpeg_quality.clamp(1, 100)))?;
+    (img, JPEG_MIME_TYPE)
+  };
+  Ok(EncodedImage { mime_type, width: img.width(), height: img.height(), data })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_fit_dimensions() {
+    assert_eq!(fit_dimensions(100, 50, 200, 200), (100, 50));
+    assert_eq!(fit_dimensions(1000, 500, 200, 200), (200, 100));
+    assert_eq!(fit_dimensions(500, 1000, 200, 200), (100, 200));
+    assert_eq!(fit_dimensions(4000, 1, 200, 200), (200, 1));
+  }
+
+  #[test]
+  fn test_derivative_key() {
+    let spec = ImageDerivativeSpec { name: "card".into(), max_width: 480, max_height: 320 };
+    assert_eq!(spec.derivative_key("abc", 85), "abc_card_480x320_q85");
+
+    let resized = ImageDerivativeSpec { max_width: 600, ..spec.clone() };
+    assert_ne!(spec.derivative_key("abc", 85), resized.derivative_key("abc", 85));
+    assert_ne!(spec.derivative_key("abc", 85), spec.derivative_key("abc", 70));
+  }
+
+  #[test]
+  fn test_image_derivatives() {
+    let mut png: Vec<u8> = vec![];
+    DynamicImage::new_rgb8(800, 400)
+      .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+      .unwrap();
+
+    let specs = vec![
+      ImageDerivativeSpec { name: "thumbnail".into(), max_width: 100, max_height: 100 },
+      ImageDerivativeSpec { name: "zoom".into(), max_width: 2000, max_height: 2000 },
+    ];
+    let result = image_derivatives(&png, &specs, 85).unwrap();
+
+    assert_eq!((result.original.width, result.original.height), (800, 400));
+    assert_eq!(result.original.mime_type, JPEG_MIME_TYPE);
+    assert_eq!(&result.original.data[..2], &[0xFF, 0xD8]);
+    assert_eq!(result.derivatives[0].0.name, "thumbnail");
+    assert_eq!((result.derivatives[0].1.width, result.derivatives[0].1.height), (100, 50));
+    assert_eq!((result.derivatives[1].1.width, result.derivatives[1].1.height), (800, 400));
+  }
+
+  #[test]
+  fn test_image_derivatives_transparency() {
+    let mut png: Vec<u8> = vec![];
+    DynamicImage::new_rgba8(300, 300)
+      .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+      .unwrap();
+
+    let specs = vec![ImageDerivativeSpec { name: "card".into(), max_width: 100, max_height: 100 }];
+    let result = image_derivatives(&png, &specs, 85).unwrap();
+
+    assert_eq!(result.original.mime_type, WEBP_MIME_TYPE);
+    assert_eq!(&result.original.data[8..12], b"WEBP");
+    assert_eq!(result.derivatives[0].1.mime_type, WEBP_MIME_TYPE);
+  }
+}
@@ -1,2 +1,5 @@
+pub mod checksum;
+pub mod images;
 pub mod net;
 pub mod slug;
+pub mod video;
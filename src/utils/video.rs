@@ -0,0 +1,395 @@
+use std::fmt;
+
+/// The containers product videos can be uploaded in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VideoContainer {
+  Mp4,
+  WebM,
+}
+
+impl VideoContainer {
+  pub fn to_mime_type(&self) -> &'static str {
+    match self {
+      Self::Mp4 => "video/mp4",
+      Self::WebM => "video/webm",
+    }
+  }
+}
+
+/// What was sniffed out of a video's container headers, without decoding any frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct VideoInfo {
+  pub container: VideoContainer,
+  /// The normalized codecs of the video tracks (h264, h265, vp8, vp9, av1, ...)
+  pub codecs: Vec<String>,
+  pub duration_ms: u64,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum VideoProbeError {
+  UnknownContainer,
+  Malformed(&'static str),
+  MissingDuration,
+  MissingVideoTrack,
+}
+
+impl fmt::Display for VideoProbeError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::UnknownContainer => write!(f, "unknown video container"),
+      Self::Malformed(msg) => write!(f, "malformed video: {}", msg),
+      Self::MissingDuration => write!(f, "the video duration is missing"),
+      Self::MissingVideoTrack => write!(f, "the video has no video track"),
+    }
+  }
+}
+
+impl std::error::Error for VideoProbeError {}
+
+/// Sniffs the container (MP4 or WebM) of the video, its video codecs and duration
+pub fn video_probe(data: &[u8]) -> Result<VideoInfo, VideoProbeError> {
+  let info = match video_container(data) {
+    Some(VideoContainer::Mp4) => mp4_probe(data)?,
+    Some(VideoContainer::WebM) => webm_probe(data)?,
+    None => return Err(VideoProbeError::UnknownContainer),
+  };
+
+  if info.codecs.is_empty() {
+    return Err(VideoProbeError::MissingVideoTrack);
+  }
+  Ok(info)
+}
+
+/// Detects the container from the first bytes of the video only,
+/// so it can be checked without having the whole file (e.g. from a ranged download)
+pub fn video_container(head: &[u8]) -> Option<VideoContainer> {
+  if head.len() >= 8 && &head[4..8] == b"ftyp" {
+    Some(VideoContainer::Mp4)
+  } else if head.starts_with(&EBML_HEADER_ID.to_be_bytes()) {
+    Some(VideoContainer::WebM)
+  } else {
+    None
+  }
+}
+
+// ------------------------------------------------------------------------------------------
+// MP4 (ISO base media file format): a tree of boxes, each one is [size u32][type 4cc][payload]
+
+fn mp4_probe(data: &[u8]) -> Result<VideoInfo, VideoProbeError> {
+  let moov = mp4_boxes(data)?
+    .into_iter()
+    .find(|(typ, _)| typ == b"moov")
+    .map(|(_, payload)| payload)
+    .ok_or(VideoProbeError::Malformed("the moov box is missing"))?;
+
+  let mut duration_ms: Option<u64> = None;
+  let mut codecs: Vec<String> = vec![];
+  for (typ, payload) in mp4_boxes(moov)? {
+    match &typ {
+      b"mvhd" => duration_ms = Some(mp4_mvhd_duration_ms(payload)?),
+      b"trak" => {
+        if let Some(codec) = mp4_trak_video_codec(payload)? {
+          codecs.push(codec);
+        }
+      }
+      _ => {}
+    }
+  }
+
+  let duration_ms = duration_ms.ok_or(VideoProbeError::MissingDuration)?;
+  Ok(VideoInfo { container: VideoContainer::Mp4, codecs, duration_ms })
+}
+
+fn mp4_boxes(mut data: &[u8]) -> Result<Vec<([u8; 4], &[u8])>, VideoProbeError> {
+  let mut boxes = vec![];
+  while data.len() >= 8 {
+    let size = read_u32(data, 0)? as u64;
+    let typ: [u8; 4] = data[4..8].try_into().unwrap();
+    let (header, size) = match size {
+      0 => (8, data.len() as u64),
+      1 => (16, read_u64(data, 8)?),
+      _ => (8, size),
+    };
+    if size < header || size > data.len() as u64 {
+      return Err(VideoProbeError::Malformed("invalid box size"));
+    }
+    boxes.push((typ, &data[header as usize..size as usize]));
+    data = &data[size as usize..];
+  }
+  Ok(boxes)
+}
+
+fn mp4_child<'a>(data: &'a [u8], typ: &[u8; 4]) -> Result<Option<&'a [u8]>, VideoProbeError> {
+  Ok(mp4_boxes(data)?.into_iter().find(|(t, _)| t == typ).map(|(_, payload)| payload))
+}
+
+fn mp4_mvhd_duration_ms(mvhd: &[u8]) -> Result<u64, VideoProbeError> {
+  let version = *mvhd.first().ok_or(VideoProbeError::Malformed("empty mvhd box"))?;
+  let (timescale, duration) = if version == 1 {
+    (read_u32(mvhd, 20)? as u64, read_u64(mvhd, 24)?)
+  } else {
+    (read_u32(mvhd, 12)? as u64, read_u32(mvhd, 16)? as u64)
+  };
+  if timescale == 0 {
+    return Err(VideoProbeError::MissingDuration);
+  }
+  Ok(duration.saturating_mul(1000) / timescale)
+}
+
+/// Returns the codec of the track if it's a video one (trak > mdia > hdlr/minf > stbl > stsd)
+fn mp4_trak_video_codec(trak: &[u8]) -> Result<Option<String>, VideoProbeError> {
+  let Some(mdia) = mp4_child(trak, b"mdia")? else { return Ok(None) };
+  let Some(hdlr) = mp4_child(mdia, b"hdlr")? else { return Ok(None) };
+  if hdlr.get(8..12) != Some(&b"vide"[..]) {
+    return Ok(None);
+  }
+
+  let stsd = match mp4_child(mdia, b"minf")? {
+    Some(minf) => match mp4_child(minf, b"stbl")? {
+      Some(stbl) => mp4_child(stbl, b"stsd")?,
+      None => None,
+    },
+    None => None,
+  };
+  // stsd: [version/flags u32][entry_count u32][first entry: size u32, codec 4cc, ...]
+  let codec = stsd
+    .and_then(|stsd| stsd.get(12..16))
+    .ok_or(VideoProbeError::Malformed("the video sample description is missing"))?;
+
+  let codec = match codec {
+    b"avc1" | b"avc3" => "h264".to_string(),
+    b"hvc1" | b"hev1" => "h265".to_string(),
+    b"av01" => "av1".to_string(),
+    b"vp08" => "vp8".to_string(),
+    b"vp09" => "vp9".to_string(),
+    other => String::from_utf8_lossy(other).to_lowercase(),
+  };
+  Ok(Some(codec))
+}
+
+// ------------------------------------------------------------------------------------------
+// WebM (a Matroska profile): a tree of EBML elements, each one is [id vint][size vint][payload]
+
+const EBML_HEADER_ID: u32 = 0x1A45DFA3;
+const EBML_DOC_TYPE_ID: u32 = 0x4282;
+const MKV_SEGMENT_ID: u32 = 0x18538067;
+const MKV_INFO_ID: u32 = 0x1549A966;
+const MKV_TIMESTAMP_SCALE_ID: u32 = 0x2AD7B1;
+const MKV_DURATION_ID: u32 = 0x4489;
+const MKV_TRACKS_ID: u32 = 0x1654AE6B;
+const MKV_TRACK_ENTRY_ID: u32 = 0xAE;
+const MKV_TRACK_TYPE_ID: u32 = 0x83;
+const MKV_CODEC_ID: u32 = 0x86;
+const MKV_TRACK_TYPE_VIDEO: u64 = 1;
+
+fn webm_probe(data: &[u8]) -> Result<VideoInfo, VideoProbeError> {
+  let elements = ebml_elements(data)?;
+  let header = ebml_child(&elements, EBML_HEADER_ID)
+    .ok_or(VideoProbeError::Malformed("the EBML header is missing"))?;
+  let doc_type = ebml_child(&ebml_elements(header)?, EBML_DOC_TYPE_ID).unwrap_or_default();
+  if doc_type != b"webm" {
+    return Err(VideoProbeError::UnknownContainer);
+  }
+
+  let segment = ebml_child(&elements, MKV_SEGMENT_ID)
+    .ok_or(VideoProbeError::Malformed("the segment is missing"))?;
+
+  let mut duration_ms: Option<u64> = None;
+  let mut codecs: Vec<String> = vec![];
+  let mut found_tracks = false;
+  let mut rest = segment;
+  // the clusters may have an unknown size, so stop as soon as the metadata is read
+  while !rest.is_empty() && (duration_ms.is_none() || !found_tracks) {
+    let (id, payload, next) = ebml_element(rest)?;
+    match id {
+      MKV_INFO_ID => duration_ms = webm_info_duration_ms(payload)?,
+      MKV_TRACKS_ID => {
+        found_tracks = true;
+        codecs = webm_video_codecs(payload)?;
+      }
+      _ => {}
+    }
+    rest = next;
+  }
+
+  let duration_ms = duration_ms.ok_or(VideoProbeError::MissingDuration)?;
+  Ok(VideoInfo { container: VideoContainer::WebM, codecs, duration_ms })
+}
+
+fn webm_info_duration_ms(info: &[u8]) -> Result<Option<u64>, VideoProbeError> {
+  let elements = ebml_elements(info)?;
+  let scale = match ebml_child(&elements, MKV_TIMESTAMP_SCALE_ID) {
+    Some(scale) => read_uint(scale),
+    None => 1_000_000,
+  };
+  let duration = match ebml_child(&elements, MKV_DURATION_ID) {
+    Some(duration) => read_float(duration)?,
+    None => return Ok(None),
+  };
+  // the duration is in scale units, the scale is in nanoseconds
+  Ok(Some((duration * scale as f64 / 1_000_000.0).round() as u64))
+}
+
+fn webm_video_codecs(tracks: &[u8]) -> Result<Vec<String>, VideoProbeError> {
+  let mut codecs = vec![];
+  for (id, entry) in ebml_elements(tracks)? {
+    if id != MKV_TRACK_ENTRY_ID {
+      continue;
+    }
+    let fields = ebml_elements(entry)?;
+    let track_type = ebml_child(&fields, MKV_TRACK_TYPE_ID).map(read_uint);
+    if track_type != Some(MKV_TRACK_TYPE_VIDEO) {
+      continue;
+    }
+    let codec = ebml_child(&fields, MKV_CODEC_ID).unwrap_or_default();
+    let codec = match codec {
+      b"V_VP8" => "vp8".to_string(),
+      b"V_VP9" => "vp9".to_string(),
+      b"V_AV1" => "av1".to_string(),
+      b"V_MPEG4/ISO/AVC" => "h264".to_string(),
+      other => String::from_utf8_lossy(other).to_lowercase(),
+    };
+    codecs.push(codec);
+  }
+  Ok(codecs)
+}
+
+fn ebml_elements(mut data: &[u8]) -> Result<Vec<(u32, &[u8])>, VideoProbeError> {
+  let mut elements = vec![];
+  while !data.is_empty() {
+    let (id, payload, next) = ebml_element(data)?;
+    elements.push((id, payload));
+    data = next;
+  }
+  Ok(elements)
+}
+
+fn ebml_child<'a>(elements: &[(u32, &'a [u8])], id: u32) -> Option<&'a [u8]> {
+  elements.iter().find(|(i, _)| *i == id).map(|(_, payload)| *payload)
+}
+
+/// Splits the first element of `data` into (id, payload, rest).
+/// An element of unknown size extends to the end of `data`.
+fn ebml_element(data: &[u8]) -> Result<(u32, &[u8], &[u8]), VideoProbeError> {
+  let (id, id_len) = ebml_vint(data, 4, true)?;
+  let (size, size_len) = ebml_vint(&data[id_len..], 8, false)?;
+  let start = id_len + size_len;
+
+  let unknown_size = size == (1u64 << (7 * size_len)) - 1;
+  let end = if unknown_size { data.len() } else { start.saturating_add(size as usize) };
+  if end > data.len() {
+    return Err(VideoProbeError::Malformed("invalid element size"));
+  }
+  Ok((id as u32, &data[start..end], &data[end..]))
+}
+
+/// Reads a variable length integer, ids keep their length marker bit, sizes don't
+fn ebml_vint(
+  data: &[u8],
+  max_len: usize,
+  keep_marker: bool,
+) -> Result<(u64, usize), VideoProbeError> {
+  let first = *data.first().ok_or(VideoProbeError::Malformed("truncated element"))?;
+  let len = first.leading_zeros() as usize + 1;
+  if len > max_len || len > data.len() {
+    return Err(VideoProbeError::Malformed("invalid variable length integer"));
+  }
+
+  let first = if keep_marker { first as u64 } else { (first as u64) & (0xFF >> len) };
+  let value = data[1..len].iter().fold(first, |acc, b| (acc << 8) | *b as u64);
+  Ok((value, len))
+}
+
+fn read_uint(data: &[u8]) -> u64 {
+  data.iter().take(8).fold(0, |acc, b| (acc << 8) | *b as u64)
+}
+
+fn read_float(data: &[u8]) -> Result<f64, VideoProbeError> {
+  match data.len() {
+    4 => Ok(f32::from_be_bytes(data.try_into().unwrap()) as f64),
+    8 => Ok(f64::from_be_bytes(data.try_into().unwrap())),
+    _ => Err(VideoProbeError::Malformed("invalid float size")),
+  }
+}
+
+fn read_u32(data: &[u8], at: usize) -> Result<u32, VideoProbeError> {
+  let bytes = data.get(at..at + 4).ok_or(VideoProbeError::Malformed("truncated box"))?;
+  Ok(u32::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_u64(data: &[u8], at: usize) -> Result<u64, VideoProbeError> {
+  let bytes = data.get(at..at + 8).ok_or(VideoProbeError::Malformed("truncated box"))?;
+  Ok(u64::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn mp4_box(typ: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+    let mut b = ((payload.len() + 8) as u32).to_be_bytes().to_vec();
+    b.extend_from_slice(typ);
+    b.extend_from_slice(payload);
+    b
+  }
+
+  fn ebml(id: &[u8], payload: &[u8]) -> Vec<u8> {
+    let mut e = id.to_vec();
+    e.extend_from_slice(&[0x01, 0, 0, 0, 0, 0, 0, payload.len() as u8]);
+    e.extend_from_slice(payload);
+    e
+  }
+
+  #[test]
+  fn test_mp4_probe() {
+    // mvhd v0: version/flags, creation, modification, timescale 1000, duration 12500
+    let mut mvhd = vec![0u8; 12];
+    mvhd.extend_from_slice(&1000u32.to_be_bytes());
+    mvhd.extend_from_slice(&12500u32.to_be_bytes());
+
+    let mut hdlr = vec![0u8; 8];
+    hdlr.extend_from_slice(b"vide");
+    let mut stsd = vec![0, 0, 0, 0, 0, 0, 0, 1];
+    stsd.extend_from_slice(&mp4_box(b"avc1", &[0u8; 8]));
+    let stbl = mp4_box(b"stbl", &mp4_box(b"stsd", &stsd));
+    let minf = mp4_box(b"minf", &stbl);
+    let mdia = mp4_box(b"mdia", &[mp4_box(b"hdlr", &hdlr), minf].concat());
+    let moov = mp4_box(b"moov", &[mp4_box(b"mvhd", &mvhd), mp4_box(b"trak", &mdia)].concat());
+
+    let file = [mp4_box(b"ftyp", b"isom"), mp4_box(b"mdat", &[0u8; 16]), moov].concat();
+    let info = video_probe(&file).unwrap();
+    assert_eq!(info.container, VideoContainer::Mp4);
+    assert_eq!(info.codecs, vec!["h264".to_string()]);
+    assert_eq!(info.duration_ms, 12500);
+  }
+
+  #[test]
+  fn test_webm_probe() {
+    let header = ebml(&[0x1A, 0x45, 0xDF, 0xA3], &ebml(&[0x42, 0x82], b"webm"));
+    let info = ebml(
+      &[0x15, 0x49, 0xA9, 0x66],
+      &[
+        ebml(&[0x2A, 0xD7, 0xB1], &1_000_000u32.to_be_bytes()),
+        ebml(&[0x44, 0x89], &8000f64.to_be_bytes()),
+      ]
+      .concat(),
+    );
+    let entry = ebml(&[0xAE], &[ebml(&[0x83], &[1]), ebml(&[0x86], b"V_VP9")].concat());
+    let tracks = ebml(&[0x16, 0x54, 0xAE, 0x6B], &entry);
+    // a live stream style cluster of unknown size after the metadata
+    let mut segment = vec![0x18, 0x53, 0x80, 0x67, 0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+    segment.extend_from_slice(&[info, tracks].concat());
+    segment.extend_from_slice(&[0x1F, 0x43, 0xB6, 0x75, 0xFF, 0x00, 0x00]);
+
+    let info = video_probe(&[header, segment].concat()).unwrap();
+    assert_eq!(info.container, VideoContainer::WebM);
+    assert_eq!(info.codecs, vec!["vp9".to_string()]);
+    assert_eq!(info.duration_ms, 8000);
+  }
+
+  #[test]
+  fn test_unknown_container() {
+    assert_eq!(video_probe(b"GIF89a........").unwrap_err(), VideoProbeError::UnknownContainer);
+    assert!(video_probe(&[0, 0, 0, 0xFF, b'f', b't', b'y', b'p']).is_err());
+  }
+}